@@ -0,0 +1,2 @@
+ALTER TABLE publications
+ADD COLUMN paper_hash VARCHAR(64) DEFAULT NULL; -- SHA3-256 hex digest of the stored paper file
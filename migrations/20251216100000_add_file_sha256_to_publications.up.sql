@@ -0,0 +1,2 @@
+ALTER TABLE publications
+ADD COLUMN file_sha256 VARCHAR(64) DEFAULT NULL; -- SHA-256 hex digest of the stored file, used to check storage integrity
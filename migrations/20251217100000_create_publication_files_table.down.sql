@@ -0,0 +1 @@
+DROP TABLE IF EXISTS publication_files CASCADE;
\ No newline at end of file
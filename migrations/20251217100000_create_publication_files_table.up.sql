@@ -0,0 +1,11 @@
+CREATE TABLE publication_files (
+    id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4 ()),
+    publication_id UUID NOT NULL REFERENCES publications (id) ON DELETE CASCADE,
+    s3key VARCHAR NOT NULL, -- S3 key of the stored file, under publications/<id>/supplementary/
+    filename VARCHAR(255) NOT NULL, -- Name the file was uploaded with
+    content_type VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL, -- Size of the file in bytes
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_publication_files_publication_id ON publication_files (publication_id);
@@ -0,0 +1 @@
+ALTER TABLE publications DROP COLUMN IF EXISTS file_size;
\ No newline at end of file
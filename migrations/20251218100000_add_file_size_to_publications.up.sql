@@ -0,0 +1,2 @@
+ALTER TABLE publications
+ADD COLUMN file_size BIGINT DEFAULT NULL; -- Size in bytes of the stored file, used to account for storage usage
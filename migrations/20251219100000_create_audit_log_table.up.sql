@@ -0,0 +1,17 @@
+CREATE TABLE audit_log (
+    id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4 ()),
+    actor VARCHAR(255) NOT NULL, -- Privy id of the user, or internal:<name> for internal services
+    method VARCHAR(16) NOT NULL,
+    path VARCHAR NOT NULL, -- Request path, without its query string
+    entity_type VARCHAR(64) NOT NULL, -- Kind of entity changed, such as publications or users
+    entity_id VARCHAR(255) DEFAULT NULL, -- Id of the entity changed, if the route names one
+    status SMALLINT NOT NULL, -- HTTP status of the response
+    request_id VARCHAR(128) DEFAULT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_actor ON audit_log (actor, created_at DESC);
+
+CREATE INDEX idx_audit_log_entity ON audit_log (entity_type, entity_id, created_at DESC);
+
+CREATE INDEX idx_audit_log_created_at ON audit_log (created_at DESC);
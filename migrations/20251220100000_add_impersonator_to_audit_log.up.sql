@@ -0,0 +1,2 @@
+ALTER TABLE audit_log
+ADD COLUMN impersonator VARCHAR(255) DEFAULT NULL; -- Privy id of the admin who made the request as the actor, if any
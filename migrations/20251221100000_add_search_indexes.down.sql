@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_publications_user_id;
+
+DROP INDEX IF EXISTS idx_publications_title_trgm;
+
+DROP INDEX IF EXISTS idx_publications_tags;
@@ -0,0 +1,9 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Tag searches, written as tags @> ARRAY[$1] so that the index applies
+CREATE INDEX idx_publications_tags ON publications USING GIN (tags);
+
+-- Title searches, which match ILIKE patterns anywhere in the title
+CREATE INDEX idx_publications_title_trgm ON publications USING GIN (title gin_trgm_ops);
+
+CREATE INDEX idx_publications_user_id ON publications (user_id, created_at DESC);
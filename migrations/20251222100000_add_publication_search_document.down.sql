@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_publications_search_document;
+
+ALTER TABLE publications DROP COLUMN IF EXISTS search_document;
+
+DROP FUNCTION IF EXISTS publication_search_document(TEXT, TEXT, TEXT[]);
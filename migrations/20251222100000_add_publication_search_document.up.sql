@@ -0,0 +1,17 @@
+-- Text searched by full-text search, weighting the title over the tags and the tags over the
+-- abstract. Wrapped in a function declared immutable since array_to_string is only stable, which
+-- generated columns do not accept.
+CREATE FUNCTION publication_search_document(title TEXT, about TEXT, tags TEXT[])
+RETURNS tsvector
+LANGUAGE sql IMMUTABLE PARALLEL SAFE
+AS $$
+    SELECT setweight(to_tsvector('english', title), 'A')
+        || setweight(to_tsvector('english', COALESCE(array_to_string(tags, ' '), '')), 'B')
+        || setweight(to_tsvector('english', COALESCE(about, '')), 'C')
+$$;
+
+ALTER TABLE publications
+    ADD COLUMN search_document tsvector
+    GENERATED ALWAYS AS (publication_search_document(title, about, tags)) STORED;
+
+CREATE INDEX idx_publications_search_document ON publications USING GIN (search_document);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS idx_publications_created_at;
@@ -0,0 +1,2 @@
+-- Listing of publications, most recent first, walked by keyset on (created_at, id)
+CREATE INDEX idx_publications_created_at ON publications (created_at DESC, id DESC);
@@ -0,0 +1,8 @@
+CREATE TABLE publication_views (
+    id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4 ()),
+    publication_id UUID NOT NULL REFERENCES publications (id) ON DELETE CASCADE,
+    viewer_id VARCHAR, -- Privy id of the authenticated viewer, NULL for anonymous views
+    viewed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_publication_views_publication_id ON publication_views (publication_id);
@@ -0,0 +1,2 @@
+-- Deleted publications are kept, so that citations of them still resolve, and can be restored
+ALTER TABLE publications ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,558 @@
+use std::collections::HashSet;
+
+use actix_web::{HttpRequest, HttpResponse, get, post, web};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    api::{
+        error::{ApiError, ErrorResponse},
+        publications::DELETED_PUBLICATION_RETENTION,
+    },
+    auth::{Privy, RequireScope, admin::ADMIN_SCOPE},
+    common::{
+        pagination::{PageQuery, Paginated},
+        tasks::TaskStatus,
+    },
+    db::{
+        s3::{ObjectStore, S3Bucket, S3Key, client::S3ObjectInfo},
+        sql::{
+            AuditLogOperations, PublicationOperations, SqlClient, UserOperations,
+            models::{AuditLogEntry, StorageUsage},
+        },
+    },
+};
+
+/// Prefix under which publication files are stored, one directory per publication or upload.
+const PUBLICATIONS_PREFIX: &str = "publications/";
+
+/// Number of users listed by the storage usage report when no limit is given, and the most it
+/// lists.
+const DEFAULT_USAGE_LIMIT: i64 = 50;
+const MAX_USAGE_LIMIT: i64 = 500;
+
+/// Objects younger than this are never treated as orphans, so that uploads whose publication is
+/// still being created are left alone.
+const ORPHAN_MIN_AGE: TimeDelta = TimeDelta::hours(24);
+
+pub fn config(conf: &mut web::ServiceConfig) {
+    let scope = web::scope("/admin")
+        .wrap(Privy)
+        .wrap(RequireScope::or_privy(ADMIN_SCOPE))
+        .service(cleanup_storage)
+        .service(normalize_storage_keys)
+        .service(storage_usage)
+        .service(revoke_user_sessions)
+        .service(tasks)
+        .service(audit_log);
+    conf.service(scope);
+}
+
+#[cfg(test)]
+mod tests;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    cleanup_storage,
+    normalize_storage_keys,
+    storage_usage,
+    revoke_user_sessions,
+    tasks,
+    audit_log
+))]
+pub struct AdminApi;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CleanupStorageQuery {
+    /// Only report the orphaned objects, `true` by default
+    dry_run: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CleanupReport {
+    pub dry_run: bool,
+    pub scanned_objects: usize,
+    pub orphaned_objects: usize,
+    pub orphaned_bytes: i64,
+    pub deleted_objects: usize,
+    pub reclaimed_bytes: i64,
+    /// Keys of the orphaned objects
+    pub orphans: Vec<String>,
+}
+
+/// Deletes, or only reports when `dry_run` is set (the default), stored objects that no
+/// publication references anymore.
+#[utoipa::path(
+    params(CleanupStorageQuery),
+    responses(
+        (status = 200, body = CleanupReport),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []), ("internal_token" = []))
+)]
+#[post("/storage/cleanup")]
+async fn cleanup_storage(
+    req: HttpRequest,
+    query: web::Query<CleanupStorageQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let report = cleanup_orphaned_files(
+        &*data.object_store,
+        &data.sql_client,
+        query.dry_run.unwrap_or(true),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Deletes, or only reports if `dry_run` is set, the stored files of publications or uploads that
+/// no publication references anymore, deleted publications referencing theirs until they can no
+/// longer be restored. Also run by the `storage-cleanup` command.
+pub async fn cleanup_orphaned_files(
+    object_store: &dyn ObjectStore,
+    sql_client: &SqlClient,
+    dry_run: bool,
+) -> Result<CleanupReport, ApiError> {
+    let files = object_store
+        .list_files(PUBLICATIONS_PREFIX, &S3Bucket::Storage)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error listing stored publication files: {}", err);
+            ApiError::internal("Failed to list stored files")
+        })?;
+
+    let directory_ids: Vec<Uuid> = files
+        .iter()
+        .filter_map(|file| storage_directory_id(&file.key))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    // The files of publications deleted for longer than they can be restored are reclaimed
+    let referenced: HashSet<Uuid> = sql_client
+        .get_referenced_storage_directories(
+            &directory_ids,
+            Utc::now() - DELETED_PUBLICATION_RETENTION,
+        )
+        .await
+        .map_err(|err| {
+            tracing::error!("Error retrieving referenced storage directories: {}", err);
+            ApiError::internal("Internal server error")
+        })?
+        .into_iter()
+        .collect();
+
+    let orphans = find_orphans(&files, &referenced, Utc::now() - ORPHAN_MIN_AGE);
+    let orphan_keys: Vec<String> = orphans.iter().map(|file| file.key.clone()).collect();
+    let orphaned_bytes: i64 = orphans.iter().map(|file| file.size).sum();
+
+    let mut deleted_objects = 0;
+    let mut reclaimed_bytes = 0;
+    if !dry_run && !orphan_keys.is_empty() {
+        let deleted: HashSet<String> = object_store
+            .delete_keys(&orphan_keys, &S3Bucket::Storage)
+            .await
+            .map_err(|err| {
+                tracing::error!("Error deleting orphaned files: {}", err);
+                ApiError::internal("Failed to delete orphaned files")
+            })?
+            .into_iter()
+            .collect();
+
+        deleted_objects = deleted.len();
+        reclaimed_bytes = orphans
+            .iter()
+            .filter(|file| deleted.contains(&file.key))
+            .map(|file| file.size)
+            .sum();
+    }
+
+    tracing::info!(
+        "Storage cleanup (dry_run={}): {} orphaned objects out of {}, {} deleted",
+        dry_run,
+        orphan_keys.len(),
+        files.len(),
+        deleted_objects
+    );
+
+    Ok(CleanupReport {
+        dry_run,
+        scanned_objects: files.len(),
+        orphaned_objects: orphan_keys.len(),
+        orphaned_bytes,
+        deleted_objects,
+        reclaimed_bytes,
+        orphans: orphan_keys,
+    })
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct NormalizeKeysQuery {
+    /// Only report the planned moves, `true` by default
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+struct KeyMove {
+    publication_id: Uuid,
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FailedKeyMove {
+    #[serde(flatten)]
+    key_move: KeyMove,
+    error: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+struct NormalizeKeysReport {
+    dry_run: bool,
+    moves: Vec<KeyMove>,
+    /// Keys the publication files were moved to
+    moved: Vec<String>,
+    failed: Vec<FailedKeyMove>,
+}
+
+/// Moves the files of publications stored outside of their own `publications/<id>/` directory
+/// to it, updating each publication as its file is moved. Only reports the planned moves when
+/// `dry_run` is set (the default).
+#[utoipa::path(
+    params(NormalizeKeysQuery),
+    responses(
+        (status = 200, body = NormalizeKeysReport),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []), ("internal_token" = []))
+)]
+#[post("/storage/normalize-keys")]
+async fn normalize_storage_keys(
+    req: HttpRequest,
+    query: web::Query<NormalizeKeysQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let dry_run = query.dry_run.unwrap_or(true);
+
+    let publications = data
+        .sql_client
+        .get_publications_with_noncanonical_s3keys()
+        .await
+        .map_err(|err| {
+            tracing::error!("Error retrieving publications to normalize: {}", err);
+            ApiError::internal("Internal server error")
+        })?;
+
+    let moves: Vec<KeyMove> = publications
+        .into_iter()
+        .filter_map(|publication| {
+            let from = publication.s3key?;
+            Some(KeyMove {
+                publication_id: publication.id,
+                to: canonical_publication_key(publication.id, &from),
+                from,
+            })
+        })
+        .collect();
+
+    let mut moved = vec![];
+    let mut failed = vec![];
+    if !dry_run {
+        for key_move in moves.iter() {
+            match move_publication_file(&data, key_move).await {
+                Ok(()) => moved.push(key_move.to.clone()),
+                Err(error) => failed.push(FailedKeyMove {
+                    key_move: key_move.clone(),
+                    error,
+                }),
+            }
+        }
+    }
+
+    tracing::info!(
+        "Storage key normalization (dry_run={}): {} publications to move, {} moved, {} failed",
+        dry_run,
+        moves.len(),
+        moved.len(),
+        failed.len()
+    );
+
+    Ok(HttpResponse::Ok().json(NormalizeKeysReport {
+        dry_run,
+        moves,
+        moved,
+        failed,
+    }))
+}
+
+/// Moves a publication file to its canonical key and points the publication at it, moving the
+/// file back if the publication cannot be updated. Returns the reason of a failure.
+async fn move_publication_file(data: &AppState, key_move: &KeyMove) -> Result<(), &'static str> {
+    let from = S3Key(key_move.from.clone());
+    let to = S3Key(key_move.to.clone());
+
+    match data
+        .object_store
+        .object_exists(&to.0, &S3Bucket::Storage)
+        .await
+    {
+        Ok(false) => {}
+        Ok(true) => return Err("Destination already exists"),
+        Err(err) => {
+            tracing::error!("Error checking destination {}: {}", to, err);
+            return Err("Failed to check destination");
+        }
+    }
+
+    if let Err(err) = data.object_store.move_file(&from, &to).await {
+        tracing::error!(
+            "Error moving file of publication {}: {}",
+            key_move.publication_id,
+            err
+        );
+        return Err("Failed to move file");
+    }
+
+    let error = match data
+        .sql_client
+        .update_publication(key_move.publication_id, None, None, None, None, Some(&to.0))
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => None,
+        // Deleted since it was listed
+        Ok(_) => Some("Publication not found"),
+        Err(err) => {
+            tracing::error!(
+                "Error updating key of publication {}: {}",
+                key_move.publication_id,
+                err
+            );
+            Some("Failed to update publication")
+        }
+    };
+    if let Some(error) = error {
+        if let Err(err) = data.object_store.move_file(&to, &from).await {
+            tracing::error!("Error moving {} back to {}: {}", to, from, err);
+        }
+        return Err(error);
+    }
+    data.publication_cache
+        .mark_changed(key_move.publication_id)
+        .await;
+
+    Ok(())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StorageUsageQuery {
+    /// Number of users to list, 50 by default and at most 500
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct StorageUsageReport {
+    /// Bytes each user may store, unlimited when absent
+    quota_bytes: Option<i64>,
+    users: Vec<StorageUsage>,
+}
+
+/// Lists the users storing the most bytes of files, largest first.
+#[utoipa::path(
+    params(StorageUsageQuery),
+    responses(
+        (status = 200, body = StorageUsageReport),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []), ("internal_token" = []))
+)]
+#[get("/storage/usage")]
+async fn storage_usage(
+    req: HttpRequest,
+    query: web::Query<StorageUsageQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_USAGE_LIMIT)
+        .clamp(1, MAX_USAGE_LIMIT);
+
+    let users = data
+        .sql_client
+        .list_storage_usage(limit)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error computing storage usage: {}", err);
+            ApiError::internal("Internal server error")
+        })?;
+
+    Ok(HttpResponse::Ok().json(StorageUsageReport {
+        quota_bytes: data.storage_quota,
+        users,
+    }))
+}
+
+/// Returns the key of a publication file within the publication's own directory.
+fn canonical_publication_key(publication_id: Uuid, s3key: &str) -> String {
+    let file_name = s3key.rsplit('/').next().unwrap_or(s3key);
+    format!("{}{}/{}", PUBLICATIONS_PREFIX, publication_id, file_name)
+}
+
+/// Extracts the id naming the `publications/<id>/` directory of a key, if it follows that layout.
+pub(crate) fn storage_directory_id(key: &str) -> Option<Uuid> {
+    let (directory, _) = key.strip_prefix(PUBLICATIONS_PREFIX)?.split_once('/')?;
+    Uuid::parse_str(directory).ok()
+}
+
+/// Selects the objects in unreferenced directories last modified before `cutoff`. Objects that do
+/// not follow the directory layout or have no modification time are never selected.
+fn find_orphans<'a>(
+    files: &'a [S3ObjectInfo],
+    referenced: &HashSet<Uuid>,
+    cutoff: DateTime<Utc>,
+) -> Vec<&'a S3ObjectInfo> {
+    files
+        .iter()
+        .filter(|file| {
+            storage_directory_id(&file.key).is_some_and(|id| !referenced.contains(&id))
+                && file
+                    .last_modified
+                    .is_some_and(|last_modified| last_modified < cutoff)
+        })
+        .collect()
+}
+
+/// Rejects every token issued so far to a user, for instance when their account is compromised,
+/// and closes their cookie sessions. Tokens issued after a new sign-in are accepted.
+#[utoipa::path(
+    responses(
+        (status = 200, body = SessionRevocation),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []), ("internal_token" = []))
+)]
+#[post("/users/{privy_id}/revoke-sessions")]
+async fn revoke_user_sessions(
+    req: HttpRequest,
+    privy_id: web::Path<String>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let revoked_at = data
+        .session_revocation
+        .revoke_subject(&privy_id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error revoking sessions of {}: {}", privy_id, err);
+            ApiError::internal("Failed to revoke sessions")
+        })?;
+    data.sessions.close_all(&privy_id).await.map_err(|err| {
+        tracing::error!("Error closing sessions of {}: {}", privy_id, err);
+        ApiError::internal("Failed to close sessions")
+    })?;
+
+    Ok(HttpResponse::Ok().json(SessionRevocation {
+        privy_id: privy_id.into_inner(),
+        revoked_at,
+        expires_in_seconds: data.session_revocation.subject_window().as_secs(),
+    }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct SessionRevocation {
+    privy_id: String,
+    /// Unix time before which the tokens of the user are rejected
+    revoked_at: u64,
+    /// Time after which the revocation is forgotten, as the revoked tokens have all expired
+    expires_in_seconds: u64,
+}
+
+/// Reports the status of the supervised background tasks, such as their last error.
+#[utoipa::path(
+    responses(
+        (status = 200, body = Vec<TaskStatus>),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []), ("internal_token" = []))
+)]
+#[get("/tasks")]
+async fn tasks(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    Ok(HttpResponse::Ok().json(data.supervisor.statuses()))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AuditLogQuery {
+    /// Privy id of the user who made the changes
+    actor: Option<String>,
+    /// Type of the entities to list entries of, such as `publications`
+    entity: Option<String>,
+    entity_id: Option<String>,
+}
+
+/// Lists the changes made through the API, most recent first.
+#[utoipa::path(
+    params(AuditLogQuery, PageQuery),
+    responses(
+        (status = 200, body = Paginated<AuditLogEntry>),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []), ("internal_token" = []))
+)]
+#[get("/audit-log")]
+async fn audit_log(
+    req: HttpRequest,
+    query: web::Query<AuditLogQuery>,
+    page: web::Query<PageQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let page = page.into_inner();
+    let actor = query.actor.as_deref();
+    let entity_type = query.entity.as_deref();
+    let entity_id = query.entity_id.as_deref();
+
+    let entries = data
+        .sql_client
+        .list_audit_log(actor, entity_type, entity_id, page)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error listing audit log: {}", err);
+            ApiError::internal("Internal server error")
+        })?;
+
+    let total_count = data
+        .sql_client
+        .count_audit_log(actor, entity_type, entity_id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error counting audit log entries: {}", err);
+            ApiError::internal("Internal server error")
+        })?;
+
+    Ok(HttpResponse::Ok().json(Paginated::new(entries, total_count, page)))
+}
@@ -0,0 +1,651 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+
+    use std::{sync::Arc, time::Duration};
+
+    use actix_web::{HttpMessage, http::StatusCode, test};
+    use sqlx::PgPool;
+
+    use crate::{
+        api::tests::{
+            authenticate, create_test_app, create_test_app_state, create_test_app_with_state,
+            create_test_app_with_store, internal_token, test_claims, tokio_runtime,
+        },
+        auth::PrivyClaims,
+        db::{
+            s3::{
+                S3Bucket,
+                mock::{MockObject, MockObjectStore},
+            },
+            sql::{
+                AuditLogOperations, PublicationOperations, SqlClient,
+                models::{NewAuditLogEntry, NewPublication},
+            },
+        },
+    };
+
+    #[sqlx::test]
+    async fn test_cleanup_storage_requires_admin_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/cleanup?dry_run=false")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/cleanup?dry_run=false")
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test]
+    async fn test_cleanup_storage_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        let referenced_key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        let orphaned_key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        let recent_key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        let two_days_ago = chrono::Utc::now() - chrono::TimeDelta::days(2);
+        for key in [&referenced_key, &orphaned_key] {
+            object_store.insert(
+                S3Bucket::Storage,
+                key,
+                MockObject {
+                    last_modified: two_days_ago,
+                    ..MockObject::new(b"%PDF".to_vec())
+                },
+            );
+        }
+        // Possibly an upload whose publication is still being created
+        object_store.insert(
+            S3Bucket::Storage,
+            &recent_key,
+            MockObject::new(b"%PDF".to_vec()),
+        );
+        sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id,
+                title: "Referenced".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(referenced_key.clone()),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/cleanup")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["dry_run"], true);
+        assert_eq!(body["scanned_objects"], 3);
+        assert_eq!(body["orphaned_objects"], 1);
+        assert_eq!(body["orphaned_bytes"], 4);
+        assert_eq!(body["deleted_objects"], 0);
+        assert_eq!(object_store.keys(S3Bucket::Storage).len(), 3);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/cleanup?dry_run=false")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["deleted_objects"], 1);
+        assert_eq!(body["reclaimed_bytes"], 4);
+        assert!(object_store.get(S3Bucket::Storage, &orphaned_key).is_none());
+        assert!(
+            object_store
+                .get(S3Bucket::Storage, &referenced_key)
+                .is_some()
+        );
+        assert!(object_store.get(S3Bucket::Storage, &recent_key).is_some());
+    }
+
+    #[sqlx::test]
+    async fn test_cleanup_storage_reclaims_deleted_publications_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        let mut keys = Vec::new();
+        for (title, deleted_for) in [
+            ("Recently deleted", "1 day"),
+            ("Deleted long ago", "31 days"),
+        ] {
+            let key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+            object_store.insert(
+                S3Bucket::Storage,
+                &key,
+                MockObject {
+                    last_modified: chrono::Utc::now() - chrono::TimeDelta::days(40),
+                    ..MockObject::new(b"%PDF".to_vec())
+                },
+            );
+            let publication = sql_client
+                .create_publication(&NewPublication {
+                    user_id: user_privy_id.clone(),
+                    title: title.to_string(),
+                    about: None,
+                    tags: None,
+                    s3key: Some(key.clone()),
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
+                })
+                .await
+                .unwrap();
+            sqlx::query("UPDATE publications SET deleted_at = NOW() - $2::interval WHERE id = $1")
+                .bind(publication.id)
+                .bind(deleted_for)
+                .execute(&pool)
+                .await
+                .unwrap();
+            keys.push(key);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/cleanup?dry_run=false")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Only the publication past its retention period loses its file
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["deleted_objects"], 1);
+        assert!(object_store.get(S3Bucket::Storage, &keys[0]).is_some());
+        assert!(object_store.get(S3Bucket::Storage, &keys[1]).is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_storage_usage_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Sized".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(format!("publications/{}/paper.pdf", uuid::Uuid::new_v4())),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: Some(4096),
+            })
+            .await
+            .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/admin/storage/usage")
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri("/admin/storage/usage?limit=10")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["quota_bytes"], serde_json::Value::Null);
+        assert_eq!(body["users"].as_array().unwrap().len(), 1);
+        assert_eq!(body["users"][0]["privy_id"], user_privy_id);
+        assert_eq!(body["users"][0]["total_bytes"], 4096);
+    }
+
+    /// Creates a publication whose file was stored under a directory unrelated to its id.
+    async fn create_legacy_publication(
+        sql_client: &SqlClient,
+        object_store: &MockObjectStore,
+        user_privy_id: String,
+    ) -> (uuid::Uuid, String) {
+        let legacy_key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(
+            S3Bucket::Storage,
+            &legacy_key,
+            MockObject::new(b"%PDF".to_vec()),
+        );
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id,
+                title: "Legacy".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(legacy_key.clone()),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+
+        (publication.id, legacy_key)
+    }
+
+    #[sqlx::test]
+    async fn test_normalize_storage_keys_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        let (publication_id, legacy_key) =
+            create_legacy_publication(&sql_client, &object_store, user_privy_id.clone()).await;
+        // Publications already in their own directory, or without a file, are left alone
+        crate::api::tests::create_test_publication(&sql_client, user_privy_id.clone()).await;
+        let canonical_key = format!("publications/{}/paper.pdf", publication_id);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/normalize-keys")
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/normalize-keys")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["dry_run"], true);
+        assert_eq!(body["moves"].as_array().unwrap().len(), 1);
+        assert_eq!(body["moves"][0]["from"], legacy_key);
+        assert_eq!(body["moves"][0]["to"], canonical_key);
+        assert!(object_store.get(S3Bucket::Storage, &legacy_key).is_some());
+
+        // Cache the publication with the URL of its legacy key
+        let get_file_url = || async {
+            let req = test::TestRequest::get()
+                .uri(&format!("/publications/{}", publication_id))
+                .to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            body["file_url"].as_str().unwrap().to_string()
+        };
+        assert!(get_file_url().await.contains(&legacy_key));
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/normalize-keys?dry_run=false")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["moved"], serde_json::json!([canonical_key]));
+        assert_eq!(body["failed"], serde_json::json!([]));
+        assert_eq!(
+            object_store.keys(S3Bucket::Storage),
+            vec![canonical_key.clone()]
+        );
+        let publication = sql_client.get_publication(publication_id).await.unwrap();
+        assert_eq!(publication.s3key, Some(canonical_key.clone()));
+        // The cached publication is dropped along with the URL of the legacy key
+        assert!(get_file_url().await.contains(&canonical_key));
+
+        // Nothing is left to move
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/normalize-keys")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["moves"], serde_json::json!([]));
+    }
+
+    #[sqlx::test]
+    async fn test_normalize_storage_keys_copy_failure_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        let (publication_id, legacy_key) =
+            create_legacy_publication(&sql_client, &object_store, user_privy_id).await;
+        object_store.set_failing_copies(true);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/storage/normalize-keys?dry_run=false")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["moved"], serde_json::json!([]));
+        assert_eq!(
+            body["failed"][0]["publication_id"],
+            publication_id.to_string()
+        );
+        assert_eq!(body["failed"][0]["from"], legacy_key);
+        assert_eq!(body["failed"][0]["error"], "Failed to move file");
+
+        assert_eq!(
+            object_store.keys(S3Bucket::Storage),
+            vec![legacy_key.clone()]
+        );
+        let publication = sql_client.get_publication(publication_id).await.unwrap();
+        assert_eq!(publication.s3key, Some(legacy_key));
+    }
+
+    #[sqlx::test]
+    async fn test_revoke_user_sessions_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+        let uri = format!("/admin/users/{}/revoke-sessions", user_privy_id);
+
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Issued before the revocation
+        let claims = PrivyClaims {
+            iat: chrono::Utc::now().timestamp() as u64 - 60,
+            ..test_claims(&user_privy_id)
+        };
+
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["privy_id"], user_privy_id);
+        assert_eq!(body["expires_in_seconds"], 3600);
+
+        let req = test::TestRequest::post().uri("/auth/logout").to_request();
+        req.extensions_mut().insert(claims);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "SESSION_REVOKED");
+
+        // Tokens from a later sign-in are accepted
+        let claims = PrivyClaims {
+            iat: chrono::Utc::now().timestamp() as u64 + 1,
+            ..test_claims(&user_privy_id)
+        };
+        let req = test::TestRequest::post().uri("/auth/logout").to_request();
+        req.extensions_mut().insert(claims);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[sqlx::test]
+    async fn test_tasks_api(pool: PgPool) {
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let app_state = create_test_app_state(pool.clone()).await;
+        let supervisor = app_state.supervisor.clone();
+        assert!(
+            supervisor.spawn("indexer", Duration::from_secs(60), || async {
+                Err("Indexer unreachable".to_string())
+            })
+        );
+        let app = test::init_service(create_test_app_with_state(app_state)).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        // The task runs on the Tokio runtime's threads
+        for _ in 0..100 {
+            if supervisor.statuses()[0].runs > 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let req = test::TestRequest::get().uri("/admin/tasks").to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get().uri("/admin/tasks").to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body[0]["name"], "indexer");
+        assert_eq!(body[0]["runs"], 1);
+        assert_eq!(body[0]["failures"], 1);
+        assert_eq!(body[0]["consecutive_failures"], 1);
+        assert_eq!(body[0]["last_error"], "Indexer unreachable");
+        assert!(body[0]["last_run_at"].is_string());
+    }
+
+    #[sqlx::test]
+    async fn test_internal_token_api(pool: PgPool) {
+        let mut app_state = create_test_app_state(pool).await;
+        app_state.internal_tokens = Arc::new([
+            internal_token("cleanup", "cleanup-secret", &["admin"]),
+            internal_token("indexer", "indexer-secret", &["indexer"]),
+        ]);
+        let app = test::init_service(create_test_app_with_state(app_state)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/admin/storage/usage")
+            .insert_header(("X-Internal-Token", "cleanup-secret"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/admin/storage/usage")
+            .insert_header(("X-Internal-Token", "indexer-secret"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri("/admin/storage/usage")
+            .insert_header(("X-Internal-Token", "not-a-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Without an internal token, admin endpoints fall back to Privy authentication
+        let req = test::TestRequest::get()
+            .uri("/admin/storage/usage")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "unauthorized");
+    }
+
+    #[sqlx::test]
+    async fn test_audit_log_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        for (actor, entity_type, entity_id) in [
+            (&user_privy_id, "publications", "1"),
+            (&user_privy_id, "publications", "2"),
+            (&user_privy_id, "authors", "3"),
+            (&admin_privy_id, "publications", "1"),
+        ] {
+            sql_client
+                .create_audit_log_entry(&NewAuditLogEntry {
+                    actor: actor.clone(),
+                    method: "PUT".to_string(),
+                    path: format!("/{}/{}", entity_type, entity_id),
+                    entity_type: entity_type.to_string(),
+                    entity_id: Some(entity_id.to_string()),
+                    status: 200,
+                    request_id: None,
+                    impersonator: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/admin/audit-log")
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/admin/audit-log?actor={}&entity=publications&limit=1",
+                user_privy_id
+            ))
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["limit"], 1);
+        assert_eq!(body["total_pages"], 2);
+        let entries = body["items"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["actor"], user_privy_id.as_str());
+        assert_eq!(entries[0]["entity_type"], "publications");
+
+        let req = test::TestRequest::get()
+            .uri("/admin/audit-log?entity=publications&entity_id=1")
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["total"], 2);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::collections::HashSet;
+
+    use chrono::{TimeDelta, Utc};
+    use uuid::Uuid;
+
+    use super::super::{canonical_publication_key, find_orphans, storage_directory_id};
+    use crate::db::s3::client::S3ObjectInfo;
+
+    fn object(key: String, age: TimeDelta) -> S3ObjectInfo {
+        S3ObjectInfo {
+            key,
+            size: 100,
+            last_modified: Some(Utc::now() - age),
+            etag: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_canonical_publication_key() {
+        let id = Uuid::new_v4();
+
+        assert_eq!(
+            canonical_publication_key(id, &format!("publications/{}/paper.pdf", Uuid::new_v4())),
+            format!("publications/{}/paper.pdf", id)
+        );
+        assert_eq!(
+            canonical_publication_key(id, "paper.pdf"),
+            format!("publications/{}/paper.pdf", id)
+        );
+    }
+
+    #[test]
+    fn test_storage_directory_id() {
+        let id = Uuid::new_v4();
+
+        assert_eq!(
+            storage_directory_id(&format!("publications/{}/paper.pdf", id)),
+            Some(id)
+        );
+        assert_eq!(
+            storage_directory_id(&format!("publications/{}/versions/old.pdf", id)),
+            Some(id)
+        );
+        assert_eq!(
+            storage_directory_id("publications/not-a-uuid/paper.pdf"),
+            None
+        );
+        assert_eq!(storage_directory_id(&format!("publications/{}", id)), None);
+        assert_eq!(storage_directory_id(&format!("avatars/{}/a.png", id)), None);
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let referenced_id = Uuid::new_v4();
+        let orphaned_id = Uuid::new_v4();
+        let referenced = HashSet::from([referenced_id]);
+        let cutoff = Utc::now() - TimeDelta::hours(24);
+
+        let files = vec![
+            object(
+                format!("publications/{}/paper.pdf", referenced_id),
+                TimeDelta::days(3),
+            ),
+            object(
+                format!("publications/{}/paper.pdf", orphaned_id),
+                TimeDelta::days(3),
+            ),
+            // Possibly an upload whose publication is still being created
+            object(
+                format!("publications/{}/recent.pdf", orphaned_id),
+                TimeDelta::hours(1),
+            ),
+            // Not laid out by this backend
+            object(
+                "publications/manual/paper.pdf".to_string(),
+                TimeDelta::days(3),
+            ),
+            S3ObjectInfo {
+                last_modified: None,
+                ..object(
+                    format!("publications/{}/undated.pdf", orphaned_id),
+                    TimeDelta::zero(),
+                )
+            },
+        ];
+
+        let orphans = find_orphans(&files, &referenced, cutoff);
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(
+            orphans[0].key,
+            format!("publications/{}/paper.pdf", orphaned_id)
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::future::{Ready, ready};
+
+use actix_web::{
+    Error, HttpMessage, HttpRequest,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::Method,
+    web,
+};
+use futures_util::future::LocalBoxFuture;
+use tracing::Instrument;
+
+use crate::{
+    AppState,
+    api::request_id::RequestId,
+    auth::{InternalCaller, PrivyClaims, impersonation::Impersonation},
+    db::sql::{AuditLogOperations, models::NewAuditLogEntry},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Middleware recording the mutations of authenticated callers in the audit log.
+///
+/// Entries are written by a background task once the response is produced, so that neither the
+/// latency nor the failure of the write affects the response, and that shutdown waits for. Only
+/// the method, path, matched entity and status are recorded, along with the admin impersonating
+/// the caller if any: never the query string, headers or body, which may carry tokens or file
+/// contents.
+pub struct AuditLog;
+
+impl<S, B> Transform<S, ServiceRequest> for AuditLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AuditLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuditLogMiddleware { service }))
+    }
+}
+
+pub struct AuditLogMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for AuditLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let audited = !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            if audited {
+                record(res.request(), res.status().as_u16());
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Spawns the write of the entry of `req`, if it was made by an authenticated caller on a route.
+fn record(req: &HttpRequest, status: u16) {
+    let Some(entry) = audit_entry(req, status) else {
+        return;
+    };
+    let Some(data) = req.app_data::<web::Data<AppState>>() else {
+        return;
+    };
+
+    let sql_client = data.sql_client.clone();
+    data.background_tasks
+        .spawn("audit log entry", move |cancellation| {
+            async move {
+                tokio::select! {
+                    result = sql_client.create_audit_log_entry(&entry) => {
+                        if let Err(err) = result {
+                            tracing::error!(
+                                "Error recording audit log entry of {} {} by {}: {}",
+                                entry.method,
+                                entry.path,
+                                entry.actor,
+                                err
+                            );
+                        }
+                    }
+                    _ = cancellation.cancelled() => {
+                        tracing::warn!(
+                            "Shut down before recording audit log entry of {} {} by {}",
+                            entry.method,
+                            entry.path,
+                            entry.actor
+                        );
+                    }
+                }
+            }
+            .in_current_span()
+        });
+}
+
+/// Returns the entry recording `req`, or `None` if its caller is anonymous or no route matched.
+fn audit_entry(req: &HttpRequest, status: u16) -> Option<NewAuditLogEntry> {
+    let actor = {
+        let extensions = req.extensions();
+        match (
+            extensions.get::<PrivyClaims>(),
+            extensions.get::<InternalCaller>(),
+        ) {
+            (Some(claims), _) => claims.sub.clone(),
+            (None, Some(caller)) => format!("internal:{}", caller.name),
+            (None, None) => return None,
+        }
+    };
+
+    let pattern = req.match_pattern()?;
+    let (entity_type, entity_id) = matched_entity(&pattern, |name| {
+        req.match_info().get(name).map(str::to_string)
+    })?;
+
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|request_id| request_id.0.clone());
+    let impersonator = req
+        .extensions()
+        .get::<Impersonation>()
+        .map(|impersonation| impersonation.admin.clone());
+
+    Some(NewAuditLogEntry {
+        actor,
+        method: req.method().to_string(),
+        path: req.path().to_string(),
+        entity_type,
+        entity_id,
+        status: status as i16,
+        request_id,
+        impersonator,
+    })
+}
+
+/// Returns the type and id of the entity a route pattern such as
+/// `/publications/{publication_id}/files` acts upon: the segment preceding its first parameter,
+/// and that parameter's value as given by `param`. Patterns without parameters only name a type,
+/// their first segment.
+fn matched_entity(
+    pattern: &str,
+    param: impl Fn(&str) -> Option<String>,
+) -> Option<(String, Option<String>)> {
+    let segments: Vec<&str> = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let Some(name) = segment
+            .strip_prefix('{')
+            .and_then(|segment| segment.strip_suffix('}'))
+        else {
+            continue;
+        };
+
+        // Parameters may carry a regex, as in `{tail:.*}`
+        let name = name.split(':').next().unwrap_or(name);
+        let entity_type = i.checked_sub(1).map(|previous| segments[previous])?;
+        return Some((entity_type.to_string(), param(name)));
+    }
+
+    segments.first().map(|segment| (segment.to_string(), None))
+}
@@ -0,0 +1,146 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{http::StatusCode, test};
+    use sqlx::PgPool;
+
+    use crate::{
+        api::tests::{
+            TEST_USER_HEADER, create_test_app, create_test_publication, create_test_user,
+            tokio_runtime,
+        },
+        common::pagination::PageQuery,
+        db::sql::{AuditLogOperations, SqlClient, models::AuditLogEntry},
+    };
+
+    /// Waits for the spawned writes to record at least `count` entries of `actor`.
+    async fn wait_for_entries(
+        sql_client: &SqlClient,
+        actor: &str,
+        count: usize,
+    ) -> Vec<AuditLogEntry> {
+        for _ in 0..100 {
+            let entries = sql_client
+                .list_audit_log(Some(actor), None, None, PageQuery::default())
+                .await
+                .unwrap();
+            if entries.len() >= count {
+                return entries;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        panic!("Expected {} audit log entries of {}", count, actor);
+    }
+
+    #[sqlx::test]
+    async fn test_audit_log_records_mutations_api(pool: PgPool) {
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let publication_id = create_test_publication(&sql_client, user_privy_id.clone()).await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", publication_id))
+            .insert_header((TEST_USER_HEADER, user_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let boundary = "auditboundary";
+        let body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nAudited title\r\n--{boundary}--\r\n"
+        );
+        let req = test::TestRequest::put()
+            .uri(&format!("/publications/{}?token=secret", publication_id))
+            .insert_header((
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            ))
+            .insert_header((TEST_USER_HEADER, user_privy_id.as_str()))
+            .insert_header(("X-Request-Id", "req-123"))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Only the update is recorded, without its query string
+        let entries = wait_for_entries(&sql_client, &user_privy_id, 1).await;
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.method, "PUT");
+        assert_eq!(entry.path, format!("/publications/{}", publication_id));
+        assert_eq!(entry.entity_type, "publications");
+        assert_eq!(entry.entity_id, Some(publication_id.to_string()));
+        assert_eq!(entry.status, 200);
+        assert_eq!(entry.request_id.as_deref(), Some("req-123"));
+    }
+
+    #[sqlx::test]
+    async fn test_audit_log_records_rejected_mutations_api(pool: PgPool) {
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let owner_privy_id = create_test_user(&sql_client).await;
+        let other_privy_id = create_test_user(&sql_client).await;
+        let publication_id = create_test_publication(&sql_client, owner_privy_id).await;
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/publications/{}", publication_id))
+            .insert_header((TEST_USER_HEADER, other_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let entries = wait_for_entries(&sql_client, &other_privy_id, 1).await;
+        assert_eq!(entries[0].method, "DELETE");
+        assert_eq!(entries[0].status, 403);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::super::matched_entity;
+
+    fn param(name: &str) -> Option<String> {
+        Some(format!("<{name}>"))
+    }
+
+    #[test]
+    fn test_matched_entity() {
+        assert_eq!(
+            matched_entity("/publications/{publication_id}", param),
+            Some((
+                "publications".to_string(),
+                Some("<publication_id>".to_string())
+            ))
+        );
+        assert_eq!(
+            matched_entity("/publications/{publication_id}/files/{file_id}", param),
+            Some((
+                "publications".to_string(),
+                Some("<publication_id>".to_string())
+            ))
+        );
+        assert_eq!(
+            matched_entity("/admin/users/{privy_id}/revoke-sessions", param),
+            Some(("users".to_string(), Some("<privy_id>".to_string())))
+        );
+        assert_eq!(
+            matched_entity("/files/{tail:.*}", param),
+            Some(("files".to_string(), Some("<tail>".to_string())))
+        );
+        assert_eq!(
+            matched_entity("/publications/upload-intent", param),
+            Some(("publications".to_string(), None))
+        );
+        assert_eq!(matched_entity("/{id}", param), None);
+        assert_eq!(matched_entity("/", param), None);
+    }
+}
@@ -0,0 +1,99 @@
+use actix_web::{HttpRequest, HttpResponse, post, web};
+use utoipa::OpenApi;
+
+use crate::{
+    AppState,
+    api::error::{ApiError, ErrorResponse},
+    auth::{AuthenticatedUser, Privy, impersonation::refuse_impersonation, session::session_token},
+};
+
+pub fn config(conf: &mut web::ServiceConfig) {
+    let scope = web::scope("/auth").service(logout).service(refresh_session);
+    conf.service(scope);
+}
+
+#[cfg(test)]
+mod tests;
+
+#[derive(OpenApi)]
+#[openapi(paths(logout, refresh_session))]
+pub struct AuthApi;
+
+/// Revokes the session of the caller's token, which is rejected from then on, and closes every
+/// cookie session of the caller.
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Session revoked, and the session cookie removed if any"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Impersonated request", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/logout", wrap = "Privy")]
+async fn logout(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    refuse_impersonation(&req)?;
+
+    data.session_revocation
+        .revoke_session(&user.claims)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error revoking session of {}: {}", user.privy_id, err);
+            ApiError::internal("Failed to revoke session")
+        })?;
+
+    data.sessions
+        .close_all(&user.privy_id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error closing sessions of {}: {}", user.privy_id, err);
+            ApiError::internal("Failed to close sessions")
+        })?;
+
+    let mut response = HttpResponse::NoContent();
+    if let Some(session_token) = session_token(&req) {
+        if let Err(err) = data.sessions.close(&session_token).await {
+            tracing::warn!("Error closing session of {}: {}", user.privy_id, err);
+        }
+        response.cookie(data.sessions.removal_cookie());
+    }
+
+    Ok(response.finish())
+}
+
+/// Opens a new cookie session for the caller, replacing the one they authenticated with if any,
+/// so that active users stay signed in.
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Session opened, its cookie is set"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Impersonated request", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/session/refresh", wrap = "Privy")]
+async fn refresh_session(
+    req: HttpRequest,
+    user: AuthenticatedUser,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    refuse_impersonation(&req)?;
+
+    let token = data.sessions.open(&user.claims).await.map_err(|err| {
+        tracing::error!("Error opening session of {}: {}", user.privy_id, err);
+        ApiError::internal("Failed to refresh session")
+    })?;
+
+    if let Some(session_token) = session_token(&req)
+        && let Err(err) = data.sessions.close(&session_token).await
+    {
+        tracing::warn!("Error closing session of {}: {}", user.privy_id, err);
+    }
+
+    Ok(HttpResponse::NoContent()
+        .cookie(data.sessions.cookie(&token))
+        .finish())
+}
@@ -0,0 +1,365 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{HttpMessage, cookie::Cookie, dev::ServiceResponse, http::StatusCode, test};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::{
+        api::tests::{
+            TEST_USER_HEADER, create_test_admin, create_test_app, create_test_publication,
+            create_test_user, test_claims, tokio_runtime,
+        },
+        auth::{
+            PrivyClaims,
+            impersonation::{IMPERSONATE_HEADER, IMPERSONATED_HEADER},
+            session::SESSION_COOKIE,
+        },
+        common::pagination::PageQuery,
+        db::sql::{AuditLogOperations, SqlClient},
+    };
+
+    /// Returns the session cookie set by `resp`.
+    fn session_cookie(resp: &ServiceResponse) -> Cookie<'static> {
+        resp.response()
+            .cookies()
+            .find(|cookie| cookie.name() == SESSION_COOKIE)
+            .expect("Expected a session cookie")
+            .into_owned()
+    }
+
+    fn current_user_request(cookie: &Cookie<'static>) -> test::TestRequest {
+        test::TestRequest::get()
+            .uri("/users/me")
+            .cookie(cookie.clone())
+    }
+
+    /// Builds a request to a protected route rejecting the upload as invalid, which is only
+    /// reached by requests passing authentication.
+    fn protected_request() -> test::TestRequest {
+        test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({
+                "file_name": "paper.txt",
+                "file_size": 200,
+                "content_type": "text/plain"
+            }))
+    }
+
+    #[sqlx::test]
+    async fn test_logout_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let claims = test_claims(&user_privy_id);
+
+        let req = protected_request().to_request();
+        req.extensions_mut().insert(claims.clone());
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post().uri("/auth/logout").to_request();
+        req.extensions_mut().insert(claims.clone());
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        let req = protected_request().to_request();
+        req.extensions_mut().insert(claims.clone());
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "SESSION_REVOKED");
+
+        // Other sessions of the user are left alone
+        let req = protected_request().to_request();
+        req.extensions_mut().insert(test_claims(&user_privy_id));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Logging out requires a session
+        let req = test::TestRequest::post().uri("/auth/logout").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_logout_closes_cookie_sessions_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+        let privy_id = "privy_test_logout_everywhere_user";
+        let sign_in = || {
+            test::TestRequest::post()
+                .uri("/users/privy/sign-in")
+                .insert_header((TEST_USER_HEADER, privy_id))
+                .to_request()
+        };
+
+        let cookie = session_cookie(&test::call_service(&app, sign_in()).await);
+        let other_cookie = session_cookie(&test::call_service(&app, sign_in()).await);
+
+        // Logging out with a token closes the cookie sessions too
+        let req = test::TestRequest::post()
+            .uri("/auth/logout")
+            .insert_header((TEST_USER_HEADER, privy_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        for cookie in [&cookie, &other_cookie] {
+            let resp = test::call_service(&app, current_user_request(cookie).to_request()).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+            let req = test::TestRequest::post()
+                .uri("/auth/session/refresh")
+                .cookie(cookie.clone())
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        // Signing in again after logging out, a millisecond later at least, opens a session
+        std::thread::sleep(Duration::from_millis(2));
+        let cookie = session_cookie(&test::call_service(&app, sign_in()).await);
+        let resp = test::call_service(&app, current_user_request(&cookie).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn test_logout_expiry_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let claims = PrivyClaims {
+            exp: chrono::Utc::now().timestamp() as u64 + 2,
+            ..test_claims(&user_privy_id)
+        };
+
+        let req = test::TestRequest::post().uri("/auth/logout").to_request();
+        req.extensions_mut().insert(claims.clone());
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        let req = protected_request().to_request();
+        req.extensions_mut().insert(claims.clone());
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "SESSION_REVOKED");
+
+        // The denylist entry lives as long as the token
+        std::thread::sleep(Duration::from_millis(2100));
+        let req = protected_request().to_request();
+        req.extensions_mut().insert(claims.clone());
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_session_cookie_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+        let privy_id = "privy_test_cookie_user";
+
+        let req = test::TestRequest::post()
+            .uri("/users/privy/sign-in")
+            .insert_header((TEST_USER_HEADER, privy_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let cookie = session_cookie(&resp);
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.secure(), Some(true));
+
+        // The cookie alone authenticates the user
+        let resp = test::call_service(&app, current_user_request(&cookie).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["user"]["privy_id"], privy_id);
+
+        let req = test::TestRequest::get().uri("/users/me").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let forged = Cookie::new(SESSION_COOKIE, "forged");
+        let resp = test::call_service(&app, current_user_request(&forged).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Signing in again opens another session
+        let req = test::TestRequest::post()
+            .uri("/users/privy/sign-in")
+            .insert_header((TEST_USER_HEADER, privy_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_ne!(session_cookie(&resp).value(), cookie.value());
+    }
+
+    #[sqlx::test]
+    async fn test_session_refresh_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+
+        let req = test::TestRequest::post()
+            .uri("/users/privy/sign-in")
+            .insert_header((TEST_USER_HEADER, "privy_test_refresh_user"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let cookie = session_cookie(&resp);
+
+        let req = test::TestRequest::post()
+            .uri("/auth/session/refresh")
+            .cookie(cookie.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let refreshed = session_cookie(&resp);
+        assert_ne!(refreshed.value(), cookie.value());
+
+        // The refreshed session replaces the previous one
+        let resp = test::call_service(&app, current_user_request(&refreshed).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = test::call_service(&app, current_user_request(&cookie).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/auth/session/refresh")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_session_logout_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+
+        let req = test::TestRequest::post()
+            .uri("/users/privy/sign-in")
+            .insert_header((TEST_USER_HEADER, "privy_test_logout_user"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let cookie = session_cookie(&resp);
+
+        let req = test::TestRequest::post()
+            .uri("/auth/logout")
+            .cookie(cookie.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let removal = session_cookie(&resp);
+        assert_eq!(removal.value(), "");
+        assert_eq!(
+            removal.max_age(),
+            Some(actix_web::cookie::time::Duration::ZERO)
+        );
+
+        let resp = test::call_service(&app, current_user_request(&cookie).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_impersonation_api(pool: PgPool) {
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let admin_privy_id = create_test_admin(&sql_client).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let publication_id = create_test_publication(&sql_client, user_privy_id.clone()).await;
+
+        let req = test::TestRequest::get()
+            .uri("/users/me")
+            .insert_header((TEST_USER_HEADER, admin_privy_id.as_str()))
+            .insert_header((IMPERSONATE_HEADER, user_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(IMPERSONATED_HEADER).unwrap(), "true");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["user"]["privy_id"], user_privy_id.as_str());
+
+        // Mutations are made as the user, and audited with both identities
+        let req = test::TestRequest::delete()
+            .uri(&format!("/publications/{}", publication_id))
+            .insert_header((TEST_USER_HEADER, admin_privy_id.as_str()))
+            .insert_header((IMPERSONATE_HEADER, user_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        let mut entries = Vec::new();
+        for _ in 0..100 {
+            entries = sql_client
+                .list_audit_log(Some(&user_privy_id), None, None, PageQuery::default())
+                .await
+                .unwrap();
+            if !entries.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].method, "DELETE");
+        assert_eq!(
+            entries[0].impersonator.as_deref(),
+            Some(admin_privy_id.as_str())
+        );
+
+        // Requests without the header are the admin's own
+        let req = test::TestRequest::get()
+            .uri("/users/me")
+            .insert_header((TEST_USER_HEADER, admin_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().get(IMPERSONATED_HEADER).is_none());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["user"]["privy_id"], admin_privy_id.as_str());
+
+        let req = test::TestRequest::get()
+            .uri("/users/me")
+            .insert_header((TEST_USER_HEADER, admin_privy_id.as_str()))
+            .insert_header((IMPERSONATE_HEADER, "privy_no_such_user"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_impersonation_requires_admin_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let other_privy_id = create_test_user(&sql_client).await;
+
+        let req = test::TestRequest::get()
+            .uri("/users/me")
+            .insert_header((TEST_USER_HEADER, user_privy_id.as_str()))
+            .insert_header((IMPERSONATE_HEADER, other_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(resp.headers().get(IMPERSONATED_HEADER).is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_impersonation_refused_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let admin_privy_id = create_test_admin(&sql_client).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+
+        // Endpoints opening or closing the user's sessions refuse impersonated requests
+        for uri in [
+            "/users/privy/sign-in",
+            "/auth/session/refresh",
+            "/auth/logout",
+        ] {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .insert_header((TEST_USER_HEADER, admin_privy_id.as_str()))
+                .insert_header((IMPERSONATE_HEADER, user_privy_id.as_str()))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN, "{uri}");
+            assert!(resp.response().cookies().next().is_none());
+        }
+    }
+}
@@ -1,13 +1,19 @@
-use actix_web::{
-    HttpResponse, delete,
-    error::{ErrorConflict, ErrorInternalServerError, ErrorNotFound},
-    get, post, put, web,
-};
+use actix_web::{HttpRequest, HttpResponse, delete, get, post, put, web};
 use serde::Deserialize;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
 use crate::{
     AppState,
-    db::sql::{AuthorOperations, PrivyId, models::NewAuthor},
+    api::{
+        error::{ApiError, ErrorResponse},
+        response::MessageResponse,
+    },
+    auth::{Privy, require_self_or_admin},
+    common::pagination::{PageQuery, Paginated},
+    db::sql::{
+        AuthorOperations, PrivyId,
+        models::{Author, NewAuthor},
+    },
 };
 
 pub fn config(conf: &mut web::ServiceConfig) {
@@ -21,15 +27,38 @@ pub fn config(conf: &mut web::ServiceConfig) {
     conf.service(scope);
 }
 
-#[derive(Deserialize)]
+#[cfg(test)]
+mod tests;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_author,
+    get_author,
+    update_author,
+    delete_author,
+    list_authors,
+    search_authors
+))]
+pub struct AuthorsApi;
+
+#[derive(Deserialize, ToSchema)]
 pub struct CreateAuthorRequest {
+    #[schema(value_type = String)]
     privy_id: PrivyId,
     name: String,
     email: Option<String>,
     affiliation: Option<String>,
 }
 
-#[post("/create")]
+#[utoipa::path(
+    responses(
+        (status = 200, body = Author),
+        (status = 401, body = ErrorResponse),
+        (status = 409, description = "Author or email already exists", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/create", wrap = "Privy")]
 async fn create_author(
     request: web::Json<CreateAuthorRequest>,
     data: web::Data<AppState>,
@@ -39,16 +68,16 @@ async fn create_author(
             .sql_client
             .author_email_exists(email)
             .await
-            .map_err(|_| ErrorInternalServerError("Internal server error"))?;
+            .map_err(|_| ApiError::internal("Internal server error"))?;
 
         if email_exists {
-            return Err(ErrorConflict("Author with that email already exists"));
+            return Err(ApiError::conflict("Author with that email already exists").into());
         }
     }
 
     let author_by_privy_id = data.sql_client.get_author(&request.privy_id).await;
     if author_by_privy_id.is_ok() {
-        return Err(ErrorConflict("Author with that privy_id already exists"));
+        return Err(ApiError::conflict("Author with that privy_id already exists").into());
     }
 
     let new_author = NewAuthor {
@@ -64,48 +93,65 @@ async fn create_author(
         .await
         .map_err(|err| {
             tracing::error!("Error creating author: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     Ok(HttpResponse::Ok().json(author))
 }
 
+#[utoipa::path(responses(
+    (status = 200, body = Author),
+    (status = 404, description = "Author not found", body = ErrorResponse)
+))]
 #[get("/{privy_id}")]
 async fn get_author(
-    privy_id: web::Path<PrivyId>,
+    privy_id: web::Path<String>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let author = data.sql_client.get_author(&privy_id).await.map_err(|err| {
         tracing::error!("Error retrieving author: {}", err);
         match err {
-            sqlx::Error::RowNotFound => ErrorNotFound("Author not found"),
-            _ => ErrorInternalServerError("Internal server error"),
+            sqlx::Error::RowNotFound => ApiError::not_found("Author not found"),
+            _ => ApiError::internal("Internal server error"),
         }
     })?;
 
     Ok(HttpResponse::Ok().json(author))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateAuthorRequest {
     name: Option<String>,
     email: Option<String>,
     affiliation: Option<String>,
 }
 
-#[put("/{privy_id}")]
+#[utoipa::path(
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Neither the author nor an admin", body = ErrorResponse),
+        (status = 404, description = "Author not found", body = ErrorResponse),
+        (status = 409, description = "Email used by another author", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[put("/{privy_id}", wrap = "Privy")]
 async fn update_author(
-    privy_id: web::Path<PrivyId>,
+    req: HttpRequest,
+    privy_id: web::Path<String>,
     request: web::Json<UpdateAuthorRequest>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    require_self_or_admin(&req, &data.sql_client, &privy_id).await?;
+
     // Check if new email already exists (if email is being updated)
     if let Some(email) = &request.email {
         let email_exists = data
             .sql_client
             .author_email_exists(email)
             .await
-            .map_err(|_| ErrorInternalServerError("Internal server error"))?;
+            .map_err(|_| ApiError::internal("Internal server error"))?;
 
         if email_exists {
             // Check if it's the same author
@@ -113,9 +159,10 @@ async fn update_author(
             match existing_author {
                 Ok(existing) => {
                     if existing.privy_id != *privy_id {
-                        return Err(ErrorConflict(
+                        return Err(ApiError::conflict(
                             "Another author with that email already exists",
-                        ));
+                        )
+                        .into());
                     }
                 }
                 Err(sqlx::Error::RowNotFound) => {
@@ -123,7 +170,7 @@ async fn update_author(
                 }
                 Err(err) => {
                     tracing::error!("Error checking author email: {}", err);
-                    return Err(ErrorInternalServerError("Internal server error"));
+                    return Err(ApiError::internal("Internal server error").into());
                 }
             }
         }
@@ -140,93 +187,97 @@ async fn update_author(
         .await
         .map_err(|err| {
             tracing::error!("Error updating author: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("Author not found"));
+        return Err(ApiError::not_found("Author not found").into());
     }
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Author updated successfully"
-    })))
+    Ok(HttpResponse::Ok().json(MessageResponse::success("Author updated successfully")))
 }
 
-#[delete("/{privy_id}")]
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Author deleted"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Neither the author nor an admin", body = ErrorResponse),
+        (status = 404, description = "Author not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[delete("/{privy_id}", wrap = "Privy")]
 async fn delete_author(
-    privy_id: web::Path<PrivyId>,
+    req: HttpRequest,
+    privy_id: web::Path<String>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    require_self_or_admin(&req, &data.sql_client, &privy_id).await?;
+
     let result = data
         .sql_client
         .delete_author(&privy_id)
         .await
         .map_err(|err| {
             tracing::error!("Error deleting author: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("Author not found"));
+        return Err(ApiError::not_found("Author not found").into());
     }
 
     Ok(HttpResponse::NoContent().finish())
 }
 
+#[utoipa::path(
+    params(PageQuery),
+    responses(
+        (status = 200, body = Paginated<Author>),
+        (status = 400, description = "Invalid page", body = ErrorResponse)
+    )
+)]
 #[get("/list")]
 async fn list_authors(
     data: web::Data<AppState>,
-    query: web::Query<ListAuthorsQuery>,
+    page: web::Query<PageQuery>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let authors = data
-        .sql_client
-        .list_authors(query.page, query.limit)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error listing authors: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
-
-    let total_count = data.sql_client.count_authors().await.map_err(|err| {
-        tracing::error!("Error counting authors: {}", err);
-        ErrorInternalServerError("Internal server error")
+    let (authors, total_count) = data.sql_client.list_authors(*page).await.map_err(|err| {
+        tracing::error!("Error listing authors: {}", err);
+        ApiError::internal("Internal server error")
     })?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "authors": authors,
-        "total": total_count,
-        "page": query.page.unwrap_or(1),
-        "limit": query.limit.unwrap_or(20)
-    })))
-}
-
-#[derive(Deserialize)]
-struct ListAuthorsQuery {
-    page: Option<i64>,
-    limit: Option<i64>,
+    Ok(HttpResponse::Ok().json(Paginated::new(authors, total_count, *page)))
 }
 
+/// Lists the authors whose name contains the searched one.
+#[utoipa::path(
+    params(SearchAuthorsQuery, PageQuery),
+    responses(
+        (status = 200, body = Vec<Author>),
+        (status = 400, description = "Invalid page", body = ErrorResponse)
+    )
+)]
 #[get("/search")]
 async fn search_authors(
     data: web::Data<AppState>,
     query: web::Query<SearchAuthorsQuery>,
+    page: web::Query<PageQuery>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let authors = data
         .sql_client
-        .search_authors_by_name(&query.name, query.page, query.limit)
+        .search_authors_by_name(&query.name, *page)
         .await
         .map_err(|err| {
             tracing::error!("Error searching authors: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     Ok(HttpResponse::Ok().json(authors))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchAuthorsQuery {
     name: String,
-    page: Option<i64>,
-    limit: Option<i64>,
 }
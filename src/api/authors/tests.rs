@@ -0,0 +1,59 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::{
+        api::tests::{
+            authenticate, create_test_admin, create_test_app, create_test_author, create_test_user,
+        },
+        db::sql::{AuthorOperations, SqlClient},
+    };
+
+    #[sqlx::test]
+    async fn test_update_and_delete_author_authorization_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let author_privy_id = create_test_user(&sql_client).await;
+        create_test_author(&sql_client, &author_privy_id).await;
+        let other_privy_id = create_test_user(&sql_client).await;
+        let admin_privy_id = create_test_admin(&sql_client).await;
+
+        let update = |caller: &str, name: &str| {
+            let req = test::TestRequest::put()
+                .uri(&format!("/authors/{}", author_privy_id))
+                .set_json(json!({ "name": name }))
+                .to_request();
+            authenticate(&req, caller);
+            req
+        };
+
+        let resp = test::call_service(&app, update(&other_privy_id, "Impostor")).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Authors may update themselves, and admins anyone
+        let resp = test::call_service(&app, update(&author_privy_id, "Ada Okafor")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = test::call_service(&app, update(&admin_privy_id, "Ada N. Okafor")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let author = sql_client.get_author(&author_privy_id).await.unwrap();
+        assert_eq!(author.name, "Ada N. Okafor");
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/authors/{}", author_privy_id))
+            .to_request();
+        authenticate(&req, &other_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/authors/{}", author_privy_id))
+            .to_request();
+        authenticate(&req, &author_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(sql_client.get_author(&author_privy_id).await.is_err());
+    }
+}
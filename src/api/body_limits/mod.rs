@@ -0,0 +1,77 @@
+use actix_multipart::{
+    MultipartError,
+    form::{
+        MultipartFormConfig,
+        tempfile::{TempFileConfig, TempFileError},
+    },
+};
+use actix_web::{
+    error::{JsonPayloadError, PayloadError},
+    web,
+};
+use serde_json::json;
+
+use crate::{api::error::ApiError, config::BodyLimitsConfig};
+
+#[cfg(test)]
+mod tests;
+
+/// Text fields of multipart forms read into memory, which count towards the total limit as well.
+/// Matches the default of [MultipartFormConfig].
+const MULTIPART_MEMORY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Registers the configurations of the body extractors enforcing `limits`, so that oversized
+/// bodies are rejected with a 413 naming the limit.
+///
+/// Raw bodies, read as bytes or strings, share the JSON limit.
+pub fn config(cfg: &mut web::ServiceConfig, limits: &BodyLimitsConfig) {
+    let json_limit = limits.max_json_payload_bytes;
+    let multipart_limit = limits.max_multipart_total_bytes;
+
+    cfg.app_data(
+        web::JsonConfig::default()
+            .limit(json_limit)
+            .error_handler(move |err, _| match err {
+                JsonPayloadError::Overflow { .. }
+                | JsonPayloadError::OverflowKnownLength { .. } => ApiError::payload_too_large(
+                    format!("JSON body exceeds the limit of {json_limit} bytes"),
+                )
+                .with_details(json!({ "limit": json_limit }))
+                .into(),
+                err => err.into(),
+            }),
+    )
+    .app_data(web::PayloadConfig::default().limit(json_limit))
+    .app_data(
+        MultipartFormConfig::default()
+            .total_limit(multipart_limit)
+            .memory_limit(MULTIPART_MEMORY_LIMIT)
+            .error_handler(move |err, _| match err {
+                // Also raised when text fields exceed the memory limit
+                MultipartError::Payload(PayloadError::Overflow) => {
+                    ApiError::payload_too_large(format!(
+                        "Multipart body exceeds the limit of {multipart_limit} bytes, or of \
+                         {MULTIPART_MEMORY_LIMIT} bytes for text fields"
+                    ))
+                    .with_details(json!({
+                        "limit": multipart_limit,
+                        "memory_limit": MULTIPART_MEMORY_LIMIT,
+                    }))
+                    .into()
+                }
+                err => err.into(),
+            }),
+    );
+
+    let temp_files = TempFileConfig::default().error_handler(|err, _| match err {
+        TempFileError::FileIo(err) => {
+            tracing::error!("Error writing uploaded file: {}", err);
+            ApiError::internal("Failed to store the uploaded file").into()
+        }
+        err => err.into(),
+    });
+    cfg.app_data(match &limits.multipart_temp_dir {
+        Some(directory) => temp_files.directory(directory),
+        None => temp_files,
+    });
+}
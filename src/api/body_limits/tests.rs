@@ -0,0 +1,128 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use std::path::PathBuf;
+
+    use actix_multipart::form::{MultipartForm, tempfile::TempFile};
+    use actix_web::{
+        App, HttpResponse,
+        http::{StatusCode, header},
+        test::{TestRequest, call_service, init_service, read_body_json},
+        web,
+    };
+    use serde_json::{Value, json};
+
+    use crate::{api::body_limits::config, config::BodyLimitsConfig};
+
+    const BOUNDARY: &str = "publish3-boundary";
+
+    #[derive(MultipartForm)]
+    struct UploadForm {
+        file: TempFile,
+    }
+
+    async fn echo_json(body: web::Json<Value>) -> HttpResponse {
+        HttpResponse::Ok().json(body.into_inner())
+    }
+
+    /// Answers with the directory the uploaded file was written to.
+    async fn upload(MultipartForm(form): MultipartForm<UploadForm>) -> HttpResponse {
+        let directory = form.file.file.path().parent().unwrap().to_path_buf();
+        HttpResponse::Ok().json(json!({ "directory": directory }))
+    }
+
+    fn limits(multipart_temp_dir: Option<PathBuf>) -> BodyLimitsConfig {
+        BodyLimitsConfig {
+            max_json_payload_bytes: 64,
+            max_multipart_total_bytes: 1024,
+            multipart_temp_dir,
+        }
+    }
+
+    fn multipart_request(contents: &[u8]) -> TestRequest {
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; \
+             filename=\"paper.pdf\"\r\nContent-Type: application/pdf\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(contents);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        TestRequest::post()
+            .uri("/upload")
+            .insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            ))
+            .set_payload(body)
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_json_api() {
+        let limits = limits(None);
+        let app = init_service(
+            App::new()
+                .configure(|cfg| config(cfg, &limits))
+                .route("/echo", web::post().to(echo_json)),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/echo")
+            .set_json(json!({ "title": "Short" }))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .uri("/echo")
+            .set_json(json!({ "title": "x".repeat(100) }))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: Value = read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "payload_too_large");
+        assert_eq!(
+            body["error"]["message"],
+            "JSON body exceeds the limit of 64 bytes"
+        );
+        assert_eq!(body["error"]["details"]["limit"], 64);
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_multipart_api() {
+        let limits = limits(None);
+        let app = init_service(
+            App::new()
+                .configure(|cfg| config(cfg, &limits))
+                .route("/upload", web::post().to(upload)),
+        )
+        .await;
+
+        let resp = call_service(&app, multipart_request(&[b'%'; 2048]).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: Value = read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "payload_too_large");
+        assert_eq!(body["error"]["details"]["limit"], 1024);
+    }
+
+    #[actix_web::test]
+    async fn test_multipart_temp_dir_api() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let limits = limits(Some(temp_dir.path().to_path_buf()));
+        let app = init_service(
+            App::new()
+                .configure(|cfg| config(cfg, &limits))
+                .route("/upload", web::post().to(upload)),
+        )
+        .await;
+
+        let resp = call_service(&app, multipart_request(b"%PDF-1.7").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = read_body_json(resp).await;
+        assert_eq!(
+            PathBuf::from(body["directory"].as_str().unwrap()),
+            temp_dir.path()
+        );
+    }
+}
@@ -1,14 +1,21 @@
-use actix_web::{
-    HttpResponse, delete,
-    error::{ErrorBadRequest, ErrorConflict, ErrorInternalServerError, ErrorNotFound},
-    get, post, put, web,
-};
+use actix_web::{HttpResponse, delete, get, post, put, web};
 use serde::Deserialize;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
     AppState,
-    db::sql::{CitationOperations, models::NewCitation},
+    api::{
+        error::{ApiError, ErrorResponse},
+        publications::get_owned_publication,
+        response::MessageResponse,
+    },
+    auth::{AuthenticatedUser, Privy},
+    common::pagination::{PageQuery, Paginated},
+    db::sql::{
+        CitationOperations,
+        models::{Citation, NewCitation},
+    },
 };
 
 pub fn config(conf: &mut web::ServiceConfig) {
@@ -25,183 +32,181 @@ pub fn config(conf: &mut web::ServiceConfig) {
 #[cfg(test)]
 mod tests;
 
-#[derive(Deserialize)]
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_citation,
+    get_citation,
+    update_citation,
+    delete_citation,
+    list_citations,
+    get_citation_by_publications
+))]
+pub struct CitationsApi;
+
+#[derive(Deserialize, ToSchema)]
 pub struct CreateCitationRequest {
     citing_publication_id: Uuid,
     cited_publication_id: Uuid,
 }
 
-#[post("/create")]
+#[utoipa::path(
+    responses(
+        (status = 200, body = Citation),
+        (status = 400, description = "The publication cites itself or a missing publication", body = ErrorResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the citing publication", body = ErrorResponse),
+        (status = 404, description = "Citing publication not found", body = ErrorResponse),
+        (status = 409, description = "Citation already exists", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/create", wrap = "Privy")]
 async fn create_citation(
+    user: AuthenticatedUser,
     request: web::Json<CreateCitationRequest>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
-    // Check if citation already exists between these publications
-    let existing_citation = data
-        .sql_client
-        .get_citation_by_publications(request.citing_publication_id, request.cited_publication_id)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error checking existing citation: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
-
-    if existing_citation.is_some() {
-        return Err(ErrorConflict(
-            "Citation already exists between these publications",
-        ));
-    }
-
-    // Check that publications are not the same
+) -> Result<HttpResponse, ApiError> {
     if request.citing_publication_id == request.cited_publication_id {
-        return Err(ErrorBadRequest("A publication cannot cite itself"));
+        return Err(ApiError::validation("A publication cannot cite itself"));
     }
+    get_owned_publication(&data, &user, request.citing_publication_id).await?;
 
     let new_citation = NewCitation {
         citing_publication_id: request.citing_publication_id,
         cited_publication_id: request.cited_publication_id,
     };
 
+    // Duplicates are rejected by the unique constraint on the pair of publications
     let citation = data
         .sql_client
         .create_citation(&new_citation)
         .await
-        .map_err(|err| {
-            tracing::error!("Error creating citation: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
+        .map_err(ApiError::database("Citation"))?;
 
     Ok(HttpResponse::Ok().json(citation))
 }
 
+#[utoipa::path(responses(
+    (status = 200, body = Citation),
+    (status = 404, description = "Citation not found", body = ErrorResponse)
+))]
 #[get("/{citation_id}")]
 async fn get_citation(
     citation_id: web::Path<Uuid>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
+) -> Result<HttpResponse, ApiError> {
     let citation = data
         .sql_client
         .get_citation(*citation_id)
         .await
-        .map_err(|err| {
-            tracing::error!("Error retrieving citation: {}", err);
-            match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("Citation not found"),
-                _ => ErrorInternalServerError("Internal server error"),
-            }
-        })?;
+        .map_err(ApiError::database("Citation"))?;
 
     Ok(HttpResponse::Ok().json(citation))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateCitationRequest {
     // No fields to update for citations
 }
 
-#[put("/{citation_id}")]
+#[utoipa::path(
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the citing publication", body = ErrorResponse),
+        (status = 404, description = "Citation not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[put("/{citation_id}", wrap = "Privy")]
 async fn update_citation(
+    user: AuthenticatedUser,
     citation_id: web::Path<Uuid>,
     _request: web::Json<UpdateCitationRequest>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
+) -> Result<HttpResponse, ApiError> {
     // Check if citation exists
-    let _citation = data
+    let citation = data
         .sql_client
         .get_citation(*citation_id)
         .await
-        .map_err(|err| {
-            tracing::error!("Error retrieving citation: {}", err);
-            match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("Citation not found"),
-                _ => ErrorInternalServerError("Internal server error"),
-            }
-        })?;
+        .map_err(ApiError::database("Citation"))?;
+    get_owned_publication(&data, &user, citation.citing_publication_id).await?;
 
     // Citations have no fields to update, just return success
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Citation exists (no fields to update)"
-    })))
+    Ok(HttpResponse::Ok().json(MessageResponse::success(
+        "Citation exists (no fields to update)",
+    )))
 }
 
-#[delete("/{citation_id}")]
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Citation deleted"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the citing publication", body = ErrorResponse),
+        (status = 404, description = "Citation not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[delete("/{citation_id}", wrap = "Privy")]
 async fn delete_citation(
+    user: AuthenticatedUser,
     citation_id: web::Path<Uuid>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let result = data
+) -> Result<HttpResponse, ApiError> {
+    let citation = data
         .sql_client
-        .delete_citation(*citation_id)
+        .get_citation(*citation_id)
         .await
-        .map_err(|err| {
-            tracing::error!("Error deleting citation: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
+        .map_err(ApiError::database("Citation"))?;
+    get_owned_publication(&data, &user, citation.citing_publication_id).await?;
 
+    let result = data.sql_client.delete_citation(*citation_id).await?;
     if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("Citation not found"));
+        return Err(ApiError::not_found("Citation not found"));
     }
 
     Ok(HttpResponse::NoContent().finish())
 }
 
+#[utoipa::path(
+    params(PageQuery),
+    responses((status = 200, body = Paginated<Citation>))
+)]
 #[get("/list")]
 async fn list_citations(
     data: web::Data<AppState>,
-    query: web::Query<ListCitationsQuery>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let citations = data
-        .sql_client
-        .list_citations(query.page, query.limit)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error listing citations: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
-
-    let total_count = data.sql_client.count_citations().await.map_err(|err| {
-        tracing::error!("Error counting citations: {}", err);
-        ErrorInternalServerError("Internal server error")
-    })?;
-
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "citations": citations,
-        "total": total_count,
-        "page": query.page.unwrap_or(1),
-        "limit": query.limit.unwrap_or(20)
-    })))
-}
+    page: web::Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let page = page.into_inner();
+    let (citations, total_count) = data.sql_client.list_citations(page).await?;
 
-#[derive(Deserialize)]
-struct ListCitationsQuery {
-    page: Option<i64>,
-    limit: Option<i64>,
+    Ok(HttpResponse::Ok().json(Paginated::new(citations, total_count, page)))
 }
 
+#[utoipa::path(
+    params(CitationByPublicationsQuery),
+    responses(
+        (status = 200, body = Citation),
+        (status = 404, description = "Citation not found", body = ErrorResponse)
+    )
+)]
 #[get("/by-publications")]
 async fn get_citation_by_publications(
     data: web::Data<AppState>,
     query: web::Query<CitationByPublicationsQuery>,
-) -> Result<HttpResponse, actix_web::Error> {
+) -> Result<HttpResponse, ApiError> {
     let citation = data
         .sql_client
         .get_citation_by_publications(query.citing_publication_id, query.cited_publication_id)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error retrieving citation by publications: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
-
-    match citation {
-        Some(citation) => Ok(HttpResponse::Ok().json(citation)),
-        None => Err(ErrorNotFound(
-            "Citation not found between these publications",
-        )),
-    }
+        .await?
+        .ok_or_else(|| ApiError::not_found("Citation not found between these publications"))?;
+
+    Ok(HttpResponse::Ok().json(citation))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct CitationByPublicationsQuery {
     citing_publication_id: Uuid,
     cited_publication_id: Uuid,
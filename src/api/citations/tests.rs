@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
 
     use actix_web::{http::StatusCode, test};
@@ -6,7 +7,7 @@ mod tests {
     use sqlx::PgPool;
 
     use crate::{
-        api::tests::create_test_app,
+        api::tests::{authenticate, create_test_app},
         db::sql::{
             CitationOperations, PublicationOperations, SqlClient,
             models::{NewCitation, NewPublication},
@@ -29,6 +30,9 @@ mod tests {
                 about: Some("This publication cites another".to_string()),
                 tags: Some(vec!["citing".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -40,6 +44,9 @@ mod tests {
                 about: Some("This publication is cited".to_string()),
                 tags: Some(vec!["cited".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -53,6 +60,7 @@ mod tests {
             .uri("/citations/create")
             .set_json(&request_body)
             .to_request();
+        authenticate(&req, &user1_privy_id);
 
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
@@ -60,6 +68,47 @@ mod tests {
         let body: serde_json::Value = test::read_body_json(resp).await;
         assert_eq!(body["citing_publication_id"], pub1.id.to_string());
         assert_eq!(body["cited_publication_id"], pub2.id.to_string());
+
+        // The same citation again is rejected by the unique constraint
+        let req = test::TestRequest::post()
+            .uri("/citations/create")
+            .set_json(&request_body)
+            .to_request();
+        authenticate(&req, &user1_privy_id);
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "conflict");
+        assert_eq!(body["error"]["message"], "Citation already exists");
+
+        // Citing a missing publication violates the foreign key
+        let req = test::TestRequest::post()
+            .uri("/citations/create")
+            .set_json(json!({
+                "citing_publication_id": pub1.id.to_string(),
+                "cited_publication_id": uuid::Uuid::new_v4().to_string(),
+            }))
+            .to_request();
+        authenticate(&req, &user1_privy_id);
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "validation_error");
+
+        // Only the owner of the citing publication adds its citations
+        let req = test::TestRequest::post()
+            .uri("/citations/create")
+            .set_json(json!({
+                "citing_publication_id": pub2.id.to_string(),
+                "cited_publication_id": pub1.id.to_string(),
+            }))
+            .to_request();
+        authenticate(&req, &user1_privy_id);
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
     }
 
     #[sqlx::test]
@@ -78,6 +127,9 @@ mod tests {
                 about: Some("This publication cites another".to_string()),
                 tags: Some(vec!["citing".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -89,6 +141,9 @@ mod tests {
                 about: Some("This publication is cited".to_string()),
                 tags: Some(vec!["cited".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -130,6 +185,9 @@ mod tests {
                     about: None,
                     tags: None,
                     s3key: None,
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
                 })
                 .await
                 .unwrap();
@@ -152,8 +210,7 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
 
         let body: serde_json::Value = test::read_body_json(resp).await;
-        assert!(body["citations"].is_array());
-        let citations = body["citations"].as_array().unwrap();
+        let citations = body["items"].as_array().unwrap();
         assert!(citations.len() >= 3);
         assert!(body["total"].as_i64().unwrap() >= 3);
     }
@@ -174,6 +231,9 @@ mod tests {
                 about: Some("This publication cites another".to_string()),
                 tags: Some(vec!["citing".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -185,6 +245,9 @@ mod tests {
                 about: Some("This publication is cited".to_string()),
                 tags: Some(vec!["cited".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -203,6 +266,16 @@ mod tests {
             .uri(&format!("/citations/{}", citation.id))
             .set_json(&request_body)
             .to_request();
+        authenticate(&req, &user2_privy_id);
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/citations/{}", citation.id))
+            .set_json(&request_body)
+            .to_request();
+        authenticate(&req, &user1_privy_id);
 
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
@@ -234,6 +307,9 @@ mod tests {
                 about: Some("This publication cites another".to_string()),
                 tags: Some(vec!["citing".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -245,6 +321,9 @@ mod tests {
                 about: Some("This publication is cited".to_string()),
                 tags: Some(vec!["cited".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await
             .unwrap();
@@ -257,9 +336,19 @@ mod tests {
             .await
             .unwrap();
 
+        // The owner of the cited publication cannot remove the citation
+        let req = test::TestRequest::delete()
+            .uri(&format!("/citations/{}", citation.id))
+            .to_request();
+        authenticate(&req, &user2_privy_id);
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
         let req = test::TestRequest::delete()
             .uri(&format!("/citations/{}", citation.id))
             .to_request();
+        authenticate(&req, &user1_privy_id);
 
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::NO_CONTENT);
@@ -0,0 +1,91 @@
+use actix_web::{HttpResponse, get, http::header, web};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    api::{
+        admin, auth, authors, citations, health, metrics, publication_authors, publications, users,
+    },
+    auth::{internal::INTERNAL_TOKEN_HEADER, session::SESSION_COOKIE},
+};
+
+/// Path of the generated OpenAPI document.
+pub const OPENAPI_PATH: &str = "/api-docs/openapi.json";
+
+/// Registers the OpenAPI document and Swagger UI, which main only does when `API_DOCS_MODE` is
+/// enabled.
+pub fn config(conf: &mut web::ServiceConfig) {
+    conf.service(SwaggerUi::new("/api-docs/{_:.*}").url(OPENAPI_PATH, ApiDoc::openapi()))
+        .service(swagger_ui_index);
+}
+
+#[cfg(test)]
+mod tests;
+
+/// OpenAPI description of every endpoint, nesting the description of each API module under its
+/// scope.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Publish3 API"),
+    paths(health::healthz, health::readyz, metrics::metrics),
+    nest(
+        (path = "/admin", api = admin::AdminApi, tags = ["admin"]),
+        (path = "/auth", api = auth::AuthApi, tags = ["auth"]),
+        (path = "/users", api = users::UsersApi, tags = ["users"]),
+        (path = "/authors", api = authors::AuthorsApi, tags = ["authors"]),
+        (path = "/publications", api = publications::PublicationsApi, tags = ["publications"]),
+        (path = "/citations", api = citations::CitationsApi, tags = ["citations"]),
+        (
+            path = "/publication-authors",
+            api = publication_authors::PublicationAuthorsApi,
+            tags = ["publication-authors"]
+        ),
+    ),
+    modifiers(&SecuritySchemes)
+)]
+pub struct ApiDoc;
+
+/// Declares the ways callers authenticate, referenced by the `security` of each endpoint.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "privy",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Access token issued by Privy"))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                SESSION_COOKIE,
+                "Session opened on sign-in",
+            ))),
+        );
+        components.add_security_scheme(
+            "internal_token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                INTERNAL_TOKEN_HEADER,
+                "Token of an internal service",
+            ))),
+        );
+    }
+}
+
+/// Sends `/api-docs` to the page of Swagger UI, whose assets are resolved relative to it. The
+/// index is addressed explicitly, as trailing slashes are trimmed before routing.
+#[get("/api-docs")]
+async fn swagger_ui_index() -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, "/api-docs/index.html"))
+        .finish()
+}
@@ -0,0 +1,176 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use actix_web::{
+        App,
+        http::{StatusCode, header},
+        test::{TestRequest, call_service, init_service, read_body_json},
+    };
+    use serde_json::Value;
+    use utoipa::OpenApi;
+
+    use super::super::{ApiDoc, OPENAPI_PATH, config};
+
+    fn document() -> Value {
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    }
+
+    fn operation<'a>(document: &'a Value, path: &str, method: &str) -> &'a Value {
+        let operation = &document["paths"][path][method];
+        assert!(operation.is_object(), "{method} {path} is not documented");
+        operation
+    }
+
+    fn parameter_names(operation: &Value) -> Vec<&str> {
+        operation["parameters"]
+            .as_array()
+            .map(|parameters| {
+                parameters
+                    .iter()
+                    .map(|parameter| parameter["name"].as_str().unwrap())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Collects the `$ref` of every schema referenced within `value`.
+    fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(reference)) = object.get("$ref") {
+                    refs.push(reference);
+                }
+                object.values().for_each(|value| collect_refs(value, refs));
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect_refs(value, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_is_valid_openapi() {
+        let json = ApiDoc::openapi().to_json().unwrap();
+
+        let parsed: utoipa::openapi::OpenApi = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.info.title, "Publish3 API");
+        assert!(json.starts_with(r#"{"openapi":"3.1.0""#));
+    }
+
+    #[test]
+    fn test_every_route_is_documented() {
+        let document = document();
+
+        let operations: usize = document["paths"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|methods| methods.as_object().unwrap().len())
+            .sum();
+        assert_eq!(operations, 64);
+
+        operation(&document, "/healthz", "get");
+        operation(&document, "/metrics", "get");
+        operation(&document, "/admin/audit-log", "get");
+        operation(&document, "/admin/tasks", "get");
+        operation(&document, "/auth/session/refresh", "post");
+        operation(
+            &document,
+            "/publication-authors/count/author/{author_id}",
+            "get",
+        );
+    }
+
+    #[test]
+    fn test_parameters_are_documented() {
+        let document = document();
+
+        let get_user = operation(&document, "/users/{privy_id}", "get");
+        assert_eq!(parameter_names(get_user), vec!["privy_id"]);
+
+        let delete_file = operation(
+            &document,
+            "/publications/{publication_id}/files/{file_id}",
+            "delete",
+        );
+        assert_eq!(
+            parameter_names(delete_file),
+            vec!["publication_id", "file_id"]
+        );
+
+        let list_citations = operation(&document, "/citations/list", "get");
+        assert_eq!(parameter_names(list_citations), vec!["page", "limit"]);
+        assert_eq!(list_citations["parameters"][0]["in"], "query");
+        assert_eq!(list_citations["parameters"][0]["required"], false);
+
+        let pdf_url = operation(&document, "/publications/{publication_id}/pdf-url", "get");
+        assert_eq!(
+            parameter_names(pdf_url),
+            vec!["disposition", "publication_id"]
+        );
+    }
+
+    #[test]
+    fn test_responses_and_security_are_documented() {
+        let document = document();
+
+        let create = operation(&document, "/publications/create", "post");
+        assert!(create["responses"]["429"].is_object());
+        assert!(
+            create["requestBody"]["content"]["multipart/form-data"]["schema"]["$ref"]
+                .as_str()
+                .unwrap()
+                .ends_with("/CreatePublicationForm")
+        );
+        assert_eq!(
+            create["security"],
+            serde_json::json!([{ "privy": [] }, { "session": [] }])
+        );
+
+        let get_user = operation(&document, "/users/{privy_id}", "get");
+        assert!(get_user["responses"]["404"]["content"]["application/json"].is_object());
+
+        let schemes = &document["components"]["securitySchemes"];
+        assert_eq!(schemes["privy"]["scheme"], "bearer");
+        assert_eq!(schemes["session"]["in"], "cookie");
+        assert_eq!(schemes["internal_token"]["name"], "X-Internal-Token");
+    }
+
+    #[test]
+    fn test_every_schema_reference_resolves() {
+        let document = document();
+        let mut refs = vec![];
+        collect_refs(&document, &mut refs);
+        assert!(!refs.is_empty());
+
+        let schemas = &document["components"]["schemas"];
+        for reference in refs {
+            let name = reference
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("unexpected reference {reference}"));
+            assert!(schemas[name].is_object(), "{reference} is not defined");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_serves_document_and_swagger_ui_api() {
+        let app = init_service(App::new().configure(config)).await;
+
+        let req = TestRequest::get().uri(OPENAPI_PATH).to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Value = read_body_json(resp).await;
+        assert_eq!(body, document());
+
+        let req = TestRequest::get().uri("/api-docs").to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FOUND);
+        assert_eq!(
+            resp.headers().get(header::LOCATION).unwrap(),
+            "/api-docs/index.html"
+        );
+
+        let req = TestRequest::get().uri("/api-docs/index.html").to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
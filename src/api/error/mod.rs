@@ -0,0 +1,189 @@
+use actix_web::{
+    HttpResponse, ResponseError,
+    http::{StatusCode, header::ContentType},
+};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::error::ErrorKind;
+use utoipa::ToSchema;
+
+use crate::api::request_id::RequestId;
+
+/// Error returned by the API, rendered as
+/// `{"error": {"code": "...", "message": "...", "details": ..., "request_id": "..."}}` with its
+/// status code.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attaches machine-readable details, such as the value that failed validation.
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "validation_error", message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "forbidden", message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", message)
+    }
+
+    /// Error hiding its cause from the client, which is expected to be logged beforehand.
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    pub fn service_unavailable(message: impl Into<String>) -> Self {
+        Self::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "service_unavailable",
+            message,
+        )
+    }
+
+    /// Returns a mapper classifying the database errors of operations on `entity`, such as
+    /// `"Citation"`, as [From<sqlx::Error>] does, naming it in their messages.
+    pub fn database(entity: &'static str) -> impl Fn(sqlx::Error) -> ApiError {
+        move |err| ApiError::from_database(err, entity)
+    }
+
+    /// Classifies `err` by its SQLSTATE: a missing row is a 404, a duplicate a 409 and a
+    /// reference to a missing row or a violated check a 400, naming the constraint in the
+    /// details. Other errors are logged and hidden behind a 500.
+    fn from_database(err: sqlx::Error, entity: &str) -> ApiError {
+        let database_error = match &err {
+            sqlx::Error::RowNotFound => return ApiError::not_found(format!("{entity} not found")),
+            sqlx::Error::Database(database_error) => database_error,
+            _ => {
+                tracing::error!("Database error: {}", err);
+                return ApiError::internal("Internal server error");
+            }
+        };
+
+        let error = match database_error.kind() {
+            ErrorKind::UniqueViolation => ApiError::conflict(format!("{entity} already exists")),
+            ErrorKind::ForeignKeyViolation => {
+                ApiError::validation(format!("{entity} references a missing resource"))
+            }
+            ErrorKind::NotNullViolation | ErrorKind::CheckViolation => {
+                ApiError::validation(format!("{entity} is invalid"))
+            }
+            _ => {
+                tracing::error!("Database error: {}", err);
+                return ApiError::internal("Internal server error");
+            }
+        };
+        error.with_details(json!({ "constraint": database_error.constraint() }))
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        ApiError::from_database(err, "Resource")
+    }
+}
+
+/// Body of the responses to failed requests.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// Machine-readable code, such as `not_found`
+    pub code: &'static str,
+    pub message: String,
+    /// Machine-readable details, whose shape depends on the code
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+    /// Id of the request, to be quoted when reporting the error
+    pub request_id: Option<String>,
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Reports 5xx errors, tagged with the request they answer, before rendering them.
+    fn error_response(&self) -> HttpResponse {
+        if self.status.is_server_error() {
+            sentry::with_scope(
+                |scope| scope.set_tag("code", self.code),
+                || sentry::capture_error(self),
+            );
+        }
+
+        HttpResponse::build(self.status)
+            .content_type(ContentType::json())
+            .json(ErrorResponse {
+                error: ErrorBody {
+                    code: self.code,
+                    message: self.message.clone(),
+                    details: self.details.clone(),
+                    request_id: RequestId::current().map(|request_id| request_id.0),
+                },
+            })
+    }
+}
+
+/// Fallback service answering requests matching no route, so that they also get a JSON body.
+/// Paths matching a resource registered for other methods get a 405, others a 404.
+pub async fn default_service(
+    req: actix_web::HttpRequest,
+) -> Result<HttpResponse, actix_web::Error> {
+    Err(match req.match_pattern() {
+        Some(_) => ApiError::new(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method_not_allowed",
+            format!("Method {} is not allowed on {}", req.method(), req.path()),
+        ),
+        None => ApiError::not_found(format!("No route matches {}", req.path())),
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests;
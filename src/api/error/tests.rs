@@ -0,0 +1,100 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use actix_web::{
+        dev::ServiceResponse,
+        http::{StatusCode, header},
+        test,
+    };
+    use serde_json::json;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use crate::api::tests::{authenticate, create_test_app, create_test_user};
+    use crate::db::sql::SqlClient;
+
+    /// Asserts that `resp` is a JSON error with `status` and `code`, returning its body.
+    async fn assert_api_error(
+        resp: ServiceResponse,
+        status: StatusCode,
+        code: &str,
+    ) -> serde_json::Value {
+        assert_eq!(resp.status(), status);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], code);
+        assert!(body["error"]["message"].is_string());
+        assert!(body["error"].get("details").is_some());
+        body
+    }
+
+    #[sqlx::test]
+    async fn test_unauthorized_error_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+
+        let req = test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({
+                "file_name": "paper.pdf",
+                "file_size": 200,
+                "content_type": "application/pdf"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        let body = assert_api_error(resp, StatusCode::UNAUTHORIZED, "unauthorized").await;
+        assert_eq!(
+            body["error"]["message"],
+            "Valid Privy authentication token required"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_not_found_error_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let body = assert_api_error(resp, StatusCode::NOT_FOUND, "not_found").await;
+        assert_eq!(body["error"]["message"], "Publication not found");
+
+        // Requests matching no route are answered by the default service
+        let req = test::TestRequest::get().uri("/no/such/route").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_api_error(resp, StatusCode::NOT_FOUND, "not_found").await;
+
+        let req = test::TestRequest::patch()
+            .uri("/publications/list")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_api_error(resp, StatusCode::METHOD_NOT_ALLOWED, "method_not_allowed").await;
+    }
+
+    #[sqlx::test]
+    async fn test_validation_error_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+
+        let req = test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({
+                "file_name": "paper.txt",
+                "file_size": 200,
+                "content_type": "text/plain"
+            }))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+
+        let body = assert_api_error(resp, StatusCode::BAD_REQUEST, "validation_error").await;
+        assert_eq!(body["error"]["message"], "Only PDF files can be uploaded");
+        assert_eq!(body["error"]["details"], serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{future::Future, time::Duration};
+
+use actix_web::{HttpResponse, get, web};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{AppState, common::zresult::ZResult, db::s3::S3Bucket};
+
+/// Maximum time a single dependency check may take before it is reported as failed, so that a
+/// hung dependency does not hang the probe.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn config(conf: &mut web::ServiceConfig) {
+    conf.service(healthz).service(readyz);
+}
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Serialize, ToSchema)]
+struct Liveness {
+    #[schema(example = "ok")]
+    status: &'static str,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct Readiness {
+    /// `ok` when every dependency is healthy, `unavailable` otherwise
+    status: &'static str,
+    checks: Vec<DependencyStatus>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DependencyStatus {
+    name: &'static str,
+    healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[utoipa::path(responses((status = 200, body = Liveness)))]
+#[get("/healthz")]
+async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(Liveness { status: "ok" })
+}
+
+#[utoipa::path(responses(
+    (status = 200, description = "Every dependency is healthy", body = Readiness),
+    (status = 503, description = "A dependency is unhealthy", body = Readiness)
+))]
+#[get("/readyz")]
+async fn readyz(data: web::Data<AppState>) -> HttpResponse {
+    let redis_client = data.redis_client.clone();
+    let (database, redis, storage) = futures::join!(
+        check("database", async {
+            data.sql_client.ping().await?;
+            Ok(())
+        }),
+        async {
+            // A deployment without Redis does not wait for it
+            let client = redis_client?;
+            Some(
+                check("redis", async move {
+                    let mut connection = client.get_multiplexed_async_connection().await?;
+                    redis::cmd("PING")
+                        .query_async::<String>(&mut connection)
+                        .await?;
+                    Ok(())
+                })
+                .await,
+            )
+        },
+        check("s3", data.object_store.head_bucket(&S3Bucket::Storage)),
+    );
+
+    readiness_response(
+        [Some(database), redis, Some(storage)]
+            .into_iter()
+            .flatten()
+            .collect(),
+    )
+}
+
+/// Runs a dependency check, turning errors and timeouts into an unhealthy status.
+async fn check(name: &'static str, probe: impl Future<Output = ZResult<()>>) -> DependencyStatus {
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(_) => Some(format!("Timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+
+    if let Some(error) = &error {
+        tracing::warn!("Readiness check for {} failed: {}", name, error);
+    }
+
+    DependencyStatus {
+        name,
+        healthy: error.is_none(),
+        error,
+    }
+}
+
+fn readiness_response(checks: Vec<DependencyStatus>) -> HttpResponse {
+    let ready = checks.iter().all(|check| check.healthy);
+    let body = Readiness {
+        status: if ready { "ok" } else { "unavailable" },
+        checks,
+    };
+
+    if ready {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{http::StatusCode, test};
+    use sqlx::PgPool;
+
+    use super::super::{DependencyStatus, readiness_response};
+    use crate::{
+        api::tests::{
+            create_test_app, create_test_app_state, create_test_app_with_state,
+            create_test_app_with_store, tokio_runtime,
+        },
+        db::s3::disabled::DisabledObjectStore,
+    };
+
+    fn status(name: &'static str, error: Option<&str>) -> DependencyStatus {
+        DependencyStatus {
+            name,
+            healthy: error.is_none(),
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_healthz_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+
+        let req = test::TestRequest::get().uri("/healthz").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn test_readyz_with_unreachable_dependencies_api(pool: PgPool) {
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        // The test environment has a database and an in-memory object store, but no Redis
+        let (app, object_store) = create_test_app_with_store(pool).await;
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "unavailable");
+        let checks = body["checks"].as_array().unwrap();
+        assert_eq!(checks.len(), 3);
+        assert_eq!(checks[0]["name"], "database");
+        assert_eq!(checks[0]["healthy"], true);
+        assert_eq!(checks[1]["name"], "redis");
+        assert_eq!(checks[1]["healthy"], false);
+        assert!(checks[1]["error"].is_string());
+        assert_eq!(checks[2]["name"], "s3");
+        assert_eq!(checks[2]["healthy"], true);
+
+        object_store.set_unavailable(true);
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let resp = test::call_service(&app, req).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["checks"][2]["healthy"], false);
+        assert!(body["checks"][2]["error"].is_string());
+    }
+
+    #[sqlx::test]
+    async fn test_readyz_with_disabled_dependencies_api(pool: PgPool) {
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let mut app_state = create_test_app_state(pool).await;
+        app_state.redis_client = None;
+        app_state.object_store = Arc::new(DisabledObjectStore);
+        let app = test::init_service(create_test_app_with_state(app_state)).await;
+
+        // Disabled dependencies are not waited for
+        let req = test::TestRequest::get().uri("/readyz").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let checks = body["checks"].as_array().unwrap();
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0]["name"], "database");
+        assert_eq!(checks[1]["name"], "s3");
+    }
+
+    #[actix_web::test]
+    async fn test_readiness_response_all_healthy() {
+        let resp = readiness_response(vec![
+            status("database", None),
+            status("redis", None),
+            status("s3", None),
+        ]);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value =
+            serde_json::from_slice(&actix_web::body::to_bytes(resp.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(body["status"], "ok");
+        assert!(body["checks"][0].get("error").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_readiness_response_one_dependency_down() {
+        let resp = readiness_response(vec![
+            status("database", None),
+            status("redis", None),
+            status("s3", Some("Timed out after 2s")),
+        ]);
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body: serde_json::Value =
+            serde_json::from_slice(&actix_web::body::to_bytes(resp.into_body()).await.unwrap())
+                .unwrap();
+        assert_eq!(body["status"], "unavailable");
+        assert_eq!(body["checks"][2]["healthy"], false);
+        assert_eq!(body["checks"][2]["error"], "Timed out after 2s");
+    }
+}
@@ -0,0 +1,23 @@
+use actix_web::{HttpResponse, get, web};
+
+use crate::AppState;
+
+/// Content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub fn config(conf: &mut web::ServiceConfig) {
+    conf.service(metrics);
+}
+
+#[cfg(test)]
+mod tests;
+
+#[utoipa::path(responses(
+    (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain")
+))]
+#[get("/metrics")]
+async fn metrics(data: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(PROMETHEUS_CONTENT_TYPE)
+        .body(data.storage_metrics.render())
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use actix_web::{http::StatusCode, test};
+    use sqlx::PgPool;
+
+    use crate::{
+        api::tests::create_test_app_with_store,
+        db::{
+            s3::{
+                ObjectStore, S3Bucket,
+                metrics::{InstrumentedObjectStore, Outcome, StorageMetrics, StorageOperation},
+                mock::{MockObject, MockObjectStore},
+            },
+            sql::{PublicationOperations, SqlClient, models::NewPublication},
+        },
+    };
+
+    #[actix_web::test]
+    async fn test_instrumented_store_records_operations() {
+        let object_store = Arc::new(MockObjectStore::default());
+        let metrics = Arc::new(StorageMetrics::default());
+        let instrumented =
+            InstrumentedObjectStore::new(object_store.clone(), metrics.clone(), Duration::ZERO);
+        object_store.insert(
+            S3Bucket::Storage,
+            "publications/id/paper.pdf",
+            MockObject::new(b"0123456789".to_vec()),
+        );
+
+        instrumented
+            .get_file("publications/id/paper.pdf", &S3Bucket::Storage, None)
+            .await
+            .unwrap();
+        instrumented
+            .get_file("publications/id/missing.pdf", &S3Bucket::Storage, None)
+            .await
+            .unwrap_err();
+        instrumented
+            .get_file_url(
+                "publications/id/paper.pdf",
+                &S3Bucket::Storage,
+                Duration::from_secs(60),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let gets = metrics
+            .stats(StorageOperation::Get, Outcome::Success)
+            .unwrap();
+        assert_eq!(gets.count, 1);
+        assert_eq!(gets.bytes, 10);
+        let failed_gets = metrics
+            .stats(StorageOperation::Get, Outcome::Error)
+            .unwrap();
+        assert_eq!(failed_gets.count, 1);
+        assert_eq!(failed_gets.bytes, 0);
+        assert_eq!(
+            metrics
+                .stats(StorageOperation::Presign, Outcome::Success)
+                .unwrap()
+                .count,
+            1
+        );
+        assert_eq!(metrics.stats(StorageOperation::Put, Outcome::Success), None);
+    }
+
+    #[sqlx::test]
+    async fn test_metrics_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let s3key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(
+            S3Bucket::Storage,
+            &s3key,
+            MockObject::new(b"%PDF-1.4 0123456789".to_vec()),
+        );
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id,
+                title: "Measured".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(s3key),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/download", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            resp.headers()
+                .get(actix_web::http::header::CONTENT_TYPE)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("text/plain")
+        );
+
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(
+            body.contains("storage_operations_total{operation=\"get\",outcome=\"success\"} 1\n")
+        );
+        assert!(body.contains("storage_bytes_total{operation=\"get\",outcome=\"success\"} 19\n"));
+        assert!(body.contains(
+            "storage_operation_duration_seconds_count{operation=\"get\",outcome=\"success\"} 1\n"
+        ));
+        assert!(body.contains("# TYPE storage_operation_duration_seconds histogram"));
+    }
+}
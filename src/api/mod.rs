@@ -1,13 +1,41 @@
+use actix_web::{error::QueryPayloadError, web};
+
+use crate::api::error::ApiError;
+
+pub mod admin;
+pub mod audit;
+pub mod auth;
 pub mod authors;
+pub mod body_limits;
 pub mod citations;
+pub mod docs;
+pub mod error;
+pub mod health;
+pub mod metrics;
 pub mod publication_authors;
 pub mod publications;
+pub mod rate_limit;
+pub mod request_id;
+pub mod response;
 pub mod users;
 
 #[cfg(test)]
 pub mod tests;
 
-pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
+pub fn config(cfg: &mut web::ServiceConfig) {
+    // Invalid query strings, such as out of range pages, are answered with the JSON error body
+    cfg.app_data(
+        web::QueryConfig::default().error_handler(|err, _| match err {
+            QueryPayloadError::Deserialize(err) => {
+                ApiError::validation(format!("Invalid query string: {err}")).into()
+            }
+            err => err.into(),
+        }),
+    );
+    health::config(cfg);
+    metrics::config(cfg);
+    admin::config(cfg);
+    auth::config(cfg);
     users::config(cfg);
     authors::config(cfg);
     publications::config(cfg);
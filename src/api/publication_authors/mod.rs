@@ -1,14 +1,21 @@
-use actix_web::{
-    HttpResponse, delete,
-    error::{ErrorBadRequest, ErrorConflict, ErrorInternalServerError, ErrorNotFound},
-    get, post, put, web,
-};
-use serde::Deserialize;
+use actix_web::{HttpResponse, delete, get, post, put, web};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
     AppState,
-    db::sql::{PrivyId, PublicationAuthorOperations},
+    api::{
+        error::{ApiError, ErrorResponse},
+        publications::get_owned_publication,
+        response::{CountResponse, MessageResponse},
+    },
+    auth::{AuthenticatedUser, Privy},
+    common::pagination::PageQuery,
+    db::sql::{
+        PrivyId, PublicationAuthorOperations,
+        models::{Publication, PublicationAuthor},
+    },
 };
 
 pub fn config(conf: &mut web::ServiceConfig) {
@@ -25,18 +32,49 @@ pub fn config(conf: &mut web::ServiceConfig) {
     conf.service(scope);
 }
 
-#[derive(Deserialize)]
+#[cfg(test)]
+mod tests;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    add_author_to_publication,
+    remove_author_from_publication,
+    set_publication_authors,
+    update_author_order,
+    get_publication_authors,
+    publication_has_author,
+    count_authors_for_publication,
+    get_author_publications,
+    count_publications_for_author
+))]
+pub struct PublicationAuthorsApi;
+
+#[derive(Deserialize, ToSchema)]
 pub struct AddAuthorToPublicationRequest {
     publication_id: Uuid,
+    #[schema(value_type = String)]
     author_id: PrivyId,
     author_order: Option<i32>,
 }
 
-#[post("/add")]
+#[utoipa::path(
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Publication not found", body = ErrorResponse),
+        (status = 409, description = "Author already associated", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/add", wrap = "Privy")]
 async fn add_author_to_publication(
+    user: AuthenticatedUser,
     request: web::Json<AddAuthorToPublicationRequest>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    get_owned_publication(&data, &user, request.publication_id).await?;
+
     // Check if author is already associated with publication
     let has_author = data
         .sql_client
@@ -44,68 +82,101 @@ async fn add_author_to_publication(
         .await
         .map_err(|err| {
             tracing::error!("Error checking author association: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     if has_author {
-        return Err(ErrorConflict("Author is already associated with this publication"));
+        return Err(
+            ApiError::conflict("Author is already associated with this publication").into(),
+        );
     }
 
     // Note: We need to use the PublicationAuthorOperations trait method
     data.sql_client
-        .add_author_to_publication(request.publication_id, &request.author_id, request.author_order)
+        .add_author_to_publication(
+            request.publication_id,
+            &request.author_id,
+            request.author_order,
+        )
         .await
         .map_err(|err| {
             tracing::error!("Error adding author to publication: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Author added to publication successfully"
-    })))
+    Ok(HttpResponse::Ok().json(MessageResponse::success(
+        "Author added to publication successfully",
+    )))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct RemoveAuthorFromPublicationRequest {
     publication_id: Uuid,
+    #[schema(value_type = String)]
     author_id: PrivyId,
 }
 
-#[delete("/remove")]
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Author removed"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Author not found in publication", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[delete("/remove", wrap = "Privy")]
 async fn remove_author_from_publication(
+    user: AuthenticatedUser,
     request: web::Json<RemoveAuthorFromPublicationRequest>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    get_owned_publication(&data, &user, request.publication_id).await?;
+
     let result = data
         .sql_client
         .remove_author_from_publication(request.publication_id, &request.author_id)
         .await
         .map_err(|err| {
             tracing::error!("Error removing author from publication: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("Author not found in publication"));
+        return Err(ApiError::not_found("Author not found in publication").into());
     }
 
     Ok(HttpResponse::NoContent().finish())
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SetPublicationAuthorsRequest {
     publication_id: Uuid,
+    #[schema(value_type = Vec<String>)]
     author_ids: Vec<PrivyId>,
 }
 
-#[post("/set")]
+#[utoipa::path(
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 400, description = "Duplicate author ids", body = ErrorResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/set", wrap = "Privy")]
 async fn set_publication_authors(
+    user: AuthenticatedUser,
     request: web::Json<SetPublicationAuthorsRequest>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    get_owned_publication(&data, &user, request.publication_id).await?;
+
     // Check for duplicate author IDs
-    let unique_author_ids: Vec<PrivyId> = request.author_ids
+    let unique_author_ids: Vec<PrivyId> = request
+        .author_ids
         .iter()
         .cloned()
         .collect::<std::collections::HashSet<_>>()
@@ -113,7 +184,7 @@ async fn set_publication_authors(
         .collect();
 
     if unique_author_ids.len() != request.author_ids.len() {
-        return Err(ErrorBadRequest("Duplicate author IDs are not allowed"));
+        return Err(ApiError::validation("Duplicate author IDs are not allowed").into());
     }
 
     data.sql_client
@@ -121,65 +192,86 @@ async fn set_publication_authors(
         .await
         .map_err(|err| {
             tracing::error!("Error setting publication authors: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Publication authors set successfully"
-    })))
+    Ok(HttpResponse::Ok().json(MessageResponse::success(
+        "Publication authors set successfully",
+    )))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateAuthorOrderRequest {
     publication_id: Uuid,
+    #[schema(value_type = String)]
     author_id: PrivyId,
     author_order: i32,
 }
 
-#[put("/order")]
+#[utoipa::path(
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Author not found in publication", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[put("/order", wrap = "Privy")]
 async fn update_author_order(
+    user: AuthenticatedUser,
     request: web::Json<UpdateAuthorOrderRequest>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    get_owned_publication(&data, &user, request.publication_id).await?;
+
     let result = data
         .sql_client
-        .update_author_order(request.publication_id, &request.author_id, request.author_order)
+        .update_author_order(
+            request.publication_id,
+            &request.author_id,
+            request.author_order,
+        )
         .await
         .map_err(|err| {
             tracing::error!("Error updating author order: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("Author not found in publication"));
+        return Err(ApiError::not_found("Author not found in publication").into());
     }
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Author order updated successfully"
-    })))
+    Ok(HttpResponse::Ok().json(MessageResponse::success(
+        "Author order updated successfully",
+    )))
 }
 
+#[utoipa::path(responses((status = 200, body = Vec<PublicationAuthor>)))]
 #[get("/publication/{publication_id}")]
 async fn get_publication_authors(
     publication_id: web::Path<Uuid>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let authors = data.sql_client
+    let authors = data
+        .sql_client
         .get_publication_authors(*publication_id)
         .await
         .map_err(|err| {
             tracing::error!("Error retrieving publication authors: {}", err);
             match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("Publication not found"),
-                _ => ErrorInternalServerError("Internal server error"),
+                sqlx::Error::RowNotFound => ApiError::not_found("Publication not found"),
+                _ => ApiError::internal("Internal server error"),
             }
         })?;
 
     Ok(HttpResponse::Ok().json(authors))
 }
 
+#[utoipa::path(
+    params(HasAuthorQuery),
+    responses((status = 200, body = HasAuthorResponse))
+)]
 #[get("/has-author")]
 async fn publication_has_author(
     data: web::Data<AppState>,
@@ -191,20 +283,26 @@ async fn publication_has_author(
         .await
         .map_err(|err| {
             tracing::error!("Error checking author association: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "has_author": has_author
-    })))
+    Ok(HttpResponse::Ok().json(HasAuthorResponse { has_author }))
+}
+
+#[derive(Serialize, ToSchema)]
+struct HasAuthorResponse {
+    has_author: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct HasAuthorQuery {
     publication_id: Uuid,
+    #[param(value_type = String)]
     author_id: PrivyId,
 }
 
+#[utoipa::path(responses((status = 200, body = CountResponse)))]
 #[get("/count/{publication_id}")]
 async fn count_authors_for_publication(
     publication_id: web::Path<Uuid>,
@@ -216,41 +314,38 @@ async fn count_authors_for_publication(
         .await
         .map_err(|err| {
             tracing::error!("Error counting authors for publication: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "count": count
-    })))
+    Ok(HttpResponse::Ok().json(CountResponse { count }))
 }
 
+#[utoipa::path(
+    params(PageQuery),
+    responses((status = 200, body = Vec<Publication>))
+)]
 #[get("/author/{author_id}")]
 async fn get_author_publications(
-    author_id: web::Path<PrivyId>,
+    author_id: web::Path<String>,
     data: web::Data<AppState>,
-    query: web::Query<AuthorPublicationsQuery>,
+    page: web::Query<PageQuery>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let publications = data
         .sql_client
-        .get_author_publications(&author_id, query.page, query.limit)
+        .get_author_publications(&author_id, page.into_inner())
         .await
         .map_err(|err| {
             tracing::error!("Error retrieving author publications: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     Ok(HttpResponse::Ok().json(publications))
 }
 
-#[derive(Deserialize)]
-struct AuthorPublicationsQuery {
-    page: Option<i64>,
-    limit: Option<i64>,
-}
-
+#[utoipa::path(responses((status = 200, body = CountResponse)))]
 #[get("/count/author/{author_id}")]
 async fn count_publications_for_author(
-    author_id: web::Path<PrivyId>,
+    author_id: web::Path<String>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let count = data
@@ -259,10 +354,8 @@ async fn count_publications_for_author(
         .await
         .map_err(|err| {
             tracing::error!("Error counting publications for author: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "count": count
-    })))
+    Ok(HttpResponse::Ok().json(CountResponse { count }))
 }
@@ -0,0 +1,101 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::{
+        api::tests::{
+            authenticate, create_test_app, create_test_author, create_test_publication,
+            create_test_user,
+        },
+        db::sql::{PublicationAuthorOperations, SqlClient},
+    };
+
+    #[sqlx::test]
+    async fn test_publication_authors_authorization_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let owner_privy_id = create_test_user(&sql_client).await;
+        let other_privy_id = create_test_user(&sql_client).await;
+        let author_privy_id = create_test_user(&sql_client).await;
+        create_test_author(&sql_client, &author_privy_id).await;
+        let publication_id = create_test_publication(&sql_client, owner_privy_id.clone()).await;
+
+        let add = json!({
+            "publication_id": publication_id,
+            "author_id": author_privy_id,
+            "author_order": 1,
+        });
+        let requests = [
+            test::TestRequest::post()
+                .uri("/publication-authors/add")
+                .set_json(&add),
+            test::TestRequest::post()
+                .uri("/publication-authors/set")
+                .set_json(json!({
+                    "publication_id": publication_id,
+                    "author_ids": [author_privy_id],
+                })),
+            test::TestRequest::put()
+                .uri("/publication-authors/order")
+                .set_json(json!({
+                    "publication_id": publication_id,
+                    "author_id": author_privy_id,
+                    "author_order": 2,
+                })),
+            test::TestRequest::delete()
+                .uri("/publication-authors/remove")
+                .set_json(&add),
+        ];
+
+        // Only the owner of the publication manages its authors
+        for request in requests {
+            let req = request.to_request();
+            authenticate(&req, &other_privy_id);
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        }
+        assert!(
+            !sql_client
+                .publication_has_author(publication_id, &author_privy_id)
+                .await
+                .unwrap()
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/publication-authors/add")
+            .set_json(&add)
+            .to_request();
+        authenticate(&req, &owner_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::delete()
+            .uri("/publication-authors/remove")
+            .set_json(&add)
+            .to_request();
+        authenticate(&req, &other_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(
+            sql_client
+                .publication_has_author(publication_id, &author_privy_id)
+                .await
+                .unwrap()
+        );
+
+        // Publications that do not exist have no owner to check
+        let req = test::TestRequest::post()
+            .uri("/publication-authors/add")
+            .set_json(json!({
+                "publication_id": uuid::Uuid::new_v4(),
+                "author_id": author_privy_id,
+            }))
+            .to_request();
+        authenticate(&req, &owner_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
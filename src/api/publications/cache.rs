@@ -0,0 +1,199 @@
+use std::{sync::Arc, time::Duration};
+
+use uuid::Uuid;
+
+use crate::db::{kv::KeyValueStore, sql::models::Publication};
+
+/// How long a publication is served from the cache before being read again.
+pub const PUBLICATION_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Version of the cached JSON, part of the keys so that a deployment changing [Publication]
+/// does not read entries written by the previous one. Bump it when the shape changes.
+const CACHE_SCHEMA_VERSION: u32 = 1;
+
+/// Value cached in place of a changed publication, see [PublicationCache::mark_changed].
+const CHANGED_MARKER: &str = "changed";
+
+/// Read-through cache of the publications served by `GET /publications/{id}`, with their
+/// presigned URLs, invalidated by the handlers changing them.
+///
+/// The cache is best effort: its errors are logged and treated as misses, so that an outage of
+/// Redis only slows requests down.
+pub struct PublicationCache {
+    store: Arc<dyn KeyValueStore>,
+    ttl: Duration,
+}
+
+impl PublicationCache {
+    pub fn new(store: Arc<dyn KeyValueStore>, ttl: Duration) -> Self {
+        PublicationCache { store, ttl }
+    }
+
+    pub async fn get(&self, publication_id: Uuid) -> Option<Publication> {
+        let cached = match self.store.get(&cache_key(publication_id)).await {
+            Ok(cached) => cached.filter(|cached| cached != CHANGED_MARKER)?,
+            Err(err) => {
+                tracing::warn!(
+                    "Error reading publication {} from cache: {}",
+                    publication_id,
+                    err
+                );
+                return None;
+            }
+        };
+
+        serde_json::from_str(&cached)
+            .inspect_err(|err| {
+                tracing::warn!("Invalid cached publication {}: {}", publication_id, err);
+            })
+            .ok()
+    }
+
+    /// Caches the publication unless an entry is already cached, so that a request which read it
+    /// before it changed does not cache it again over [PublicationCache::mark_changed].
+    pub async fn set(&self, publication: &Publication) {
+        let result = match serde_json::to_string(publication) {
+            Ok(json) => self
+                .store
+                .insert(&cache_key(publication.id), &json, self.ttl)
+                .await
+                .map(|_| ()),
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            tracing::warn!("Error caching publication {}: {}", publication.id, err);
+        }
+    }
+
+    /// Removes the cached publication, for the next request to read it again.
+    pub async fn invalidate(&self, publication_id: Uuid) {
+        if let Err(err) = self.store.delete(&cache_key(publication_id)).await {
+            tracing::warn!(
+                "Error invalidating cached publication {}: {}",
+                publication_id,
+                err
+            );
+        }
+    }
+
+    /// Replaces the cached publication by a marker read as a miss, so that requests which read the
+    /// publication before it was updated or deleted do not cache it again until the marker
+    /// expires. Called once the change is committed.
+    pub async fn mark_changed(&self, publication_id: Uuid) {
+        if let Err(err) = self
+            .store
+            .set(&cache_key(publication_id), CHANGED_MARKER, self.ttl)
+            .await
+        {
+            tracing::warn!(
+                "Error invalidating cached publication {}: {}",
+                publication_id,
+                err
+            );
+        }
+    }
+}
+
+fn cache_key(publication_id: Uuid) -> String {
+    format!("pub:{publication_id}:v{CACHE_SCHEMA_VERSION}")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::db::kv::MemoryKeyValueStore;
+
+    fn publication(title: &str) -> Publication {
+        Publication {
+            id: Uuid::new_v4(),
+            user_id: Some("did:privy:cache".to_string()),
+            title: title.to_string(),
+            about: None,
+            tags: vec!["cache".to_string()],
+            s3key: None,
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            file_url: Some("https://storage.example.com/paper.pdf".to_string()),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_publication_cache() {
+        let cache = PublicationCache::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            Duration::from_secs(60),
+        );
+        let publication = publication("Cached");
+
+        assert!(cache.get(publication.id).await.is_none());
+
+        cache.set(&publication).await;
+        let cached = cache.get(publication.id).await.unwrap();
+        assert_eq!(cached.title, "Cached");
+        assert_eq!(cached.file_url, publication.file_url);
+
+        cache.invalidate(publication.id).await;
+        assert!(cache.get(publication.id).await.is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_deleted_publication_is_not_cached_again() {
+        let cache = PublicationCache::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            Duration::from_secs(60),
+        );
+        let publication = publication("Deleted");
+        cache.set(&publication).await;
+
+        // A request which read the publication before its deletion tries to cache it
+        cache.mark_changed(publication.id).await;
+        cache.set(&publication).await;
+        assert!(cache.get(publication.id).await.is_none());
+
+        // Restoring the publication lets it be cached again
+        cache.invalidate(publication.id).await;
+        cache.set(&publication).await;
+        assert!(cache.get(publication.id).await.is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_updated_publication_is_not_cached_stale() {
+        let cache = PublicationCache::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            Duration::from_secs(60),
+        );
+        let publication = publication("Before update");
+        cache.set(&publication).await;
+
+        // A request misses the cache and reads the publication, the update commits, then the
+        // request caches what it read
+        cache.invalidate(publication.id).await;
+        let read_before_update = publication.clone();
+        cache.mark_changed(publication.id).await;
+        cache.set(&read_before_update).await;
+        assert!(cache.get(publication.id).await.is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_cached_publication_expires() {
+        let cache = PublicationCache::new(Arc::new(MemoryKeyValueStore::default()), Duration::ZERO);
+        let publication = publication("Expired");
+
+        cache.set(&publication).await;
+        assert!(cache.get(publication.id).await.is_none());
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let publication_id = Uuid::nil();
+        assert_eq!(
+            cache_key(publication_id),
+            "pub:00000000-0000-0000-0000-000000000000:v1"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Publication metadata in the citation formats of reference managers, served by
+//! `GET /publications/{id}/bibtex`.
+
+use chrono::Datelike;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::db::sql::models::{Author, Publication};
+
+/// Title words skipped when picking the one of a citation key.
+const KEY_STOP_WORDS: &[&str] = &["a", "an", "and", "for", "in", "of", "on", "the", "to"];
+
+/// Citation format requested with `?format=`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CitationFormat {
+    #[default]
+    Bibtex,
+    /// Research Information Systems, read by EndNote, Zotero and Mendeley
+    Ris,
+}
+
+impl CitationFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            CitationFormat::Bibtex => "application/x-bibtex; charset=utf-8",
+            CitationFormat::Ris => "application/x-research-info-systems; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CitationFormat::Bibtex => "bib",
+            CitationFormat::Ris => "ris",
+        }
+    }
+}
+
+/// Citation of a publication, by its authors in author order.
+pub struct CitationEntry<'a> {
+    pub publication: &'a Publication,
+    pub authors: &'a [Author],
+    /// Page of the publication on the platform
+    pub url: String,
+}
+
+impl CitationEntry<'_> {
+    pub fn render(&self, format: CitationFormat) -> String {
+        match format {
+            CitationFormat::Bibtex => self.bibtex(),
+            CitationFormat::Ris => self.ris(),
+        }
+    }
+
+    /// Returns the key of the citation: the last name of the first author, the year and the first
+    /// significant word of the title, such as `okafor2025consensus`, reduced to ASCII letters and
+    /// digits so that it is the same across exports.
+    pub fn key(&self) -> String {
+        let last_name = self
+            .authors
+            .first()
+            .and_then(|author| author.name.split_whitespace().last())
+            .map(key_part)
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "anonymous".to_string());
+        let title_word = self
+            .publication
+            .title
+            .split(|c: char| !c.is_alphanumeric())
+            .map(key_part)
+            .find(|word| !word.is_empty() && !KEY_STOP_WORDS.contains(&word.as_str()))
+            .unwrap_or_default();
+
+        format!("{last_name}{}{title_word}", self.year())
+    }
+
+    fn year(&self) -> i32 {
+        self.publication.created_at.year()
+    }
+
+    fn bibtex(&self) -> String {
+        let mut fields = vec![("title", escape_bibtex(&self.publication.title))];
+        if !self.authors.is_empty() {
+            let authors: Vec<String> = self
+                .authors
+                .iter()
+                .map(|author| escape_bibtex(&author.name))
+                .collect();
+            fields.push(("author", authors.join(" and ")));
+        }
+        fields.push(("year", self.year().to_string()));
+        // URLs are read verbatim
+        fields.push(("url", self.url.clone()));
+
+        let mut entry = format!("@article{{{},\n", self.key());
+        for (name, value) in fields {
+            entry.push_str(&format!("  {name} = {{{value}}},\n"));
+        }
+        entry.push_str("}\n");
+        entry
+    }
+
+    fn ris(&self) -> String {
+        let mut lines = vec![
+            ("TY", "JOUR".to_string()),
+            ("ID", self.key()),
+            ("TI", single_line(&self.publication.title)),
+        ];
+        for author in self.authors {
+            lines.push(("AU", ris_author(&author.name)));
+        }
+        lines.push(("PY", self.year().to_string()));
+        lines.push(("UR", self.url.clone()));
+        lines.push(("ER", String::new()));
+
+        lines
+            .into_iter()
+            .map(|(tag, value)| format!("{tag}  - {value}\n"))
+            .collect()
+    }
+}
+
+/// Escapes the characters BibTeX gives a meaning to, so that titles and names are printed as
+/// written. Line breaks become spaces.
+fn escape_bibtex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in single_line(value).chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '{' | '}' | '%' | '&' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn single_line(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Writes a name as `Last, First`, the RIS convention, taking its last word as the last name.
+fn ris_author(name: &str) -> String {
+    let name = single_line(name);
+    match name.rsplit_once(' ') {
+        Some((first, last)) => format!("{last}, {first}"),
+        None => name,
+    }
+}
+
+/// Lowercases `word` to ASCII letters and digits, dropping the accents of Latin letters and any
+/// other character.
+fn key_part(word: &str) -> String {
+    let mut part = String::with_capacity(word.len());
+    for c in word.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            part.push(c);
+        } else if let Some(folded) = fold_accent(c) {
+            part.push_str(folded);
+        }
+    }
+    part
+}
+
+fn fold_accent(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'ł' | 'ľ' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ś' | 'š' | 'ş' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn publication(title: &str) -> Publication {
+        let created_at = Utc.with_ymd_and_hms(2024, 3, 4, 5, 6, 7).unwrap();
+        Publication {
+            id: Uuid::nil(),
+            user_id: None,
+            title: title.to_string(),
+            about: None,
+            tags: vec![],
+            s3key: None,
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
+            created_at,
+            updated_at: created_at,
+            file_url: None,
+        }
+    }
+
+    fn author(name: &str) -> Author {
+        Author {
+            privy_id: format!("did:privy:{name}"),
+            name: name.to_string(),
+            email: None,
+            affiliation: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn entry<'a>(publication: &'a Publication, authors: &'a [Author]) -> CitationEntry<'a> {
+        CitationEntry {
+            publication,
+            authors,
+            url: "https://publish3.example/publications/1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_escape_bibtex() {
+        assert_eq!(
+            escape_bibtex("{Rust} & 100% of C_1 #2 $5 \\o/"),
+            "\\{Rust\\} \\& 100\\% of C\\_1 \\#2 \\$5 \\textbackslash{}o/"
+        );
+        assert_eq!(
+            escape_bibtex("Zoë  Müller\non\tlines"),
+            "Zoë Müller on lines"
+        );
+    }
+
+    #[test]
+    fn test_citation_key() {
+        let title = publication("The {Ångström} Consensus: 100% Safe");
+        let authors = [author("Zoë Müller-Łęcka"), author("Ada Okafor")];
+        assert_eq!(entry(&title, &authors).key(), "mullerlecka2024angstrom");
+
+        let authors = [author("Émile Ðurić")];
+        assert_eq!(
+            entry(&publication("On proofs of storage"), &authors).key(),
+            "duric2024proofs"
+        );
+        // Without authors or words, the other parts are kept
+        assert_eq!(entry(&publication("A & the"), &[]).key(), "anonymous2024");
+        assert_eq!(
+            entry(&publication("Œuvres"), &[author("李")]).key(),
+            "anonymous2024oeuvres"
+        );
+    }
+
+    #[test]
+    fn test_bibtex() {
+        let publication = publication("Braces {and} 50% & more");
+        let authors = [author("Ada Okafor"), author("Chen Wei")];
+        assert_eq!(
+            entry(&publication, &authors).render(CitationFormat::Bibtex),
+            "@article{okafor2024braces,\n\
+             \x20 title = {Braces \\{and\\} 50\\% \\& more},\n\
+             \x20 author = {Ada Okafor and Chen Wei},\n\
+             \x20 year = {2024},\n\
+             \x20 url = {https://publish3.example/publications/1},\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_ris() {
+        let publication = publication("Braces {and} 50%\n& more");
+        let authors = [author("Ada Okafor"), author("Hana")];
+        assert_eq!(
+            entry(&publication, &authors).render(CitationFormat::Ris),
+            "TY  - JOUR\n\
+             ID  - okafor2024braces\n\
+             TI  - Braces {and} 50% & more\n\
+             AU  - Okafor, Ada\n\
+             AU  - Hana\n\
+             PY  - 2024\n\
+             UR  - https://publish3.example/publications/1\n\
+             ER  - \n"
+        );
+    }
+}
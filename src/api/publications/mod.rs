@@ -1,69 +1,549 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
 use actix_multipart::form::{MultipartForm, tempfile::TempFile, text::Text};
 use actix_web::{
-    HttpResponse, delete,
-    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound},
-    get, post, put, web,
+    HttpRequest, HttpResponse, delete, get,
+    http::{StatusCode, header},
+    post, put, web,
 };
-use serde::Deserialize;
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
     AppState,
-    db::sql::{
-        CitationOperations, PrivyId, PublicationAuthorOperations, PublicationOperations,
-        models::NewPublication,
+    api::{
+        admin::storage_directory_id,
+        error::{ApiError, ErrorResponse},
+        publications::export::{CitationEntry, CitationFormat},
+        rate_limit::{PUBLISH, RateLimit},
+        response::MessageResponse,
+    },
+    auth::{AuthenticatedUser, MaybeAuthenticated, Privy},
+    common::{
+        filename::{content_disposition, display_filename, sanitize_filename},
+        hash::{FileHashes, hash_byte_stream, hash_local_file},
+        pagination::{Cursor, CursorPage, CursorQuery, PageQuery, Paginated},
+        time::rfc3339_millis,
+        zresult::ZResult,
+    },
+    db::{
+        s3::{
+            ByteRange, ObjectStore, S3Bucket, S3Contents, S3Key, client::S3ObjectInfo,
+            store::StorageUnavailable,
+        },
+        sql::{
+            CitationOperations, PrivyId, PublicationAuthorOperations, PublicationFileOperations,
+            PublicationOperations, UserOperations, ViewOperations,
+            models::{
+                Author, CitedPublication, NewPublication, NewPublicationFile, Publication,
+                PublicationAuthor, PublicationFile, TrendingPublication,
+            },
+        },
     },
 };
 
+const PUBLICATION_CONTENT_TYPE: &str = "application/pdf";
+/// Bytes every PDF file starts with.
+const PDF_MAGIC: &[u8] = b"%PDF-";
+const UPLOAD_INTENT_EXPIRY: Duration = Duration::from_secs(15 * 60);
+
+/// Names of the S3 user metadata entries recorded on uploaded publication files.
+const METADATA_UPLOADER: &str = "uploader";
+const METADATA_PUBLICATION_ID: &str = "publication-id";
+const METADATA_ORIGINAL_FILENAME: &str = "original-filename";
+const METADATA_SHA3_HASH: &str = "sha3-hash";
+const METADATA_SHA256: &str = "sha256";
+
+/// Content types accepted for supplementary files: documents, datasets and archives.
+const SUPPLEMENTARY_CONTENT_TYPES: &[&str] = &[
+    "application/pdf",
+    "application/json",
+    "application/zip",
+    "application/gzip",
+    "application/x-tar",
+    "text/csv",
+    "text/plain",
+    "text/markdown",
+    "image/png",
+    "image/jpeg",
+];
+const MAX_SUPPLEMENTARY_FILE_SIZE: usize = 50 * 1024 * 1024;
+
+/// Size of the pipe between the bundle archive writer and the response, and of the chunks sent.
+const BUNDLE_BUFFER_SIZE: usize = 64 * 1024;
+
+const DEFAULT_TRENDING_WINDOW_DAYS: i32 = 7;
+const MAX_TRENDING_WINDOW_DAYS: i32 = 365;
+const DEFAULT_TRENDING_LIMIT: i64 = 10;
+const MAX_TRENDING_LIMIT: i64 = 50;
+
+/// Number of metadata lookups the storage listing runs concurrently.
+const METADATA_LOOKUP_CONCURRENCY: usize = 16;
+
+/// How long a deleted publication can be restored. Storage cleanup then deletes its files.
+pub(crate) const DELETED_PUBLICATION_RETENTION: TimeDelta = TimeDelta::days(30);
+
 pub fn config(conf: &mut web::ServiceConfig) {
     let scope = web::scope("/publications")
+        .service(create_upload_intent)
         .service(create_publication)
         .service(list_publications)
         .service(list_publications_by_user)
+        .service(search_publications)
         .service(search_publications_by_title)
         .service(search_publications_by_tag)
+        .service(get_trending_publications)
+        .service(get_publications_batch)
         .service(get_publication)
+        .service(get_publication_pdf_url)
+        .service(download_publication_file)
+        .service(download_publication_bundle)
         .service(update_publication)
         .service(delete_publication)
+        .service(restore_publication)
         .service(get_publication_authors_handler)
         .service(get_publication_citations)
-        .service(get_cited_by);
+        .service(get_cited_by)
+        .service(export_publication_citation)
+        .service(get_publication_stats)
+        .service(upload_supplementary_files)
+        .service(list_supplementary_files)
+        .service(delete_supplementary_file)
+        .service(list_publication_storage)
+        .service(verify_publication_file);
     conf.service(scope);
 }
 
+pub mod cache;
+pub mod export;
 #[cfg(test)]
 mod tests;
+pub mod upload_intents;
+pub mod views;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_upload_intent,
+    create_publication,
+    get_publications_batch,
+    get_publication,
+    get_publication_pdf_url,
+    download_publication_file,
+    download_publication_bundle,
+    update_publication,
+    delete_publication,
+    restore_publication,
+    list_publications,
+    list_publications_by_user,
+    search_publications,
+    search_publications_by_title,
+    search_publications_by_tag,
+    get_trending_publications,
+    get_publication_authors_handler,
+    get_publication_citations,
+    get_cited_by,
+    export_publication_citation,
+    get_publication_stats,
+    list_publication_storage,
+    upload_supplementary_files,
+    list_supplementary_files,
+    delete_supplementary_file,
+    verify_publication_file
+))]
+pub struct PublicationsApi;
 
-#[derive(MultipartForm)]
+#[derive(MultipartForm, ToSchema)]
 #[allow(non_snake_case)]
 pub struct CreatePublicationForm {
+    #[schema(value_type = String)]
     title: Text<String>,
+    #[schema(value_type = Option<String>)]
     about: Option<Text<String>>,
+    /// JSON array of tags
+    #[schema(value_type = Option<String>)]
     tags: Option<Text<String>>,
-    authors: Option<Text<String>>,   // JSON array of author privy_ids
-    citations: Option<Text<String>>, // JSON array of publication UUIDs to cite
+    /// JSON array of author privy_ids
+    #[schema(value_type = Option<String>)]
+    authors: Option<Text<String>>,
+    /// JSON array of publication UUIDs to cite
+    #[schema(value_type = Option<String>)]
+    citations: Option<Text<String>>,
+    /// PDF file of the publication
+    #[schema(value_type = Option<String>, format = Binary)]
     file: Option<TempFile>,
+    /// Key returned by an upload intent, instead of a file
+    #[schema(value_type = Option<String>)]
+    s3key: Option<Text<String>>,
+    /// Hex SHA3-256 of the file uploaded to s3key
+    #[schema(value_type = Option<String>)]
+    sha3_hash: Option<Text<String>>,
+    /// Size in bytes of the file uploaded to s3key
+    #[schema(value_type = Option<i64>)]
+    file_size: Option<Text<i64>>,
 }
 
-#[post("/create")]
-async fn create_publication(
-    req: actix_web::HttpRequest,
-    MultipartForm(form): MultipartForm<CreatePublicationForm>,
+#[derive(Deserialize, ToSchema)]
+pub struct UploadIntentRequest {
+    file_name: String,
+    file_size: i64,
+    content_type: String,
+}
+
+/// Presigned URL to upload a publication file to, before creating the publication with its key.
+#[derive(Serialize, ToSchema)]
+struct UploadIntentResponse {
+    upload_url: String,
+    s3key: String,
+    expires_in_seconds: u64,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, body = UploadIntentResponse),
+        (status = 400, description = "Not a PDF or invalid size", body = ErrorResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 413, description = "Storage quota exceeded", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/upload-intent", wrap = "Privy")]
+async fn create_upload_intent(
+    user: AuthenticatedUser,
+    request: web::Json<UploadIntentRequest>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
-    // Get user ID from authenticated user
-    let claims = crate::auth::privy::get_privy_claims(&req).ok_or_else(|| {
-        actix_web::error::ErrorUnauthorized("Valid Privy authentication token required")
+) -> Result<HttpResponse, ApiError> {
+    if request.content_type != PUBLICATION_CONTENT_TYPE {
+        return Err(ApiError::validation("Only PDF files can be uploaded"));
+    }
+
+    if request.file_size <= 0 || request.file_size > data.max_publication_file_size {
+        return Err(ApiError::validation(format!(
+            "File size must be between 1 and {} bytes",
+            data.max_publication_file_size
+        )));
+    }
+
+    check_storage_quota(&data, &user.privy_id, request.file_size).await?;
+
+    // The publication created from the upload is given the id naming its directory
+    let s3key = publication_file_key(Uuid::new_v4(), &request.file_name);
+
+    let upload_url = data
+        .object_store
+        .presign_put(
+            &s3key,
+            &S3Bucket::Storage,
+            &request.content_type,
+            request.file_size,
+            UPLOAD_INTENT_EXPIRY,
+        )
+        .await
+        .map_err(|err| {
+            tracing::error!("Error presigning upload URL: {}", err);
+            ApiError::internal("Failed to create upload URL")
+        })?;
+    data.upload_intents
+        .record(&s3key.0, &user.privy_id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error recording upload intent for {}: {}", s3key.0, err);
+            ApiError::internal("Failed to create upload URL")
+        })?;
+
+    tracing::info!(
+        "Issued upload intent for '{}' to user {}",
+        s3key,
+        user.privy_id
+    );
+
+    Ok(HttpResponse::Ok().json(UploadIntentResponse {
+        upload_url,
+        s3key: s3key.0,
+        expires_in_seconds: UPLOAD_INTENT_EXPIRY.as_secs(),
+    }))
+}
+
+/// Rejects, with a 413 reporting the current usage, uploads of `additional_bytes` that would take
+/// the files stored by `user_id` over the configured quota.
+async fn check_storage_quota(
+    data: &AppState,
+    user_id: &str,
+    additional_bytes: i64,
+) -> Result<(), ApiError> {
+    let Some(quota) = data.storage_quota else {
+        return Ok(());
+    };
+
+    let usage = data.sql_client.get_storage_usage(user_id).await?;
+
+    if usage + additional_bytes > quota {
+        return Err(
+            ApiError::payload_too_large("Storage quota exceeded").with_details(serde_json::json!({
+                "usage_bytes": usage,
+                "quota_bytes": quota,
+                "requested_bytes": additional_bytes
+            })),
+        );
+    }
+
+    Ok(())
+}
+
+/// Rejects uploaded publication files that are empty, larger than `max_size` or do not start
+/// with the PDF magic bytes, whatever their name and declared content type.
+async fn validate_pdf(file: &TempFile, max_size: i64) -> Result<(), ApiError> {
+    if file.size == 0 {
+        return Err(ApiError::validation("The publication file is empty"));
+    }
+    if file.size as i64 > max_size {
+        return Err(ApiError::validation(format!(
+            "The publication file is {} bytes, larger than the maximum of {} bytes",
+            file.size, max_size
+        )));
+    }
+
+    let mut magic = [0; PDF_MAGIC.len()];
+    let read = async {
+        let mut reader = tokio::fs::File::open(file.file.path()).await?;
+        tokio::io::AsyncReadExt::read_exact(&mut reader, &mut magic).await
+    };
+    // Files shorter than the magic bytes are not PDFs either
+    if let Err(err) = read.await
+        && err.kind() != std::io::ErrorKind::UnexpectedEof
+    {
+        tracing::error!("Error reading uploaded file: {}", err);
+        return Err(ApiError::internal("Failed to upload file"));
+    }
+    if magic != PDF_MAGIC {
+        return Err(ApiError::validation(
+            "The publication file is not a PDF document",
+        ));
+    }
+
+    Ok(())
+}
+
+/// A publication file written to storage, with the digests computed while uploading it.
+struct StoredPublicationFile {
+    s3key: String,
+    hashes: FileHashes,
+    size: i64,
+}
+
+/// Returns the key of a file named `file_name` in the `publications/<id>/` directory of a
+/// publication.
+fn publication_file_key(publication_id: Uuid, file_name: &str) -> S3Key {
+    S3Key(format!(
+        "publications/{}/{}",
+        publication_id,
+        sanitize_filename(file_name)
+    ))
+}
+
+/// Uploads a publication file to the publication's directory in the storage bucket, tagging it
+/// with its uploader, publication and content hashes.
+async fn store_publication_file(
+    object_store: &dyn ObjectStore,
+    file: &TempFile,
+    uploader: Option<&str>,
+    publication_id: Uuid,
+) -> Result<StoredPublicationFile, ApiError> {
+    let original_file_name = file.file_name.as_deref().unwrap_or_default();
+    let s3key = publication_file_key(publication_id, original_file_name);
+
+    store_tagged_file(object_store, file, s3key, uploader, Some(publication_id)).await
+}
+
+/// Uploads a file to the storage bucket under `s3key`, tagging it with its uploader, publication
+/// and content hashes.
+async fn store_tagged_file(
+    object_store: &dyn ObjectStore,
+    file: &TempFile,
+    s3key: S3Key,
+    uploader: Option<&str>,
+    publication_id: Option<Uuid>,
+) -> Result<StoredPublicationFile, ApiError> {
+    let original_file_name = file.file_name.as_deref().unwrap_or_default();
+    let path = file.file.path().to_path_buf();
+    let hashes = web::block(move || hash_local_file(&path))
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|hashed| hashed)
+        .map_err(|err| {
+            tracing::error!("Error hashing uploaded file: {}", err);
+            ApiError::internal("Failed to upload file")
+        })?;
+
+    let metadata = publication_file_metadata(uploader, publication_id, original_file_name, &hashes);
+
+    let stored_key = object_store
+        .store_file_at(file, s3key, metadata)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error uploading file to S3: {}", err);
+            if err.is::<StorageUnavailable>() {
+                ApiError::service_unavailable("Storage unavailable")
+            } else {
+                ApiError::internal("Failed to upload file")
+            }
+        })?;
+
+    Ok(StoredPublicationFile {
+        s3key: stored_key.0,
+        hashes,
+        size: file.size as i64,
+    })
+}
+
+/// Builds the S3 user metadata recorded on a publication file, so that bucket audits can trace
+/// objects back to their uploader and publication.
+fn publication_file_metadata(
+    uploader: Option<&str>,
+    publication_id: Option<Uuid>,
+    original_file_name: &str,
+    hashes: &FileHashes,
+) -> HashMap<String, String> {
+    let mut metadata = HashMap::from([
+        (
+            METADATA_ORIGINAL_FILENAME.to_string(),
+            original_file_name.to_string(),
+        ),
+        (METADATA_SHA3_HASH.to_string(), hashes.sha3_256.clone()),
+        (METADATA_SHA256.to_string(), hashes.sha256.clone()),
+    ]);
+    if let Some(uploader) = uploader {
+        metadata.insert(METADATA_UPLOADER.to_string(), uploader.to_string());
+    }
+    if let Some(publication_id) = publication_id {
+        metadata.insert(
+            METADATA_PUBLICATION_ID.to_string(),
+            publication_id.to_string(),
+        );
+    }
+    metadata
+}
+
+/// Checks that a file uploaded through an upload intent issued to `user_id` landed in storage with
+/// the declared size and contents, is not the file of a publication yet and fits in the user's
+/// storage quota, returning its verified hashes.
+async fn verify_uploaded_file(
+    data: &AppState,
+    user_id: &str,
+    s3key: &str,
+    sha3_hash: Option<&str>,
+    file_size: Option<i64>,
+) -> Result<FileHashes, ApiError> {
+    if !s3key.starts_with("publications/") || s3key.split('/').any(|segment| segment == "..") {
+        return Err(ApiError::validation("Invalid s3key"));
+    }
+
+    let (Some(sha3_hash), Some(file_size)) = (sha3_hash, file_size) else {
+        return Err(ApiError::validation(
+            "sha3_hash and file_size are required when providing an s3key",
+        ));
+    };
+
+    let uploader = data.upload_intents.uploader(s3key).await.map_err(|err| {
+        tracing::error!("Error reading upload intent for {}: {}", s3key, err);
+        ApiError::internal("Failed to check uploaded file")
     })?;
+    match uploader {
+        None => {
+            return Err(ApiError::validation(
+                "The s3key was not issued by an upload intent, or it expired",
+            ));
+        }
+        Some(uploader) if uploader != user_id => {
+            return Err(ApiError::forbidden("The s3key was issued to another user"));
+        }
+        Some(_) => {}
+    }
+    if data.sql_client.is_s3key_referenced(s3key).await? {
+        return Err(ApiError::conflict(
+            "The s3key is already the file of a publication",
+        ));
+    }
+
+    let object_store = data.object_store.as_ref();
+    let stored_size = object_store
+        .get_file_size(s3key, &S3Bucket::Storage)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error checking uploaded file {}: {}", s3key, err);
+            ApiError::internal("Failed to check uploaded file")
+        })?;
+
+    match stored_size {
+        None => return Err(ApiError::validation("Uploaded file not found in storage")),
+        Some(size) if size != file_size => {
+            return Err(ApiError::validation(
+                "Uploaded file size does not match the declared file_size",
+            ));
+        }
+        Some(_) => {}
+    }
+    check_storage_quota(data, user_id, file_size).await?;
+
+    let hashes = compute_file_hashes(object_store, s3key)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error hashing uploaded file {}: {}", s3key, err);
+            ApiError::internal("Failed to verify uploaded file")
+        })?;
+
+    if !hashes.sha3_256.eq_ignore_ascii_case(sha3_hash) {
+        return Err(ApiError::validation(
+            "Uploaded file does not match the declared sha3_hash",
+        ));
+    }
+
+    Ok(hashes)
+}
+
+/// Streams a stored file from S3 and computes its digests.
+async fn compute_file_hashes(object_store: &dyn ObjectStore, s3key: &str) -> ZResult<FileHashes> {
+    let body = object_store.retrieve_storage_file(s3key).await?.body;
+    hash_byte_stream(body).await
+}
 
-    let user_id = claims.sub;
+#[utoipa::path(
+    request_body(content = CreatePublicationForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = Publication),
+        (status = 400, body = ErrorResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "The s3key was issued to another user", body = ErrorResponse),
+        (status = 409, description = "The s3key is already the file of a publication", body = ErrorResponse),
+        (status = 413, description = "Storage quota exceeded", body = ErrorResponse),
+        (status = 429, description = "Too many publications created recently", body = ErrorResponse),
+        (status = 503, description = "Storage unavailable", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/create", wrap = "RateLimit::new(PUBLISH)", wrap = "Privy")]
+async fn create_publication(
+    user: AuthenticatedUser,
+    MultipartForm(form): MultipartForm<CreatePublicationForm>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = user.privy_id;
     // Parse tags from JSON array string
     let tags = if let Some(tags_text) = &form.tags {
         match serde_json::from_str::<Vec<String>>(&tags_text.0) {
             Ok(tags) => Some(tags),
             Err(err) => {
                 tracing::error!("Failed to parse tags JSON: {}", err);
-                return Err(ErrorBadRequest("Invalid tags format. Expected JSON array"));
+                return Err(ApiError::validation(
+                    "Invalid tags format. Expected JSON array",
+                ));
             }
         }
     } else {
@@ -76,7 +556,7 @@ async fn create_publication(
             Ok(authors) => Some(authors),
             Err(err) => {
                 tracing::error!("Failed to parse authors JSON: {}", err);
-                return Err(ErrorBadRequest(
+                return Err(ApiError::validation(
                     "Invalid authors format. Expected JSON array of author IDs",
                 ));
             }
@@ -91,7 +571,7 @@ async fn create_publication(
             Ok(citations) => Some(citations),
             Err(err) => {
                 tracing::error!("Failed to parse citations JSON: {}", err);
-                return Err(ErrorBadRequest(
+                return Err(ApiError::validation(
                     "Invalid citations format. Expected JSON array of publication UUIDs",
                 ));
             }
@@ -99,29 +579,61 @@ async fn create_publication(
     } else {
         None
     };
+    if let Some(authors) = &authors
+        && authors.iter().collect::<HashSet<_>>().len() < authors.len()
+    {
+        return Err(ApiError::validation("Authors must not be repeated"));
+    }
 
-    // Handle file upload if present
+    if form.file.is_some() && form.s3key.is_some() {
+        return Err(ApiError::validation(
+            "Provide either a file or an s3key, not both",
+        ));
+    }
+    if let Some(file) = &form.file {
+        validate_pdf(file, data.max_publication_file_size).await?;
+    }
+
+    // The id of the publication is chosen before creating it, so that its file is stored in its
+    // directory: the one allocated by the upload intent, or a new one
+    let mut publication_id = Uuid::new_v4();
+
+    // Verify a file uploaded directly to S3 through an upload intent
     let mut s3key = None;
-    if let Some(file) = form.file {
-        // Upload file to S3 using the storage bucket
-        let file_name = file
-            .file_name
-            .as_ref()
-            .map(|n| n.to_string())
-            .unwrap_or_else(|| "unnamed.pdf".to_string());
-
-        let s3_path = format!("publications/{}/{}", Uuid::new_v4(), file_name);
-
-        // Use upload_storage_files which expects Vec<TempFile>
-        data.s3_client
-            .upload_storage_files(vec![file], Some(s3_path.clone().into()))
-            .await
-            .map_err(|err| {
-                tracing::error!("Error uploading file to S3: {}", err);
-                ErrorInternalServerError("Failed to upload file")
-            })?;
+    let mut hashes = None;
+    let mut file_size = None;
+    if let Some(uploaded_key) = &form.s3key {
+        publication_id = storage_directory_id(&uploaded_key.0)
+            .ok_or_else(|| ApiError::validation("Invalid s3key"))?;
+        hashes = Some(
+            verify_uploaded_file(
+                &data,
+                &user_id,
+                &uploaded_key.0,
+                form.sha3_hash.as_ref().map(|h| h.0.as_str()),
+                form.file_size.as_ref().map(|s| s.0),
+            )
+            .await?,
+        );
+        s3key = Some(uploaded_key.0.clone());
+        file_size = form.file_size.as_ref().map(|s| s.0);
+    }
 
-        s3key = Some(s3_path);
+    // Handle file upload if present, keeping its key to delete it if the publication is not created
+    let mut stored_s3key = None;
+    if let Some(file) = form.file {
+        check_storage_quota(&data, &user_id, file.size as i64).await?;
+        let stored_file = store_publication_file(
+            data.object_store.as_ref(),
+            &file,
+            Some(&user_id),
+            publication_id,
+        )
+        .await?;
+        stored_s3key = Some(stored_file.s3key.clone());
+        s3key = Some(stored_file.s3key);
+        hashes = Some(stored_file.hashes);
+        file_size = Some(stored_file.size);
     }
 
     let new_publication = NewPublication {
@@ -130,412 +642,1752 @@ async fn create_publication(
         about: form.about.map(|a| a.0),
         tags,
         s3key,
+        paper_hash: hashes.as_ref().map(|hashes| hashes.sha3_256.clone()),
+        file_sha256: hashes.map(|hashes| hashes.sha256),
+        file_size,
     };
 
-    let publication = data
+    // The publication, its authors and citations are created together, so that a missing author or
+    // cited publication leaves nothing behind
+    let publication = match data
         .sql_client
-        .create_publication(&new_publication)
+        .create_publication_full(
+            publication_id,
+            &new_publication,
+            authors.as_deref().unwrap_or_default(),
+            citations.as_deref().unwrap_or_default(),
+        )
         .await
-        .map_err(|err| {
-            tracing::error!("Error creating publication: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
+    {
+        Ok(publication) => publication,
+        Err(err) => {
+            if let Some(s3key) = stored_s3key
+                && let Err(err) = data
+                    .object_store
+                    .delete_file(&S3Key(s3key.clone()), &S3Bucket::Storage)
+                    .await
+            {
+                tracing::error!("Error deleting unrecorded file {}: {}", s3key, err);
+            }
+            return Err(ApiError::database("Publication")(err));
+        }
+    };
 
-    // Associate authors with the publication if any are provided
-    if let Some(author_ids) = authors {
-        if let Err(err) = data
-            .sql_client
-            .set_publication_authors(publication.id, &author_ids)
-            .await
-        {
-            tracing::error!(
-                "Error setting authors for publication {}: {}",
-                publication.id,
-                err
-            );
-            // Continue even if setting authors fails
+    Ok(HttpResponse::Ok().json(publication))
+}
+
+/// Publication as seen by a viewer, who is told whether they own it when logged in, with its
+/// authors and audience.
+#[derive(Serialize, ToSchema)]
+struct PublicationView {
+    #[serde(flatten)]
+    publication: Publication,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_owner: Option<bool>,
+    /// Views recorded before this one
+    view_count: i64,
+    /// Authors, in author order
+    authors: Vec<Author>,
+    /// Citations of the publication by others
+    citation_count: i64,
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, body = PublicationView),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    ),
+    security((), ("privy" = []), ("session" = []))
+)]
+#[get("/{publication_id}")]
+async fn get_publication(
+    MaybeAuthenticated(user): MaybeAuthenticated,
+    publication_id: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let publication = match data.publication_cache.get(*publication_id).await {
+        Some(publication) => publication,
+        None => {
+            let publication = data
+                .sql_client
+                .get_publication(*publication_id)
+                .await
+                .map_err(ApiError::database("Publication"))?
+                .load_s3_contents(data.object_store.as_ref(), data.presign_expiry)
+                .await
+                .map_err(|err| {
+                    tracing::error!("Error presigning URLs of publication: {}", err);
+                    ApiError::internal("Internal server error")
+                })?;
+            data.publication_cache.set(&publication).await;
+            publication
         }
+    };
+
+    let (view_count, authors, citation_count) = futures::try_join!(
+        data.sql_client.get_view_count(publication.id),
+        data.sql_client
+            .get_publication_author_profiles(publication.id),
+        data.sql_client
+            .count_citations_to_publication(publication.id),
+    )?;
+    let viewer = user.map(|user| user.privy_id);
+    record_view(&data, publication.id, viewer.clone());
+
+    let is_owner = viewer.map(|viewer| publication.user_id.as_ref() == Some(&viewer));
+
+    Ok(HttpResponse::Ok().json(PublicationView {
+        publication,
+        is_owner,
+        view_count,
+        authors,
+        citation_count,
+    }))
+}
+
+/// Most publications `POST /publications/batch` returns at once.
+const MAX_BATCH_PUBLICATIONS: usize = 100;
+
+#[derive(Deserialize, ToSchema)]
+struct BatchPublicationsRequest {
+    /// Publications to return, at most 100
+    ids: Vec<Uuid>,
+}
+
+#[utoipa::path(
+    request_body = BatchPublicationsRequest,
+    responses(
+        (status = 200, description = "Publications in the order of the ids, `null` for those not found", body = Vec<Option<PublicationView>>),
+        (status = 400, description = "More than 100 ids", body = ErrorResponse)
+    ),
+    security((), ("privy" = []), ("session" = []))
+)]
+#[post("/batch")]
+async fn get_publications_batch(
+    MaybeAuthenticated(user): MaybeAuthenticated,
+    request: web::Json<BatchPublicationsRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let ids = &request.ids;
+    if ids.len() > MAX_BATCH_PUBLICATIONS {
+        return Err(ApiError::validation(format!(
+            "At most {MAX_BATCH_PUBLICATIONS} publications can be fetched at once, got {}",
+            ids.len()
+        )));
     }
+    let unique_ids: Vec<Uuid> = ids
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
 
-    // Create citations if any are provided
-    if let Some(cited_publication_ids) = citations {
-        for cited_publication_id in cited_publication_ids {
-            // Check that we're not citing ourselves
-            if cited_publication_id == publication.id {
-                tracing::warn!(
-                    "Publication {} attempted to cite itself, skipping",
-                    publication.id
-                );
-                continue;
-            }
+    let (publications, mut authors, citation_counts, view_counts) = futures::try_join!(
+        data.sql_client.get_publications_by_ids(&unique_ids),
+        data.sql_client
+            .get_publications_author_profiles(&unique_ids),
+        data.sql_client.count_citations_to_publications(&unique_ids),
+        data.sql_client.get_view_counts(&unique_ids),
+    )
+    .map_err(ApiError::database("Publication"))?;
+    let publications = futures::future::try_join_all(publications.iter().map(|publication| {
+        publication.load_s3_contents(data.object_store.as_ref(), data.presign_expiry)
+    }))
+    .await
+    .map_err(|err| {
+        tracing::error!("Error presigning URLs of publications: {}", err);
+        ApiError::internal("Internal server error")
+    })?;
 
-            // Check if citation already exists
-            let existing_citation = data
-                .sql_client
-                .get_citation_by_publications(publication.id, cited_publication_id)
-                .await;
-
-            match existing_citation {
-                Ok(Some(_)) => {
-                    tracing::warn!(
-                        "Citation already exists between {} and {}, skipping",
-                        publication.id,
-                        cited_publication_id
-                    );
-                    continue;
-                }
-                Ok(None) => {
-                    // Create the citation
-                    let new_citation = crate::db::sql::models::NewCitation {
-                        citing_publication_id: publication.id,
-                        cited_publication_id,
-                    };
-
-                    if let Err(err) = data.sql_client.create_citation(&new_citation).await {
-                        tracing::error!(
-                            "Error creating citation from {} to {}: {}",
-                            publication.id,
-                            cited_publication_id,
-                            err
-                        );
-                        // Continue with other citations even if one fails
+    // Fetching publications in batch, for instance for a reading list, does not count as viewing
+    // them
+    let viewer = user.map(|user| user.privy_id);
+    let found: HashMap<Uuid, PublicationView> = publications
+        .into_iter()
+        .map(|publication| {
+            let id = publication.id;
+            let view = PublicationView {
+                is_owner: viewer
+                    .as_ref()
+                    .map(|viewer| publication.user_id.as_ref() == Some(viewer)),
+                view_count: view_counts.get(&id).copied().unwrap_or(0),
+                authors: authors.remove(&id).unwrap_or_default(),
+                citation_count: citation_counts.get(&id).copied().unwrap_or(0),
+                publication,
+            };
+            (id, view)
+        })
+        .collect();
+
+    let items: Vec<Option<&PublicationView>> = ids.iter().map(|id| found.get(id)).collect();
+    Ok(HttpResponse::Ok().json(items))
+}
+
+/// Spawns the recording of a view of the publication, unless `viewer` already viewed it
+/// recently. Failures are only logged, the view being lost.
+fn record_view(data: &AppState, publication_id: Uuid, viewer: Option<PrivyId>) {
+    let sql_client = data.sql_client.clone();
+    let view_deduplication = data.view_deduplication.clone();
+    data.background_tasks
+        .spawn("publication view", move |cancellation| {
+            async move {
+                let record = async {
+                    if view_deduplication
+                        .is_new_view(publication_id, viewer.as_deref())
+                        .await
+                    {
+                        sql_client
+                            .record_view(publication_id, viewer.as_ref())
+                            .await?;
                     }
-                }
-                Err(err) => {
-                    tracing::error!("Error checking existing citation: {}", err);
-                    // Continue with other citations
+                    Ok::<_, sqlx::Error>(())
+                };
+                tokio::select! {
+                    result = record => {
+                        if let Err(err) = result {
+                            tracing::error!(
+                                "Error recording view of publication {}: {}",
+                                publication_id,
+                                err
+                            );
+                        }
+                    }
+                    _ = cancellation.cancelled() => {}
                 }
             }
-        }
-    }
+            .in_current_span()
+        });
+}
 
-    Ok(HttpResponse::Ok().json(publication))
+/// Audience of a publication.
+#[derive(Serialize, ToSchema)]
+struct PublicationStats {
+    /// Views of the publication, repeated views by a user within an hour counting once
+    views: i64,
+    /// Publications citing it
+    citations: i64,
 }
 
-#[get("/{publication_id}")]
-async fn get_publication(
+#[utoipa::path(
+    responses(
+        (status = 200, body = PublicationStats),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    )
+)]
+#[get("/{publication_id}/stats")]
+async fn get_publication_stats(
     publication_id: web::Path<Uuid>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let publication = data
-        .sql_client
+) -> Result<HttpResponse, ApiError> {
+    data.sql_client
         .get_publication(*publication_id)
         .await
-        .map_err(|err| {
-            tracing::error!("Error retrieving publication: {}", err);
-            match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("Publication not found"),
-                _ => ErrorInternalServerError("Internal server error"),
-            }
-        })?;
+        .map_err(ApiError::database("Publication"))?;
 
-    Ok(HttpResponse::Ok().json(publication))
+    let (views, citations) = futures::try_join!(
+        data.sql_client.get_view_count(*publication_id),
+        data.sql_client
+            .count_citations_to_publication(*publication_id),
+    )?;
+
+    Ok(HttpResponse::Ok().json(PublicationStats { views, citations }))
 }
 
-#[derive(MultipartForm)]
-#[allow(non_snake_case)]
-pub struct UpdatePublicationForm {
-    userId: Option<Text<String>>, // Changed from Uuid to String
-    title: Option<Text<String>>,
-    about: Option<Text<String>>,
-    tags: Option<Text<String>>, // JSON array string like ["tag1", "tag2"]
-    file: Option<TempFile>,
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum Disposition {
+    /// Rendered by the browser, for instance in the reader UI
+    Inline,
+    /// Saved as a file
+    #[default]
+    Attachment,
 }
 
-#[put("/{publication_id}")]
-async fn update_publication(
-    publication_id: web::Path<Uuid>,
-    MultipartForm(form): MultipartForm<UpdatePublicationForm>,
-    data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
-    // Parse tags from JSON array string if provided
-    let tags = if let Some(tags_text) = &form.tags {
-        match serde_json::from_str::<Vec<String>>(&tags_text.0) {
-            Ok(tags) => Some(tags),
-            Err(err) => {
-                tracing::error!("Failed to parse tags JSON: {}", err);
-                return Err(ErrorBadRequest("Invalid tags format. Expected JSON array"));
-            }
+impl Disposition {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Disposition::Inline => "inline",
+            Disposition::Attachment => "attachment",
         }
-    } else {
-        None
-    };
+    }
+}
 
-    // Handle file upload if present
-    let mut s3key = None;
-    if let Some(file) = form.file {
-        // Upload file to S3 using the storage bucket
-        let file_name = file
-            .file_name
-            .as_ref()
-            .map(|n| n.to_string())
-            .unwrap_or_else(|| "unnamed.pdf".to_string());
-
-        let s3_path = format!("publications/{}/{}", Uuid::new_v4(), file_name);
-
-        // Use upload_storage_files which expects Vec<TempFile>
-        data.s3_client
-            .upload_storage_files(vec![file], Some(s3_path.clone().into()))
-            .await
-            .map_err(|err| {
-                tracing::error!("Error uploading file to S3: {}", err);
-                ErrorInternalServerError("Failed to upload file")
-            })?;
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DispositionQuery {
+    /// How browsers should present the file, `attachment` by default
+    #[serde(default)]
+    #[param(inline)]
+    disposition: Disposition,
+}
 
-        s3key = Some(s3_path);
-    }
+/// Name a publication file is served under: the one it was uploaded with, or else the last
+/// segment of its key.
+fn publication_file_name<'a>(s3key: &'a str, metadata: &'a HashMap<String, String>) -> &'a str {
+    metadata
+        .get(METADATA_ORIGINAL_FILENAME)
+        .map(String::as_str)
+        .filter(|file_name| !file_name.is_empty())
+        .unwrap_or_else(|| s3key.rsplit('/').next().unwrap_or(s3key))
+}
 
-    let result = data
+#[utoipa::path(
+    params(DispositionQuery),
+    responses(
+        (status = 200, body = PdfUrlResponse),
+        (status = 404, description = "Publication or file not found", body = ErrorResponse)
+    )
+)]
+#[get("/{publication_id}/pdf-url")]
+async fn get_publication_pdf_url(
+    publication_id: web::Path<Uuid>,
+    query: web::Query<DispositionQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let publication = data
         .sql_client
-        .update_publication(
-            *publication_id,
-            form.userId.as_ref().map(|u| u.0.as_str()),
-            form.title.as_ref().map(|t| t.0.as_str()),
-            form.about.as_ref().map(|a| a.0.as_str()),
-            tags.as_deref(),
-            s3key.as_deref(),
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+
+    let s3key = publication
+        .s3key
+        .as_deref()
+        .filter(|s3key| !s3key.is_empty())
+        .ok_or_else(|| ApiError::not_found("Publication has no file"))?;
+
+    // A URL to a missing object would only fail once the client follows it, so the metadata
+    // lookup doubles as an existence check
+    let metadata = data
+        .object_store
+        .get_file_metadata(s3key, &S3Bucket::Storage)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error checking stored file {}: {}", s3key, err);
+            ApiError::internal("Failed to create download URL")
+        })?
+        .ok_or_else(|| {
+            tracing::error!(
+                "File {} of publication {} is missing from storage",
+                s3key,
+                publication.id
+            );
+            ApiError::not_found("File missing from storage")
+        })?;
+
+    let disposition = content_disposition(
+        query.disposition.as_str(),
+        publication_file_name(s3key, &metadata),
+    );
+    let expires_at = Utc::now() + data.presign_expiry;
+    let url = data
+        .object_store
+        .get_file_url(
+            s3key,
+            &S3Bucket::Storage,
+            data.presign_expiry,
+            Some(&disposition),
         )
         .await
         .map_err(|err| {
-            tracing::error!("Error updating publication: {}", err);
-            ErrorInternalServerError("Internal server error")
+            tracing::error!("Error presigning download URL: {}", err);
+            ApiError::internal("Failed to create download URL")
         })?;
 
-    if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("Publication not found"));
-    }
+    Ok(HttpResponse::Ok().json(PdfUrlResponse {
+        url,
+        expires_in_seconds: data.presign_expiry.as_secs(),
+        expires_at,
+    }))
+}
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Publication updated successfully"
-    })))
+/// Presigned URL to download a publication file from.
+#[derive(Serialize, ToSchema)]
+struct PdfUrlResponse {
+    url: String,
+    expires_in_seconds: u64,
+    #[serde(with = "rfc3339_millis")]
+    expires_at: DateTime<Utc>,
 }
 
-#[delete("/{publication_id}")]
-async fn delete_publication(
+/// Streams the publication file, honoring single `Range` requests so that viewers can render the
+/// first pages without downloading the whole file.
+#[utoipa::path(
+    params(DispositionQuery),
+    responses(
+        (status = 200, description = "The whole file", content_type = "application/pdf"),
+        (status = 206, description = "The requested range of the file", content_type = "application/pdf"),
+        (status = 404, description = "Publication or file not found", body = ErrorResponse),
+        (status = 416, description = "The requested range is outside the file")
+    )
+)]
+#[get("/{publication_id}/download")]
+async fn download_publication_file(
+    req: HttpRequest,
     publication_id: web::Path<Uuid>,
+    query: web::Query<DispositionQuery>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
-    // First get the publication to check if it has an S3 file
+) -> Result<HttpResponse, ApiError> {
     let publication = data
         .sql_client
         .get_publication(*publication_id)
         .await
+        .map_err(ApiError::database("Publication"))?;
+
+    let s3key = publication
+        .s3key
+        .as_deref()
+        .filter(|s3key| !s3key.is_empty())
+        .ok_or_else(|| ApiError::not_found("Publication has no file"))?;
+
+    let size = data
+        .object_store
+        .get_file_size(s3key, &S3Bucket::Storage)
+        .await
         .map_err(|err| {
-            tracing::error!("Error retrieving publication: {}", err);
-            match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("Publication not found"),
-                _ => ErrorInternalServerError("Internal server error"),
-            }
-        })?;
+            tracing::error!("Error checking stored file {}: {}", s3key, err);
+            ApiError::internal("Failed to download file")
+        })?
+        .ok_or_else(|| {
+            tracing::error!(
+                "File {} of publication {} is missing from storage",
+                s3key,
+                publication.id
+            );
+            ApiError::not_found("File missing from storage")
+        })? as u64;
 
-    // Delete S3 file if it exists
-    if let Some(s3key) = &publication.s3key {
-        // Extract just the filename from the path for deletion
-        if let Some(file_name) = s3key.split('/').last() {
-            if let Err(err) = data
-                .s3_client
-                .delete_storage_files(vec![file_name.to_string()], None)
-                .await
-            {
-                tracing::warn!("Failed to delete S3 file {}: {}", s3key, err);
-                // Continue with database deletion even if S3 deletion fails
-            }
+    let range_header = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    let range = match requested_range(range_header, size) {
+        RequestedRange::Full => None,
+        RequestedRange::Partial(range) => Some(range),
+        RequestedRange::Unsatisfiable => {
+            return Ok(HttpResponse::RangeNotSatisfiable()
+                .insert_header((header::CONTENT_RANGE, format!("bytes */{}", size)))
+                .finish());
         }
-    }
+    };
 
-    let result = data
-        .sql_client
-        .delete_publication(*publication_id)
+    let file = data
+        .object_store
+        .get_file(s3key, &S3Bucket::Storage, range)
         .await
         .map_err(|err| {
-            tracing::error!("Error deleting publication: {}", err);
-            ErrorInternalServerError("Internal server error")
+            tracing::error!("Error retrieving stored file {}: {}", s3key, err);
+            ApiError::internal("Failed to download file")
         })?;
 
-    if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("Publication not found"));
-    }
+    let (mut response, content_length) = match range {
+        Some(range) => {
+            let mut response = HttpResponse::build(StatusCode::PARTIAL_CONTENT);
+            response.insert_header((
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", range.start, range.end, size),
+            ));
+            (response, range.length())
+        }
+        None => (HttpResponse::Ok(), size),
+    };
 
-    Ok(HttpResponse::NoContent().finish())
+    let disposition = content_disposition(
+        query.disposition.as_str(),
+        publication_file_name(s3key, &file.metadata),
+    );
+    let body = futures::stream::unfold(file.body, |mut body| async move {
+        body.try_next().await.transpose().map(|chunk| (chunk, body))
+    });
+
+    Ok(response
+        .content_type(
+            file.content_type
+                .unwrap_or_else(|| PUBLICATION_CONTENT_TYPE.to_string()),
+        )
+        .insert_header((header::CONTENT_LENGTH, content_length))
+        .insert_header((header::CONTENT_DISPOSITION, disposition))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .streaming(body))
 }
 
-#[get("/list")]
-async fn list_publications(
-    data: web::Data<AppState>,
-    query: web::Query<ListPublicationsQuery>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let publications = data
+#[derive(Debug, PartialEq, Eq)]
+enum RequestedRange {
+    /// No range, or one that cannot be parsed and is therefore ignored
+    Full,
+    Partial(ByteRange),
+    /// A range starting past the end of the file, or several ranges, which are not supported
+    Unsatisfiable,
+}
+
+/// Interprets the `Range` header of a download request for a file of `size` bytes.
+fn requested_range(header: Option<&str>, size: u64) -> RequestedRange {
+    let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+        return RequestedRange::Full;
+    };
+    if spec.contains(',') {
+        return RequestedRange::Unsatisfiable;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return RequestedRange::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Suffix range, the last `length` bytes of the file
+        ("", length) => match length.parse::<u64>() {
+            Ok(0) => return RequestedRange::Unsatisfiable,
+            Ok(length) => (size.saturating_sub(length), size.saturating_sub(1)),
+            Err(_) => return RequestedRange::Full,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, size.saturating_sub(1)),
+            Err(_) => return RequestedRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+            _ => return RequestedRange::Full,
+        },
+    };
+
+    if size == 0 || start >= size {
+        return RequestedRange::Unsatisfiable;
+    }
+
+    RequestedRange::Partial(ByteRange { start, end })
+}
+
+/// A stored object to be written into a publication bundle.
+struct BundleEntry {
+    s3key: String,
+    /// Name of the entry in the archive, or `None` to name it after the stored file.
+    name: Option<String>,
+}
+
+/// Streams a zip archive of the publication file and its supplementary files, which are placed
+/// under `supplementary/`. Files are stored uncompressed and fetched one at a time, so memory use
+/// does not depend on their size.
+#[utoipa::path(responses(
+    (status = 200, description = "Zip archive of the publication files", content_type = "application/zip"),
+    (status = 404, description = "Publication or files not found", body = ErrorResponse)
+))]
+#[get("/{publication_id}/bundle.zip")]
+async fn download_publication_bundle(
+    publication_id: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let publication = data
+        .sql_client
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+
+    let supplementary_files = data
+        .sql_client
+        .list_publication_files(publication.id)
+        .await?;
+
+    let mut entries: Vec<BundleEntry> = publication
+        .s3key
+        .iter()
+        .filter(|s3key| !s3key.is_empty())
+        .map(|s3key| BundleEntry {
+            s3key: s3key.clone(),
+            name: None,
+        })
+        .collect();
+    let mut names = std::collections::HashSet::new();
+    for file in supplementary_files {
+        let name = unique_entry_name(&mut names, &sanitize_filename(&file.filename));
+        entries.push(BundleEntry {
+            s3key: file.s3key,
+            name: Some(format!("supplementary/{}", name)),
+        });
+    }
+    if entries.is_empty() {
+        return Err(ApiError::not_found("Publication has no files"));
+    }
+
+    let (reader, writer) = tokio::io::duplex(BUNDLE_BUFFER_SIZE);
+    let object_store = data.object_store.clone();
+    let publication_id = publication.id;
+    let writing = tokio::spawn(async move {
+        let result = write_bundle(object_store.as_ref(), entries, writer).await;
+        if let Err(err) = &result {
+            tracing::error!(
+                "Error writing bundle of publication {}: {}",
+                publication_id,
+                err
+            );
+        }
+        result
+    });
+
+    let body = futures::stream::unfold(Some((reader, writing)), |state| async move {
+        let (mut reader, writing) = state?;
+        let mut buffer = vec![0; BUNDLE_BUFFER_SIZE];
+        match tokio::io::AsyncReadExt::read(&mut reader, &mut buffer).await {
+            Ok(0) => match writing.await {
+                Ok(Ok(())) => None,
+                // Failing the stream aborts the response, rather than serving a truncated archive
+                // as if it were complete
+                Ok(Err(err)) => Some((Err(std::io::Error::other(err.to_string())), None)),
+                Err(err) => Some((Err(std::io::Error::other(err)), None)),
+            },
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(bytes::Bytes::from(buffer)), Some((reader, writing))))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            content_disposition("attachment", &format!("{}.zip", publication.title)),
+        ))
+        .streaming(body))
+}
+
+/// Writes a zip archive of `entries` to `writer`, fetching each object only once the previous
+/// one has been written.
+async fn write_bundle(
+    object_store: &dyn ObjectStore,
+    entries: Vec<BundleEntry>,
+    writer: tokio::io::DuplexStream,
+) -> ZResult<()> {
+    let mut zip = async_zip::base::write::ZipFileWriter::with_tokio(writer);
+
+    for entry in entries {
+        let mut file = object_store
+            .get_file(&entry.s3key, &S3Bucket::Storage, None)
+            .await?;
+        let name = entry.name.unwrap_or_else(|| {
+            sanitize_filename(publication_file_name(&entry.s3key, &file.metadata))
+        });
+
+        let builder = async_zip::ZipEntryBuilder::new(name.into(), async_zip::Compression::Stored);
+        let mut entry_writer = zip.write_entry_stream(builder).await?;
+        while let Some(chunk) = file.body.try_next().await? {
+            futures::AsyncWriteExt::write_all(&mut entry_writer, &chunk).await?;
+        }
+        entry_writer.close().await?;
+    }
+
+    let mut writer = zip.close().await?.into_inner();
+    tokio::io::AsyncWriteExt::shutdown(&mut writer).await?;
+
+    Ok(())
+}
+
+/// Returns `name`, or a variant numbered after it if it is already in `names`, and records the
+/// result so that archive entries never collide.
+fn unique_entry_name(names: &mut std::collections::HashSet<String>, name: &str) -> String {
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 => name.split_at(index),
+        _ => (name, ""),
+    };
+
+    let mut candidate = name.to_string();
+    let mut counter = 1;
+    while !names.insert(candidate.clone()) {
+        counter += 1;
+        candidate = format!("{}-{}{}", stem, counter, extension);
+    }
+
+    candidate
+}
+
+#[derive(MultipartForm, ToSchema)]
+pub struct UpdatePublicationForm {
+    #[schema(value_type = Option<String>)]
+    title: Option<Text<String>>,
+    #[schema(value_type = Option<String>)]
+    about: Option<Text<String>>,
+    /// JSON array string like ["tag1", "tag2"]
+    #[schema(value_type = Option<String>)]
+    tags: Option<Text<String>>,
+    /// New PDF file of the publication, the previous one is kept as a version
+    #[schema(value_type = Option<String>, format = Binary)]
+    file: Option<TempFile>,
+}
+
+#[utoipa::path(
+    request_body(content = UpdatePublicationForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = MessageResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[put("/{publication_id}", wrap = "Privy")]
+async fn update_publication(
+    user: AuthenticatedUser,
+    publication_id: web::Path<Uuid>,
+    MultipartForm(form): MultipartForm<UpdatePublicationForm>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    // Parse tags from JSON array string if provided
+    let tags = if let Some(tags_text) = &form.tags {
+        match serde_json::from_str::<Vec<String>>(&tags_text.0) {
+            Ok(tags) => Some(tags),
+            Err(err) => {
+                tracing::error!("Failed to parse tags JSON: {}", err);
+                return Err(ApiError::validation(
+                    "Invalid tags format. Expected JSON array",
+                ));
+            }
+        }
+    } else {
+        None
+    };
+
+    let publication = data
         .sql_client
-        .list_publications(query.page, query.limit)
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+    require_publication_owner(&user, &publication)?;
+
+    // Handle file upload if present
+    let mut stored_file = None;
+    let mut archived_key = None;
+    if let Some(file) = form.file {
+        validate_pdf(&file, data.max_publication_file_size).await?;
+
+        if let Some(owner) = &publication.user_id {
+            check_storage_quota(&data, owner, file.size as i64).await?;
+        }
+
+        // Keep the file being replaced as a version of the publication
+        if let Some(previous_key) = &publication.s3key {
+            archived_key =
+                archive_publication_file(data.object_store.as_ref(), publication.id, previous_key)
+                    .await?;
+        }
+
+        stored_file = Some(
+            store_publication_file(
+                data.object_store.as_ref(),
+                &file,
+                Some(&user.privy_id),
+                publication.id,
+            )
+            .await?,
+        );
+    }
+
+    let error = match data
+        .sql_client
+        .update_publication(
+            *publication_id,
+            None,
+            form.title.as_ref().map(|t| t.0.as_str()),
+            form.about.as_ref().map(|a| a.0.as_str()),
+            tags.as_deref(),
+            stored_file.as_ref().map(|file| file.s3key.as_str()),
+        )
+        .await
+    {
+        Ok(result) if result.rows_affected() > 0 => None,
+        Ok(_) => Some(ApiError::not_found("Publication not found")),
+        Err(err) => Some(ApiError::from(err)),
+    };
+    if let Some(error) = error {
+        // Neither the new file nor the version of the previous one are recorded. A new file with the
+        // name of the previous one replaced it, and is put back from its version instead.
+        let mut unrecorded: Vec<&String> = archived_key.iter().collect();
+        if let Some(stored_file) = &stored_file {
+            match &archived_key {
+                Some(version_key) if publication.s3key.as_ref() == Some(&stored_file.s3key) => {
+                    if let Err(err) = data
+                        .object_store
+                        .copy_file(
+                            &S3Key(version_key.clone()),
+                            &S3Key(stored_file.s3key.clone()),
+                        )
+                        .await
+                    {
+                        tracing::error!(
+                            "Error restoring {} from {}: {}",
+                            stored_file.s3key,
+                            version_key,
+                            err
+                        );
+                        // The version is the only copy left of the previous file
+                        unrecorded.clear();
+                    }
+                }
+                _ => unrecorded.push(&stored_file.s3key),
+            }
+        }
+        for s3key in unrecorded {
+            if let Err(err) = data
+                .object_store
+                .delete_file(&S3Key(s3key.clone()), &S3Bucket::Storage)
+                .await
+            {
+                tracing::error!("Error deleting unrecorded file {}: {}", s3key, err);
+            }
+        }
+        return Err(error);
+    }
+
+    // The hashes and size of the previous file no longer describe the publication
+    if let Some(stored_file) = &stored_file {
+        data.sql_client
+            .set_publication_file_details(
+                *publication_id,
+                &stored_file.hashes.sha3_256,
+                &stored_file.hashes.sha256,
+                stored_file.size,
+            )
+            .await?;
+    }
+    data.publication_cache.mark_changed(*publication_id).await;
+
+    Ok(HttpResponse::Ok().json(MessageResponse::success("Publication updated successfully")))
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Publication deleted"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[delete("/{publication_id}", wrap = "Privy")]
+async fn delete_publication(
+    user: AuthenticatedUser,
+    publication_id: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let publication = data
+        .sql_client
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+    require_publication_owner(&user, &publication)?;
+
+    // Stored files are kept, so that restoring the publication brings them back
+    let result = data.sql_client.delete_publication(*publication_id).await?;
+    data.publication_cache.mark_changed(*publication_id).await;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Publication not found"));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Publication restored", body = Publication),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+        (status = 404, description = "No publication with this id deleted within the retention period", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/{publication_id}/restore", wrap = "Privy")]
+async fn restore_publication(
+    req: actix_web::HttpRequest,
+    publication_id: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let publication = data
+        .sql_client
+        .restore_publication(*publication_id, Utc::now() - DELETED_PUBLICATION_RETENTION)
+        .await
+        .map_err(ApiError::database("Deleted publication"))?;
+    data.publication_cache.invalidate(*publication_id).await;
+
+    Ok(HttpResponse::Ok().json(publication))
+}
+
+/// Copies the file stored under `s3key` into the publication's `versions/` directory. Files that
+/// are missing from the bucket are skipped, as there is nothing left to preserve. Returns the key
+/// of the copy, if any.
+async fn archive_publication_file(
+    object_store: &dyn ObjectStore,
+    publication_id: Uuid,
+    s3key: &str,
+) -> Result<Option<String>, ApiError> {
+    let exists = object_store
+        .get_file_size(s3key, &S3Bucket::Storage)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error retrieving metadata of {}: {}", s3key, err);
+            ApiError::internal("Failed to archive previous file")
+        })?
+        .is_some();
+
+    if !exists {
+        tracing::warn!(
+            "Previous file {} of publication {} not found in S3, skipping archive",
+            s3key,
+            publication_id
+        );
+        return Ok(None);
+    }
+
+    let version_key = publication_version_key(publication_id, s3key, Utc::now());
+    object_store
+        .copy_file(&S3Key(s3key.to_string()), &S3Key(version_key.clone()))
         .await
         .map_err(|err| {
-            tracing::error!("Error listing publications: {}", err);
-            ErrorInternalServerError("Internal server error")
+            tracing::error!("Error archiving {}: {}", s3key, err);
+            ApiError::internal("Failed to archive previous file")
         })?;
 
-    let total_count = data.sql_client.count_publications().await.map_err(|err| {
-        tracing::error!("Error counting publications: {}", err);
-        ErrorInternalServerError("Internal server error")
-    })?;
+    Ok(Some(version_key))
+}
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "publications": publications,
-        "total": total_count,
-        "page": query.page.unwrap_or(1),
-        "limit": query.limit.unwrap_or(20)
-    })))
+/// Returns the key under which a replaced file of a publication is archived.
+fn publication_version_key(
+    publication_id: Uuid,
+    s3key: &str,
+    archived_at: DateTime<Utc>,
+) -> String {
+    let file_name = s3key.rsplit('/').next().unwrap_or(s3key);
+    format!(
+        "{}{}_{}",
+        publication_versions_prefix(publication_id),
+        archived_at.format("%Y%m%dT%H%M%S%.3fZ"),
+        file_name
+    )
 }
 
-#[derive(Deserialize)]
-struct ListPublicationsQuery {
-    page: Option<i64>,
-    limit: Option<i64>,
+fn publication_versions_prefix(publication_id: Uuid) -> String {
+    format!("publications/{}/versions/", publication_id)
+}
+
+/// Returns the storage prefixes holding a publication's objects: its own directory and, for
+/// files stored under a directory not named after the publication, the directory of its file.
+fn publication_storage_prefixes(publication: &Publication) -> Vec<String> {
+    let mut prefixes = vec![format!("publications/{}/", publication.id)];
+
+    if let Some(s3key) = &publication.s3key
+        && let Some((directory, _)) = s3key.rsplit_once('/')
+        && let Some(("publications", segment)) = directory.split_once('/')
+        && !segment.is_empty()
+        && !segment.contains('/')
+        && segment != ".."
+    {
+        let prefix = format!("{}/", directory);
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix);
+        }
+    }
+
+    prefixes
+}
+
+/// Lists publications, most recent first, by page or, given a cursor, after the cursor's
+/// publication. Every page but the last returns the cursor of the next one.
+#[utoipa::path(
+    params(PageQuery, CursorQuery),
+    responses(
+        (status = 200, description = "Page of publications, as a `CursorPage` when `cursor` is given", body = Paginated<Publication>),
+        (status = 400, description = "Invalid page or cursor", body = ErrorResponse)
+    )
+)]
+#[get("/list")]
+async fn list_publications(
+    data: web::Data<AppState>,
+    page: web::Query<PageQuery>,
+    cursor: web::Query<CursorQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(cursor) = cursor.cursor {
+        // One more publication than asked for tells whether another page follows
+        let publications = data
+            .sql_client
+            .list_publications_after(cursor, page.limit() + 1)
+            .await?;
+
+        return Ok(HttpResponse::Ok().json(CursorPage::new(
+            publications,
+            page.limit(),
+            publication_cursor,
+        )));
+    }
+
+    let (publications, total_count) = data.sql_client.list_publications(*page).await?;
+
+    Ok(HttpResponse::Ok().json(
+        Paginated::new(publications, total_count, *page).with_next_cursor(publication_cursor),
+    ))
+}
+
+fn publication_cursor(publication: &Publication) -> Cursor {
+    Cursor {
+        created_at: publication.created_at,
+        id: publication.id,
+    }
 }
 
+#[utoipa::path(
+    params(PageQuery),
+    responses(
+        (status = 200, body = Paginated<Publication>),
+        (status = 400, description = "Invalid page", body = ErrorResponse)
+    )
+)]
 #[get("/user/{privy_id}")]
 async fn list_publications_by_user(
     privy_id: web::Path<String>,
     data: web::Data<AppState>,
-    query: web::Query<ListPublicationsQuery>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let publications = data
+    page: web::Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (publications, total_count) = data
         .sql_client
-        .list_publications_by_user(&privy_id, query.page, query.limit)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error listing user publications: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
+        .list_publications_by_user(&privy_id, *page)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(Paginated::new(publications, total_count, *page)))
+}
 
-    let total_count = data
+/// Searches the titles, tags and abstracts of publications for the words of the query, matching
+/// their other forms too, such as "learn" for "learning".
+#[utoipa::path(
+    params(SearchPublicationsQuery, PageQuery),
+    responses(
+        (status = 200, description = "Matching publications, most relevant first", body = Paginated<Publication>),
+        (status = 400, description = "Empty search query or invalid page", body = ErrorResponse)
+    )
+)]
+#[get("/search")]
+async fn search_publications(
+    data: web::Data<AppState>,
+    query: web::Query<SearchPublicationsQuery>,
+    page: web::Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if query.query.trim().is_empty() {
+        return Err(ApiError::validation("Search query cannot be empty"));
+    }
+
+    let (publications, total_count) = data
         .sql_client
-        .count_publications_by_user(&privy_id)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error counting user publications: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
+        .search_publications(&query.query, *page)
+        .await?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "publications": publications,
-        "total": total_count,
-        "page": query.page.unwrap_or(1),
-        "limit": query.limit.unwrap_or(20)
-    })))
+    Ok(HttpResponse::Ok().json(Paginated::new(publications, total_count, *page)))
 }
 
+#[utoipa::path(
+    params(SearchPublicationsQuery, PageQuery),
+    responses(
+        (status = 200, description = "Matching publications, most relevant first", body = Paginated<TitleSearchResult>),
+        (status = 400, description = "Empty search query or invalid page", body = ErrorResponse)
+    )
+)]
 #[get("/search/title")]
 async fn search_publications_by_title(
     data: web::Data<AppState>,
     query: web::Query<SearchPublicationsQuery>,
-) -> Result<HttpResponse, actix_web::Error> {
+    page: web::Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
     if query.query.is_empty() {
-        return Err(ErrorBadRequest("Search query cannot be empty"));
+        return Err(ApiError::validation("Search query cannot be empty"));
     }
 
-    let publications = data
+    let (matches, total_count) = data
         .sql_client
-        .search_publications_by_title(&query.query, query.page, query.limit)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error searching publications by title: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
+        .search_publications_by_title(&query.query, *page)
+        .await?;
+    let results: Vec<TitleSearchResult> = matches
+        .into_iter()
+        .map(|found| TitleSearchResult {
+            highlight: title_highlight(&found.publication.title, &query.query),
+            publication: found.publication,
+            score: found.score,
+        })
+        .collect();
 
-    Ok(HttpResponse::Ok().json(publications))
+    Ok(HttpResponse::Ok().json(Paginated::new(results, total_count, *page)))
+}
+
+/// Publication found by a title search.
+#[derive(Serialize, ToSchema)]
+struct TitleSearchResult {
+    #[serde(flatten)]
+    publication: Publication,
+    /// Relevance to the query: 2 and above for an exact title, 1 and above for a title starting
+    /// with it, plus the similarity of the title to it
+    score: f32,
+    /// Part of the title matching the query, for clients to emphasize
+    #[serde(skip_serializing_if = "Option::is_none")]
+    highlight: Option<TitleHighlight>,
 }
 
+/// Range of characters of a title, counted in Unicode scalar values.
+#[derive(Debug, PartialEq, Eq, Serialize, ToSchema)]
+struct TitleHighlight {
+    start: usize,
+    end: usize,
+}
+
+/// Returns the first range of `title` equal to `query` ignoring case.
+fn title_highlight(title: &str, query: &str) -> Option<TitleHighlight> {
+    let title: Vec<char> = title.chars().collect();
+    let query: Vec<char> = query.chars().collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    let start = title.windows(query.len()).position(|window| {
+        window
+            .iter()
+            .zip(&query)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+    })?;
+    Some(TitleHighlight {
+        start,
+        end: start + query.len(),
+    })
+}
+
+#[utoipa::path(
+    params(SearchByTagQuery, PageQuery),
+    responses(
+        (status = 200, description = "Tagged publications, most recent first", body = Paginated<Publication>),
+        (status = 400, description = "Empty tag or invalid page", body = ErrorResponse)
+    )
+)]
 #[get("/search/tag")]
 async fn search_publications_by_tag(
     data: web::Data<AppState>,
     query: web::Query<SearchByTagQuery>,
-) -> Result<HttpResponse, actix_web::Error> {
+    page: web::Query<PageQuery>,
+) -> Result<HttpResponse, ApiError> {
     if query.tag.is_empty() {
-        return Err(ErrorBadRequest("Tag cannot be empty"));
+        return Err(ApiError::validation("Tag cannot be empty"));
+    }
+
+    let (publications, total_count) = data
+        .sql_client
+        .search_publications_by_tag(&query.tag, *page)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(Paginated::new(publications, total_count, *page)))
+}
+
+/// Ranks publications by the citations they received lately, for the trending rail of the
+/// homepage. Recent publications fill the rail when too few were cited within the window.
+#[utoipa::path(
+    params(TrendingQuery),
+    responses(
+        (status = 200, description = "Trending publications, highest score first", body = Vec<TrendingPublication>),
+        (status = 400, description = "Invalid window or limit", body = ErrorResponse)
+    )
+)]
+#[get("/trending")]
+async fn get_trending_publications(
+    data: web::Data<AppState>,
+    query: web::Query<TrendingQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let window_days = query.window_days.unwrap_or(DEFAULT_TRENDING_WINDOW_DAYS);
+    if !(1..=MAX_TRENDING_WINDOW_DAYS).contains(&window_days) {
+        return Err(ApiError::validation(format!(
+            "window_days must be between 1 and {MAX_TRENDING_WINDOW_DAYS}"
+        )));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_TRENDING_LIMIT);
+    if !(1..=MAX_TRENDING_LIMIT).contains(&limit) {
+        return Err(ApiError::validation(format!(
+            "limit must be between 1 and {MAX_TRENDING_LIMIT}"
+        )));
     }
 
     let publications = data
         .sql_client
-        .search_publications_by_tag(&query.tag, query.page, query.limit)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error searching publications by tag: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
+        .get_trending_publications(window_days, limit)
+        .await?;
 
     Ok(HttpResponse::Ok().json(publications))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TrendingQuery {
+    /// Days of citations counted, 7 by default and at most 365
+    window_days: Option<i32>,
+    /// Number of publications, 10 by default and at most 50
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchPublicationsQuery {
+    /// Words to search for
     query: String,
-    page: Option<i64>,
-    limit: Option<i64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchByTagQuery {
     tag: String,
-    page: Option<i64>,
-    limit: Option<i64>,
 }
 
+#[utoipa::path(responses((status = 200, body = Vec<PublicationAuthor>)))]
 #[get("/{publication_id}/authors")]
 async fn get_publication_authors_handler(
     publication_id: web::Path<Uuid>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
+) -> Result<HttpResponse, ApiError> {
     let authors =
         PublicationAuthorOperations::get_publication_authors(&*data.sql_client, *publication_id)
             .await
-            .map_err(|err| {
-                tracing::error!("Error retrieving publication authors: {}", err);
-                match err {
-                    sqlx::Error::RowNotFound => ErrorNotFound("Publication not found"),
-                    _ => ErrorInternalServerError("Internal server error"),
-                }
-            })?;
+            .map_err(ApiError::database("Publication"))?;
 
     Ok(HttpResponse::Ok().json(authors))
 }
 
+#[utoipa::path(responses(
+    (status = 200, description = "Cited publications, deleted ones as `{\"id\": ..., \"deleted\": true}`", body = Vec<CitedPublication>)
+))]
 #[get("/{publication_id}/citations")]
 async fn get_publication_citations(
     publication_id: web::Path<Uuid>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
+) -> Result<HttpResponse, ApiError> {
     let citations = data
         .sql_client
         .get_publication_citations(*publication_id)
         .await
-        .map_err(|err| {
-            tracing::error!("Error retrieving publication citations: {}", err);
-            match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("Publication not found"),
-                _ => ErrorInternalServerError("Internal server error"),
-            }
-        })?;
+        .map_err(ApiError::database("Publication"))?;
 
     Ok(HttpResponse::Ok().json(citations))
 }
 
+#[utoipa::path(responses((status = 200, body = Vec<Publication>)))]
 #[get("/{publication_id}/cited-by")]
 async fn get_cited_by(
     publication_id: web::Path<Uuid>,
     data: web::Data<AppState>,
-) -> Result<HttpResponse, actix_web::Error> {
+) -> Result<HttpResponse, ApiError> {
     let cited_by = data
         .sql_client
         .get_cited_by(*publication_id)
         .await
+        .map_err(ApiError::database("Publication"))?;
+
+    Ok(HttpResponse::Ok().json(cited_by))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CitationFormatQuery {
+    /// Format of the citation, `bibtex` by default
+    #[serde(default)]
+    #[param(inline)]
+    format: CitationFormat,
+}
+
+#[utoipa::path(
+    params(CitationFormatQuery),
+    responses(
+        (status = 200, description = "BibTeX entry, or RIS record with `format=ris`", body = String, content_type = "application/x-bibtex"),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    )
+)]
+#[get("/{publication_id}/bibtex")]
+async fn export_publication_citation(
+    publication_id: web::Path<Uuid>,
+    query: web::Query<CitationFormatQuery>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let (publication, authors) = futures::try_join!(
+        data.sql_client.get_publication(*publication_id),
+        data.sql_client
+            .get_publication_author_profiles(*publication_id)
+    )
+    .map_err(ApiError::database("Publication"))?;
+
+    let entry = CitationEntry {
+        publication: &publication,
+        authors: &authors,
+        url: format!(
+            "{}/publications/{}",
+            data.server_base_url.trim_end_matches('/'),
+            publication.id
+        ),
+    };
+    let format = query.format;
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            content_disposition(
+                "attachment",
+                &format!("{}.{}", entry.key(), format.extension()),
+            ),
+        ))
+        .body(entry.render(format)))
+}
+
+/// Lists the objects stored for a publication, for admins debugging storage. Served under
+/// `/storage` rather than `/files`, which lists the supplementary files of the publication.
+#[utoipa::path(
+    responses(
+        (status = 200, body = PublicationStorage),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[get("/{publication_id}/storage", wrap = "Privy")]
+async fn list_publication_storage(
+    req: actix_web::HttpRequest,
+    publication_id: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let publication = data
+        .sql_client
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+
+    let mut files = vec![];
+    for prefix in publication_storage_prefixes(&publication) {
+        let listing = data
+            .object_store
+            .list_files(&prefix, &S3Bucket::Storage)
+            .await
+            .map_err(|err| {
+                tracing::error!("Error listing S3 objects under {}: {}", prefix, err);
+                ApiError::internal("Failed to list publication files")
+            })?;
+        files.extend(listing);
+    }
+
+    // Listings carry no user metadata, fetch it for each object
+    let files: Vec<S3ObjectInfo> = futures::stream::iter(files)
+        .map(|mut file| {
+            let object_store = data.object_store.as_ref();
+            async move {
+                file.metadata = object_store
+                    .get_file_metadata(&file.key, &S3Bucket::Storage)
+                    .await?;
+                ZResult::Ok(file)
+            }
+        })
+        .buffered(METADATA_LOOKUP_CONCURRENCY)
+        .try_collect()
+        .await
         .map_err(|err| {
-            tracing::error!("Error retrieving publications that cite this one: {}", err);
-            match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("Publication not found"),
-                _ => ErrorInternalServerError("Internal server error"),
+            tracing::error!("Error retrieving metadata of publication files: {}", err);
+            ApiError::internal("Failed to list publication files")
+        })?;
+
+    let versions_prefix = publication_versions_prefix(publication.id);
+    let versions: Vec<_> = files
+        .iter()
+        .filter(|file| file.key.starts_with(&versions_prefix))
+        .cloned()
+        .collect();
+
+    Ok(HttpResponse::Ok().json(PublicationStorage {
+        publication_id: publication.id,
+        s3key: publication.s3key,
+        files,
+        versions,
+    }))
+}
+
+/// Objects stored for a publication, among which the archived versions of its file.
+#[derive(Serialize, ToSchema)]
+struct PublicationStorage {
+    publication_id: Uuid,
+    s3key: Option<String>,
+    files: Vec<S3ObjectInfo>,
+    versions: Vec<S3ObjectInfo>,
+}
+
+/// Ensures the request comes from the user who created `publication`.
+fn require_publication_owner(
+    user: &AuthenticatedUser,
+    publication: &Publication,
+) -> Result<(), ApiError> {
+    if publication.user_id.as_ref() != Some(&user.privy_id) {
+        return Err(ApiError::forbidden(
+            "Only the owner of the publication can manage it",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the publication, ensuring the caller owns it, for the handlers of the records that
+/// belong to a publication, such as its citations and authors.
+pub(crate) async fn get_owned_publication(
+    data: &AppState,
+    user: &AuthenticatedUser,
+    publication_id: Uuid,
+) -> Result<Publication, ApiError> {
+    let publication = data
+        .sql_client
+        .get_publication(publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+    require_publication_owner(user, &publication)?;
+
+    Ok(publication)
+}
+
+fn publication_supplementary_prefix(publication_id: Uuid) -> String {
+    format!("publications/{}/supplementary/", publication_id)
+}
+
+/// Checks a supplementary file against the accepted content types and size limit, returning its
+/// content type.
+fn validate_supplementary_file(file: &TempFile) -> Result<&str, ApiError> {
+    let content_type = file
+        .content_type
+        .as_ref()
+        .map(|mime| mime.essence_str())
+        .filter(|content_type| SUPPLEMENTARY_CONTENT_TYPES.contains(content_type))
+        .ok_or_else(|| {
+            ApiError::validation(format!(
+                "Unsupported content type, expected one of: {}",
+                SUPPLEMENTARY_CONTENT_TYPES.join(", ")
+            ))
+        })?;
+
+    if file.size == 0 || file.size > MAX_SUPPLEMENTARY_FILE_SIZE {
+        return Err(ApiError::validation(format!(
+            "File size must be between 1 and {} bytes",
+            MAX_SUPPLEMENTARY_FILE_SIZE
+        )));
+    }
+
+    Ok(content_type)
+}
+
+#[derive(MultipartForm, ToSchema)]
+pub struct SupplementaryFilesForm {
+    /// Files to attach, repeated once per file
+    #[multipart(rename = "file")]
+    #[schema(rename = "file", value_type = Vec<String>, format = Binary)]
+    files: Vec<TempFile>,
+}
+
+/// Attaches supplementary files, such as datasets, appendices or code archives, to a publication.
+#[utoipa::path(
+    request_body(content = SupplementaryFilesForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, body = Vec<PublicationFile>),
+        (status = 400, description = "Unsupported content type or size", body = ErrorResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Publication not found", body = ErrorResponse),
+        (status = 413, description = "Storage quota exceeded", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/{publication_id}/files", wrap = "Privy")]
+async fn upload_supplementary_files(
+    user: AuthenticatedUser,
+    publication_id: web::Path<Uuid>,
+    MultipartForm(form): MultipartForm<SupplementaryFilesForm>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let publication = data
+        .sql_client
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+    require_publication_owner(&user, &publication)?;
+
+    if form.files.is_empty() {
+        return Err(ApiError::validation("No file provided"));
+    }
+    // Reject the whole upload before storing anything
+    let content_types = form
+        .files
+        .iter()
+        .map(validate_supplementary_file)
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(owner) = &publication.user_id {
+        let total_size = form.files.iter().map(|file| file.size as i64).sum();
+        check_storage_quota(&data, owner, total_size).await?;
+    }
+
+    let uploader = publication.user_id.as_deref();
+    let mut created = vec![];
+    for (file, content_type) in form.files.iter().zip(content_types) {
+        let original_file_name = file.file_name.as_deref().unwrap_or_default();
+        let s3key = S3Key(format!(
+            "{}{}/{}",
+            publication_supplementary_prefix(publication.id),
+            Uuid::new_v4(),
+            sanitize_filename(original_file_name)
+        ));
+        let stored_file = store_tagged_file(
+            data.object_store.as_ref(),
+            file,
+            s3key,
+            uploader,
+            Some(publication.id),
+        )
+        .await?;
+
+        let mut filename = display_filename(original_file_name);
+        if filename.is_empty() {
+            filename = sanitize_filename(original_file_name);
+        }
+        let new_file = NewPublicationFile {
+            publication_id: publication.id,
+            s3key: stored_file.s3key,
+            filename,
+            content_type: content_type.to_string(),
+            size: file.size as i64,
+        };
+
+        let publication_file = match data.sql_client.create_publication_file(&new_file).await {
+            Ok(publication_file) => publication_file,
+            Err(err) => {
+                tracing::error!("Error recording supplementary file: {}", err);
+                if let Err(err) = data
+                    .object_store
+                    .delete_file(&S3Key(new_file.s3key.clone()), &S3Bucket::Storage)
+                    .await
+                {
+                    tracing::error!("Error deleting unrecorded file {}: {}", new_file.s3key, err);
+                }
+                return Err(ApiError::internal("Internal server error"));
             }
+        };
+        created.push(publication_file);
+    }
+
+    let created = load_supplementary_urls(&data, created).await?;
+
+    Ok(HttpResponse::Created().json(created))
+}
+
+/// Lists the supplementary files of a publication, with presigned download URLs.
+#[utoipa::path(responses(
+    (status = 200, body = Vec<PublicationFile>),
+    (status = 404, description = "Publication not found", body = ErrorResponse)
+))]
+#[get("/{publication_id}/files")]
+async fn list_supplementary_files(
+    publication_id: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    data.sql_client
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+
+    let files = data
+        .sql_client
+        .list_publication_files(*publication_id)
+        .await?;
+
+    let files = load_supplementary_urls(&data, files).await?;
+
+    Ok(HttpResponse::Ok().json(files))
+}
+
+async fn load_supplementary_urls(
+    data: &AppState,
+    files: Vec<PublicationFile>,
+) -> Result<Vec<PublicationFile>, ApiError> {
+    futures::future::try_join_all(
+        files
+            .iter()
+            .map(|file| file.load_s3_contents(data.object_store.as_ref(), data.presign_expiry)),
+    )
+    .await
+    .map_err(|err| {
+        tracing::error!("Error presigning URLs of supplementary files: {}", err);
+        ApiError::internal("Internal server error")
+    })
+}
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "File deleted"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not the owner of the publication", body = ErrorResponse),
+        (status = 404, description = "Publication or file not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[delete("/{publication_id}/files/{file_id}", wrap = "Privy")]
+async fn delete_supplementary_file(
+    user: AuthenticatedUser,
+    path: web::Path<(Uuid, Uuid)>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let (publication_id, file_id) = path.into_inner();
+
+    let publication = data
+        .sql_client
+        .get_publication(publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+    require_publication_owner(&user, &publication)?;
+
+    let file = data
+        .sql_client
+        .get_publication_file(publication_id, file_id)
+        .await
+        .map_err(ApiError::database("File"))?;
+
+    data.object_store
+        .delete_file(&S3Key(file.s3key.clone()), &S3Bucket::Storage)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error deleting supplementary file {}: {}", file.s3key, err);
+            ApiError::internal("Failed to delete file")
         })?;
 
-    Ok(HttpResponse::Ok().json(cited_by))
+    data.sql_client.delete_publication_file(file.id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, body = FileVerification),
+        (status = 409, description = "No checksum recorded for the file", body = ErrorResponse),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+        (status = 404, description = "Publication not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/{publication_id}/verify-file", wrap = "Privy")]
+async fn verify_publication_file(
+    req: actix_web::HttpRequest,
+    publication_id: web::Path<Uuid>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    crate::auth::require_admin(&req, &data.sql_client).await?;
+
+    let publication = data
+        .sql_client
+        .get_publication(*publication_id)
+        .await
+        .map_err(ApiError::database("Publication"))?;
+
+    let s3key = publication
+        .s3key
+        .ok_or_else(|| ApiError::not_found("Publication has no file"))?;
+    let expected_sha256 = publication
+        .file_sha256
+        .ok_or_else(|| ApiError::conflict("Publication has no recorded checksum"))?;
+
+    let stored_size = data
+        .object_store
+        .get_file_size(&s3key, &S3Bucket::Storage)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error checking stored file {}: {}", s3key, err);
+            ApiError::internal("Failed to verify file")
+        })?;
+
+    let body = match stored_size {
+        Some(_) => Some(
+            data.object_store
+                .retrieve_storage_file(&s3key)
+                .await
+                .map_err(|err| {
+                    tracing::error!("Error retrieving stored file {}: {}", s3key, err);
+                    ApiError::internal("Failed to verify file")
+                })?
+                .body,
+        ),
+        None => None,
+    };
+
+    let integrity = check_file_integrity(&expected_sha256, body)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error hashing stored file {}: {}", s3key, err);
+            ApiError::internal("Failed to verify file")
+        })?;
+
+    if integrity.status != FileIntegrityStatus::Ok {
+        tracing::warn!(
+            "Integrity check of {} for publication {} failed: {:?}",
+            s3key,
+            publication.id,
+            integrity
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(FileVerification {
+        publication_id: publication.id,
+        s3key,
+        expected_sha256,
+        actual_sha256: integrity.actual_sha256,
+        status: integrity.status,
+    }))
+}
+
+/// Outcome of re-hashing the stored file of a publication.
+#[derive(Serialize, ToSchema)]
+struct FileVerification {
+    publication_id: Uuid,
+    s3key: String,
+    expected_sha256: String,
+    actual_sha256: Option<String>,
+    status: FileIntegrityStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum FileIntegrityStatus {
+    /// The stored object hashes to the recorded checksum
+    Ok,
+    /// The stored object differs from what was uploaded
+    Mismatch,
+    /// There is no object under the recorded key
+    Missing,
+}
+
+#[derive(Debug)]
+struct FileIntegrity {
+    status: FileIntegrityStatus,
+    actual_sha256: Option<String>,
+}
+
+/// Re-hashes a stored object, `None` if missing, and compares it with the recorded checksum.
+async fn check_file_integrity(
+    expected_sha256: &str,
+    body: Option<ByteStream>,
+) -> ZResult<FileIntegrity> {
+    let Some(body) = body else {
+        return Ok(FileIntegrity {
+            status: FileIntegrityStatus::Missing,
+            actual_sha256: None,
+        });
+    };
+
+    let actual_sha256 = hash_byte_stream(body).await?.sha256;
+    let status = if actual_sha256.eq_ignore_ascii_case(expected_sha256) {
+        FileIntegrityStatus::Ok
+    } else {
+        FileIntegrityStatus::Mismatch
+    };
+
+    Ok(FileIntegrity {
+        status,
+        actual_sha256: Some(actual_sha256),
+    })
 }
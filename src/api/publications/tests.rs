@@ -1,15 +1,78 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
 
+    use std::sync::Arc;
+
     use actix_web::{http::StatusCode, test};
+    use aws_sdk_s3::primitives::ByteStream;
     use serde_json::json;
     use sqlx::PgPool;
 
     use crate::{
-        api::tests::create_test_app,
-        db::sql::{PublicationOperations, SqlClient, models::NewPublication},
+        api::tests::{
+            TEST_USER_HEADER, authenticate, create_test_app, create_test_app_with_store,
+            create_test_author, create_test_publication, create_test_user, tokio_runtime,
+        },
+        common::hash::hash_byte_stream,
+        db::{
+            s3::{
+                ObjectStore, S3Bucket,
+                mock::{MockObject, MockObjectStore},
+            },
+            sql::{
+                CitationOperations, PublicationAuthorOperations, PublicationFileOperations,
+                PublicationOperations, SqlClient, ViewOperations,
+                models::{NewCitation, NewPublication},
+            },
+        },
     };
 
+    /// Helper function to create a multipart form body made only of text fields
+    /// Returns (boundary, body_bytes) tuple
+    fn create_text_multipart_body(fields: &[(&str, &str)]) -> (String, Vec<u8>) {
+        let boundary = "testboundary12345";
+        let mut body = Vec::new();
+
+        for (name, value) in fields {
+            body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            body.extend_from_slice(
+                format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name).as_bytes(),
+            );
+            body.extend_from_slice(value.as_bytes());
+            body.extend_from_slice(b"\r\n");
+        }
+
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        (boundary.to_string(), body)
+    }
+
+    /// Helper function to create a multipart form body made of `file` fields
+    /// Each file is given as a (filename, content_type, contents) tuple
+    fn create_files_multipart_body(files: &[(&str, &str, &[u8])]) -> (String, Vec<u8>) {
+        let boundary = "testboundary12345";
+        let mut body = Vec::new();
+
+        for (filename, content_type, contents) in files {
+            body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n",
+                    filename
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(format!("Content-Type: {}\r\n\r\n", content_type).as_bytes());
+            body.extend_from_slice(contents);
+            body.extend_from_slice(b"\r\n");
+        }
+
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        (boundary.to_string(), body)
+    }
+
     /// Helper function to create multipart form body for publication create/update
     /// Returns (boundary, body_bytes) tuple
     fn create_publication_multipart_body(
@@ -102,6 +165,7 @@ mod tests {
             .insert_header(("Content-Length", body.len()))
             .set_payload(body)
             .to_request();
+        authenticate(&req, &user_privy_id);
 
         // Call the service
         let resp = test::call_service(&app, req).await;
@@ -141,6 +205,9 @@ mod tests {
             about: Some("Test description".to_string()),
             tags: Some(vec!["test".to_string()]),
             s3key: None,
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
         };
 
         let publication = sql_client
@@ -158,6 +225,77 @@ mod tests {
         let body: serde_json::Value = test::read_body_json(resp).await;
         assert_eq!(body["title"], "Test Get Publication");
         assert_eq!(body["id"], publication.id.to_string());
+        assert!(body.get("file_url").is_none());
+        assert!(body.get("is_owner").is_none());
+
+        // Logged in viewers are told whether they own the publication
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", publication.id))
+            .to_request();
+        crate::api::tests::authenticate(&req, &user_privy_id);
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["is_owner"], true);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", publication.id))
+            .to_request();
+        crate::api::tests::authenticate(&req, "privy_someone_else");
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["is_owner"], false);
+    }
+
+    #[sqlx::test]
+    async fn test_get_publication_with_file_url_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let publication_with_key = |s3key: &str| NewPublication {
+            user_id: user_privy_id.clone(),
+            title: "Publication with a file".to_string(),
+            about: None,
+            tags: None,
+            s3key: Some(s3key.to_string()),
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
+        };
+
+        let s3key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        let publication = sql_client
+            .create_publication(&publication_with_key(&s3key))
+            .await
+            .unwrap();
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let file_url = body["file_url"].as_str().unwrap();
+        assert!(file_url.contains(&s3key));
+        assert!(file_url.contains("method=GET"));
+
+        // An empty key points at no object and gets no URL
+        let publication = sql_client
+            .create_publication(&publication_with_key(""))
+            .await
+            .unwrap();
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body.get("file_url").is_none());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/pdf-url", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
 
     #[sqlx::test]
@@ -175,6 +313,9 @@ mod tests {
                 about: Some(format!("Description {}", i)),
                 tags: Some(vec!["test".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             };
             sql_client
                 .create_publication(&new_publication)
@@ -190,10 +331,320 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
 
         let body: serde_json::Value = test::read_body_json(resp).await;
-        assert!(body["publications"].is_array());
-        let publications = body["publications"].as_array().unwrap();
+        let publications = body["items"].as_array().unwrap();
         assert!(publications.len() >= 3);
         assert!(body["total"].as_i64().unwrap() >= 3);
+        assert_eq!(body["page"], 1);
+        assert_eq!(body["limit"], 20);
+        assert_eq!(body["total_pages"], 1);
+
+        // A page past the last one still reports the total
+        let req = test::TestRequest::get()
+            .uri("/publications/list?page=100&limit=10")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let empty: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(empty["items"].as_array().unwrap().len(), 0);
+        assert_eq!(empty["total"], body["total"]);
+
+        for query in ["page=0", "limit=0", "limit=101", "page=first"] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/publications/list?{query}"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{query}");
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["error"]["code"], "validation_error");
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_list_publications_by_cursor_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let mut existing = vec![];
+        for _ in 0..5 {
+            existing.push(
+                crate::api::tests::create_test_publication(&sql_client, user_privy_id.clone())
+                    .await
+                    .to_string(),
+            );
+        }
+        let get = async |uri: String| -> serde_json::Value {
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{uri}");
+            test::read_body_json(resp).await
+        };
+        let ids = |page: &serde_json::Value| -> Vec<String> {
+            page["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // The first page is requested by page, and carries the cursor of the next one
+        let page = get("/publications/list?limit=2".to_string()).await;
+        assert_eq!(page["total_pages"], 3);
+        let mut walked = ids(&page);
+        let mut next_cursor = page["next_cursor"].as_str().map(str::to_string);
+
+        // Publications created meanwhile do not shift the following pages
+        while let Some(cursor) = next_cursor {
+            crate::api::tests::create_test_publication(&sql_client, user_privy_id.clone()).await;
+            let page = get(format!("/publications/list?limit=2&cursor={cursor}")).await;
+            assert_eq!(page["limit"], 2);
+            assert!(page.get("total").is_none());
+            walked.extend(ids(&page));
+            next_cursor = page["next_cursor"].as_str().map(str::to_string);
+        }
+        existing.reverse();
+        assert_eq!(walked, existing);
+
+        // The last page by number has no next cursor, with 2 publications created meanwhile
+        let page = get("/publications/list?page=4&limit=2".to_string()).await;
+        assert_eq!(page["total_pages"], 4);
+        assert_eq!(ids(&page).len(), 1);
+        assert!(page.get("next_cursor").is_none());
+
+        let req = test::TestRequest::get()
+            .uri("/publications/list?cursor=invalid")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "validation_error");
+    }
+
+    /// Returns the sorted field names of a JSON object.
+    fn fields(value: &serde_json::Value) -> Vec<&str> {
+        let mut fields: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        fields.sort_unstable();
+        fields
+    }
+
+    /// Locks the field names of the publication and author responses, which clients rely on.
+    #[sqlx::test]
+    async fn test_response_shapes(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        create_test_author(&sql_client, &user_privy_id).await;
+        let publication_id = create_test_publication(&sql_client, user_privy_id.clone()).await;
+        sql_client
+            .set_publication_authors(publication_id, std::slice::from_ref(&user_privy_id))
+            .await
+            .unwrap();
+
+        let get = async |uri: String| -> serde_json::Value {
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK, "{uri}");
+            test::read_body_json(resp).await
+        };
+        let publication_fields = [
+            "about",
+            "created_at",
+            "file_sha256",
+            "file_size",
+            "id",
+            "paper_hash",
+            "s3key",
+            "tags",
+            "title",
+            "updated_at",
+            "user_id",
+        ];
+
+        let publication = get(format!("/publications/{publication_id}")).await;
+        let mut view_fields = [
+            &publication_fields[..],
+            &["authors", "citation_count", "view_count"],
+        ]
+        .concat();
+        view_fields.sort_unstable();
+        assert_eq!(fields(&publication), view_fields);
+
+        // Timestamps are RFC 3339 in UTC with milliseconds
+        let stored = sql_client.get_publication(publication_id).await.unwrap();
+        let expected = stored
+            .created_at
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        assert_eq!(publication["created_at"], expected.as_str());
+        let created_at = publication["created_at"].as_str().unwrap();
+        assert_eq!(created_at.len(), "2025-01-02T03:04:05.678Z".len());
+        assert!(created_at.ends_with('Z'));
+
+        let list = get("/publications/list".to_string()).await;
+        assert_eq!(
+            fields(&list),
+            ["items", "limit", "page", "total", "total_pages"]
+        );
+        assert_eq!(fields(&list["items"][0]), publication_fields);
+
+        let author = get(format!("/authors/{user_privy_id}")).await;
+        assert_eq!(
+            fields(&author),
+            [
+                "affiliation",
+                "created_at",
+                "email",
+                "name",
+                "privy_id",
+                "updated_at"
+            ]
+        );
+
+        // Both author listings of a publication return its authorship rows, not author profiles
+        for uri in [
+            format!("/publications/{publication_id}/authors"),
+            format!("/publication-authors/publication/{publication_id}"),
+        ] {
+            let authors = get(uri).await;
+            assert_eq!(
+                fields(&authors[0]),
+                ["author_id", "author_order", "publication_id"]
+            );
+        }
+
+        let author_publications = get(format!("/publication-authors/author/{user_privy_id}")).await;
+        assert_eq!(fields(&author_publications[0]), publication_fields);
+    }
+
+    #[sqlx::test]
+    async fn test_get_publication_is_cached_until_updated(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Cached Title".to_string(),
+                about: None,
+                tags: None,
+                s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+        let get_title = async || {
+            let req = test::TestRequest::get()
+                .uri(&format!("/publications/{}", publication.id))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            body["title"].as_str().unwrap().to_string()
+        };
+
+        // The first read fills the cache, which serves the next ones without the database
+        assert_eq!(get_title().await, "Cached Title");
+        sql_client
+            .update_publication(
+                publication.id,
+                None,
+                Some("Changed Behind"),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(get_title().await, "Cached Title");
+
+        // Updating through the API invalidates the cached publication before it expires
+        let (boundary, body) = create_text_multipart_body(&[("title", "Updated Title")]);
+        let req = test::TestRequest::put()
+            .uri(&format!("/publications/{}", publication.id))
+            .insert_header((
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            ))
+            .set_payload(body)
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(get_title().await, "Updated Title");
+    }
+
+    #[sqlx::test]
+    async fn test_publication_views_api(pool: PgPool) {
+        // Views are recorded by tasks spawned on the runtime
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let owner_privy_id = create_test_user(&sql_client).await;
+        let reader_privy_id = create_test_user(&sql_client).await;
+        let publication_id = create_test_publication(&sql_client, owner_privy_id.clone()).await;
+        let citing_id = create_test_publication(&sql_client, owner_privy_id.clone()).await;
+        sql_client
+            .create_citation(&NewCitation {
+                citing_publication_id: citing_id,
+                cited_publication_id: publication_id,
+            })
+            .await
+            .unwrap();
+
+        let view = async |viewer: Option<&str>| -> serde_json::Value {
+            let mut req =
+                test::TestRequest::get().uri(&format!("/publications/{}", publication_id));
+            if let Some(viewer) = viewer {
+                req = req.insert_header((TEST_USER_HEADER, viewer.to_string()));
+            }
+            let resp = test::call_service(&app, req.to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            test::read_body_json(resp).await
+        };
+        let wait_for_views = async |count: i64| {
+            for _ in 0..100 {
+                if sql_client.get_view_count(publication_id).await.unwrap() >= count {
+                    return;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            panic!("Expected {count} views");
+        };
+
+        assert_eq!(view(Some(&reader_privy_id)).await["view_count"], 0);
+        wait_for_views(1).await;
+
+        // Refreshing within the hour does not count again, unlike anonymous views
+        view(Some(&reader_privy_id)).await;
+        view(None).await;
+        view(Some(&owner_privy_id)).await;
+        wait_for_views(3).await;
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(view(None).await["view_count"], 3);
+
+        wait_for_views(4).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/stats", publication_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let stats: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(stats, json!({"views": 4, "citations": 1}));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/stats", uuid::Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
 
     #[sqlx::test]
@@ -211,6 +662,9 @@ mod tests {
             about: Some("Original description".to_string()),
             tags: Some(vec!["original".to_string()]),
             s3key: None,
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
         };
 
         let publication = sql_client
@@ -239,6 +693,7 @@ mod tests {
             .insert_header(("Content-Length", body.len()))
             .set_payload(body)
             .to_request();
+        authenticate(&req, &user_privy_id);
 
         // Call the service
         let resp = test::call_service(&app, req).await;
@@ -285,6 +740,9 @@ mod tests {
             about: Some("Will be deleted".to_string()),
             tags: Some(vec!["delete".to_string()]),
             s3key: None,
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
         };
 
         let publication = sql_client
@@ -296,6 +754,7 @@ mod tests {
         let req = test::TestRequest::delete()
             .uri(&format!("/publications/{}", publication.id))
             .to_request();
+        authenticate(&req, &user_privy_id);
 
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::NO_CONTENT);
@@ -309,6 +768,85 @@ mod tests {
         assert_eq!(get_resp.status(), StatusCode::NOT_FOUND);
     }
 
+    #[sqlx::test]
+    async fn test_delete_publication_authorization_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let owner_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let other_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let publication_id =
+            crate::api::tests::create_test_publication(&sql_client, owner_privy_id.clone()).await;
+
+        // Anonymous requests are rejected by the Privy middleware
+        let req = test::TestRequest::delete()
+            .uri(&format!("/publications/{}", publication_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/publications/{}", publication_id))
+            .insert_header((TEST_USER_HEADER, other_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "forbidden");
+        assert!(sql_client.get_publication(publication_id).await.is_ok());
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/publications/{}", publication_id))
+            .insert_header((TEST_USER_HEADER, owner_privy_id.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(sql_client.get_publication(publication_id).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_update_publication_authorization_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let owner_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let other_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let publication_id =
+            crate::api::tests::create_test_publication(&sql_client, owner_privy_id.clone()).await;
+        let update_request = |user_id: Option<&str>| {
+            let mut req = with_multipart(
+                test::TestRequest::put().uri(&format!("/publications/{}", publication_id)),
+                create_publication_multipart_body(
+                    Some(&other_privy_id),
+                    "Taken Over",
+                    None,
+                    None,
+                    false,
+                ),
+            );
+            if let Some(user_id) = user_id {
+                req = req.insert_header((TEST_USER_HEADER, user_id.to_string()));
+            }
+            req.to_request()
+        };
+
+        // Anonymous requests are rejected by the Privy middleware
+        let resp = test::call_service(&app, update_request(None)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = test::call_service(&app, update_request(Some(&other_privy_id))).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "forbidden");
+        let publication = sql_client.get_publication(publication_id).await.unwrap();
+        assert_ne!(publication.title, "Taken Over");
+
+        // The owner can update it, but not hand it over through a userId field
+        let resp = test::call_service(&app, update_request(Some(&owner_privy_id))).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let publication = sql_client.get_publication(publication_id).await.unwrap();
+        assert_eq!(publication.title, "Taken Over");
+        assert_eq!(publication.user_id, Some(owner_privy_id));
+    }
+
     #[sqlx::test]
     async fn test_search_publications_by_title_api(pool: PgPool) {
         let app = test::init_service(create_test_app(pool.clone()).await).await;
@@ -330,6 +868,9 @@ mod tests {
                 about: Some("Test description".to_string()),
                 tags: Some(vec!["ai".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             };
             sql_client
                 .create_publication(&new_publication)
@@ -344,12 +885,70 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
 
-        let body: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["page"], 1);
+        let body = body["items"].as_array().unwrap();
         assert_eq!(body.len(), 2);
         for publication in body {
             let title = publication["title"].as_str().unwrap();
             assert!(title.contains("Learning"));
+            assert!(publication["score"].as_f64().unwrap() < 1.0);
+        }
+        let machine_learning = body
+            .iter()
+            .find(|publication| publication["title"] == "Machine Learning Advances")
+            .unwrap();
+        assert_eq!(
+            machine_learning["highlight"],
+            json!({ "start": 8, "end": 16 })
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_search_publications_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        for title in ["Learning to rank", "Deep learning", "Consensus"] {
+            sql_client
+                .create_publication(&NewPublication {
+                    user_id: user_privy_id.clone(),
+                    title: title.to_string(),
+                    about: None,
+                    tags: None,
+                    s3key: None,
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
+                })
+                .await
+                .unwrap();
         }
+
+        let req = test::TestRequest::get()
+            .uri("/publications/search?query=learn&limit=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["total_pages"], 2);
+
+        let req = test::TestRequest::get()
+            .uri("/publications/search?query=learn&page=3&limit=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["items"].as_array().unwrap().is_empty());
+        assert_eq!(body["total"], 2);
+
+        let req = test::TestRequest::get()
+            .uri("/publications/search?query=%20")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
     #[sqlx::test]
@@ -373,6 +972,9 @@ mod tests {
                 about: Some("Test description".to_string()),
                 tags: Some(tags),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             };
             sql_client
                 .create_publication(&new_publication)
@@ -387,11 +989,1810 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
 
-        let body: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["total_pages"], 1);
+        let body = body["items"].as_array().unwrap();
         assert_eq!(body.len(), 2);
         for publication in body {
             let tags = publication["tags"].as_array().unwrap();
             assert!(tags.contains(&json!("ai")));
         }
     }
+
+    #[sqlx::test]
+    async fn test_trending_publications_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let cited = create_test_publication(&sql_client, user_privy_id.clone()).await;
+        let citing = create_test_publication(&sql_client, user_privy_id.clone()).await;
+        let newest = create_test_publication(&sql_client, user_privy_id.clone()).await;
+        sql_client
+            .create_citation(&NewCitation {
+                citing_publication_id: citing,
+                cited_publication_id: cited,
+            })
+            .await
+            .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/publications/trending?window_days=7&limit=2")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert_eq!(body.len(), 2);
+        assert_eq!(body[0]["id"], cited.to_string());
+        assert_eq!(body[0]["recent_citations"], 1);
+        assert!(body[0]["title"].is_string());
+        assert_eq!(body[1]["id"], newest.to_string());
+        assert_eq!(body[1]["score"], 0.0);
+
+        for query in ["window_days=0", "window_days=366", "limit=0", "limit=51"] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/publications/trending?{query}"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{query}");
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_upload_intent_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let req = test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({
+                "file_name": "../My Paper (final).pdf",
+                "file_size": 1024,
+                "content_type": "application/pdf"
+            }))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let s3key = body["s3key"].as_str().unwrap();
+        assert!(s3key.starts_with("publications/"));
+        assert!(s3key.ends_with("/My_Paper__final_.pdf"));
+        assert!(!s3key.contains(".."));
+
+        let upload_url = body["upload_url"].as_str().unwrap();
+        assert!(upload_url.contains(s3key));
+        assert!(upload_url.contains("method=PUT"));
+        assert_eq!(body["expires_in_seconds"], 900);
+    }
+
+    #[sqlx::test]
+    async fn test_upload_intent_validation_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let invalid_requests = [
+            json!({"file_name": "paper.docx", "file_size": 1024, "content_type": "application/msword"}),
+            json!({"file_name": "paper.pdf", "file_size": 0, "content_type": "application/pdf"}),
+            json!({"file_name": "paper.pdf", "file_size": 1_000_000_000_i64, "content_type": "application/pdf"}),
+        ];
+
+        for request_body in invalid_requests {
+            let req = test::TestRequest::post()
+                .uri("/publications/upload-intent")
+                .set_json(&request_body)
+                .to_request();
+            authenticate(&req, &user_privy_id);
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+
+        // Upload intents are only issued to authenticated users
+        let req = test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({"file_name": "paper.pdf", "file_size": 1024, "content_type": "application/pdf"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_create_publication_with_s3key_validation_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let invalid_forms: [&[(&str, &str)]; 3] = [
+            // Missing sha3_hash and file_size
+            &[
+                ("title", "Direct Upload"),
+                ("s3key", "publications/0a7e2b0c/paper.pdf"),
+            ],
+            // Key outside of the publications prefix
+            &[
+                ("title", "Direct Upload"),
+                ("s3key", "users/someone/paper.pdf"),
+                ("sha3_hash", "00"),
+                ("file_size", "1024"),
+            ],
+            // Key escaping the publications prefix
+            &[
+                ("title", "Direct Upload"),
+                ("s3key", "publications/../users/paper.pdf"),
+                ("sha3_hash", "00"),
+                ("file_size", "1024"),
+            ],
+        ];
+
+        for fields in invalid_forms {
+            let (boundary, body) = create_text_multipart_body(fields);
+            let req = test::TestRequest::post()
+                .uri("/publications/create")
+                .insert_header((
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={}", boundary),
+                ))
+                .set_payload(body)
+                .to_request();
+            authenticate(&req, &user_privy_id);
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+
+        assert_eq!(sql_client.count_publications().await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn test_list_publication_storage_requires_admin_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+        let publication_id =
+            crate::api::tests::create_test_publication(&sql_client, user_privy_id.clone()).await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/storage", publication_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Owning the publication is not enough to inspect the bucket
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/storage", publication_id))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/storage", uuid::Uuid::new_v4()))
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_get_publication_pdf_url_api(pool: PgPool) {
+        let object_store = Arc::new(MockObjectStore::default());
+        let mut app_state = crate::api::tests::create_test_app_state(pool.clone()).await;
+        app_state.presign_expiry = std::time::Duration::from_secs(1234);
+        app_state.object_store = object_store.clone();
+        let app =
+            test::init_service(crate::api::tests::create_test_app_with_state(app_state)).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let s3key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(S3Bucket::Storage, &s3key, MockObject::new(b"%PDF".to_vec()));
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Downloadable".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(s3key.clone()),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+
+        let before = chrono::Utc::now();
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/pdf-url", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["expires_in_seconds"], 1234);
+        assert!(body["url"].as_str().unwrap().contains("expires_in=1234"));
+        let expires_at: chrono::DateTime<chrono::Utc> =
+            serde_json::from_value(body["expires_at"].clone()).unwrap();
+        let expected = before + chrono::Duration::seconds(1234);
+        assert!(expires_at >= expected);
+        assert!(expires_at <= expected + chrono::Duration::seconds(60));
+        // Without an uploaded name, the file is named after its key
+        assert!(
+            body["url"].as_str().unwrap().contains(
+                "response-content-disposition=attachment%3B%20filename%3D%22paper%2Epdf%22"
+            )
+        );
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/publications/{}/pdf-url?disposition=inline",
+                publication.id
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(
+            body["url"]
+                .as_str()
+                .unwrap()
+                .contains("response-content-disposition=inline%3B")
+        );
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/publications/{}/pdf-url?disposition=embedded",
+                publication.id
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Files lost from storage are reported instead of presigned
+        object_store
+            .delete_keys(&[s3key], &S3Bucket::Storage)
+            .await
+            .unwrap();
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/pdf-url", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["message"], "File missing from storage");
+
+        // Publications without a file have nothing to download
+        let publication_id =
+            crate::api::tests::create_test_publication(&sql_client, user_privy_id).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/pdf-url", publication_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_verify_publication_file_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        let unverifiable = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Uploaded before checksums".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(format!("publications/{}/paper.pdf", uuid::Uuid::new_v4())),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+        let uri = format!("/publications/{}/verify-file", unverifiable.id);
+
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let without_file =
+            crate::api::tests::create_test_publication(&sql_client, user_privy_id).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/publications/{}/verify-file", without_file))
+            .to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+    /// Sets a multipart form built by the helpers above as the body of `request`
+    fn with_multipart(
+        request: test::TestRequest,
+        (boundary, body): (String, Vec<u8>),
+    ) -> test::TestRequest {
+        request
+            .insert_header((
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            ))
+            .insert_header(("Content-Length", body.len()))
+            .set_payload(body)
+    }
+
+    #[sqlx::test]
+    async fn test_authentication_surface_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        crate::api::tests::create_test_publication(&sql_client, user_privy_id).await;
+
+        // Rejected by the middleware before the multipart body is even parsed
+        let req = test::TestRequest::post()
+            .uri("/publications/create")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "unauthorized");
+
+        // Reads stay public
+        let req = test::TestRequest::get()
+            .uri("/publications/list")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["total"], 1);
+    }
+
+    #[sqlx::test]
+    async fn test_create_publication_with_file_api(pool: PgPool) {
+        // Uploads are hashed on the blocking thread pool
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let req = with_multipart(
+            test::TestRequest::post().uri("/publications/create"),
+            create_publication_multipart_body(
+                Some(&user_privy_id),
+                "With a file",
+                None,
+                None,
+                true,
+            ),
+        )
+        .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let s3key = body["s3key"].as_str().unwrap();
+        // The file is stored in the directory of the publication
+        assert_eq!(
+            s3key,
+            format!("publications/{}/test.pdf", body["id"].as_str().unwrap())
+        );
+        assert_eq!(
+            object_store.keys(S3Bucket::Storage),
+            vec![s3key.to_string()]
+        );
+
+        let stored = object_store.get(S3Bucket::Storage, s3key).unwrap();
+        assert!(stored.bytes.starts_with(b"%PDF-1.4"));
+        assert_eq!(stored.content_type.as_deref(), Some("application/pdf"));
+        assert_eq!(stored.metadata["uploader"], user_privy_id);
+        assert_eq!(stored.metadata["original-filename"], "test.pdf");
+
+        let hashes = hash_byte_stream(ByteStream::from(stored.bytes))
+            .await
+            .unwrap();
+        assert_eq!(body["paper_hash"], hashes.sha3_256);
+        assert_eq!(body["file_sha256"], hashes.sha256);
+    }
+
+    #[sqlx::test]
+    async fn test_create_publication_is_atomic_api(pool: PgPool) {
+        // Uploads are hashed on the blocking thread pool
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        create_test_author(&sql_client, &user_privy_id).await;
+        let cited_id = create_test_publication(&sql_client, user_privy_id.clone()).await;
+
+        let create_request = |authors: serde_json::Value, citations: serde_json::Value| {
+            let (boundary, mut body) = create_publication_multipart_body(
+                Some(&user_privy_id),
+                "With authors",
+                None,
+                None,
+                true,
+            );
+            // Adds the authors and citations before the closing boundary
+            let closing = format!("--{boundary}--\r\n");
+            body.truncate(body.len() - closing.len());
+            for (name, value) in [("authors", authors), ("citations", citations)] {
+                body.extend_from_slice(
+                    format!(
+                        "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+                    )
+                    .as_bytes(),
+                );
+            }
+            body.extend_from_slice(closing.as_bytes());
+
+            let req = with_multipart(
+                test::TestRequest::post().uri("/publications/create"),
+                (boundary, body),
+            )
+            .to_request();
+            authenticate(&req, &user_privy_id);
+            req
+        };
+
+        // A missing author fails the whole creation, and the uploaded file is deleted
+        let req = create_request(
+            json!([user_privy_id, "did:privy:missing"]),
+            json!([cited_id]),
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "validation_error");
+        assert_eq!(sql_client.count_publications().await.unwrap(), 1);
+        assert_eq!(sql_client.count_citations().await.unwrap(), 0);
+        assert!(object_store.keys(S3Bucket::Storage).is_empty());
+
+        // So does a missing cited publication
+        let req = create_request(json!([user_privy_id]), json!([uuid::Uuid::new_v4()]));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(sql_client.count_publications().await.unwrap(), 1);
+        assert!(object_store.keys(S3Bucket::Storage).is_empty());
+
+        let req = create_request(json!([user_privy_id, user_privy_id]), json!([]));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = create_request(json!([user_privy_id]), json!([cited_id, cited_id]));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let publication_id = body["id"].as_str().unwrap().parse().unwrap();
+        let authors =
+            PublicationAuthorOperations::get_publication_authors(&sql_client, publication_id)
+                .await
+                .unwrap();
+        assert_eq!(authors.len(), 1);
+        assert_eq!(authors[0].author_id, user_privy_id);
+        assert_eq!(sql_client.count_citations().await.unwrap(), 1);
+        assert_eq!(object_store.keys(S3Bucket::Storage).len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_create_publication_with_missing_bucket_api(pool: PgPool) {
+        // Uploads are hashed on the blocking thread pool
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let create_request = || {
+            let req = with_multipart(
+                test::TestRequest::post().uri("/publications/create"),
+                create_publication_multipart_body(
+                    Some(&user_privy_id),
+                    "Missing bucket",
+                    None,
+                    None,
+                    true,
+                ),
+            )
+            .to_request();
+            authenticate(&req, &user_privy_id);
+            req
+        };
+
+        // The bucket is recreated and the upload retried
+        object_store.set_missing_bucket(true);
+        let resp = test::call_service(&app, create_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(object_store.bucket_creations(), 1);
+        assert_eq!(object_store.keys(S3Bucket::Storage).len(), 1);
+
+        // A bucket that cannot be recreated makes storage unavailable
+        object_store.set_missing_bucket(true);
+        object_store.set_failing_bucket_creation(true);
+        let resp = test::call_service(&app, create_request()).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(object_store.bucket_creations(), 2);
+        assert_eq!(object_store.keys(S3Bucket::Storage).len(), 1);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["message"], "Storage unavailable");
+    }
+
+    #[sqlx::test]
+    async fn test_create_publication_from_upload_intent_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let other_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let bytes = b"%PDF-1.4 uploaded directly".to_vec();
+        let file_size = bytes.len().to_string();
+        let req = test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({
+                "file_name": "paper.pdf",
+                "file_size": bytes.len(),
+                "content_type": "application/pdf"
+            }))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let s3key = body["s3key"].as_str().unwrap().to_string();
+
+        object_store.insert(S3Bucket::Storage, &s3key, MockObject::new(bytes.clone()));
+        let hashes = hash_byte_stream(ByteStream::from(bytes.clone()))
+            .await
+            .unwrap();
+
+        let create = |s3key: &str, sha3_hash: &str, privy_id: &str| {
+            let req = with_multipart(
+                test::TestRequest::post().uri("/publications/create"),
+                create_text_multipart_body(&[
+                    ("title", "Direct Upload"),
+                    ("s3key", s3key),
+                    ("sha3_hash", sha3_hash),
+                    ("file_size", &file_size),
+                ]),
+            )
+            .to_request();
+            authenticate(&req, privy_id);
+            req
+        };
+
+        // A hash that does not match the uploaded object is rejected
+        let other_hash = "0".repeat(64);
+        let resp = test::call_service(&app, create(&s3key, &other_hash, &user_privy_id)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // The key can only be claimed by the user it was issued to
+        let resp =
+            test::call_service(&app, create(&s3key, &hashes.sha3_256, &other_privy_id)).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Keys not issued by an upload intent are rejected, even when a file is stored under them
+        let unissued_key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(
+            S3Bucket::Storage,
+            &unissued_key,
+            MockObject::new(bytes.clone()),
+        );
+        let resp = test::call_service(
+            &app,
+            create(&unissued_key, &hashes.sha3_256, &user_privy_id),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(sql_client.count_publications().await.unwrap(), 0);
+
+        let resp = test::call_service(&app, create(&s3key, &hashes.sha3_256, &user_privy_id)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["s3key"], s3key);
+        // The publication is named after the directory allocated by the upload intent
+        assert_eq!(
+            s3key,
+            format!("publications/{}/paper.pdf", body["id"].as_str().unwrap())
+        );
+        assert_eq!(body["paper_hash"], hashes.sha3_256);
+        assert_eq!(body["file_sha256"], hashes.sha256);
+
+        // The file of a publication cannot be claimed by another one
+        let resp = test::call_service(&app, create(&s3key, &hashes.sha3_256, &user_privy_id)).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        assert_eq!(sql_client.count_publications().await.unwrap(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_update_publication_file_api(pool: PgPool) {
+        // Uploads are hashed on the blocking thread pool
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let previous_key = format!("publications/{}/old.pdf", uuid::Uuid::new_v4());
+        object_store.insert(
+            S3Bucket::Storage,
+            &previous_key,
+            MockObject::new(b"%PDF-1.4 previous".to_vec()),
+        );
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Versioned".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(previous_key.clone()),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+
+        let req = with_multipart(
+            test::TestRequest::put().uri(&format!("/publications/{}", publication.id)),
+            create_publication_multipart_body(None, "Versioned", None, None, true),
+        )
+        .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let updated = sql_client.get_publication(publication.id).await.unwrap();
+        let current_key = updated.s3key.unwrap();
+        assert_eq!(
+            current_key,
+            format!("publications/{}/test.pdf", publication.id)
+        );
+        assert!(updated.paper_hash.is_some());
+        assert!(updated.file_sha256.is_some());
+
+        let versions_prefix = format!("publications/{}/versions/", publication.id);
+        let versions: Vec<String> = object_store
+            .keys(S3Bucket::Storage)
+            .into_iter()
+            .filter(|key| key.starts_with(&versions_prefix))
+            .collect();
+        assert_eq!(versions.len(), 1);
+        assert!(versions[0].ends_with("_old.pdf"));
+        assert_eq!(
+            object_store
+                .get(S3Bucket::Storage, &versions[0])
+                .unwrap()
+                .bytes,
+            b"%PDF-1.4 previous"
+        );
+
+        // A failed update leaves neither the new file nor another version behind, and puts back
+        // the file it replaced under the same name
+        object_store.insert(
+            S3Bucket::Storage,
+            &current_key,
+            MockObject::new(b"%PDF-1.4 current".to_vec()),
+        );
+        let stored_keys = object_store.keys(S3Bucket::Storage);
+        let req = with_multipart(
+            test::TestRequest::put().uri(&format!("/publications/{}", publication.id)),
+            create_publication_multipart_body(None, &"x".repeat(600), None, None, true),
+        )
+        .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(object_store.keys(S3Bucket::Storage), stored_keys);
+        assert_eq!(
+            object_store
+                .get(S3Bucket::Storage, &current_key)
+                .unwrap()
+                .bytes,
+            b"%PDF-1.4 current"
+        );
+        let unchanged = sql_client.get_publication(publication.id).await.unwrap();
+        assert_eq!(unchanged.s3key.unwrap(), current_key);
+    }
+
+    #[sqlx::test]
+    async fn test_delete_and_restore_publication_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        let s3key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(S3Bucket::Storage, &s3key, MockObject::new(b"%PDF".to_vec()));
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Deleted then restored".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(s3key.clone()),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+        let citing = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Citing".to_string(),
+                about: None,
+                tags: None,
+                s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+        for (citing_id, cited_id) in [(publication.id, citing.id), (citing.id, publication.id)] {
+            sql_client
+                .create_citation(&NewCitation {
+                    citing_publication_id: citing_id,
+                    cited_publication_id: cited_id,
+                })
+                .await
+                .unwrap();
+        }
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/publications/{}", publication.id))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        // The files are kept for a restore
+        assert!(object_store.get(S3Bucket::Storage, &s3key).is_some());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/publications/list")
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["total"], 1);
+        assert_eq!(resp["items"][0]["id"], citing.id.to_string());
+
+        // Citations of the deleted publication still resolve, to a tombstone
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/citations", citing.id))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp, json!([{ "id": publication.id, "deleted": true }]));
+        // while its own citations no longer count
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/cited-by", citing.id))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp, json!([]));
+
+        // Only admins restore publications
+        let restore = || {
+            test::TestRequest::post()
+                .uri(&format!("/publications/{}/restore", publication.id))
+                .to_request()
+        };
+        let resp = test::call_service(&app, restore()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let req = restore();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = restore();
+        authenticate(&req, &admin_privy_id);
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["id"], publication.id.to_string());
+        assert_eq!(resp["s3key"], s3key);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/citations", citing.id))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp[0]["id"], publication.id.to_string());
+        assert_eq!(resp[0]["title"], publication.title);
+
+        // Restoring a publication that is not deleted finds nothing
+        let req = restore();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_export_publication_citation_api(pool: PgPool) {
+        use crate::db::sql::{AuthorOperations, models::NewAuthor};
+
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+
+        let mut authors = vec![];
+        for name in ["Zoë Müller", "Ada Okafor"] {
+            let privy_id = create_test_user(&sql_client).await;
+            sql_client
+                .create_author(&NewAuthor {
+                    privy_id: privy_id.clone(),
+                    name: name.to_string(),
+                    email: None,
+                    affiliation: None,
+                })
+                .await
+                .unwrap();
+            authors.push(privy_id);
+        }
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: authors[0].clone(),
+                title: "The {Rust} & 100% safe kernel".to_string(),
+                about: None,
+                tags: None,
+                s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+        sql_client
+            .set_publication_authors(publication.id, &authors)
+            .await
+            .unwrap();
+        let key = format!("muller{}rust", publication.created_at.format("%Y"));
+        let url = format!("http://localhost:8080/publications/{}", publication.id);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/bibtex", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/x-bibtex; charset=utf-8"
+        );
+        assert_eq!(
+            resp.headers().get("content-disposition").unwrap(),
+            format!("attachment; filename=\"{key}.bib\"").as_str()
+        );
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.starts_with(&format!("@article{{{key},\n")));
+        assert!(body.contains("  title = {The \\{Rust\\} \\& 100\\% safe kernel},\n"));
+        assert!(body.contains("  author = {Zoë Müller and Ada Okafor},\n"));
+        assert!(body.contains(&format!("  url = {{{url}}},\n")));
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/publications/{}/bibtex?format=ris",
+                publication.id
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.starts_with("TY  - JOUR\n"));
+        assert!(body.contains("AU  - Müller, Zoë\nAU  - Okafor, Ada\n"));
+        assert!(body.contains(&format!("UR  - {url}\n")));
+        assert!(body.ends_with("ER  - \n"));
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/publications/{}/bibtex?format=docx",
+                publication.id
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/bibtex", uuid::Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_get_publications_batch_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        create_test_author(&sql_client, &user_privy_id).await;
+
+        let first = create_test_publication(&sql_client, user_privy_id.clone()).await;
+        let second = create_test_publication(&sql_client, user_privy_id.clone()).await;
+        sql_client
+            .set_publication_authors(first, std::slice::from_ref(&user_privy_id))
+            .await
+            .unwrap();
+        sql_client
+            .create_citation(&NewCitation {
+                citing_publication_id: second,
+                cited_publication_id: first,
+            })
+            .await
+            .unwrap();
+        sql_client.record_view(second, None).await.unwrap();
+        let missing = uuid::Uuid::new_v4();
+
+        let req = test::TestRequest::post()
+            .uri("/publications/batch")
+            .set_json(json!({ "ids": [second, missing, first, second] }))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let items: serde_json::Value = test::read_body_json(resp).await;
+        let items = items.as_array().unwrap();
+
+        // Items follow the requested ids, duplicates included and missing ones as null
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0]["id"], second.to_string());
+        assert!(items[1].is_null());
+        assert_eq!(items[2]["id"], first.to_string());
+        assert_eq!(items[3], items[0]);
+
+        // Publications come with their authors and audience
+        assert_eq!(items[0]["is_owner"], true);
+        assert_eq!(items[0]["view_count"], 1);
+        assert_eq!(items[0]["authors"], json!([]));
+        assert_eq!(items[0]["citation_count"], 0);
+        assert_eq!(items[2]["view_count"], 0);
+        assert_eq!(items[2]["citation_count"], 1);
+        assert_eq!(items[2]["authors"][0]["privy_id"], user_privy_id);
+
+        // Anonymous requests are not told about ownership, and views are not recorded
+        let req = test::TestRequest::post()
+            .uri("/publications/batch")
+            .set_json(json!({ "ids": [first] }))
+            .to_request();
+        let anonymous: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(anonymous[0].get("is_owner").is_none());
+        assert_eq!(sql_client.get_view_count(first).await.unwrap(), 0);
+
+        // Each item is what a single get returns
+        for (item, id) in [(&items[0], second), (&items[2], first)] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/publications/{}", id))
+                .to_request();
+            authenticate(&req, &user_privy_id);
+            let single: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(item, &single);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/publications/batch")
+            .set_json(json!({ "ids": [] }))
+            .to_request();
+        let items: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(items, json!([]));
+
+        let too_many: Vec<uuid::Uuid> = (0..101).map(|_| uuid::Uuid::new_v4()).collect();
+        let req = test::TestRequest::post()
+            .uri("/publications/batch")
+            .set_json(json!({ "ids": too_many }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_verify_publication_file_integrity_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let admin_privy_id = crate::api::tests::create_test_admin(&sql_client).await;
+
+        let bytes = b"%PDF-1.4 verified".to_vec();
+        let hashes = hash_byte_stream(ByteStream::from(bytes.clone()))
+            .await
+            .unwrap();
+        let s3key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(S3Bucket::Storage, &s3key, MockObject::new(bytes));
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id,
+                title: "Verified".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(s3key.clone()),
+                paper_hash: Some(hashes.sha3_256),
+                file_sha256: Some(hashes.sha256.clone()),
+                file_size: None,
+            })
+            .await
+            .unwrap();
+        let uri = format!("/publications/{}/verify-file", publication.id);
+
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["actual_sha256"], hashes.sha256);
+
+        object_store.insert(
+            S3Bucket::Storage,
+            &s3key,
+            MockObject::new(b"%PDF-1.4 tampered".to_vec()),
+        );
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "mismatch");
+
+        object_store
+            .delete_keys(&[s3key], &S3Bucket::Storage)
+            .await
+            .unwrap();
+        let req = test::TestRequest::post().uri(&uri).to_request();
+        authenticate(&req, &admin_privy_id);
+        let resp = test::call_service(&app, req).await;
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "missing");
+    }
+
+    #[sqlx::test]
+    async fn test_download_publication_file_api(pool: PgPool) {
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let payload = b"%PDF-1.4 0123456789".to_vec();
+        let s3key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(
+            S3Bucket::Storage,
+            &s3key,
+            MockObject {
+                content_type: Some("application/pdf".to_string()),
+                metadata: std::collections::HashMap::from([(
+                    "original-filename".to_string(),
+                    "论文 final.pdf".to_string(),
+                )]),
+                ..MockObject::new(payload.clone())
+            },
+        );
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id,
+                title: "Streamed".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(s3key.clone()),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+        let uri = format!("/publications/{}/download", publication.id);
+        let header = |resp: &actix_web::dev::ServiceResponse, name: &str| {
+            resp.headers()
+                .get(name)
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(header(&resp, "Accept-Ranges").as_deref(), Some("bytes"));
+        assert_eq!(
+            header(&resp, "Content-Type").as_deref(),
+            Some("application/pdf")
+        );
+        assert_eq!(
+            header(&resp, "Content-Length"),
+            Some(payload.len().to_string())
+        );
+        assert_eq!(
+            header(&resp, "Content-Disposition").as_deref(),
+            Some(
+                "attachment; filename=\"___final.pdf\"; filename*=UTF-8''%E8%AE%BA%E6%96%87%20final.pdf"
+            )
+        );
+        assert_eq!(test::read_body(resp).await, payload);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("{}?disposition=inline", uri))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            header(&resp, "Content-Disposition")
+                .unwrap()
+                .starts_with("inline; filename=\"___final.pdf\"")
+        );
+
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("Range", "bytes=9-12"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            header(&resp, "Content-Range").as_deref(),
+            Some("bytes 9-12/19")
+        );
+        assert_eq!(header(&resp, "Content-Length").as_deref(), Some("4"));
+        assert_eq!(test::read_body(resp).await, "0123");
+
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("Range", "bytes=-3"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            header(&resp, "Content-Range").as_deref(),
+            Some("bytes 16-18/19")
+        );
+        assert_eq!(test::read_body(resp).await, "789");
+
+        // Malformed ranges are ignored
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("Range", "bytes=abc"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(test::read_body(resp).await, payload);
+
+        for range in ["bytes=0-1,4-5", "bytes=19-"] {
+            let req = test::TestRequest::get()
+                .uri(&uri)
+                .insert_header(("Range", range))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+            assert_eq!(
+                header(&resp, "Content-Range").as_deref(),
+                Some("bytes */19")
+            );
+        }
+
+        object_store
+            .delete_keys(&[s3key], &S3Bucket::Storage)
+            .await
+            .unwrap();
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_supplementary_files_api(pool: PgPool) {
+        // Uploads are hashed on the blocking thread pool
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let owner_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let other_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let publication_id =
+            crate::api::tests::create_test_publication(&sql_client, owner_privy_id.clone()).await;
+        let uri = format!("/publications/{}/files", publication_id);
+        let files: &[(&str, &str, &[u8])] = &[
+            ("dataset.csv", "text/csv", b"a,b\n1,2\n"),
+            ("code.zip", "application/zip", b"PK\x03\x04"),
+        ];
+
+        let req = with_multipart(
+            test::TestRequest::post().uri(&uri),
+            create_files_multipart_body(files),
+        )
+        .to_request();
+        authenticate(&req, &other_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = with_multipart(
+            test::TestRequest::post().uri(&uri),
+            create_files_multipart_body(files),
+        )
+        .to_request();
+        authenticate(&req, &owner_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let created: serde_json::Value = test::read_body_json(resp).await;
+        let created = created.as_array().unwrap();
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0]["filename"], "dataset.csv");
+        assert_eq!(created[0]["content_type"], "text/csv");
+        assert_eq!(created[0]["size"], 8);
+        assert!(
+            created[0]["file_url"]
+                .as_str()
+                .unwrap()
+                .contains("method=GET")
+        );
+
+        let supplementary_prefix = format!("publications/{}/supplementary/", publication_id);
+        let stored_keys = object_store.keys(S3Bucket::Storage);
+        assert_eq!(stored_keys.len(), 2);
+        assert!(
+            stored_keys
+                .iter()
+                .all(|key| key.starts_with(&supplementary_prefix))
+        );
+        let dataset_key = created[0]["s3key"].as_str().unwrap();
+        assert_eq!(
+            object_store
+                .get(S3Bucket::Storage, dataset_key)
+                .unwrap()
+                .bytes,
+            b"a,b\n1,2\n"
+        );
+
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let listed: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+
+        let file_id = created[0]["id"].as_str().unwrap();
+        let req = test::TestRequest::delete()
+            .uri(&format!("{}/{}", uri, file_id))
+            .to_request();
+        authenticate(&req, &other_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("{}/{}", uri, file_id))
+            .to_request();
+        authenticate(&req, &owner_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(object_store.get(S3Bucket::Storage, dataset_key).is_none());
+        assert_eq!(object_store.keys(S3Bucket::Storage).len(), 1);
+
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        let listed: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+        assert_eq!(listed[0]["filename"], "code.zip");
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("{}/{}", uri, file_id))
+            .to_request();
+        authenticate(&req, &owner_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_upload_supplementary_file_content_type_api(pool: PgPool) {
+        // Multipart files are written on the blocking thread pool
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let owner_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+        let publication_id =
+            crate::api::tests::create_test_publication(&sql_client, owner_privy_id.clone()).await;
+
+        // A single disallowed file rejects the whole upload
+        let req = with_multipart(
+            test::TestRequest::post().uri(&format!("/publications/{}/files", publication_id)),
+            create_files_multipart_body(&[
+                ("dataset.csv", "text/csv", b"a,b\n"),
+                ("setup.exe", "application/x-msdownload", b"MZ"),
+            ]),
+        )
+        .to_request();
+        authenticate(&req, &owner_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        assert!(object_store.keys(S3Bucket::Storage).is_empty());
+        assert!(
+            sql_client
+                .list_publication_files(publication_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_download_publication_bundle_api(pool: PgPool) {
+        // The archive is written by a task on the Tokio runtime
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let (app, object_store) = create_test_app_with_store(pool.clone()).await;
+        let app = test::init_service(app).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        let s3key = format!("publications/{}/paper.pdf", uuid::Uuid::new_v4());
+        object_store.insert(
+            S3Bucket::Storage,
+            &s3key,
+            MockObject {
+                metadata: std::collections::HashMap::from([(
+                    "original-filename".to_string(),
+                    "My Paper.pdf".to_string(),
+                )]),
+                ..MockObject::new(b"%PDF-1.4 paper".to_vec())
+            },
+        );
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Bundled".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(s3key),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await
+            .unwrap();
+
+        for (index, content) in [b"a,b\n1,2\n".as_slice(), b"c,d\n3,4\n"].iter().enumerate() {
+            let s3key = format!(
+                "publications/{}/supplementary/{}/data.csv",
+                publication.id, index
+            );
+            object_store.insert(S3Bucket::Storage, &s3key, MockObject::new(content.to_vec()));
+            sql_client
+                .create_publication_file(&crate::db::sql::models::NewPublicationFile {
+                    publication_id: publication.id,
+                    s3key,
+                    filename: "data.csv".to_string(),
+                    content_type: "text/csv".to_string(),
+                    size: content.len() as i64,
+                })
+                .await
+                .unwrap();
+        }
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/bundle.zip", publication.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/zip"
+        );
+        assert_eq!(
+            resp.headers().get("content-disposition").unwrap(),
+            "attachment; filename=\"Bundled.zip\""
+        );
+
+        let archive = test::read_body(resp).await.to_vec();
+        let reader = async_zip::base::read::mem::ZipFileReader::new(archive)
+            .await
+            .unwrap();
+        let mut contents = vec![];
+        for index in 0..reader.file().entries().len() {
+            let name = reader.file().entries()[index]
+                .filename()
+                .as_str()
+                .unwrap()
+                .to_string();
+            let mut bytes = vec![];
+            reader
+                .reader_with_entry(index)
+                .await
+                .unwrap()
+                .read_to_end_checked(&mut bytes)
+                .await
+                .unwrap();
+            contents.push((name, bytes));
+        }
+        assert_eq!(
+            contents,
+            vec![
+                ("My_Paper.pdf".to_string(), b"%PDF-1.4 paper".to_vec()),
+                ("supplementary/data.csv".to_string(), b"a,b\n1,2\n".to_vec()),
+                (
+                    "supplementary/data-2.csv".to_string(),
+                    b"c,d\n3,4\n".to_vec()
+                ),
+            ]
+        );
+
+        let empty_publication =
+            crate::api::tests::create_test_publication(&sql_client, user_privy_id).await;
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}/bundle.zip", empty_publication))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_storage_quota_api(pool: PgPool) {
+        // Multipart files are written on the blocking thread pool
+        let runtime = tokio_runtime();
+        let _guard = runtime.enter();
+
+        let object_store = Arc::new(MockObjectStore::default());
+        let mut app_state = crate::api::tests::create_test_app_state(pool.clone()).await;
+        app_state.storage_quota = Some(1000);
+        app_state.object_store = object_store.clone();
+        let app =
+            test::init_service(crate::api::tests::create_test_app_with_state(app_state)).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = crate::api::tests::create_test_user(&sql_client).await;
+
+        sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Already stored".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(format!("publications/{}/paper.pdf", uuid::Uuid::new_v4())),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: Some(900),
+            })
+            .await
+            .unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({
+                "file_name": "paper.pdf",
+                "file_size": 200,
+                "content_type": "application/pdf"
+            }))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["details"]["usage_bytes"], 900);
+        assert_eq!(body["error"]["details"]["quota_bytes"], 1000);
+        assert_eq!(body["error"]["details"]["requested_bytes"], 200);
+
+        let req = test::TestRequest::post()
+            .uri("/publications/upload-intent")
+            .set_json(json!({
+                "file_name": "paper.pdf",
+                "file_size": 100,
+                "content_type": "application/pdf"
+            }))
+            .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let s3key = body["s3key"].as_str().unwrap().to_string();
+
+        // Files stored since the upload intent count when creating the publication
+        sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id.clone(),
+                title: "Stored meanwhile".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(format!("publications/{}/paper.pdf", uuid::Uuid::new_v4())),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: Some(50),
+            })
+            .await
+            .unwrap();
+        object_store.insert(S3Bucket::Storage, &s3key, MockObject::new(vec![b'0'; 100]));
+        let req = with_multipart(
+            test::TestRequest::post().uri("/publications/create"),
+            create_text_multipart_body(&[
+                ("title", "Direct Upload"),
+                ("s3key", &s3key),
+                ("sha3_hash", &"0".repeat(64)),
+                ("file_size", "100"),
+            ]),
+        )
+        .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let publication_id =
+            crate::api::tests::create_test_publication(&sql_client, user_privy_id.clone()).await;
+        let req = with_multipart(
+            test::TestRequest::post().uri(&format!("/publications/{}/files", publication_id)),
+            create_files_multipart_body(&[("data.csv", "text/csv", &[b'0'; 200])]),
+        )
+        .to_request();
+        authenticate(&req, &user_privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::io::Write;
+
+    use actix_multipart::form::tempfile::TempFile;
+    use actix_web::{ResponseError, http::StatusCode};
+    use aws_sdk_s3::primitives::ByteStream;
+
+    use super::super::{
+        FileIntegrityStatus, RequestedRange, TitleHighlight, check_file_integrity,
+        publication_file_metadata, publication_storage_prefixes, publication_version_key,
+        requested_range, title_highlight, unique_entry_name, validate_pdf,
+    };
+    use crate::common::hash::FileHashes;
+    use crate::db::s3::ByteRange;
+    use crate::db::sql::models::Publication;
+
+    fn temp_file(file_name: &str, content: &[u8]) -> TempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(content).unwrap();
+        TempFile {
+            file,
+            content_type: Some(actix_web::mime::APPLICATION_PDF),
+            file_name: Some(file_name.to_string()),
+            size: content.len(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_validate_pdf() {
+        let pdf = temp_file("paper.pdf", b"%PDF-1.7\n%%EOF");
+        assert!(validate_pdf(&pdf, 1024).await.is_ok());
+
+        // A PNG renamed to .pdf and declared as one
+        let png = temp_file("paper.pdf", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        let err = validate_pdf(&png, 1024).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert!(err.to_string().contains("not a PDF"), "{err}");
+
+        let truncated = temp_file("paper.pdf", b"%PD");
+        assert!(validate_pdf(&truncated, 1024).await.is_err());
+
+        let oversized = temp_file("paper.pdf", b"%PDF-1.7 0123456789");
+        let err = validate_pdf(&oversized, 10).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert!(err.to_string().contains("maximum of 10 bytes"), "{err}");
+
+        let empty = temp_file("paper.pdf", b"");
+        let err = validate_pdf(&empty, 1024).await.unwrap_err();
+        assert!(err.to_string().contains("empty"), "{err}");
+    }
+
+    #[test]
+    fn test_requested_range() {
+        let partial = |start, end| RequestedRange::Partial(ByteRange { start, end });
+
+        assert_eq!(requested_range(None, 100), RequestedRange::Full);
+        assert_eq!(requested_range(Some("bytes=0-9"), 100), partial(0, 9));
+        assert_eq!(requested_range(Some("bytes=90-"), 100), partial(90, 99));
+        assert_eq!(requested_range(Some("bytes=90-200"), 100), partial(90, 99));
+        assert_eq!(requested_range(Some("bytes=-10"), 100), partial(90, 99));
+        assert_eq!(requested_range(Some("bytes=-200"), 100), partial(0, 99));
+
+        for malformed in ["bytes=abc", "bytes=9-0", "items=0-9", "bytes=0"] {
+            assert_eq!(
+                requested_range(Some(malformed), 100),
+                RequestedRange::Full,
+                "{malformed}"
+            );
+        }
+
+        for unsatisfiable in ["bytes=100-", "bytes=100-200", "bytes=-0", "bytes=0-1,5-6"] {
+            assert_eq!(
+                requested_range(Some(unsatisfiable), 100),
+                RequestedRange::Unsatisfiable,
+                "{unsatisfiable}"
+            );
+        }
+        assert_eq!(
+            requested_range(Some("bytes=0-"), 0),
+            RequestedRange::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn test_publication_storage_prefixes() {
+        let publication_id = uuid::Uuid::new_v4();
+        let publication_with_key = |s3key: Option<&str>| Publication {
+            id: publication_id,
+            user_id: None,
+            title: "Prefixes".to_string(),
+            about: None,
+            tags: vec![],
+            s3key: s3key.map(str::to_string),
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            file_url: None,
+        };
+        let own_prefix = format!("publications/{}/", publication_id);
+
+        assert_eq!(
+            publication_storage_prefixes(&publication_with_key(None)),
+            vec![own_prefix.clone()]
+        );
+        assert_eq!(
+            publication_storage_prefixes(&publication_with_key(Some(&format!(
+                "{}paper.pdf",
+                own_prefix
+            )))),
+            vec![own_prefix.clone()]
+        );
+        assert_eq!(
+            publication_storage_prefixes(&publication_with_key(Some(
+                "publications/5d1c7c1e/paper.pdf"
+            ))),
+            vec![own_prefix.clone(), "publications/5d1c7c1e/".to_string()]
+        );
+
+        // Keys that would widen the deletion beyond a single publication directory are ignored
+        for s3key in [
+            "publications/paper.pdf",
+            "publications/../paper.pdf",
+            "publications/a/b/paper.pdf",
+            "s3://bucket/paper.pdf",
+        ] {
+            assert_eq!(
+                publication_storage_prefixes(&publication_with_key(Some(s3key))),
+                vec![own_prefix.clone()]
+            );
+        }
+    }
+
+    #[test]
+    fn test_publication_version_key() {
+        use chrono::TimeZone;
+
+        let publication_id = uuid::Uuid::new_v4();
+        let archived_at = chrono::Utc
+            .with_ymd_and_hms(2025, 12, 16, 9, 30, 5)
+            .unwrap();
+
+        assert_eq!(
+            publication_version_key(
+                publication_id,
+                &format!("publications/{}/paper.pdf", uuid::Uuid::new_v4()),
+                archived_at
+            ),
+            format!(
+                "publications/{}/versions/20251216T093005.000Z_paper.pdf",
+                publication_id
+            )
+        );
+        assert_eq!(
+            publication_version_key(publication_id, "paper.pdf", archived_at),
+            format!(
+                "publications/{}/versions/20251216T093005.000Z_paper.pdf",
+                publication_id
+            )
+        );
+    }
+
+    #[test]
+    fn test_publication_file_metadata() {
+        let publication_id = uuid::Uuid::new_v4();
+        let hashes = FileHashes {
+            sha3_256: "abc123".to_string(),
+            sha256: "def456".to_string(),
+        };
+
+        let metadata = publication_file_metadata(
+            Some("did:privy:uploader"),
+            Some(publication_id),
+            "Résumé final.pdf",
+            &hashes,
+        );
+        assert_eq!(metadata.len(), 5);
+        assert_eq!(metadata["uploader"], "did:privy:uploader");
+        assert_eq!(metadata["publication-id"], publication_id.to_string());
+        assert_eq!(metadata["original-filename"], "Résumé final.pdf");
+        assert_eq!(metadata["sha3-hash"], "abc123");
+        assert_eq!(metadata["sha256"], "def456");
+
+        let metadata = publication_file_metadata(None, None, "paper.pdf", &hashes);
+        assert_eq!(metadata.len(), 3);
+        assert!(!metadata.contains_key("uploader"));
+        assert!(!metadata.contains_key("publication-id"));
+    }
+
+    #[test]
+    fn test_unique_entry_name() {
+        let mut names = std::collections::HashSet::new();
+        assert_eq!(unique_entry_name(&mut names, "data.csv"), "data.csv");
+        assert_eq!(unique_entry_name(&mut names, "data.csv"), "data-2.csv");
+        assert_eq!(unique_entry_name(&mut names, "data.csv"), "data-3.csv");
+        assert_eq!(unique_entry_name(&mut names, "README"), "README");
+        assert_eq!(unique_entry_name(&mut names, "README"), "README-2");
+    }
+
+    #[actix_web::test]
+    async fn test_check_file_integrity() {
+        // SHA-256 of "abc"
+        let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let integrity = check_file_integrity(expected, Some(ByteStream::from_static(b"abc")))
+            .await
+            .unwrap();
+        assert_eq!(integrity.status, FileIntegrityStatus::Ok);
+        assert_eq!(integrity.actual_sha256.as_deref(), Some(expected));
+
+        let integrity = check_file_integrity(
+            &expected.to_uppercase(),
+            Some(ByteStream::from_static(b"abc")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(integrity.status, FileIntegrityStatus::Ok);
+
+        let integrity = check_file_integrity(expected, Some(ByteStream::from_static(b"abd")))
+            .await
+            .unwrap();
+        assert_eq!(integrity.status, FileIntegrityStatus::Mismatch);
+        assert_ne!(integrity.actual_sha256.as_deref(), Some(expected));
+
+        let integrity = check_file_integrity(expected, None).await.unwrap();
+        assert_eq!(integrity.status, FileIntegrityStatus::Missing);
+        assert!(integrity.actual_sha256.is_none());
+    }
+
+    #[test]
+    fn test_title_highlight() {
+        let highlight = |title, query| title_highlight(title, query).map(|h| (h.start, h.end));
+        assert_eq!(highlight("A graph primer", "Graph"), Some((2, 7)));
+        assert_eq!(highlight("Graph", "graph"), Some((0, 5)));
+        // Offsets count characters, not bytes
+        assert_eq!(highlight("Étude des graphes", "GRAPH"), Some((10, 15)));
+        assert_eq!(highlight("Hypergraphs", "graphs"), Some((5, 11)));
+        assert_eq!(highlight("Graph", "graphs"), None);
+        assert_eq!(title_highlight("Graph", ""), None::<TitleHighlight>);
+    }
 }
@@ -0,0 +1,62 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{common::zresult::ZResult, db::kv::KeyValueStore};
+
+/// How long the key of an upload intent can be used to create a publication: longer than its
+/// presigned URL is valid, and well within the age from which unreferenced uploads are swept.
+pub const UPLOAD_INTENT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Keys issued by `POST /publications/upload-intent`, remembered with the user they were issued
+/// to, so that only that user creates a publication from the file uploaded under them.
+pub struct UploadIntents {
+    store: Arc<dyn KeyValueStore>,
+    ttl: Duration,
+}
+
+impl UploadIntents {
+    pub fn new(store: Arc<dyn KeyValueStore>, ttl: Duration) -> Self {
+        UploadIntents { store, ttl }
+    }
+
+    pub async fn record(&self, s3key: &str, uploader: &str) -> ZResult<()> {
+        self.store.set(&intent_key(s3key), uploader, self.ttl).await
+    }
+
+    /// Returns the user `s3key` was issued to, unless it was not issued or its intent expired.
+    pub async fn uploader(&self, s3key: &str) -> ZResult<Option<String>> {
+        self.store.get(&intent_key(s3key)).await
+    }
+}
+
+fn intent_key(s3key: &str) -> String {
+    format!("upload-intent:{s3key}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::kv::MemoryKeyValueStore;
+
+    #[actix_web::test]
+    async fn test_upload_intents() {
+        let intents = UploadIntents::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            Duration::from_secs(60),
+        );
+        let s3key = "publications/0a7e2b0c/paper.pdf";
+
+        assert_eq!(intents.uploader(s3key).await.unwrap(), None);
+        intents.record(s3key, "did:privy:uploader").await.unwrap();
+        assert_eq!(
+            intents.uploader(s3key).await.unwrap().as_deref(),
+            Some("did:privy:uploader")
+        );
+        assert_eq!(
+            intents
+                .uploader("publications/0a7e2b0c/other.pdf")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::{sync::Arc, time::Duration};
+
+use uuid::Uuid;
+
+use crate::db::kv::KeyValueStore;
+
+/// How long views of a publication by the same user count as one.
+pub const VIEW_DEDUPLICATION_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Tells which views of `GET /publications/{id}` to record, so that a user refreshing a
+/// publication counts once per window. Anonymous views are always recorded.
+///
+/// Errors of the store are logged and the view recorded, so that an outage of Redis only inflates
+/// the counts.
+pub struct ViewDeduplication {
+    store: Arc<dyn KeyValueStore>,
+    window: Duration,
+}
+
+impl ViewDeduplication {
+    pub fn new(store: Arc<dyn KeyValueStore>, window: Duration) -> Self {
+        ViewDeduplication { store, window }
+    }
+
+    /// Returns whether the view of the publication by `viewer` is the first within the window.
+    pub async fn is_new_view(&self, publication_id: Uuid, viewer: Option<&str>) -> bool {
+        let Some(viewer) = viewer else {
+            return true;
+        };
+
+        self.store
+            .insert(&view_key(publication_id, viewer), "1", self.window)
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!(
+                    "Error deduplicating view of publication {}: {}",
+                    publication_id,
+                    err
+                );
+                true
+            })
+    }
+}
+
+fn view_key(publication_id: Uuid, viewer: &str) -> String {
+    format!("views:{publication_id}:{viewer}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::kv::MemoryKeyValueStore;
+
+    #[actix_web::test]
+    async fn test_view_deduplication() {
+        let deduplication = ViewDeduplication::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            Duration::from_secs(60),
+        );
+        let publication_id = Uuid::new_v4();
+
+        assert!(
+            deduplication
+                .is_new_view(publication_id, Some("did:privy:a"))
+                .await
+        );
+        assert!(
+            !deduplication
+                .is_new_view(publication_id, Some("did:privy:a"))
+                .await
+        );
+        assert!(
+            deduplication
+                .is_new_view(publication_id, Some("did:privy:b"))
+                .await
+        );
+        assert!(
+            deduplication
+                .is_new_view(Uuid::new_v4(), Some("did:privy:a"))
+                .await
+        );
+        assert!(deduplication.is_new_view(publication_id, None).await);
+        assert!(deduplication.is_new_view(publication_id, None).await);
+    }
+
+    #[actix_web::test]
+    async fn test_views_count_again_after_the_window() {
+        let deduplication =
+            ViewDeduplication::new(Arc::new(MemoryKeyValueStore::default()), Duration::ZERO);
+        let publication_id = Uuid::new_v4();
+
+        assert!(deduplication.is_new_view(publication_id, Some("a")).await);
+        assert!(deduplication.is_new_view(publication_id, Some("a")).await);
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    future::{Ready, ready},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::{
+    Error, HttpMessage, ResponseError,
+    body::EitherBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::{
+        StatusCode,
+        header::{self, HeaderValue},
+    },
+    web,
+};
+use async_trait::async_trait;
+use futures_util::future::LocalBoxFuture;
+use uuid::Uuid;
+
+use crate::{
+    AppState, api::error::ApiError, auth::PrivyClaims, common::zresult::ZResult,
+    db::kv::RedisConnection,
+};
+
+/// Class of the endpoints creating publications, each of which costs gas and Privy calls.
+pub const PUBLISH: &str = "publish";
+
+/// Limits applied when `RATE_LIMITS` does not override them.
+pub const DEFAULT_RATE_LIMITS: &str = "publish:3/3600";
+
+/// Prefix of the sorted sets holding the hits of a user on a class of endpoints, followed by
+/// `<class>:<privy_id>`.
+const RATE_LIMIT_KEY_PREFIX: &str = "ratelimit:";
+
+/// Records a hit in the sliding window of `KEYS[1]` unless it is full, atomically.
+///
+/// Arguments are the current time and window length in milliseconds, the limit and a unique
+/// member. Returns `{1, 0}` if the hit was recorded, `{0, oldest}` with the time of the oldest hit
+/// of the window otherwise.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', tonumber(ARGV[1]) - tonumber(ARGV[2]))
+if redis.call('ZCARD', KEYS[1]) < tonumber(ARGV[3]) then
+    redis.call('ZADD', KEYS[1], ARGV[1], ARGV[4])
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return {1, 0}
+end
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+return {0, tonumber(oldest[2])}
+"#;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum number of requests a user may make to a class of endpoints within a sliding window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitRule {
+    pub class: String,
+    pub limit: u32,
+    pub window: Duration,
+}
+
+impl RateLimitRule {
+    /// Parses `class:limit/window_secs` entries separated by semicolons, such as
+    /// `publish:3/3600;purchase:20/3600`.
+    pub fn parse_all(rules: &str) -> Result<Vec<RateLimitRule>, String> {
+        rules
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let parsed = entry.split_once(':').and_then(|(class, rate)| {
+                    let (limit, window) = rate.split_once('/')?;
+                    Some(RateLimitRule {
+                        class: class.trim().to_string(),
+                        limit: limit.trim().parse().ok()?,
+                        window: Duration::from_secs(window.trim().parse().ok()?),
+                    })
+                });
+
+                parsed
+                    .filter(|rule| !rule.class.is_empty() && !rule.window.is_zero())
+                    .ok_or_else(|| format!("'{entry}' is not of the form class:limit/window_secs"))
+            })
+            .collect()
+    }
+}
+
+/// Store of the timestamps of the hits of each sliding window.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Records a hit at `now`, in milliseconds, in the window of `key` if fewer than `limit` hits
+    /// happened within `window` before it. Returns `None` if it was recorded, and otherwise the
+    /// time of the oldest hit of the window, which frees a slot once it slides out.
+    async fn hit(&self, key: &str, now: i64, window: Duration, limit: u32) -> ZResult<Option<i64>>;
+}
+
+/// [RateLimitStore] keeping each window in a Redis sorted set scored by time.
+pub struct RedisRateLimitStore {
+    connection: RedisConnection,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(client: redis::Client) -> Self {
+        RedisRateLimitStore {
+            connection: RedisConnection::new(client),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn hit(&self, key: &str, now: i64, window: Duration, limit: u32) -> ZResult<Option<i64>> {
+        let mut connection = self.connection.get().await?;
+        let (recorded, oldest): (i64, i64) = redis::cmd("EVAL")
+            .arg(SLIDING_WINDOW_SCRIPT)
+            .arg(1)
+            .arg(key)
+            .arg(now)
+            .arg(window.as_millis() as u64)
+            .arg(limit)
+            .arg(Uuid::new_v4().to_string())
+            .query_async(&mut connection)
+            .await?;
+
+        Ok((recorded == 0).then_some(oldest))
+    }
+}
+
+/// Request rejected by a [RateLimitRule].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub class: String,
+    pub limit: u32,
+    /// Unix timestamp, in milliseconds, at which the next request will be allowed
+    pub reset_at: i64,
+}
+
+/// Per-user limits of the classes of expensive endpoints, applied by the [RateLimit] middleware.
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    rules: HashMap<String, RateLimitRule>,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, rules: Vec<RateLimitRule>) -> Self {
+        RateLimiter {
+            store,
+            rules: rules
+                .into_iter()
+                .map(|rule| (rule.class.clone(), rule))
+                .collect(),
+        }
+    }
+
+    /// Records a request of `privy_id` to an endpoint of `class` at `now`, in milliseconds, unless
+    /// it exceeds the limit of the class. Classes without a rule are unlimited.
+    pub async fn check_at(
+        &self,
+        class: &str,
+        privy_id: &str,
+        now: i64,
+    ) -> ZResult<Result<(), RateLimited>> {
+        let Some(rule) = self.rules.get(class) else {
+            return Ok(Ok(()));
+        };
+
+        let key = format!("{RATE_LIMIT_KEY_PREFIX}{class}:{privy_id}");
+        let oldest = self.store.hit(&key, now, rule.window, rule.limit).await?;
+
+        Ok(match oldest {
+            None => Ok(()),
+            Some(oldest) => Err(RateLimited {
+                class: class.to_string(),
+                limit: rule.limit,
+                reset_at: oldest + rule.window.as_millis() as i64,
+            }),
+        })
+    }
+
+    pub async fn check(&self, class: &str, privy_id: &str) -> ZResult<Result<(), RateLimited>> {
+        self.check_at(class, privy_id, chrono::Utc::now().timestamp_millis())
+            .await
+    }
+}
+
+impl From<RateLimited> for ApiError {
+    fn from(rate_limited: RateLimited) -> Self {
+        ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limited",
+            format!(
+                "At most {} '{}' requests are allowed in the current window",
+                rate_limited.limit, rate_limited.class
+            ),
+        )
+        .with_details(serde_json::json!({
+            "class": rate_limited.class,
+            "limit": rate_limited.limit,
+            "reset_at": ceil_secs(rate_limited.reset_at),
+        }))
+    }
+}
+
+/// Converts non-negative `millis` to seconds, rounding up so that clients never retry too early.
+fn ceil_secs(millis: i64) -> i64 {
+    (millis + 999) / 1000
+}
+
+/// Middleware limiting how often each user may call the endpoints of a class, answering a 429
+/// with the time at which the window frees up otherwise.
+///
+/// It reads the caller from the claims set by the [crate::auth::Privy] middleware, which must wrap
+/// it. Requests without a user, such as those of internal services, are not limited, and requests
+/// are let through when the limits cannot be checked, so that a Redis outage does not block users.
+pub struct RateLimit {
+    class: &'static str,
+}
+
+impl RateLimit {
+    pub fn new(class: &'static str) -> Self {
+        RateLimit { class }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            class: self.class,
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    class: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let privy_id = req
+            .extensions()
+            .get::<PrivyClaims>()
+            .map(|claims| claims.sub.clone());
+        let data = req.app_data::<web::Data<AppState>>().cloned();
+        let service = self.service.clone();
+        let class = self.class;
+
+        Box::pin(async move {
+            let (Some(privy_id), Some(data)) = (privy_id, data) else {
+                return Ok(service.call(req).await?.map_into_left_body());
+            };
+
+            let rate_limited = data
+                .rate_limiter
+                .check(class, &privy_id)
+                .await
+                .unwrap_or_else(|err| {
+                    tracing::error!("Error checking '{}' rate limit: {}", class, err);
+                    Ok(())
+                });
+
+            match rate_limited {
+                Ok(()) => Ok(service.call(req).await?.map_into_left_body()),
+                Err(rate_limited) => {
+                    tracing::warn!("User {} exceeded the '{}' rate limit", privy_id, class);
+                    let retry_after =
+                        (rate_limited.reset_at - chrono::Utc::now().timestamp_millis()).max(0);
+                    let mut response = ApiError::from(rate_limited).error_response();
+                    response.headers_mut().insert(
+                        header::RETRY_AFTER,
+                        HeaderValue::from(ceil_secs(retry_after)),
+                    );
+                    Ok(req.into_response(response).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+/// In-memory [RateLimitStore] standing in for Redis when `REDIS_MODE=disabled` and in tests.
+#[derive(Default)]
+pub struct MemoryRateLimitStore {
+    windows: std::sync::Mutex<HashMap<String, Vec<i64>>>,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn hit(&self, key: &str, now: i64, window: Duration, limit: u32) -> ZResult<Option<i64>> {
+        let mut windows = self.windows.lock().unwrap();
+        let hits = windows.entry(key.to_string()).or_default();
+        hits.retain(|hit| *hit > now - window.as_millis() as i64);
+
+        if hits.len() < limit as usize {
+            hits.push(now);
+            return Ok(None);
+        }
+        Ok(hits.iter().min().copied())
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use actix_web::{
+        http::{StatusCode, header},
+        test,
+    };
+    use sqlx::PgPool;
+
+    use crate::{
+        api::{
+            rate_limit::PUBLISH,
+            tests::{
+                TEST_USER_HEADER, create_test_app_state, create_test_app_with_state,
+                create_test_user, test_rate_limiter,
+            },
+        },
+        common::pagination::PageQuery,
+        db::sql::{PublicationOperations, SqlClient},
+    };
+
+    fn create_publication_request(privy_id: &str, title: &str) -> test::TestRequest {
+        let boundary = "ratelimitboundary";
+        let body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\n{title}\r\n--{boundary}--\r\n"
+        );
+        test::TestRequest::post()
+            .uri("/publications/create")
+            .insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            ))
+            .insert_header((TEST_USER_HEADER, privy_id))
+            .set_payload(body)
+    }
+
+    #[sqlx::test]
+    async fn test_rate_limit_api(pool: PgPool) {
+        let mut app_state = create_test_app_state(pool.clone()).await;
+        app_state.rate_limiter = test_rate_limiter(2);
+        let app = test::init_service(create_test_app_with_state(app_state)).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let other_privy_id = create_test_user(&sql_client).await;
+
+        for title in ["First", "Second"] {
+            let req = create_publication_request(&user_privy_id, title).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        let req = create_publication_request(&user_privy_id, "Third").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: i64 = resp
+            .headers()
+            .get(header::RETRY_AFTER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after > 3500 && retry_after <= 3600);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "rate_limited");
+        assert_eq!(body["error"]["details"]["class"], PUBLISH);
+        assert_eq!(body["error"]["details"]["limit"], 2);
+        let reset_at = body["error"]["details"]["reset_at"].as_i64().unwrap();
+        assert!(reset_at > chrono::Utc::now().timestamp() + 3500);
+
+        // Limits are per user
+        let req = create_publication_request(&other_privy_id, "Other").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Rejected requests are not counted, nor is anything created
+        let (publications, _) = sql_client
+            .list_publications_by_user(&user_privy_id, PageQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(publications.len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::super::{MemoryRateLimitStore, PUBLISH, RateLimitRule, RateLimited, RateLimiter};
+
+    const SECOND: i64 = 1000;
+
+    #[test]
+    fn test_parse_rate_limits() {
+        assert_eq!(
+            RateLimitRule::parse_all("publish:3/3600; purchase:20/60;").unwrap(),
+            vec![
+                RateLimitRule {
+                    class: "publish".to_string(),
+                    limit: 3,
+                    window: Duration::from_secs(3600),
+                },
+                RateLimitRule {
+                    class: "purchase".to_string(),
+                    limit: 20,
+                    window: Duration::from_secs(60),
+                },
+            ]
+        );
+
+        for invalid in [
+            "publish",
+            "publish:3",
+            "publish:x/60",
+            ":3/60",
+            "publish:3/0",
+        ] {
+            assert!(RateLimitRule::parse_all(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_sliding_window() {
+        let limiter = RateLimiter::new(
+            Arc::new(MemoryRateLimitStore::default()),
+            vec![RateLimitRule {
+                class: PUBLISH.to_string(),
+                limit: 3,
+                window: Duration::from_secs(3600),
+            }],
+        );
+        let start = 1_700_000_000 * SECOND;
+        let check = |offset: i64| limiter.check_at(PUBLISH, "user", start + offset);
+
+        assert_eq!(check(0).await.unwrap(), Ok(()));
+        assert_eq!(check(1000 * SECOND).await.unwrap(), Ok(()));
+        assert_eq!(check(2000 * SECOND).await.unwrap(), Ok(()));
+
+        // The window is full until its oldest hit slides out of it
+        let rate_limited = RateLimited {
+            class: PUBLISH.to_string(),
+            limit: 3,
+            reset_at: start + 3600 * SECOND,
+        };
+        assert_eq!(
+            check(2500 * SECOND).await.unwrap(),
+            Err(rate_limited.clone())
+        );
+        assert_eq!(check(3600 * SECOND - 1).await.unwrap(), Err(rate_limited));
+
+        // Across the boundary a slot frees up, and only one
+        assert_eq!(check(3600 * SECOND).await.unwrap(), Ok(()));
+        assert_eq!(
+            check(3700 * SECOND).await.unwrap(),
+            Err(RateLimited {
+                class: PUBLISH.to_string(),
+                limit: 3,
+                reset_at: start + 4600 * SECOND,
+            })
+        );
+
+        // Users and unconfigured classes are independent
+        assert_eq!(
+            limiter.check_at(PUBLISH, "other", start).await.unwrap(),
+            Ok(())
+        );
+        for _ in 0..10 {
+            assert_eq!(
+                limiter.check_at("purchase", "user", start).await.unwrap(),
+                Ok(())
+            );
+        }
+    }
+}
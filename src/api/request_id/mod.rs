@@ -0,0 +1,123 @@
+use std::future::{Ready, ready};
+
+use actix_web::{
+    Error, HttpMessage,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::header::{HeaderName, HeaderValue},
+};
+use futures_util::future::LocalBoxFuture;
+use sentry::{Hub, SentryFutureExt};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::common::error_reporting;
+
+/// Header carrying the request id, read from requests and set on every response.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest request id accepted from clients, matching the `audit_log.request_id` column.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: RequestId;
+}
+
+#[cfg(test)]
+mod tests;
+
+/// Id correlating the logs and responses of a request, stored in its extensions by the
+/// [AssignRequestId] middleware.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Returns the id of the request being handled, if called from within [AssignRequestId].
+    pub fn current() -> Option<RequestId> {
+        CURRENT_REQUEST_ID.try_with(RequestId::clone).ok()
+    }
+
+    /// Returns the id given by a client in `header`, unless it is too long or not printable, in
+    /// which case a new one is generated.
+    fn from_header(header: Option<&HeaderValue>) -> Self {
+        header
+            .and_then(|header| header.to_str().ok())
+            .filter(|request_id| {
+                !request_id.is_empty()
+                    && request_id.len() <= MAX_REQUEST_ID_LEN
+                    && request_id.bytes().all(|byte| byte.is_ascii_graphic())
+            })
+            .map(|request_id| RequestId(request_id.to_string()))
+            .unwrap_or_else(|| RequestId(Uuid::new_v4().to_string()))
+    }
+}
+
+/// Middleware assigning every request an id, taken from its `X-Request-Id` header or generated,
+/// and echoing it on the response.
+///
+/// The rest of the chain runs within a `request` tracing span carrying the id, matched route and
+/// authenticated user, so that every log of the request can be correlated, and with its own error
+/// reporting hub, tagged likewise. [crate::api::error::ApiError] responses include the id in their
+/// body.
+pub struct AssignRequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for AssignRequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AssignRequestIdMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AssignRequestIdMiddleware { service }))
+    }
+}
+
+pub struct AssignRequestIdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for AssignRequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = RequestId::from_header(req.headers().get(REQUEST_ID_HEADER));
+        req.extensions_mut().insert(request_id.clone());
+
+        // The caller is only known once the Privy middleware authenticated the request
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id.0,
+            method = %req.method(),
+            path = %req.path(),
+            route = req.match_pattern(),
+            privy_id = tracing::field::Empty
+        );
+        let hub = error_reporting::request_hub(&request_id.0, req.match_pattern().as_deref());
+        let header = HeaderValue::from_str(&request_id.0).ok();
+        // Middlewares authenticate some callers as soon as they are called, before being polled
+        let fut = Hub::run(hub.clone(), || span.in_scope(|| self.service.call(req)));
+        let fut = CURRENT_REQUEST_ID.scope(request_id, fut);
+
+        Box::pin(async move {
+            let mut res = fut.bind_hub(hub).instrument(span).await?;
+            if let Some(header) = header {
+                res.headers_mut().insert(REQUEST_ID_HEADER, header);
+            }
+            Ok(res)
+        })
+    }
+}
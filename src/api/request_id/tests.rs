@@ -0,0 +1,128 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use actix_web::{App, HttpResponse, http::StatusCode, test, web};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use crate::{
+        api::{
+            request_id::{AssignRequestId, REQUEST_ID_HEADER},
+            tests::{TEST_USER_HEADER, TestAuth, create_test_app},
+        },
+        auth::Privy,
+        common::logging::{self, CapturedLogs, LogFormat},
+    };
+
+    #[sqlx::test]
+    async fn test_request_id_round_trip_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+
+        let req = test::TestRequest::get()
+            .uri("/healthz")
+            .insert_header((REQUEST_ID_HEADER, "client-request-42"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(REQUEST_ID_HEADER).unwrap(),
+            "client-request-42"
+        );
+
+        // Error bodies carry the id as well
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", Uuid::new_v4()))
+            .insert_header((REQUEST_ID_HEADER, "client-request-43"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            resp.headers().get(REQUEST_ID_HEADER).unwrap(),
+            "client-request-43"
+        );
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["request_id"], "client-request-43");
+    }
+
+    #[sqlx::test]
+    async fn test_request_id_generated_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool).await).await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/publications/{}", Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let request_id = resp
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(Uuid::parse_str(&request_id).is_ok());
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["request_id"], request_id.as_str());
+
+        // Ids that could not be logged or stored safely are replaced
+        for invalid in ["has spaces", &"x".repeat(129)] {
+            let req = test::TestRequest::get()
+                .uri("/healthz")
+                .insert_header((REQUEST_ID_HEADER, invalid))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            let request_id = resp.headers().get(REQUEST_ID_HEADER).unwrap();
+            assert!(Uuid::parse_str(request_id.to_str().unwrap()).is_ok());
+        }
+    }
+
+    async fn handle_item() -> HttpResponse {
+        tracing::info!("Handling item");
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_request_span_fields_api() {
+        let logs = CapturedLogs::default();
+        let _subscriber =
+            tracing::subscriber::set_default(logging::subscriber(LogFormat::Json, logs.clone()));
+
+        let app = test::init_service(
+            App::new()
+                .service(
+                    web::resource("/items/{item_id}")
+                        .wrap(Privy)
+                        .route(web::get().to(handle_item)),
+                )
+                .route("/public/{item_id}", web::get().to(handle_item))
+                .wrap(AssignRequestId)
+                .wrap(TestAuth),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/items/42")
+            .insert_header((REQUEST_ID_HEADER, "client-request-44"))
+            .insert_header((TEST_USER_HEADER, "did:privy:alice"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get().uri("/public/42").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let lines = logs.json_lines();
+        assert_eq!(lines.len(), 2);
+
+        let span = &lines[0]["span"];
+        assert_eq!(lines[0]["message"], "Handling item");
+        assert_eq!(span["request_id"], "client-request-44");
+        assert_eq!(span["route"], "/items/{item_id}");
+        assert_eq!(span["privy_id"], "did:privy:alice");
+
+        let span = &lines[1]["span"];
+        assert_eq!(span["route"], "/public/{item_id}");
+        assert!(span["privy_id"].is_null());
+    }
+}
@@ -0,0 +1,26 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Acknowledges a change that returns no resource.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MessageResponse {
+    /// Always `success`
+    #[schema(example = "success")]
+    pub status: &'static str,
+    pub message: &'static str,
+}
+
+impl MessageResponse {
+    pub fn success(message: &'static str) -> Self {
+        MessageResponse {
+            status: "success",
+            message,
+        }
+    }
+}
+
+/// Number of records matching a request.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CountResponse {
+    pub count: i64,
+}
@@ -1,15 +1,42 @@
 // Test utilities for API endpoint testing
-use std::sync::Arc;
+mod test_auth;
 
-use actix_web::{App, web::Data};
-use aws_sdk_s3::config::Credentials;
+pub use test_auth::{TEST_USER_HEADER, TestAuth};
+
+use std::{sync::Arc, time::Duration};
+
+use actix_web::{
+    App, HttpMessage,
+    web::{self, Data},
+};
 use redis::Client;
 use sqlx::postgres::PgPool;
 use uuid::Uuid;
 
 use crate::{
     AppState,
-    db::{s3::client::S3Client, sql::SqlClient},
+    api::{
+        publications::{
+            cache::{PUBLICATION_CACHE_TTL, PublicationCache},
+            upload_intents::{UPLOAD_INTENT_TTL, UploadIntents},
+            views::{VIEW_DEDUPLICATION_WINDOW, ViewDeduplication},
+        },
+        rate_limit::{MemoryRateLimitStore, PUBLISH, RateLimitRule, RateLimiter},
+    },
+    auth::{
+        PrivyClaims, internal::InternalApiToken, revocation::SessionRevocation,
+        session::CookieSessions,
+    },
+    common::{shutdown::TaskRegistry, tasks::TaskSupervisor},
+    db::{
+        kv::MemoryKeyValueStore,
+        s3::{
+            ObjectStore,
+            metrics::{InstrumentedObjectStore, StorageMetrics},
+            mock::MockObjectStore,
+        },
+        sql::SqlClient,
+    },
 };
 
 pub async fn create_test_app(
@@ -23,36 +50,148 @@ pub async fn create_test_app(
         InitError = (),
     >,
 > {
+    create_test_app_with_state(create_test_app_state(pool).await)
+}
+
+/// Builds the application state used by [create_test_app], for tests that need to tweak it.
+pub async fn create_test_app_state(pool: PgPool) -> AppState {
     let sql_client = Arc::new(SqlClient::new(pool).await);
 
-    let s3_credentials = Credentials::new(
-        "test_access_key".to_string(),
-        "test_secret_key".to_string(),
-        None,
-        None,
-        "Test",
-    );
-
-    let s3_client = Arc::new(
-        S3Client::new(
-            s3_credentials,
-            None,
-            Some("http://localhost:9000".to_string()),
-        )
-        .await,
-    );
-
-    let redis_client = Client::open("redis://localhost:6379").unwrap();
-
-    let app_state = Data::new(AppState {
+    let redis_client = Some(Client::open("redis://localhost:6379").unwrap());
+    let storage_metrics = Arc::new(StorageMetrics::default());
+    let background_tasks = Arc::new(TaskRegistry::default());
+
+    AppState {
         sql_client,
         redis_client,
-        s3_client,
-    });
+        object_store: instrument(Arc::new(MockObjectStore::default()), &storage_metrics),
+        storage_metrics,
+        server_base_url: "http://localhost:8080".to_string(),
+        presign_expiry: Duration::from_secs(300),
+        storage_quota: None,
+        max_publication_file_size: 100 * 1024 * 1024,
+        session_revocation: Arc::new(SessionRevocation::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            Duration::ZERO,
+            Duration::from_secs(3600),
+        )),
+        sessions: Arc::new(CookieSessions::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            Duration::from_secs(3600),
+            Duration::from_secs(24 * 3600),
+            true,
+        )),
+        internal_tokens: Arc::new([]),
+        rate_limiter: test_rate_limiter(u32::MAX),
+        background_tasks: background_tasks.clone(),
+        supervisor: Arc::new(TaskSupervisor::new(background_tasks)),
+        publication_cache: Arc::new(PublicationCache::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            PUBLICATION_CACHE_TTL,
+        )),
+        view_deduplication: Arc::new(ViewDeduplication::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            VIEW_DEDUPLICATION_WINDOW,
+        )),
+        upload_intents: Arc::new(UploadIntents::new(
+            Arc::new(MemoryKeyValueStore::default()),
+            UPLOAD_INTENT_TTL,
+        )),
+    }
+}
+
+/// Returns a rate limiter allowing `limit` requests per hour to each class of endpoints. Tests
+/// use a limit too high to be reached unless they exercise rate limiting.
+pub fn test_rate_limiter(limit: u32) -> Arc<RateLimiter> {
+    Arc::new(RateLimiter::new(
+        Arc::new(MemoryRateLimitStore::default()),
+        vec![RateLimitRule {
+            class: PUBLISH.to_string(),
+            limit,
+            window: Duration::from_secs(3600),
+        }],
+    ))
+}
 
+pub fn create_test_app_with_state(
+    app_state: AppState,
+) -> App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse<actix_web::body::BoxBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
     App::new()
-        .app_data(app_state.clone())
+        .app_data(Data::new(app_state))
         .configure(crate::api::config)
+        .default_service(web::to(crate::api::error::default_service))
+        .wrap(crate::api::audit::AuditLog)
+        .wrap(crate::api::request_id::AssignRequestId)
+        .wrap(TestAuth)
+}
+
+/// Builds a test app together with a handle on its in-memory object store, to seed or inspect
+/// stored files.
+pub async fn create_test_app_with_store(
+    pool: PgPool,
+) -> (
+    App<
+        impl actix_web::dev::ServiceFactory<
+            actix_web::dev::ServiceRequest,
+            Config = (),
+            Response = actix_web::dev::ServiceResponse<actix_web::body::BoxBody>,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    >,
+    Arc<MockObjectStore>,
+) {
+    let object_store = Arc::new(MockObjectStore::default());
+    let mut app_state = create_test_app_state(pool).await;
+    app_state.object_store = instrument(object_store.clone(), &app_state.storage_metrics);
+
+    (create_test_app_with_state(app_state), object_store)
+}
+
+/// Wraps a test store so that its operations are recorded into `storage_metrics`, as in
+/// production.
+fn instrument(
+    object_store: Arc<MockObjectStore>,
+    storage_metrics: &Arc<StorageMetrics>,
+) -> Arc<dyn ObjectStore> {
+    Arc::new(InstrumentedObjectStore::new(
+        object_store,
+        storage_metrics.clone(),
+        Duration::from_secs(1),
+    ))
+}
+
+/// `sqlx::test` runs on async-std, while the Redis client and Tokio timers need a Tokio reactor.
+/// Tests that reach them enter the returned runtime's context for their duration.
+pub fn tokio_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Runtime::new().unwrap()
+}
+
+/// Marks a built test request as authenticated by inserting the claims the Privy middleware would
+/// produce for `privy_id`. Requests still being built can set [TEST_USER_HEADER] instead.
+pub fn authenticate(req: &impl HttpMessage, privy_id: &str) {
+    req.extensions_mut().insert(test_claims(privy_id));
+}
+
+/// Returns the claims of a fresh session of `privy_id`, issued now and valid for an hour.
+pub fn test_claims(privy_id: &str) -> PrivyClaims {
+    let now = chrono::Utc::now().timestamp() as u64;
+    PrivyClaims {
+        sid: format!("test_session_{}", Uuid::new_v4()),
+        sub: privy_id.to_string(),
+        aud: "test_app".to_string(),
+        iss: "privy.io".to_string(),
+        iat: now,
+        exp: now + 3600,
+    }
 }
 
 pub async fn create_test_user(sql_client: &SqlClient) -> String {
@@ -67,6 +206,14 @@ pub async fn create_test_user(sql_client: &SqlClient) -> String {
     user.privy_id
 }
 
+pub async fn create_test_admin(sql_client: &SqlClient) -> String {
+    use crate::db::sql::UserOperations;
+
+    let privy_id = create_test_user(sql_client).await;
+    sql_client.set_user_admin(&privy_id, true).await.unwrap();
+    privy_id
+}
+
 pub async fn create_test_author(sql_client: &SqlClient, user_privy_id: &str) -> String {
     use crate::db::sql::{AuthorOperations, models::NewAuthor};
 
@@ -90,6 +237,9 @@ pub async fn create_test_publication(sql_client: &SqlClient, user_privy_id: Stri
         about: Some("Test publication description".to_string()),
         tags: Some(vec!["test".to_string(), "research".to_string()]),
         s3key: None,
+        paper_hash: None,
+        file_sha256: None,
+        file_size: None,
     };
 
     let publication = sql_client
@@ -122,3 +272,14 @@ pub async fn create_test_pool() -> PgPool {
         .await
         .expect("Failed to create test database pool")
 }
+
+/// Returns an internal API token named `name`, for the plain `token`, granted `scopes`.
+pub fn internal_token(name: &str, token: &str, scopes: &[&str]) -> InternalApiToken {
+    use sha2::Digest;
+
+    InternalApiToken {
+        name: name.to_string(),
+        sha256: sha2::Sha256::digest(token.as_bytes()).into(),
+        scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+    }
+}
@@ -0,0 +1,62 @@
+use std::future::{Ready, ready};
+
+use actix_web::{
+    Error, HttpMessage,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+};
+
+use crate::{api::tests::test_claims, auth::PrivyClaims};
+
+/// Header naming the Privy id of the user a test request is made as.
+pub const TEST_USER_HEADER: &str = "X-Test-User";
+
+/// Test-only middleware authenticating requests as the user named by their [TEST_USER_HEADER],
+/// by inserting the claims the Privy middleware would produce for a real token.
+pub struct TestAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for TestAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = TestAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TestAuthMiddleware { service }))
+    }
+}
+
+pub struct TestAuthMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TestAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let privy_id = req
+            .headers()
+            .get(TEST_USER_HEADER)
+            .and_then(|privy_id| privy_id.to_str().ok())
+            .map(str::to_string);
+
+        // Claims inserted by the test itself take precedence
+        if let Some(privy_id) = privy_id
+            && !req.extensions().contains::<PrivyClaims>()
+        {
+            req.extensions_mut().insert(test_claims(&privy_id));
+        }
+
+        self.service.call(req)
+    }
+}
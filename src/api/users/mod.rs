@@ -1,17 +1,22 @@
-use actix_web::{
-    HttpRequest, HttpResponse, delete,
-    error::{ErrorConflict, ErrorInternalServerError, ErrorNotFound},
-    get, post, web,
-};
+use actix_web::{HttpRequest, HttpResponse, delete, get, post, web};
+use serde::Serialize;
+use utoipa::{OpenApi, ToSchema};
 
 use crate::{
     AppState,
-    db::sql::{AuthorOperations, PrivyId, UserOperations, models::NewUser},
+    api::error::{ApiError, ErrorResponse},
+    auth::{AuthenticatedUser, Privy, impersonation::refuse_impersonation, require_self_or_admin},
+    common::pagination::{PageQuery, Paginated},
+    db::sql::{
+        AuthorOperations, PrivyId, UserOperations,
+        models::{Author, NewUser, User},
+    },
 };
 
 pub fn config(conf: &mut web::ServiceConfig) {
     let scope = web::scope("/users")
         .service(create_user)
+        .service(get_current_user)
         .service(get_user)
         .service(delete_user)
         .service(list_users)
@@ -19,11 +24,47 @@ pub fn config(conf: &mut web::ServiceConfig) {
     conf.service(scope);
 }
 
-#[post("/create")]
+#[cfg(test)]
+mod tests;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_user,
+    get_current_user,
+    get_user,
+    delete_user,
+    list_users,
+    sign_in
+))]
+pub struct UsersApi;
+
+/// User together with their author profile, if they created one.
+#[derive(Serialize, ToSchema)]
+struct UserProfile {
+    user: User,
+    author: Option<Author>,
+}
+
+/// Creates the caller's user, whose Privy id must be the one of their token.
+#[utoipa::path(
+    responses(
+        (status = 200, body = User),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Another user's Privy id", body = ErrorResponse),
+        (status = 409, description = "User already exists", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/create", wrap = "Privy")]
 async fn create_user(
+    user: AuthenticatedUser,
     data: web::Data<AppState>,
     body: web::Json<CreateUserRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    if body.privy_id != user.privy_id {
+        return Err(ApiError::forbidden("Users can only create their own user").into());
+    }
+
     // Check if user with privy_id already exists
     let user_by_privy_id = data
         .sql_client
@@ -31,7 +72,7 @@ async fn create_user(
         .await;
 
     if user_by_privy_id.is_ok() {
-        return Err(ErrorConflict("User with that privy_id already exists"));
+        return Err(ApiError::conflict("User with that privy_id already exists").into());
     }
 
     let new_user = NewUser {
@@ -44,20 +85,55 @@ async fn create_user(
         .await
         .map_err(|err| {
             tracing::error!("Error creating user: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     Ok(HttpResponse::Ok().json(user))
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 struct CreateUserRequest {
+    #[schema(value_type = String)]
     privy_id: PrivyId,
 }
 
+/// Returns the caller's user and author, if any.
+#[utoipa::path(
+    responses(
+        (status = 200, body = UserProfile),
+        (status = 401, body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[get("/me", wrap = "Privy")]
+async fn get_current_user(
+    user: AuthenticatedUser,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let author = data.sql_client.get_author(&user.privy_id).await.ok();
+    let user = data
+        .sql_client
+        .get_user(user.privy_id)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error retrieving user: {}", err);
+            match err {
+                sqlx::Error::RowNotFound => ApiError::not_found("User not found"),
+                _ => ApiError::internal("Internal server error"),
+            }
+        })?;
+
+    Ok(HttpResponse::Ok().json(UserProfile { user, author }))
+}
+
+#[utoipa::path(responses(
+    (status = 200, body = UserProfile),
+    (status = 404, description = "User not found", body = ErrorResponse)
+))]
 #[get("/{privy_id}")]
 async fn get_user(
-    privy_id: web::Path<PrivyId>,
+    privy_id: web::Path<String>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let user = data
@@ -67,98 +143,97 @@ async fn get_user(
         .map_err(|err| {
             tracing::error!("Error retrieving user: {}", err);
             match err {
-                sqlx::Error::RowNotFound => ErrorNotFound("User not found"),
-                _ => ErrorInternalServerError("Internal server error"),
+                sqlx::Error::RowNotFound => ApiError::not_found("User not found"),
+                _ => ApiError::internal("Internal server error"),
             }
         })?;
 
     let author = data.sql_client.get_author(&privy_id).await.ok();
 
-    let response = serde_json::json!({
-        "user": user,
-        "author": author,
-    });
-
-    Ok(HttpResponse::Ok().json(response))
+    Ok(HttpResponse::Ok().json(UserProfile { user, author }))
 }
 
-#[delete("/{privy_id}")]
+/// Deletes a user, at their own request or an admin's.
+#[utoipa::path(
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "Neither the user nor an admin", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[delete("/{privy_id}", wrap = "Privy")]
 async fn delete_user(
-    privy_id: web::Path<PrivyId>,
+    req: HttpRequest,
+    privy_id: web::Path<String>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    require_self_or_admin(&req, &data.sql_client, &privy_id).await?;
+
     let result = data
         .sql_client
         .delete_user(privy_id.to_string())
         .await
         .map_err(|err| {
             tracing::error!("Error deleting user: {}", err);
-            ErrorInternalServerError("Internal server error")
+            ApiError::internal("Internal server error")
         })?;
 
     if result.rows_affected() == 0 {
-        return Err(ErrorNotFound("User not found"));
+        return Err(ApiError::not_found("User not found").into());
     }
 
     Ok(HttpResponse::NoContent().finish())
 }
 
+#[utoipa::path(
+    params(PageQuery),
+    responses((status = 200, body = Paginated<User>))
+)]
 #[get("/list")]
 async fn list_users(
     data: web::Data<AppState>,
-    query: web::Query<ListUsersQuery>,
+    page: web::Query<PageQuery>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let users = data
-        .sql_client
-        .list_users(query.page, query.limit)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error listing users: {}", err);
-            ErrorInternalServerError("Internal server error")
-        })?;
-
-    let total_count = data.sql_client.count_users().await.map_err(|err| {
-        tracing::error!("Error counting users: {}", err);
-        ErrorInternalServerError("Internal server error")
+    let page = page.into_inner();
+    let (users, total_count) = data.sql_client.list_users(page).await.map_err(|err| {
+        tracing::error!("Error listing users: {}", err);
+        ApiError::internal("Internal server error")
     })?;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "users": users,
-        "total": total_count,
-        "page": query.page.unwrap_or(1),
-        "limit": query.limit.unwrap_or(20)
-    })))
+    Ok(HttpResponse::Ok().json(Paginated::new(users, total_count, page)))
 }
 
-#[derive(serde::Deserialize)]
-struct ListUsersQuery {
-    page: Option<i64>,
-    limit: Option<i64>,
-}
-
-#[post("/privy/sign-in")]
+/// Signs in the caller, creating their user on first sign-in, and opens a cookie session letting
+/// later requests authenticate without their token.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Signed in an existing user", body = UserProfile),
+        (status = 201, description = "Created the user on their first sign-in", body = UserProfile),
+        (status = 401, body = ErrorResponse),
+        (status = 403, description = "The request impersonates a user", body = ErrorResponse)
+    ),
+    security(("privy" = []), ("session" = []))
+)]
+#[post("/privy/sign-in", wrap = "Privy")]
 async fn sign_in(
     req: HttpRequest,
+    user: AuthenticatedUser,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let claims = crate::auth::privy::get_privy_claims(&req).ok_or_else(|| {
-        actix_web::error::ErrorUnauthorized("Valid Privy authentication token required")
-    })?;
+    // Sessions opened for an impersonated user would outlive the admin's request
+    refuse_impersonation(&req)?;
 
-    let privy_id = claims.sub;
+    let privy_id = user.privy_id;
 
     let existing_user = data.sql_client.get_user_by_privy_id(privy_id.clone()).await;
 
-    match existing_user {
+    let (mut response, body) = match existing_user {
         Ok(user) => {
-            let existing_author = data.sql_client.get_author(&privy_id).await;
+            let author = data.sql_client.get_author(&privy_id).await.ok();
 
-            let response = serde_json::json!({
-                "user": user,
-                "author": existing_author.ok(),
-            });
-
-            Ok(HttpResponse::Ok().json(response))
+            (HttpResponse::Ok(), UserProfile { user, author })
         }
         Err(sqlx::Error::RowNotFound) => {
             let new_user = NewUser {
@@ -171,18 +246,24 @@ async fn sign_in(
                 .await
                 .map_err(|err| {
                     tracing::error!("Error creating user: {}", err);
-                    ErrorInternalServerError("Failed to create user")
+                    ApiError::internal("Failed to create user")
                 })?;
 
-            let response = serde_json::json!({
-                "user": user,
-            });
-
-            Ok(HttpResponse::Created().json(response))
+            (HttpResponse::Created(), UserProfile { user, author: None })
         }
         Err(err) => {
             tracing::error!("Error checking user existence: {}", err);
-            Err(ErrorInternalServerError("Internal server error"))
+            return Err(ApiError::internal("Internal server error").into());
         }
+    };
+
+    // Clients can keep using their token if no session can be opened
+    match data.sessions.open(&user.claims).await {
+        Ok(token) => {
+            response.cookie(data.sessions.cookie(&token));
+        }
+        Err(err) => tracing::error!("Error opening session of {}: {}", privy_id, err),
     }
+
+    Ok(response.json(body))
 }
@@ -0,0 +1,72 @@
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::{
+        api::tests::{authenticate, create_test_admin, create_test_app, create_test_user},
+        db::sql::{SqlClient, UserOperations},
+    };
+
+    #[sqlx::test]
+    async fn test_create_user_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let privy_id = "privy_test_created_user";
+
+        // Users cannot be created for another Privy id
+        let req = test::TestRequest::post()
+            .uri("/users/create")
+            .set_json(json!({ "privy_id": "privy_test_someone_else" }))
+            .to_request();
+        authenticate(&req, privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(
+            sql_client
+                .get_user("privy_test_someone_else".to_string())
+                .await
+                .is_err()
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/users/create")
+            .set_json(json!({ "privy_id": privy_id }))
+            .to_request();
+        authenticate(&req, privy_id);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["privy_id"], privy_id);
+    }
+
+    #[sqlx::test]
+    async fn test_delete_user_api(pool: PgPool) {
+        let app = test::init_service(create_test_app(pool.clone()).await).await;
+        let sql_client = SqlClient::new(pool).await;
+        let user_privy_id = create_test_user(&sql_client).await;
+        let other_privy_id = create_test_user(&sql_client).await;
+        let admin_privy_id = create_test_admin(&sql_client).await;
+
+        let delete = |privy_id: &str, caller: &str| {
+            let req = test::TestRequest::delete()
+                .uri(&format!("/users/{}", privy_id))
+                .to_request();
+            authenticate(&req, caller);
+            req
+        };
+
+        let resp = test::call_service(&app, delete(&user_privy_id, &other_privy_id)).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(sql_client.get_user(user_privy_id.clone()).await.is_ok());
+
+        // Users may delete themselves, and admins anyone
+        let resp = test::call_service(&app, delete(&user_privy_id, &user_privy_id)).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = test::call_service(&app, delete(&other_privy_id, &admin_privy_id)).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(sql_client.get_user(other_privy_id).await.is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use actix_web::{HttpMessage, HttpRequest};
+
+use crate::{
+    api::error::ApiError,
+    auth::{InternalCaller, get_privy_claims, privy::unauthorized},
+    db::sql::{SqlClient, UserOperations},
+};
+
+/// Scope of the internal tokens allowed to call admin endpoints.
+pub const ADMIN_SCOPE: &str = "admin";
+
+/// Ensures the request comes from an authenticated user flagged as admin, or from an internal
+/// service granted the admin scope.
+pub async fn require_admin(req: &HttpRequest, sql_client: &SqlClient) -> Result<(), ApiError> {
+    if req
+        .extensions()
+        .get::<InternalCaller>()
+        .is_some_and(|caller| caller.has_scope(ADMIN_SCOPE))
+    {
+        return Ok(());
+    }
+
+    let claims = get_privy_claims(req).ok_or_else(unauthorized)?;
+
+    match sql_client.get_user(claims.sub).await {
+        Ok(user) if user.is_admin => Ok(()),
+        Ok(_) | Err(sqlx::Error::RowNotFound) => {
+            Err(ApiError::forbidden("Admin privileges required"))
+        }
+        Err(err) => {
+            tracing::error!("Error checking admin privileges: {}", err);
+            Err(ApiError::internal("Internal server error"))
+        }
+    }
+}
+
+/// Ensures the request comes from the user `privy_id` themselves, or else from an admin as
+/// checked by [require_admin].
+pub async fn require_self_or_admin(
+    req: &HttpRequest,
+    sql_client: &SqlClient,
+    privy_id: &str,
+) -> Result<(), ApiError> {
+    if get_privy_claims(req).is_some_and(|claims| claims.sub == privy_id) {
+        return Ok(());
+    }
+
+    require_admin(req, sql_client).await
+}
@@ -0,0 +1,98 @@
+use actix_web::{HttpMessage, HttpRequest, dev::ServiceRequest, http::header::HeaderName, web};
+
+use crate::{
+    AppState,
+    api::error::ApiError,
+    auth::PrivyClaims,
+    db::sql::{PrivyId, UserOperations},
+};
+
+/// Header naming the Privy id of the user an admin makes a request as.
+pub const IMPERSONATE_HEADER: HeaderName = HeaderName::from_static("x-impersonate-user");
+
+/// Header set on the responses to impersonated requests.
+pub const IMPERSONATED_HEADER: HeaderName = HeaderName::from_static("x-impersonated");
+
+/// Marks a request an admin made as another user, whose claims replaced the admin's. Exposed to
+/// handlers and the audit log through request extensions.
+#[derive(Debug, Clone)]
+pub struct Impersonation {
+    /// Privy id of the admin
+    pub admin: PrivyId,
+}
+
+/// Returns the user `req` asks to be made as, if any.
+pub fn impersonation_target(req: &ServiceRequest) -> Option<PrivyId> {
+    req.headers()
+        .get(IMPERSONATE_HEADER)
+        .and_then(|target| target.to_str().ok())
+        .map(|target| target.trim().to_string())
+        .filter(|target| !target.is_empty())
+}
+
+/// Makes the request of the admin of `claims` as `target`, returning the claims standing in for
+/// the admin's. Fails if the caller is not an admin or `target` does not exist.
+pub async fn impersonate(
+    req: &ServiceRequest,
+    claims: &PrivyClaims,
+    target: PrivyId,
+) -> Result<PrivyClaims, ApiError> {
+    let data = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or_else(|| ApiError::internal("Internal server error"))?;
+
+    let is_admin = match data.sql_client.get_user(claims.sub.clone()).await {
+        Ok(user) => user.is_admin,
+        Err(sqlx::Error::RowNotFound) => false,
+        Err(err) => {
+            tracing::error!("Error checking admin privileges: {}", err);
+            return Err(ApiError::internal("Internal server error"));
+        }
+    };
+    if !is_admin {
+        tracing::warn!(
+            "Non-admin {} attempted to impersonate {}",
+            claims.sub,
+            target
+        );
+        return Err(ApiError::forbidden("Only admins may impersonate users"));
+    }
+
+    match data.sql_client.get_user(target.clone()).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => {
+            return Err(ApiError::not_found("Impersonated user not found"));
+        }
+        Err(err) => {
+            tracing::error!("Error retrieving impersonated user: {}", err);
+            return Err(ApiError::internal("Internal server error"));
+        }
+    }
+
+    tracing::info!(
+        "Admin {} impersonating {} on {} {}",
+        claims.sub,
+        target,
+        req.method(),
+        req.path()
+    );
+    req.extensions_mut().insert(Impersonation {
+        admin: claims.sub.clone(),
+    });
+
+    Ok(PrivyClaims {
+        sub: target,
+        ..claims.clone()
+    })
+}
+
+/// Rejects impersonated requests, for endpoints too sensitive to be called by an admin on behalf
+/// of a user, such as those managing the user's sessions.
+pub fn refuse_impersonation(req: &HttpRequest) -> Result<(), ApiError> {
+    match req.extensions().get::<Impersonation>() {
+        Some(_) => Err(ApiError::forbidden(
+            "This endpoint cannot be called while impersonating a user",
+        )),
+        None => Ok(()),
+    }
+}
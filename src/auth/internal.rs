@@ -0,0 +1,205 @@
+use std::{
+    future::{Ready, ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    Error, HttpMessage, ResponseError,
+    body::EitherBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    web,
+};
+use futures_util::future::LocalBoxFuture;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::{AppState, api::error::ApiError, common::error_reporting};
+
+/// Header carrying the token of internal services, such as the indexer or cron jobs.
+pub const INTERNAL_TOKEN_HEADER: &str = "X-Internal-Token";
+
+/// Token granted to an internal service, of which only the SHA-256 digest is configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternalApiToken {
+    /// Name of the service, for logs
+    pub name: String,
+    pub sha256: [u8; 32],
+    pub scopes: Vec<String>,
+}
+
+impl InternalApiToken {
+    /// Parses `name:sha256:scope,scope` entries separated by semicolons, such as
+    /// `indexer:5e88…42d8:indexer;cleanup:9f86…0f08:admin`.
+    pub fn parse_all(tokens: &str) -> Result<Vec<InternalApiToken>, String> {
+        tokens
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut parts = entry.splitn(3, ':');
+                let (Some(name), Some(digest), Some(scopes)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(format!("'{entry}' is not of the form name:sha256:scopes"));
+                };
+
+                let sha256 = hex::decode(digest)
+                    .ok()
+                    .and_then(|digest| <[u8; 32]>::try_from(digest).ok())
+                    .ok_or_else(|| format!("Digest of '{name}' is not a hex SHA-256 digest"))?;
+
+                Ok(InternalApiToken {
+                    name: name.to_string(),
+                    sha256,
+                    scopes: scopes
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|scope| !scope.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Internal service authenticated by its token, exposed to handlers through request extensions.
+#[derive(Debug, Clone)]
+pub struct InternalCaller {
+    pub name: String,
+    pub scopes: Vec<String>,
+}
+
+impl InternalCaller {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| granted == scope)
+    }
+}
+
+/// Returns the caller owning `token` among `tokens`. Every digest is compared in constant time, so
+/// that timings reveal neither which token matched nor how much of it.
+pub fn authenticate_internal(token: &str, tokens: &[InternalApiToken]) -> Option<InternalCaller> {
+    let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+
+    tokens
+        .iter()
+        .fold(None, |caller, candidate| {
+            let matches: bool = candidate.sha256.ct_eq(&digest).into();
+            caller.or(matches.then_some(candidate))
+        })
+        .map(|token| InternalCaller {
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+        })
+}
+
+/// Middleware restricting routes to internal services whose token grants a scope.
+///
+/// Built with [RequireScope::new], it replaces user authentication. Built with
+/// [RequireScope::or_privy] and wrapped around the [crate::auth::Privy] middleware, requests
+/// without an internal token are left to Privy, so that either kind of caller is accepted.
+pub struct RequireScope {
+    scope: &'static str,
+    or_privy: bool,
+}
+
+impl RequireScope {
+    pub fn new(scope: &'static str) -> Self {
+        RequireScope {
+            scope,
+            or_privy: false,
+        }
+    }
+
+    pub fn or_privy(scope: &'static str) -> Self {
+        RequireScope {
+            scope,
+            or_privy: true,
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireScope
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireScopeMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireScopeMiddleware {
+            service: Rc::new(service),
+            scope: self.scope,
+            or_privy: self.or_privy,
+        }))
+    }
+}
+
+pub struct RequireScopeMiddleware<S> {
+    service: Rc<S>,
+    scope: &'static str,
+    or_privy: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireScopeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let token = req
+            .headers()
+            .get(INTERNAL_TOKEN_HEADER)
+            .map(|token| token.to_str().unwrap_or_default().to_string());
+
+        let Some(token) = token else {
+            if self.or_privy {
+                let fut = self.service.call(req);
+                return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+            }
+
+            let response = ApiError::unauthorized("Internal API token required").error_response();
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        };
+
+        let caller = req
+            .app_data::<web::Data<AppState>>()
+            .and_then(|data| authenticate_internal(&token, &data.internal_tokens));
+
+        let error = match caller {
+            Some(caller) if caller.has_scope(self.scope) => {
+                error_reporting::set_actor(&format!("internal:{}", caller.name));
+                req.extensions_mut().insert(caller);
+                let fut = self.service.call(req);
+                return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+            }
+            Some(caller) => {
+                tracing::warn!(
+                    "Internal caller '{}' lacks the '{}' scope",
+                    caller.name,
+                    self.scope
+                );
+                ApiError::forbidden(format!(
+                    "Internal API token lacks the '{}' scope",
+                    self.scope
+                ))
+            }
+            None => ApiError::unauthorized("Invalid internal API token"),
+        };
+
+        let response = error.error_response();
+        Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) })
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use jsonwebtoken::{DecodingKey, jwk::JwkSet};
+
+use crate::common::zresult::ZResult;
+
+/// Base URL of the endpoints serving the verification keys of Privy apps.
+const PRIVY_JWKS_URL_BASE: &str = "https://auth.privy.io/api/v1/apps";
+
+/// How long fetched keys are used before being fetched again.
+const JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum delay between two fetches triggered by tokens signed with an unknown key, so that
+/// tokens with made-up key ids cannot make us hammer Privy.
+const MISS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+const JWKS_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// No verification key is known for the key id of a token.
+#[derive(Debug, thiserror::Error)]
+#[error("No verification key matches key id {0:?}")]
+pub struct UnknownKeyId(pub Option<String>);
+
+/// Source of the JSON Web Key Set used to verify tokens.
+#[async_trait]
+pub trait JwksFetcher: Send + Sync {
+    async fn fetch(&self) -> ZResult<JwkSet>;
+}
+
+/// [JwksFetcher] downloading the keys of a Privy app from Privy.
+pub struct HttpJwksFetcher {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl HttpJwksFetcher {
+    pub fn new(app_id: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(JWKS_FETCH_TIMEOUT)
+            .build()
+            .expect("Failed to build the JWKS HTTP client");
+
+        HttpJwksFetcher {
+            client,
+            url: format!("{PRIVY_JWKS_URL_BASE}/{app_id}/jwks.json"),
+        }
+    }
+}
+
+#[async_trait]
+impl JwksFetcher for HttpJwksFetcher {
+    async fn fetch(&self) -> ZResult<JwkSet> {
+        Ok(self
+            .client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[derive(Default)]
+struct CachedKeys {
+    keys: HashMap<String, DecodingKey>,
+    /// When keys were last fetched, successfully or not
+    fetched_at: Option<Instant>,
+    /// Whether the last fetch failed
+    unreachable: bool,
+    last_miss_refresh: Option<Instant>,
+}
+
+/// Verification keys fetched through a [JwksFetcher], cached for [JWKS_TTL] and looked up by key
+/// id. A static key, if any, is used instead while the key set cannot be fetched.
+pub struct JwksCache {
+    fetcher: Arc<dyn JwksFetcher>,
+    fallback: Option<DecodingKey>,
+    ttl: Duration,
+    miss_refresh_interval: Duration,
+    cached: Mutex<CachedKeys>,
+}
+
+impl JwksCache {
+    pub fn new(fetcher: Arc<dyn JwksFetcher>, fallback: Option<DecodingKey>) -> Self {
+        JwksCache {
+            fetcher,
+            fallback,
+            ttl: JWKS_TTL,
+            miss_refresh_interval: MISS_REFRESH_INTERVAL,
+            cached: Mutex::new(CachedKeys::default()),
+        }
+    }
+
+    /// Overrides how long keys are cached and how often unknown key ids may trigger a refresh.
+    pub fn with_intervals(mut self, ttl: Duration, miss_refresh_interval: Duration) -> Self {
+        self.ttl = ttl;
+        self.miss_refresh_interval = miss_refresh_interval;
+        self
+    }
+
+    /// Fetches the key set, replacing the cached keys. On failure the previous keys are kept.
+    pub async fn refresh(&self) -> ZResult<()> {
+        let result = self.fetcher.fetch().await;
+
+        let mut cached = self.cached.lock().unwrap();
+        cached.fetched_at = Some(Instant::now());
+        cached.unreachable = result.is_err();
+
+        let jwks = result?;
+        cached.keys = jwks
+            .keys
+            .iter()
+            .filter_map(|jwk| {
+                let kid = jwk.common.key_id.clone()?;
+                DecodingKey::from_jwk(jwk)
+                    .inspect_err(|err| tracing::warn!("Ignoring invalid JWK '{}': {}", kid, err))
+                    .ok()
+                    .map(|key| (kid, key))
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    /// Returns the key verifying tokens signed with `kid`, refreshing the cached keys when they
+    /// are stale or do not know `kid`.
+    pub async fn decoding_key(&self, kid: Option<&str>) -> ZResult<DecodingKey> {
+        let refreshed = self.is_stale();
+        if refreshed {
+            self.refresh_logged().await;
+        }
+
+        if let Some(kid) = kid {
+            if let Some(key) = self.cached_key(kid) {
+                return Ok(key);
+            }
+
+            if !refreshed && self.claim_miss_refresh() {
+                self.refresh_logged().await;
+                if let Some(key) = self.cached_key(kid) {
+                    return Ok(key);
+                }
+            }
+        }
+
+        if self.cached.lock().unwrap().unreachable
+            && let Some(fallback) = &self.fallback
+        {
+            return Ok(fallback.clone());
+        }
+
+        Err(UnknownKeyId(kid.map(str::to_string)).into())
+    }
+
+    async fn refresh_logged(&self) {
+        if let Err(err) = self.refresh().await {
+            tracing::warn!("Failed to fetch verification keys: {}", err);
+        }
+    }
+
+    fn cached_key(&self, kid: &str) -> Option<DecodingKey> {
+        self.cached.lock().unwrap().keys.get(kid).cloned()
+    }
+
+    fn is_stale(&self) -> bool {
+        self.cached
+            .lock()
+            .unwrap()
+            .fetched_at
+            .is_none_or(|fetched_at| fetched_at.elapsed() >= self.ttl)
+    }
+
+    /// Returns whether an unknown key id may trigger a refresh, recording it if so.
+    fn claim_miss_refresh(&self) -> bool {
+        let mut cached = self.cached.lock().unwrap();
+        let allowed = cached
+            .last_miss_refresh
+            .is_none_or(|last| last.elapsed() >= self.miss_refresh_interval);
+        if allowed {
+            cached.last_miss_refresh = Some(Instant::now());
+        }
+        allowed
+    }
+}
@@ -1,4 +1,17 @@
+pub mod admin;
+pub mod impersonation;
+pub mod internal;
+pub mod jwks;
 pub mod privy;
+pub mod revocation;
+pub mod session;
+pub mod user;
 
 // Re-export commonly used items
+pub use admin::{require_admin, require_self_or_admin};
+pub use internal::{InternalCaller, RequireScope};
 pub use privy::{PrivyClaims, get_privy_claims, verify_privy_token, Privy, PrivyMiddleware};
+pub use user::{AuthenticatedUser, MaybeAuthenticated};
+
+#[cfg(test)]
+mod tests;
@@ -1,23 +1,47 @@
-use std::future::{Ready, ready};
+use std::{
+    future::{Ready, ready},
+    rc::Rc,
+    sync::Arc,
+};
 
 use actix_web::{
-    Error, HttpMessage,
+    Error, HttpMessage, ResponseError,
+    body::EitherBody,
     dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::{
+        StatusCode,
+        header::{self, HeaderMap, HeaderValue},
+    },
+    web,
 };
 use futures_util::future::LocalBoxFuture;
 use jsonwebtoken::{Algorithm, DecodingKey, Validation};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
-use crate::CONFIG;
+use crate::{
+    AppState, CONFIG,
+    api::error::ApiError,
+    auth::{
+        InternalCaller,
+        impersonation::{IMPERSONATED_HEADER, impersonate, impersonation_target},
+        jwks::{HttpJwksFetcher, JwksCache},
+        session::session_token,
+    },
+    common::error_reporting,
+};
 
 lazy_static! {
-    static ref VALIDATION: Validation = {
-        let mut validation = Validation::new(Algorithm::ES256);
-        validation.set_audience(&[&CONFIG.privy_app_id]);
-        validation.set_issuer(&["privy.io"]);
-        validation
-    };
+    static ref VALIDATION: Validation =
+        token_validation(&CONFIG.privy_app_id, CONFIG.privy_token_leeway_secs);
+    static ref PRIVY_KEYS: JwksCache = JwksCache::new(
+        Arc::new(HttpJwksFetcher::new(&CONFIG.privy_app_id)),
+        CONFIG.privy_jwt_verification_key.as_ref().and_then(|pem| {
+            DecodingKey::from_ec_pem(pem)
+                .inspect_err(|err| tracing::error!("Invalid PRIVY_JWT_VERIFICATION_KEY: {}", err))
+                .ok()
+        }),
+    );
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -41,79 +65,245 @@ pub fn get_privy_claims(req: &actix_web::HttpRequest) -> Option<PrivyClaims> {
     req.extensions().get::<PrivyClaims>().cloned()
 }
 
+/// Returns the token of a `Authorization: Bearer <token>` header, if any.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+}
+
+/// Error of requests lacking a valid Privy token.
+pub fn unauthorized() -> ApiError {
+    ApiError::unauthorized("Valid Privy authentication token required")
+}
+
+/// Fetches the Privy verification keys, so that the first requests do not wait for them.
+pub async fn load_verification_keys() {
+    match PRIVY_KEYS.refresh().await {
+        Ok(()) => tracing::info!("Loaded Privy verification keys"),
+        Err(err) => tracing::warn!("Failed to fetch Privy verification keys: {}", err),
+    }
+}
+
+/// Reason why a Privy token was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TokenError {
+    #[error("Token expired")]
+    Expired,
+    #[error("Token signature does not match any verification key")]
+    InvalidSignature,
+    #[error("Token was issued for another app")]
+    WrongAudience,
+    #[error("Malformed token: {0}")]
+    Malformed(String),
+}
+
+impl From<jsonwebtoken::errors::Error> for TokenError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+
+        match err.kind() {
+            ErrorKind::ExpiredSignature => TokenError::Expired,
+            ErrorKind::InvalidSignature
+            | ErrorKind::InvalidEcdsaKey
+            | ErrorKind::InvalidAlgorithm => TokenError::InvalidSignature,
+            ErrorKind::InvalidAudience | ErrorKind::InvalidIssuer => TokenError::WrongAudience,
+            _ => TokenError::Malformed(err.to_string()),
+        }
+    }
+}
+
+impl From<TokenError> for ApiError {
+    fn from(err: TokenError) -> Self {
+        match err {
+            TokenError::Expired => ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "TOKEN_EXPIRED",
+                "Privy authentication token expired",
+            ),
+            _ => ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "TOKEN_INVALID",
+                "Invalid Privy authentication token",
+            ),
+        }
+    }
+}
+
+/// Validation of the tokens of the Privy app `app_id`, tolerating clocks off by up to
+/// `leeway_secs`.
+pub fn token_validation(app_id: &str, leeway_secs: u64) -> Validation {
+    let mut validation = Validation::new(Algorithm::ES256);
+    validation.set_audience(&[app_id]);
+    validation.set_issuer(&["privy.io"]);
+    validation.leeway = leeway_secs;
+    validation
+}
+
+/// Checks the signature and claims of `token` against `key`.
+pub fn decode_token(
+    token: &str,
+    key: &DecodingKey,
+    validation: &Validation,
+) -> Result<PrivyClaims, TokenError> {
+    Ok(jsonwebtoken::decode::<PrivyClaims>(token, key, validation)?.claims)
+}
+
+/// Error of requests whose session was logged out or revoked.
+fn session_revoked() -> ApiError {
+    ApiError::new(
+        StatusCode::UNAUTHORIZED,
+        "SESSION_REVOKED",
+        "Session has been revoked",
+    )
+}
+
+/// Returns whether the session of `claims` was revoked. Requests are let through when the
+/// denylist cannot be checked, so that a Redis outage does not lock every user out.
+pub(crate) async fn is_revoked(req: &actix_web::HttpRequest, claims: &PrivyClaims) -> bool {
+    let Some(data) = req.app_data::<web::Data<AppState>>().cloned() else {
+        return false;
+    };
+
+    data.session_revocation
+        .is_revoked(claims)
+        .await
+        .unwrap_or_else(|err| {
+            tracing::error!("Error checking session revocation: {}", err);
+            false
+        })
+}
+
+/// Returns the claims of the cookie session of `session_token`, if it exists.
+async fn session_claims(req: &ServiceRequest, session_token: &str) -> Option<PrivyClaims> {
+    let data = req.app_data::<web::Data<AppState>>().cloned()?;
+    data.sessions.authenticate(session_token).await
+}
+
 // Helper function to verify a Privy token
-pub fn verify_privy_token(token: &str) -> Result<PrivyClaims, jsonwebtoken::errors::Error> {
-    let decoding_key = DecodingKey::from_ec_pem(&CONFIG.privy_jwt_verification_key)?;
+pub async fn verify_privy_token(token: &str) -> Result<PrivyClaims, TokenError> {
+    let header = jsonwebtoken::decode_header(token)?;
+    let decoding_key = PRIVY_KEYS
+        .decoding_key(header.kid.as_deref())
+        .await
+        .map_err(|err| {
+            tracing::debug!("No key to verify Privy token: {}", err);
+            TokenError::InvalidSignature
+        })?;
 
-    jsonwebtoken::decode::<PrivyClaims>(token, &decoding_key, &VALIDATION)
-        .map(|token_data| token_data.claims)
+    decode_token(token, &decoding_key, &VALIDATION)
 }
 
+/// Middleware rejecting requests without a valid Privy token and exposing the claims of valid ones
+/// to handlers. Requests without an `Authorization` header may authenticate with the cookie of a
+/// session opened on sign-in instead, see [crate::auth::session::CookieSessions], and admins may
+/// act as another user, see [crate::auth::impersonation]. Applied to the routes that need an authenticated user, such as every mutation,
+/// while public reads are left open.
 pub struct Privy;
 
 impl<S, B> Transform<S, ServiceRequest> for Privy
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type InitError = ();
     type Transform = PrivyMiddleware<S>;
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(PrivyMiddleware { service }))
+        ready(Ok(PrivyMiddleware {
+            service: Rc::new(service),
+        }))
     }
 }
 
 pub struct PrivyMiddleware<S> {
-    service: S,
+    service: Rc<S>,
 }
 
 impl<S, B> Service<ServiceRequest> for PrivyMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let auth_header = req.headers().get("Authorization");
-
-        if let Some(auth_header) = auth_header {
-            if let Ok(auth_str) = auth_header.to_str() {
-                if auth_str.starts_with("Bearer ") {
-                    let token = &auth_str[7..]; // Remove "Bearer " prefix
-
-                    match verify_privy_token(token) {
-                        Ok(claims) => {
-                            req.extensions_mut().insert(claims);
-                            let fut = self.service.call(req);
-                            return Box::pin(async move {
-                                let res = fut.await?;
-                                Ok(res)
-                            });
-                        }
-                        Err(err) => {
-                            tracing::warn!("Invalid Privy token: {}", err);
+        // Internal services authenticated by an outer [crate::auth::RequireScope] have no user
+        if req.extensions().contains::<InternalCaller>() {
+            let fut = self.service.call(req);
+            return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+        }
+
+        // Requests already authenticated by an outer layer, such as the test harness, skip token
+        // verification
+        let claims = req.extensions().get::<PrivyClaims>().cloned();
+        let token = bearer_token(req.headers()).map(str::to_string);
+        let session_token = session_token(req.request());
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let claims = match (claims, token, session_token) {
+                (Some(claims), _, _) => claims,
+                (None, Some(token), _) => match verify_privy_token(&token).await {
+                    Ok(claims) => claims,
+                    Err(err) => {
+                        tracing::warn!("Invalid Privy token: {}", err);
+                        let response = ApiError::from(err).error_response();
+                        return Ok(req.into_response(response).map_into_right_body());
+                    }
+                },
+                // Without a token, fall back to the session cookie
+                (None, None, Some(session_token)) => {
+                    match session_claims(&req, &session_token).await {
+                        Some(claims) => claims,
+                        None => {
+                            let response = unauthorized().error_response();
+                            return Ok(req.into_response(response).map_into_right_body());
                         }
                     }
                 }
+                (None, None, None) => {
+                    let response = unauthorized().error_response();
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            };
+
+            if is_revoked(req.request(), &claims).await {
+                let response = session_revoked().error_response();
+                return Ok(req.into_response(response).map_into_right_body());
             }
-        }
 
-        let error = actix_web::error::ErrorUnauthorized(serde_json::json!({
-            "error": "Unauthorized",
-            "message": "Valid Privy authentication token required"
-        }));
+            // Admins may make the request as another user
+            let (claims, impersonated) = match impersonation_target(&req) {
+                Some(target) => match impersonate(&req, &claims, target).await {
+                    Ok(claims) => (claims, true),
+                    Err(err) => {
+                        let response = err.error_response();
+                        return Ok(req.into_response(response).map_into_right_body());
+                    }
+                },
+                None => (claims, false),
+            };
 
-        Box::pin(async move { Err(error) })
+            tracing::Span::current().record("privy_id", claims.sub.as_str());
+            error_reporting::set_actor(&claims.sub);
+            req.extensions_mut().insert(claims);
+            let mut res = service.call(req).await?;
+            if impersonated {
+                res.headers_mut()
+                    .insert(IMPERSONATED_HEADER, HeaderValue::from_static("true"));
+            }
+            Ok(res.map_into_left_body())
+        })
     }
 }
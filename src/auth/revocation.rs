@@ -0,0 +1,82 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{auth::PrivyClaims, common::zresult::ZResult, db::kv::KeyValueStore};
+
+/// Prefix of the keys denylisting a session, followed by its id.
+const SESSION_KEY_PREFIX: &str = "auth:revoked:session:";
+
+/// Prefix of the keys denylisting every token of a user issued up to a time, followed by their
+/// Privy id.
+const SUBJECT_KEY_PREFIX: &str = "auth:revoked:subject:";
+
+/// Denylist of revoked Privy sessions, checked by the [crate::auth::Privy] middleware once a
+/// token is verified.
+pub struct SessionRevocation {
+    store: Arc<dyn KeyValueStore>,
+    /// Time past their expiry during which tokens are still accepted, and must stay denylisted
+    leeway: Duration,
+    /// How long revoking every session of a user keeps rejecting their older tokens
+    subject_window: Duration,
+}
+
+impl SessionRevocation {
+    pub fn new(store: Arc<dyn KeyValueStore>, leeway: Duration, subject_window: Duration) -> Self {
+        SessionRevocation {
+            store,
+            leeway,
+            subject_window,
+        }
+    }
+
+    /// Revokes the session of `claims` until its token expires.
+    pub async fn revoke_session(&self, claims: &PrivyClaims) -> ZResult<()> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let remaining = Duration::from_millis((claims.exp as i64 * 1000 - now).max(0) as u64);
+        let ttl = remaining + self.leeway;
+        if ttl.is_zero() {
+            return Ok(());
+        }
+
+        self.store
+            .set(&format!("{SESSION_KEY_PREFIX}{}", claims.sid), "1", ttl)
+            .await
+    }
+
+    /// Revokes every token of `privy_id` issued until now, for the subject revocation window.
+    /// Returns the revocation time, as a Unix timestamp.
+    pub async fn revoke_subject(&self, privy_id: &str) -> ZResult<u64> {
+        let revoked_at = chrono::Utc::now().timestamp() as u64;
+        self.store
+            .set(
+                &format!("{SUBJECT_KEY_PREFIX}{privy_id}"),
+                &revoked_at.to_string(),
+                self.subject_window,
+            )
+            .await?;
+
+        Ok(revoked_at)
+    }
+
+    pub fn subject_window(&self) -> Duration {
+        self.subject_window
+    }
+
+    /// Returns whether the session of `claims`, or every token of its user issued until then,
+    /// was revoked.
+    pub async fn is_revoked(&self, claims: &PrivyClaims) -> ZResult<bool> {
+        let keys = [
+            format!("{SESSION_KEY_PREFIX}{}", claims.sid),
+            format!("{SUBJECT_KEY_PREFIX}{}", claims.sub),
+        ];
+        let values = self.store.get_all(&keys).await?;
+
+        let session_revoked = values.first().is_some_and(Option::is_some);
+        let subject_revoked = values
+            .get(1)
+            .and_then(Option::as_deref)
+            .and_then(|revoked_at| revoked_at.parse::<u64>().ok())
+            .is_some_and(|revoked_at| claims.iat <= revoked_at);
+
+        Ok(session_revoked || subject_revoked)
+    }
+}
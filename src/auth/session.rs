@@ -0,0 +1,182 @@
+use std::{sync::Arc, time::Duration};
+
+use actix_web::{
+    HttpRequest,
+    cookie::{Cookie, SameSite, time},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{auth::PrivyClaims, common::zresult::ZResult, db::kv::KeyValueStore};
+
+/// Name of the cookie carrying the session token.
+pub const SESSION_COOKIE: &str = "publish3_session";
+
+/// Prefix of the keys holding a session, followed by the SHA-256 digest of its token, so that
+/// the store never holds usable tokens.
+const SESSION_KEY_PREFIX: &str = "auth:session:";
+
+/// Prefix of the keys holding when every session of a user was last closed, followed by their
+/// Privy id.
+const CLOSED_KEY_PREFIX: &str = "auth:sessions-closed:";
+
+/// Session as held by the store.
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    #[serde(flatten)]
+    claims: PrivyClaims,
+    /// Unix timestamp in milliseconds, zero for the sessions stored without it
+    #[serde(default)]
+    opened_at: i64,
+}
+
+/// Cookie sessions opened when users sign in with a Privy token, letting later requests
+/// authenticate with the cookie alone. The [crate::auth::Privy] middleware falls back to them
+/// when a request has no `Authorization` header.
+///
+/// A session holds the claims of the token it was opened with, expiring with the session rather
+/// than the token, so that logging out or revoking the user's sessions also rejects it.
+///
+/// Refreshing a session keeps the issue time of the token the first session was opened with, and
+/// no session outlives `max_age` past it, so that a stolen cookie cannot be refreshed forever.
+pub struct CookieSessions {
+    store: Arc<dyn KeyValueStore>,
+    ttl: Duration,
+    /// Longest time a session and its refreshes last after the Privy token they were opened with
+    /// was issued
+    max_age: Duration,
+    /// Whether cookies are only sent over HTTPS
+    secure: bool,
+}
+
+impl CookieSessions {
+    pub fn new(
+        store: Arc<dyn KeyValueStore>,
+        ttl: Duration,
+        max_age: Duration,
+        secure: bool,
+    ) -> Self {
+        CookieSessions {
+            store,
+            ttl,
+            max_age,
+            secure,
+        }
+    }
+
+    /// Opens a session for the user of `claims`, returning its token. Fails when `claims` were
+    /// issued more than `max_age` ago, which sessions authenticating a refresh never were.
+    pub async fn open(&self, claims: &PrivyClaims) -> ZResult<String> {
+        let now = chrono::Utc::now();
+        let expires_at = (now + self.ttl)
+            .timestamp()
+            .min(claims.iat as i64 + self.max_age.as_secs() as i64);
+        let lifetime = expires_at - now.timestamp();
+        if lifetime <= 0 {
+            return Err("Session has reached its maximum age".into());
+        }
+
+        // Two random UUIDs, making up 244 random bits
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let session = StoredSession {
+            claims: PrivyClaims {
+                exp: expires_at as u64,
+                ..claims.clone()
+            },
+            opened_at: now.timestamp_millis(),
+        };
+
+        self.store
+            .set(
+                &session_key(&token),
+                &serde_json::to_string(&session)?,
+                Duration::from_secs(lifetime as u64),
+            )
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Returns the claims of the session of `token`, if it exists, has not expired and was
+    /// opened after the sessions of its user were last closed.
+    pub async fn claims(&self, token: &str) -> ZResult<Option<PrivyClaims>> {
+        let Some(session) = self.store.get(&session_key(token)).await? else {
+            return Ok(None);
+        };
+        let session: StoredSession = serde_json::from_str(&session)?;
+
+        let closed_at = self
+            .store
+            .get(&closed_key(&session.claims.sub))
+            .await?
+            .and_then(|closed_at| closed_at.parse::<i64>().ok());
+        if closed_at.is_some_and(|closed_at| session.opened_at <= closed_at) {
+            return Ok(None);
+        }
+
+        Ok(Some(session.claims))
+    }
+
+    /// Returns the claims of the session of `token`, treating sessions that cannot be read as
+    /// missing, so that a Redis outage only affects cookie authentication.
+    pub async fn authenticate(&self, token: &str) -> Option<PrivyClaims> {
+        self.claims(token)
+            .await
+            .inspect_err(|err| tracing::error!("Error reading session: {}", err))
+            .ok()
+            .flatten()
+    }
+
+    pub async fn close(&self, token: &str) -> ZResult<()> {
+        self.store.delete(&session_key(token)).await
+    }
+
+    /// Closes every session of `privy_id` opened until now. The closing time is kept for
+    /// `max_age`, past which the sessions it closes have all expired.
+    pub async fn close_all(&self, privy_id: &str) -> ZResult<()> {
+        self.store
+            .set(
+                &closed_key(privy_id),
+                &chrono::Utc::now().timestamp_millis().to_string(),
+                self.max_age,
+            )
+            .await
+    }
+
+    /// Returns the cookie handing `token` to the client.
+    pub fn cookie(&self, token: &str) -> Cookie<'static> {
+        Cookie::build(SESSION_COOKIE, token.to_string())
+            .path("/")
+            .http_only(true)
+            .secure(self.secure)
+            .same_site(SameSite::Lax)
+            .max_age(time::Duration::seconds(self.ttl.as_secs() as i64))
+            .finish()
+    }
+
+    /// Returns the cookie removing the session cookie from the client.
+    pub fn removal_cookie(&self) -> Cookie<'static> {
+        let mut cookie = self.cookie("");
+        cookie.make_removal();
+        cookie
+    }
+}
+
+/// Returns the session token of the cookie of `req`, if any.
+pub fn session_token(req: &HttpRequest) -> Option<String> {
+    req.cookie(SESSION_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+fn closed_key(privy_id: &str) -> String {
+    format!("{CLOSED_KEY_PREFIX}{privy_id}")
+}
+
+fn session_key(token: &str) -> String {
+    format!(
+        "{SESSION_KEY_PREFIX}{}",
+        hex::encode(Sha256::digest(token.as_bytes()))
+    )
+}
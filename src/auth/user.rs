@@ -0,0 +1,87 @@
+use std::future::{Ready, ready};
+
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web};
+use futures_util::future::LocalBoxFuture;
+
+use crate::{
+    AppState,
+    auth::{
+        PrivyClaims,
+        privy::{bearer_token, get_privy_claims, is_revoked, unauthorized, verify_privy_token},
+        session::session_token,
+    },
+    db::sql::PrivyId,
+};
+
+/// User authenticated by the [crate::auth::Privy] middleware. Extracting it from a request
+/// without Privy claims fails with a JSON 401.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub privy_id: PrivyId,
+    pub session_id: String,
+    pub claims: PrivyClaims,
+}
+
+impl From<PrivyClaims> for AuthenticatedUser {
+    fn from(claims: PrivyClaims) -> Self {
+        AuthenticatedUser {
+            privy_id: claims.sub.clone(),
+            session_id: claims.sid.clone(),
+            claims,
+        }
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            get_privy_claims(req)
+                .map(AuthenticatedUser::from)
+                .ok_or_else(|| unauthorized().into()),
+        )
+    }
+}
+
+/// User of a public route, which may or may not be authenticated. As public routes are not
+/// wrapped by the [crate::auth::Privy] middleware, the bearer token, or else the session cookie,
+/// is verified here, and requests with neither, an invalid one or a revoked session are treated
+/// as anonymous.
+#[derive(Debug, Clone)]
+pub struct MaybeAuthenticated(pub Option<AuthenticatedUser>);
+
+impl FromRequest for MaybeAuthenticated {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        if let Some(claims) = get_privy_claims(req) {
+            return Box::pin(ready(Ok(MaybeAuthenticated(Some(claims.into())))));
+        }
+
+        let token = bearer_token(req.headers()).map(str::to_string);
+        let session_token = session_token(req);
+        let data = req.app_data::<web::Data<AppState>>().cloned();
+        let req = req.clone();
+        Box::pin(async move {
+            let claims = match (token, session_token, data) {
+                (Some(token), _, _) => verify_privy_token(&token)
+                    .await
+                    .inspect_err(|err| tracing::debug!("Ignoring invalid Privy token: {}", err))
+                    .ok(),
+                (None, Some(session_token), Some(data)) => {
+                    data.sessions.authenticate(&session_token).await
+                }
+                _ => None,
+            };
+            let claims = match claims {
+                Some(claims) if !is_revoked(&req, &claims).await => Some(claims),
+                _ => None,
+            };
+
+            Ok(MaybeAuthenticated(claims.map(AuthenticatedUser::from)))
+        })
+    }
+}
@@ -0,0 +1,243 @@
+//! Command line of the server: `serve`, the default, and one-shot commands for deployments and
+//! cron jobs, which exit with a non-zero code when they fail.
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use sqlx::PgPool;
+
+use crate::{
+    api::admin::cleanup_orphaned_files,
+    common::logging,
+    config::{CommandConfig, DatabasePoolConfig},
+    db::{
+        self,
+        s3::client::S3Client,
+        sql::{PrivyId, SqlClient, UserOperations},
+    },
+    seed,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Publish3 backend")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Serves the API, the default when no command is given
+    Serve,
+    #[command(flatten)]
+    Task(Task),
+}
+
+/// Commands running once against the database, then exiting.
+#[derive(Debug, PartialEq, Eq, Subcommand)]
+pub enum Task {
+    /// Applies the pending database migrations
+    Migrate,
+    /// Grants admin rights to a user, who must have signed in once
+    CreateAdmin { privy_id: PrivyId },
+    /// Deletes the stored files that no publication references anymore
+    StorageCleanup {
+        /// Only report the orphaned files
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Fills the database with a deterministic dataset, with DEV_MODE=enabled
+    Seed {
+        /// Number of publications to seed
+        #[arg(long, default_value_t = seed::DEFAULT_SEED_COUNT)]
+        count: usize,
+    },
+}
+
+/// Runs `task`, reading only the variables it uses, and returns the process's exit code.
+pub async fn run(task: Task) -> ExitCode {
+    let storage = matches!(task, Task::StorageCleanup { .. });
+    let config = match CommandConfig::from_env(storage) {
+        Ok(config) => config,
+        Err(errors) => {
+            eprintln!("{errors}");
+            return ExitCode::FAILURE;
+        }
+    };
+    logging::init(config.log_format);
+
+    let Some(pool) = connect_database(&config.database_url, &config.database_pool).await else {
+        return ExitCode::FAILURE;
+    };
+
+    let result = match task {
+        Task::Migrate => migrate(&pool).await,
+        Task::CreateAdmin { privy_id } => {
+            create_admin(&SqlClient::new(pool).await, &privy_id).await
+        }
+        Task::StorageCleanup { dry_run } => {
+            storage_cleanup(&config, &SqlClient::new(pool).await, dry_run).await
+        }
+        Task::Seed { count } => {
+            if config.dev_mode {
+                seed_database(&SqlClient::new(pool).await, count).await
+            } else {
+                Err("Seeding is only allowed with DEV_MODE=enabled".to_string())
+            }
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            tracing::error!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Connects to the database, logging why it could not.
+pub async fn connect_database(url: &str, config: &DatabasePoolConfig) -> Option<PgPool> {
+    match db::sql::connect(url, config).await {
+        Ok(pool) => {
+            tracing::info!("Connected to the database");
+            Some(pool)
+        }
+        Err(sqlx::Error::PoolTimedOut) => {
+            tracing::error!(
+                "Failed to connect to the database within {}s, check DATABASE_URL and that the \
+                 database accepts connections",
+                config.acquire_timeout_secs
+            );
+            None
+        }
+        Err(err) => {
+            tracing::error!("Failed to connect to the database: {}", err);
+            None
+        }
+    }
+}
+
+async fn migrate(pool: &PgPool) -> Result<(), String> {
+    sqlx::migrate!()
+        .run(pool)
+        .await
+        .map_err(|err| format!("Failed to run the migrations: {err}"))?;
+    tracing::info!("Migrations applied");
+    Ok(())
+}
+
+async fn create_admin(sql_client: &SqlClient, privy_id: &PrivyId) -> Result<(), String> {
+    let result = sql_client
+        .set_user_admin(privy_id, true)
+        .await
+        .map_err(|err| format!("Failed to grant admin rights: {err}"))?;
+    if result.rows_affected() == 0 {
+        return Err(format!(
+            "User {privy_id} not found, they must sign in first"
+        ));
+    }
+    tracing::info!("{} is now an admin", privy_id);
+    Ok(())
+}
+
+async fn storage_cleanup(
+    config: &CommandConfig,
+    sql_client: &SqlClient,
+    dry_run: bool,
+) -> Result<(), String> {
+    let Some(s3) = &config.s3 else {
+        return Err("Storage is disabled, there is nothing to clean up".to_string());
+    };
+    let object_store = S3Client::from_config(s3).await;
+
+    // Failures are logged by the cleanup itself
+    let report = cleanup_orphaned_files(&object_store, sql_client, dry_run)
+        .await
+        .map_err(|err| format!("Storage cleanup failed: {err}"))?;
+    for key in &report.orphans {
+        tracing::info!("Orphaned file: {}", key);
+    }
+    Ok(())
+}
+
+async fn seed_database(sql_client: &SqlClient, count: usize) -> Result<(), String> {
+    let report = seed::seed(sql_client, count)
+        .await
+        .map_err(|err| format!("Failed to seed the database: {err}"))?;
+    tracing::info!(
+        "Seeded {} users, {} authors, {} publications and {} citations",
+        report.users,
+        report.authors,
+        report.publications,
+        report.citations
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sql::models::NewUser;
+
+    fn parse(args: &[&str]) -> Result<Option<Command>, clap::Error> {
+        Cli::try_parse_from(["publish3-backend"].iter().chain(args)).map(|cli| cli.command)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(parse(&["serve"]).unwrap(), Some(Command::Serve));
+        assert_eq!(
+            parse(&["migrate"]).unwrap(),
+            Some(Command::Task(Task::Migrate))
+        );
+        assert_eq!(
+            parse(&["create-admin", "did:privy:alice"]).unwrap(),
+            Some(Command::Task(Task::CreateAdmin {
+                privy_id: "did:privy:alice".to_string()
+            }))
+        );
+        assert_eq!(
+            parse(&["storage-cleanup", "--dry-run"]).unwrap(),
+            Some(Command::Task(Task::StorageCleanup { dry_run: true }))
+        );
+        assert_eq!(
+            parse(&["storage-cleanup"]).unwrap(),
+            Some(Command::Task(Task::StorageCleanup { dry_run: false }))
+        );
+        assert_eq!(
+            parse(&["seed"]).unwrap(),
+            Some(Command::Task(Task::Seed {
+                count: seed::DEFAULT_SEED_COUNT
+            }))
+        );
+        assert_eq!(
+            parse(&["seed", "--count", "7"]).unwrap(),
+            Some(Command::Task(Task::Seed { count: 7 }))
+        );
+
+        assert!(parse(&["create-admin"]).is_err());
+        assert!(parse(&["seed", "--count", "many"]).is_err());
+        assert!(parse(&["reindex"]).is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_create_admin(pool: PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool).await;
+        let privy_id = "did:privy:alice".to_string();
+
+        assert!(create_admin(&sql_client, &privy_id).await.is_err());
+
+        sql_client
+            .create_user(&NewUser {
+                privy_id: privy_id.clone(),
+            })
+            .await?;
+        assert!(!sql_client.get_user(privy_id.clone()).await?.is_admin);
+
+        create_admin(&sql_client, &privy_id).await.unwrap();
+        assert!(sql_client.get_user(privy_id).await?.is_admin);
+        Ok(())
+    }
+}
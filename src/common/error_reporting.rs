@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use sentry::{ClientInitGuard, ClientOptions, Hub, integrations::tracing::EventFilter};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::{Layer, registry::LookupSpan};
+
+/// Starts reporting errors to Sentry if `dsn` is set. Pending reports are sent when the returned
+/// guard is dropped, which must therefore live until the process exits.
+///
+/// Without a DSN, no client is bound and reporting does nothing.
+pub fn init(dsn: Option<&str>) -> Option<ClientInitGuard> {
+    let dsn = dsn?;
+    Some(sentry::init((
+        dsn,
+        ClientOptions {
+            release: sentry::release_name!(),
+            ..Default::default()
+        },
+    )))
+}
+
+/// Returns a layer recording logs as breadcrumbs of the errors later reported. Errors are only
+/// reported explicitly, as for [crate::api::error::ApiError], so that a failure logged then
+/// returned to the client is not reported twice.
+pub fn tracing_layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    sentry::integrations::tracing::layer().event_filter(|metadata| match *metadata.level() {
+        Level::ERROR | Level::WARN | Level::INFO => EventFilter::Breadcrumb,
+        _ => EventFilter::Ignore,
+    })
+}
+
+/// Returns a hub isolating the breadcrumbs and tags of a request, tagging its reports with
+/// `request_id` and the `route` it matched.
+pub fn request_hub(request_id: &str, route: Option<&str>) -> Arc<Hub> {
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    hub.configure_scope(|scope| {
+        scope.set_tag("request_id", request_id);
+        if let Some(route) = route {
+            scope.set_tag("route", route);
+        }
+    });
+    hub
+}
+
+/// Tags the reports of the current request with the caller who made it, once authenticated.
+pub fn set_actor(actor: &str) {
+    sentry::configure_scope(|scope| scope.set_tag("actor", actor));
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App, HttpResponse,
+        http::StatusCode,
+        rt::System,
+        test::{TestRequest, call_service, init_service},
+        web,
+    };
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::{
+        api::{
+            error::ApiError,
+            request_id::{AssignRequestId, REQUEST_ID_HEADER},
+            tests::{TEST_USER_HEADER, TestAuth},
+        },
+        auth::Privy,
+    };
+
+    async fn fail_internally() -> Result<HttpResponse, ApiError> {
+        tracing::error!("Error retrieving item: connection reset");
+        Err(ApiError::internal("Internal server error"))
+    }
+
+    async fn fail_not_found() -> Result<HttpResponse, ApiError> {
+        Err(ApiError::not_found("Item not found"))
+    }
+
+    #[test]
+    fn test_disabled_without_dsn() {
+        assert!(init(None).is_none());
+        assert!(Hub::current().client().is_none());
+    }
+
+    #[test]
+    fn test_server_errors_are_reported() {
+        let _subscriber =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(tracing_layer()));
+
+        let events = sentry::test::with_captured_events(|| {
+            System::new().block_on(async {
+                let app = init_service(
+                    App::new()
+                        .service(
+                            web::resource("/items/{item_id}")
+                                .wrap(Privy)
+                                .route(web::get().to(fail_internally)),
+                        )
+                        .route("/missing/{item_id}", web::get().to(fail_not_found))
+                        .wrap(AssignRequestId)
+                        .wrap(TestAuth),
+                )
+                .await;
+
+                let req = TestRequest::get()
+                    .uri("/items/42")
+                    .insert_header((REQUEST_ID_HEADER, "client-request-45"))
+                    .insert_header((TEST_USER_HEADER, "did:privy:alice"))
+                    .to_request();
+                let resp = call_service(&app, req).await;
+                assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+                // Client errors are not reported
+                let req = TestRequest::get().uri("/missing/42").to_request();
+                let resp = call_service(&app, req).await;
+                assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+            })
+        });
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(
+            event.exception[0].value.as_deref(),
+            Some("Internal server error")
+        );
+        assert_eq!(event.tags["request_id"], "client-request-45");
+        assert_eq!(event.tags["route"], "/items/{item_id}");
+        assert_eq!(event.tags["actor"], "did:privy:alice");
+        assert_eq!(event.tags["code"], "internal_error");
+        assert_eq!(
+            event.breadcrumbs[0].message.as_deref(),
+            Some("Error retrieving item: connection reset")
+        );
+    }
+}
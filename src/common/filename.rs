@@ -0,0 +1,180 @@
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+/// Characters percent-encoded in the `filename*` parameter of a `Content-Disposition` header,
+/// everything but the `attr-char` set of RFC 5987.
+const EXT_VALUE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// Maximum length, in characters, of a sanitized file name.
+const MAX_FILENAME_LENGTH: usize = 128;
+
+/// Longest extension kept when a file name has to be shortened.
+const MAX_EXTENSION_LENGTH: usize = 16;
+
+/// Maximum length, in characters, of a file name kept for display.
+const MAX_DISPLAY_FILENAME_LENGTH: usize = 255;
+
+/// File name used when nothing usable is left of the client-provided one.
+const DEFAULT_FILENAME: &str = "file.pdf";
+
+/// Reduces a client-provided file name to a safe object key segment: directories and control
+/// characters are stripped, whitespace runs become a single `_`, anything outside
+/// `[A-Za-z0-9._-]` is replaced by `_` and the result is capped at [MAX_FILENAME_LENGTH]
+/// characters, keeping the extension.
+pub fn sanitize_filename(file_name: &str) -> String {
+    let base_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let without_control: String = base_name.chars().filter(|c| !c.is_control()).collect();
+    let collapsed = without_control
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_");
+
+    let sanitized: String = collapsed
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_start_matches('.');
+
+    if sanitized.trim_matches(['_', '.']).is_empty() {
+        return DEFAULT_FILENAME.to_string();
+    }
+
+    truncate_filename(sanitized)
+}
+
+/// Reduces a client-provided file name to a name fit for display, keeping non-ASCII characters
+/// that [sanitize_filename] would replace: directories and control characters are stripped and
+/// the result is capped at [MAX_DISPLAY_FILENAME_LENGTH] characters.
+pub fn display_filename(file_name: &str) -> String {
+    let base_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+    base_name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_DISPLAY_FILENAME_LENGTH)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Builds a `Content-Disposition` header value as described in RFC 6266. The `filename`
+/// parameter carries an ASCII-only fallback, while names that do not survive sanitization
+/// unchanged are also given as UTF-8 in `filename*`.
+pub fn content_disposition(disposition: &str, file_name: &str) -> String {
+    let display_name = display_filename(file_name);
+    let fallback = sanitize_filename(&display_name);
+
+    if display_name.is_empty() || display_name == fallback {
+        return format!("{}; filename=\"{}\"", disposition, fallback);
+    }
+
+    format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        disposition,
+        fallback,
+        utf8_percent_encode(&display_name, EXT_VALUE_ENCODE_SET)
+    )
+}
+
+fn truncate_filename(file_name: &str) -> String {
+    if file_name.len() <= MAX_FILENAME_LENGTH {
+        return file_name.to_string();
+    }
+
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && extension.len() < MAX_EXTENSION_LENGTH => {
+            let stem_length = MAX_FILENAME_LENGTH - extension.len() - 1;
+            format!("{}.{}", &stem[..stem_length], extension)
+        }
+        _ => file_name[..MAX_FILENAME_LENGTH].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_filename_keeps_simple_names() {
+        assert_eq!(sanitize_filename("paper.pdf"), "paper.pdf");
+        assert_eq!(sanitize_filename("my-paper_v2.pdf"), "my-paper_v2.pdf");
+    }
+
+    #[test]
+    fn test_sanitize_filename_strips_directories() {
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("..\\..\\boot.ini"), "boot.ini");
+        assert_eq!(sanitize_filename("/"), DEFAULT_FILENAME);
+        assert_eq!(sanitize_filename(".."), DEFAULT_FILENAME);
+        assert_eq!(sanitize_filename(".hidden.pdf"), "hidden.pdf");
+    }
+
+    #[test]
+    fn test_sanitize_filename_cleans_characters() {
+        assert_eq!(sanitize_filename("my \t  paper.pdf"), "my_paper.pdf");
+        assert_eq!(sanitize_filename("pa\u{0}per\n.pdf"), "paper.pdf");
+        assert_eq!(sanitize_filename("résumé.pdf"), "r_sum_.pdf");
+        assert_eq!(sanitize_filename("论文.pdf"), "__.pdf");
+    }
+
+    #[test]
+    fn test_sanitize_filename_defaults_when_empty() {
+        assert_eq!(sanitize_filename(""), DEFAULT_FILENAME);
+        assert_eq!(sanitize_filename("   "), DEFAULT_FILENAME);
+        assert_eq!(sanitize_filename("论文"), DEFAULT_FILENAME);
+    }
+
+    #[test]
+    fn test_display_filename() {
+        assert_eq!(display_filename("论文 final.pdf"), "论文 final.pdf");
+        assert_eq!(display_filename("../data/set\n.csv"), "set.csv");
+        assert_eq!(display_filename("  "), "");
+        assert_eq!(display_filename(&"é".repeat(300)).chars().count(), 255);
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        assert_eq!(
+            content_disposition("attachment", "paper.pdf"),
+            "attachment; filename=\"paper.pdf\""
+        );
+        assert_eq!(
+            content_disposition("inline", "论文 final.pdf"),
+            "inline; filename=\"___final.pdf\"; filename*=UTF-8''%E8%AE%BA%E6%96%87%20final.pdf"
+        );
+        assert_eq!(
+            content_disposition("inline", "dir/\"quoted\";.pdf"),
+            "inline; filename=\"_quoted__.pdf\"; filename*=UTF-8''%22quoted%22%3B.pdf"
+        );
+        assert_eq!(
+            content_disposition("attachment", ""),
+            format!("attachment; filename=\"{}\"", DEFAULT_FILENAME)
+        );
+    }
+
+    #[test]
+    fn test_sanitize_filename_caps_length() {
+        let sanitized = sanitize_filename(&format!("{}.pdf", "a".repeat(300)));
+        assert_eq!(sanitized.len(), MAX_FILENAME_LENGTH);
+        assert!(sanitized.ends_with(".pdf"));
+
+        let sanitized = sanitize_filename(&"b".repeat(300));
+        assert_eq!(sanitized.len(), MAX_FILENAME_LENGTH);
+    }
+}
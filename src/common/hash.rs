@@ -0,0 +1,98 @@
+use aws_sdk_s3::primitives::ByteStream;
+use sha2::Sha256;
+use sha3::{Digest, Sha3_256};
+
+use crate::common::zresult::ZResult;
+
+/// Digests of a stored file, computed together in a single pass over its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHashes {
+    /// Hex SHA3-256 digest, the paper hash anchored on chain
+    pub sha3_256: String,
+    /// Hex SHA-256 digest, the checksum used to verify storage integrity
+    pub sha256: String,
+}
+
+#[derive(Default)]
+struct FileHasher {
+    sha3_256: Sha3_256,
+    sha256: Sha256,
+}
+
+impl FileHasher {
+    fn update(&mut self, data: &[u8]) {
+        self.sha3_256.update(data);
+        self.sha256.update(data);
+    }
+
+    fn finalize(self) -> FileHashes {
+        FileHashes {
+            sha3_256: hex::encode(self.sha3_256.finalize()),
+            sha256: hex::encode(self.sha256.finalize()),
+        }
+    }
+}
+
+impl std::io::Write for FileHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hashes a file on local disk. This blocks, run it off the async executor.
+pub fn hash_local_file(path: &std::path::Path) -> std::io::Result<FileHashes> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = FileHasher::default();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+/// Hashes a byte stream, such as the body of an S3 object, without buffering it in memory.
+pub async fn hash_byte_stream(mut body: ByteStream) -> ZResult<FileHashes> {
+    let mut hasher = FileHasher::default();
+
+    while let Some(chunk) = body.try_next().await? {
+        hasher.update(&chunk);
+    }
+
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const EMPTY_SHA3_256: &str = "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a";
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    const ABC_SHA3_256: &str = "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532";
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_hash_local_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let hashes = hash_local_file(file.path()).unwrap();
+        assert_eq!(hashes.sha3_256, EMPTY_SHA3_256);
+        assert_eq!(hashes.sha256, EMPTY_SHA256);
+
+        file.write_all(b"abc").unwrap();
+        let hashes = hash_local_file(file.path()).unwrap();
+        assert_eq!(hashes.sha3_256, ABC_SHA3_256);
+        assert_eq!(hashes.sha256, ABC_SHA256);
+    }
+
+    #[actix_web::test]
+    async fn test_hash_byte_stream() {
+        let hashes = hash_byte_stream(ByteStream::from_static(b"abc"))
+            .await
+            .unwrap();
+        assert_eq!(hashes.sha3_256, ABC_SHA3_256);
+        assert_eq!(hashes.sha256, ABC_SHA256);
+    }
+}
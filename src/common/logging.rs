@@ -0,0 +1,141 @@
+use std::str::FromStr;
+
+use tracing::{Subscriber, level_filters::LevelFilter};
+use tracing_subscriber::{
+    EnvFilter, Layer, fmt::MakeWriter, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use crate::common::error_reporting;
+
+/// Format of the logs written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines, for local development
+    Pretty,
+    /// One JSON object per line, with the event's fields at the top level, for log aggregation
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Returns a subscriber writing logs in `format` to `writer`, filtered by `RUST_LOG` and at the
+/// `info` level by default, and recording them as breadcrumbs of reported errors.
+///
+/// JSON lines carry the fields of the enclosing spans as well, such as the request id, route and
+/// caller of the `request` span opened by [crate::api::request_id::AssignRequestId].
+pub fn subscriber<W>(format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let fmt = tracing_subscriber::fmt::layer().with_writer(writer);
+    let fmt = match format {
+        LogFormat::Pretty => fmt.boxed(),
+        LogFormat::Json => fmt.json().flatten_event(true).boxed(),
+    };
+
+    Box::new(
+        tracing_subscriber::registry()
+            .with(
+                EnvFilter::builder()
+                    .with_default_directive(LevelFilter::INFO.into())
+                    .from_env_lossy(),
+            )
+            .with(fmt)
+            .with(error_reporting::tracing_layer()),
+    )
+}
+
+/// Sets the global subscriber, writing to stdout in `format`.
+pub fn init(format: LogFormat) {
+    subscriber(format, std::io::stdout).init();
+}
+
+/// Writer keeping logs in memory, for tests asserting what is logged.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl CapturedLogs {
+    /// Returns the JSON lines written so far.
+    pub fn json_lines(&self) -> Vec<serde_json::Value> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl<'writer> MakeWriter<'writer> for CapturedLogs {
+    type Writer = CapturedLogs;
+
+    fn make_writer(&'writer self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_from_str() {
+        assert_eq!("pretty".parse(), Ok(LogFormat::Pretty));
+        assert_eq!("json".parse(), Ok(LogFormat::Json));
+        assert_eq!("JSON".parse::<LogFormat>(), Err(()));
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let logs = CapturedLogs::default();
+        let subscriber = subscriber(LogFormat::Json, logs.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "request",
+                request_id = "request-1",
+                route = "/publications/{publication_id}",
+                privy_id = "did:privy:alice"
+            );
+            let _entered = span.enter();
+            tracing::warn!(status = 503, "Storage is unavailable");
+        });
+
+        let lines = logs.json_lines();
+        assert_eq!(lines.len(), 1);
+
+        let line = &lines[0];
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["message"], "Storage is unavailable");
+        assert_eq!(line["status"], 503);
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["span"]["name"], "request");
+        assert_eq!(line["span"]["request_id"], "request-1");
+        assert_eq!(line["span"]["route"], "/publications/{publication_id}");
+        assert_eq!(line["span"]["privy_id"], "did:privy:alice");
+    }
+}
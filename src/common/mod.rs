@@ -1 +1,9 @@
+pub mod error_reporting;
+pub mod filename;
+pub mod hash;
+pub mod logging;
+pub mod pagination;
+pub mod shutdown;
+pub mod tasks;
+pub mod time;
 pub mod zresult;
@@ -0,0 +1,288 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema, openapi::path::Parameter};
+use uuid::Uuid;
+
+/// Number of items per page when the request does not give one.
+pub const DEFAULT_PAGE_LIMIT: i64 = 20;
+
+/// Most items a page may hold.
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
+/// Page of a listing requested in the query string, rejected on deserialization unless `page` is
+/// at least 1 and `limit` between 1 and [MAX_PAGE_LIMIT].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "PageParams")]
+pub struct PageQuery {
+    page: i64,
+    limit: i64,
+}
+
+impl PageQuery {
+    pub fn new(page: i64, limit: i64) -> Result<Self, String> {
+        if page < 1 {
+            return Err(format!("page must be at least 1, got {page}"));
+        }
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(format!(
+                "limit must be between 1 and {MAX_PAGE_LIMIT}, got {limit}"
+            ));
+        }
+        Ok(PageQuery { page, limit })
+    }
+
+    /// Number of the page, starting at 1.
+    pub fn page(&self) -> i64 {
+        self.page
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit
+    }
+
+    /// Number of items before the page.
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.limit
+    }
+}
+
+impl Default for PageQuery {
+    fn default() -> Self {
+        PageQuery {
+            page: 1,
+            limit: DEFAULT_PAGE_LIMIT,
+        }
+    }
+}
+
+/// Query string parameters of a [PageQuery], before validation.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PageParams {
+    /// Page to return, starting at 1
+    page: Option<i64>,
+    /// Number of items per page, 20 by default and at most 100
+    limit: Option<i64>,
+}
+
+impl TryFrom<PageParams> for PageQuery {
+    type Error = String;
+
+    fn try_from(params: PageParams) -> Result<Self, Self::Error> {
+        PageQuery::new(
+            params.page.unwrap_or(1),
+            params.limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+        )
+    }
+}
+
+impl IntoParams for PageQuery {
+    fn into_params(
+        parameter_in_provider: impl Fn() -> Option<utoipa::openapi::path::ParameterIn>,
+    ) -> Vec<Parameter> {
+        PageParams::into_params(parameter_in_provider)
+    }
+}
+
+/// Page of a listing, as returned by the list endpoints.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Number of items across all pages
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+    pub total_pages: i64,
+    /// Cursor of the last item, to fetch the following ones by keyset on the listings supporting
+    /// it, unless this is the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, total: i64, page: PageQuery) -> Self {
+        Paginated {
+            items,
+            total,
+            page: page.page,
+            limit: page.limit,
+            total_pages: (total + page.limit - 1) / page.limit,
+            next_cursor: None,
+        }
+    }
+
+    /// Sets the cursor of the last item, as returned by `cursor_of`, when pages follow this one.
+    pub fn with_next_cursor(mut self, cursor_of: impl Fn(&T) -> Cursor) -> Self {
+        if self.page < self.total_pages {
+            self.next_cursor = self.items.last().map(|item| cursor_of(item).encode());
+        }
+        self
+    }
+}
+
+/// Position in a listing ordered by creation time, most recent first, given to clients as an
+/// opaque string to fetch the items after it. Unlike pages, cursors do not shift when items are
+/// added while a client walks through the listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    /// Encodes the cursor as URL-safe base64 of its timestamp, in microseconds as stored by
+    /// Postgres, and id.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}",
+            self.created_at.timestamp_micros(),
+            self.id
+        ))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid cursor: {cursor}");
+        let decoded = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+        let (micros, id) = decoded.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Cursor {
+            created_at: micros
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_micros)
+                .ok_or_else(invalid)?,
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl TryFrom<String> for Cursor {
+    type Error = String;
+
+    fn try_from(cursor: String) -> Result<Self, Self::Error> {
+        Cursor::decode(&cursor)
+    }
+}
+
+/// Cursor requested in the query string of the listings supporting keyset pagination.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CursorQuery {
+    /// `next_cursor` of the previous page, to return the items after it instead of `page`
+    #[param(value_type = Option<String>)]
+    pub cursor: Option<Cursor>,
+}
+
+/// Page of a listing requested by [Cursor].
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub limit: i64,
+    /// Cursor to fetch the following items with, unless this is the last page
+    pub next_cursor: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    /// Builds the page from up to `limit + 1` items, the extra one only telling that more follow.
+    pub fn new(mut items: Vec<T>, limit: i64, cursor_of: impl Fn(&T) -> Cursor) -> Self {
+        let next_cursor = if items.len() as i64 > limit {
+            items.truncate(limit as usize);
+            items.last().map(|item| cursor_of(item).encode())
+        } else {
+            None
+        };
+
+        CursorPage {
+            items,
+            limit,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{error::QueryPayloadError, web::Query};
+
+    use super::*;
+
+    fn parse(query: &str) -> Result<PageQuery, QueryPayloadError> {
+        Query::from_query(query).map(Query::into_inner)
+    }
+
+    #[test]
+    fn test_page_query() {
+        assert_eq!(parse("").unwrap(), PageQuery::default());
+        let page = parse("page=3&limit=10").unwrap();
+        assert_eq!((page.page(), page.limit(), page.offset()), (3, 10, 20));
+        assert_eq!(parse("limit=100").unwrap().limit(), MAX_PAGE_LIMIT);
+        // Other parameters are left to the handlers' own queries
+        assert_eq!(parse("query=rust&page=2").unwrap().page(), 2);
+
+        for invalid in ["page=0", "page=-1", "limit=0", "limit=101", "page=first"] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_paginated() {
+        let page = PageQuery::new(2, 10).unwrap();
+        let paginated = Paginated::new(vec!["a"; 10], 25, page);
+        assert_eq!(
+            (paginated.page, paginated.limit, paginated.total_pages),
+            (2, 10, 3)
+        );
+        assert_eq!(Paginated::<()>::new(vec![], 0, page).total_pages, 0);
+        assert_eq!(Paginated::new(vec![()], 20, page).total_pages, 2);
+    }
+
+    #[test]
+    fn test_cursor() {
+        let cursor = Cursor {
+            created_at: DateTime::from_timestamp_micros(1_735_787_045_678_901).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()), Ok(cursor));
+
+        let query = Query::<CursorQuery>::from_query(&format!("cursor={}", cursor.encode()))
+            .unwrap()
+            .into_inner();
+        assert_eq!(query.cursor, Some(cursor));
+        assert!(
+            Query::<CursorQuery>::from_query("")
+                .unwrap()
+                .cursor
+                .is_none()
+        );
+
+        let no_id = URL_SAFE_NO_PAD.encode("1735787045678901");
+        for invalid in ["", "not base64!", &no_id, &URL_SAFE_NO_PAD.encode("now:id")] {
+            assert!(Cursor::decode(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_next_cursor() {
+        let cursor_of = |id: &u128| Cursor {
+            created_at: DateTime::UNIX_EPOCH,
+            id: Uuid::from_u128(*id),
+        };
+
+        let page = CursorPage::new(vec![1, 2, 3], 2, cursor_of);
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next_cursor, Some(cursor_of(&2).encode()));
+        assert_eq!(CursorPage::new(vec![1, 2], 2, cursor_of).next_cursor, None);
+
+        let page = PageQuery::new(1, 2).unwrap();
+        let paginated = Paginated::new(vec![1, 2], 3, page).with_next_cursor(cursor_of);
+        assert_eq!(paginated.next_cursor, Some(cursor_of(&2).encode()));
+        let last = Paginated::new(vec![3], 3, PageQuery::new(2, 2).unwrap());
+        assert_eq!(last.with_next_cursor(cursor_of).next_cursor, None);
+    }
+}
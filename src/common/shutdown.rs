@@ -0,0 +1,241 @@
+use std::{collections::HashMap, future::Future, sync::Mutex, time::Duration};
+
+use tokio::{
+    runtime::Handle,
+    task::{Id, JoinError, JoinSet},
+    time::{Instant, timeout_at},
+};
+use tokio_util::sync::CancellationToken;
+
+/// Time cancelled tasks are given to return, for instance after recording what they could not
+/// finish, before they are aborted.
+const CANCELLATION_GRACE: Duration = Duration::from_secs(1);
+
+/// Waits until the process is asked to stop, by SIGTERM or SIGINT, returning the signal's name.
+pub async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+        tokio::select! {
+            _ = terminate.recv() => "SIGTERM",
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
+
+/// Background tasks that outlive the request spawning them, such as audit log writes, but must
+/// not be lost when the server stops.
+///
+/// Tasks are spawned on the runtime given to [TaskRegistry::new], so that they keep running once
+/// the HTTP workers have stopped, or on the caller's runtime for a [TaskRegistry::default]. On [TaskRegistry::shutdown], the registry stops accepting tasks
+/// and waits for the running ones; those still running when the grace period ends are cancelled
+/// through their token, then aborted if they do not return.
+#[derive(Default)]
+pub struct TaskRegistry {
+    runtime: Option<Handle>,
+    cancellation: CancellationToken,
+    tasks: Mutex<Tasks>,
+}
+
+#[derive(Default)]
+struct Tasks {
+    /// Set once shutdown has started, after which no task is spawned
+    closed: bool,
+    running: JoinSet<()>,
+    names: HashMap<Id, &'static str>,
+}
+
+impl Tasks {
+    /// Forgets a task that returned, logging it if it panicked.
+    fn remove(&mut self, result: Result<(Id, ()), JoinError>) -> Option<&'static str> {
+        let (id, panic) = match result {
+            Ok((id, ())) => (id, None),
+            Err(err) => (err.id(), Some(err)),
+        };
+        let name = self.names.remove(&id);
+        if let Some(err) = panic {
+            tracing::error!("Background task {} failed: {}", name.unwrap_or("?"), err);
+        }
+        name
+    }
+}
+
+/// What became of the tasks running when [TaskRegistry::shutdown] was called.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Number of tasks that returned within the grace period
+    pub finished: usize,
+    /// Tasks that returned once cancelled
+    pub cancelled: Vec<&'static str>,
+    /// Tasks that ignored their cancellation and were aborted
+    pub aborted: Vec<&'static str>,
+}
+
+impl TaskRegistry {
+    pub fn new(runtime: Handle) -> Self {
+        TaskRegistry {
+            runtime: Some(runtime),
+            ..Default::default()
+        }
+    }
+
+    /// Spawns the future returned by `task`, which is given a token cancelled when shutdown can
+    /// no longer wait for it. Returns `false`, without spawning it, once shutdown has started or
+    /// if there is no runtime to spawn it on.
+    pub fn spawn<F, Fut>(&self, name: &'static str, task: F) -> bool
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.closed {
+            tracing::warn!("Not starting {} as the server is shutting down", name);
+            return false;
+        }
+
+        while let Some(result) = tasks.running.try_join_next_with_id() {
+            tasks.remove(result);
+        }
+
+        let Some(runtime) = self.runtime.clone().or_else(|| Handle::try_current().ok()) else {
+            tracing::warn!("No runtime to start {} on", name);
+            return false;
+        };
+
+        let future = task(self.cancellation.child_token());
+        let handle = tasks.running.spawn_on(future, &runtime);
+        tasks.names.insert(handle.id(), name);
+        true
+    }
+
+    /// Stops accepting tasks and waits up to `grace` for the running ones to return, before
+    /// cancelling the remaining ones.
+    pub async fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        let mut tasks = {
+            let mut tasks = self.tasks.lock().unwrap();
+            tasks.closed = true;
+            Tasks {
+                closed: true,
+                running: std::mem::take(&mut tasks.running),
+                names: std::mem::take(&mut tasks.names),
+            }
+        };
+        let mut report = ShutdownReport::default();
+
+        let deadline = Instant::now() + grace;
+        while let Ok(Some(result)) = timeout_at(deadline, tasks.running.join_next_with_id()).await {
+            tasks.remove(result);
+            report.finished += 1;
+        }
+        if tasks.running.is_empty() {
+            return report;
+        }
+
+        self.cancellation.cancel();
+        let deadline = Instant::now() + CANCELLATION_GRACE;
+        while let Ok(Some(result)) = timeout_at(deadline, tasks.running.join_next_with_id()).await {
+            report.cancelled.extend(tasks.remove(result));
+        }
+
+        tasks.running.shutdown().await;
+        report.aborted = tasks.names.into_values().collect();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use super::*;
+
+    fn registry() -> TaskRegistry {
+        TaskRegistry::new(Handle::current())
+    }
+
+    #[actix_web::test]
+    async fn test_shutdown_waits_for_running_tasks() {
+        let registry = registry();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let task_done = done.clone();
+        assert!(registry.spawn("write", |_| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            task_done.store(true, Ordering::SeqCst);
+        }));
+        assert!(registry.spawn("noop", |_| async {}));
+
+        let report = registry.shutdown(Duration::from_secs(5)).await;
+        assert!(done.load(Ordering::SeqCst));
+        assert_eq!(
+            report,
+            ShutdownReport {
+                finished: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_spawn_refused_once_shutting_down() {
+        let registry = registry();
+        registry.shutdown(Duration::ZERO).await;
+
+        let started = Arc::new(AtomicBool::new(false));
+        let task_started = started.clone();
+        assert!(!registry.spawn("late", |_| async move {
+            task_started.store(true, Ordering::SeqCst);
+        }));
+
+        tokio::task::yield_now().await;
+        assert!(!started.load(Ordering::SeqCst));
+    }
+
+    #[actix_web::test]
+    async fn test_overdue_tasks_are_cancelled_then_aborted() {
+        let registry = registry();
+        let recorded = Arc::new(AtomicBool::new(false));
+
+        let task_recorded = recorded.clone();
+        registry.spawn("cooperative", |cancellation| async move {
+            cancellation.cancelled().await;
+            task_recorded.store(true, Ordering::SeqCst);
+        });
+        registry.spawn("stuck", |_| std::future::pending());
+
+        let report = registry.shutdown(Duration::from_millis(20)).await;
+        assert!(recorded.load(Ordering::SeqCst));
+        assert_eq!(
+            report,
+            ShutdownReport {
+                finished: 0,
+                cancelled: vec!["cooperative"],
+                aborted: vec!["stuck"],
+            }
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_finished_tasks_are_forgotten() {
+        let registry = registry();
+        for _ in 0..10 {
+            registry.spawn("noop", |_| async {});
+        }
+        tokio::task::yield_now().await;
+
+        registry.spawn("noop", |_| async {});
+        let tasks = registry.tasks.lock().unwrap();
+        assert_eq!(tasks.running.len(), 1);
+        assert_eq!(tasks.names.len(), 1);
+    }
+}
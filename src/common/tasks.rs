@@ -0,0 +1,227 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::common::{shutdown::TaskRegistry, time::rfc3339_millis};
+
+/// Delay before restarting a task after its first consecutive failure, doubled after each further
+/// one.
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay between restarts of a failing task.
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// What is known of a supervised task, as reported by `GET /admin/tasks`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TaskStatus {
+    pub name: &'static str,
+    /// Number of completed runs, failed or not
+    pub runs: u64,
+    pub failures: u64,
+    /// Failures since the last successful run, which the restart delay grows with
+    pub consecutive_failures: u32,
+    #[serde(with = "rfc3339_millis::option")]
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Error or panic of the last failed run
+    pub last_error: Option<String>,
+}
+
+/// Runs long-lived background tasks, such as periodic jobs, restarting them when they fail.
+///
+/// A task is run every `interval`. When it returns an error or panics, the failure is logged and
+/// recorded, and it is run again after a backoff doubling with each consecutive failure, up to
+/// [MAX_RESTART_BACKOFF]. Tasks are spawned on a [TaskRegistry], so that they stop with the
+/// server.
+pub struct TaskSupervisor {
+    registry: Arc<TaskRegistry>,
+    backoff: Duration,
+    max_backoff: Duration,
+    statuses: Arc<Mutex<Vec<TaskStatus>>>,
+}
+
+impl TaskSupervisor {
+    pub fn new(registry: Arc<TaskRegistry>) -> Self {
+        TaskSupervisor {
+            registry,
+            backoff: RESTART_BACKOFF,
+            max_backoff: MAX_RESTART_BACKOFF,
+            statuses: Arc::default(),
+        }
+    }
+
+    /// Overrides the delay before the first restart of a failing task and the longest delay.
+    pub fn with_backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Runs the future returned by `task` now, then every `interval` after it succeeds. Returns
+    /// `false`, as [TaskRegistry::spawn] does, if the task could not be started.
+    pub fn spawn<F, Fut>(&self, name: &'static str, interval: Duration, task: F) -> bool
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send,
+    {
+        let index = {
+            let mut statuses = self.statuses.lock().unwrap();
+            statuses.push(TaskStatus {
+                name,
+                runs: 0,
+                failures: 0,
+                consecutive_failures: 0,
+                last_run_at: None,
+                last_error: None,
+            });
+            statuses.len() - 1
+        };
+
+        let statuses = self.statuses.clone();
+        let (backoff, max_backoff) = (self.backoff, self.max_backoff);
+        self.registry.spawn(name, move |cancellation| async move {
+            loop {
+                let result = tokio::select! {
+                    _ = cancellation.cancelled() => return,
+                    result = AssertUnwindSafe(task()).catch_unwind() => result,
+                };
+                let error = match result {
+                    Ok(Ok(())) => None,
+                    Ok(Err(err)) => Some(err),
+                    Err(panic) => Some(format!("Panicked: {}", panic_message(&*panic))),
+                };
+
+                let consecutive_failures = {
+                    let mut statuses = statuses.lock().unwrap();
+                    let status = &mut statuses[index];
+                    status.runs += 1;
+                    status.last_run_at = Some(Utc::now());
+                    match &error {
+                        Some(error) => {
+                            status.failures += 1;
+                            status.consecutive_failures += 1;
+                            status.last_error = Some(error.clone());
+                        }
+                        None => status.consecutive_failures = 0,
+                    }
+                    status.consecutive_failures
+                };
+
+                let delay = match error {
+                    None => interval,
+                    Some(error) => {
+                        let delay = restart_delay(backoff, max_backoff, consecutive_failures);
+                        tracing::error!(
+                            "Task {} failed ({} in a row), restarting in {}s: {}",
+                            name,
+                            consecutive_failures,
+                            delay.as_secs_f64(),
+                            error
+                        );
+                        delay
+                    }
+                };
+
+                tokio::select! {
+                    _ = cancellation.cancelled() => return,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+        })
+    }
+
+    /// Returns the status of every supervised task, in the order they were spawned.
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.statuses.lock().unwrap().clone()
+    }
+}
+
+/// Returns the delay before restarting a task after `consecutive_failures`, at least one.
+fn restart_delay(backoff: Duration, max_backoff: Duration, consecutive_failures: u32) -> Duration {
+    backoff
+        .saturating_mul(2u32.saturating_pow(consecutive_failures - 1))
+        .min(max_backoff)
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause")
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{runtime::Handle, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_restart_delay() {
+        let delay = |failures| {
+            restart_delay(Duration::from_secs(1), Duration::from_secs(60), failures).as_secs()
+        };
+        assert_eq!(delay(1), 1);
+        assert_eq!(delay(2), 2);
+        assert_eq!(delay(3), 4);
+        assert_eq!(delay(7), 60);
+        assert_eq!(delay(40), 60);
+    }
+
+    #[actix_web::test]
+    async fn test_failing_task_is_restarted_with_backoff() {
+        tokio::time::pause();
+        let registry = Arc::new(TaskRegistry::new(Handle::current()));
+        let supervisor = TaskSupervisor::new(registry.clone())
+            .with_backoff(Duration::from_secs(1), Duration::from_secs(60));
+
+        let started = Instant::now();
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let task_runs = runs.clone();
+        assert!(supervisor.spawn("flaky", Duration::from_secs(30), move || {
+            let runs = task_runs.clone();
+            async move {
+                let run = {
+                    let mut runs = runs.lock().unwrap();
+                    runs.push(started.elapsed().as_secs());
+                    runs.len()
+                };
+                match run {
+                    1 => Err("Connection refused".to_string()),
+                    2 => panic!("Unexpected response"),
+                    _ => Ok(()),
+                }
+            }
+        }));
+
+        // Runs at once, then 1s after the first failure, 2s after the second, then every 30s
+        tokio::time::sleep(Duration::from_secs(34)).await;
+        assert_eq!(*runs.lock().unwrap(), vec![0, 1, 3, 33]);
+
+        let statuses = supervisor.statuses();
+        assert_eq!(statuses.len(), 1);
+        let status = &statuses[0];
+        assert_eq!(status.name, "flaky");
+        assert_eq!(status.runs, 4);
+        assert_eq!(status.failures, 2);
+        assert_eq!(status.consecutive_failures, 0);
+        assert!(status.last_run_at.is_some());
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("Panicked: Unexpected response")
+        );
+
+        // Shutdown stops the task between runs
+        let report = registry.shutdown(Duration::from_secs(1)).await;
+        assert_eq!(report.cancelled, vec!["flaky"]);
+    }
+}
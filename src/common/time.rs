@@ -0,0 +1,97 @@
+//! Serialization of the timestamps returned by the API, all formatted as RFC 3339 in UTC with
+//! milliseconds, such as `2025-01-02T03:04:05.678Z`.
+//!
+//! Apply it with `#[serde(with = "rfc3339_millis")]`, or `rfc3339_millis::option` for optional
+//! timestamps. Any RFC 3339 timestamp is accepted when deserializing.
+
+pub mod rfc3339_millis {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn format(timestamp: &DateTime<Utc>) -> String {
+        timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
+    pub fn serialize<S: Serializer>(
+        timestamp: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(timestamp))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        DateTime::<Utc>::deserialize(deserializer)
+    }
+
+    pub mod option {
+        use chrono::{DateTime, Utc};
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            timestamp: &Option<DateTime<Utc>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match timestamp {
+                Some(timestamp) => serializer.serialize_str(&super::format(timestamp)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<DateTime<Utc>>, D::Error> {
+            Option::<DateTime<Utc>>::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+
+    use super::rfc3339_millis;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Timestamps {
+        #[serde(with = "rfc3339_millis")]
+        at: DateTime<Utc>,
+        #[serde(with = "rfc3339_millis::option")]
+        until: Option<DateTime<Utc>>,
+    }
+
+    #[test]
+    fn test_rfc3339_millis() {
+        let at = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+        let timestamps = Timestamps {
+            // Sub-millisecond digits are truncated
+            at: at + chrono::Duration::microseconds(678_901),
+            until: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&timestamps).unwrap(),
+            r#"{"at":"2025-01-02T03:04:05.678Z","until":null}"#
+        );
+
+        let timestamps = Timestamps {
+            at,
+            until: Some(at),
+        };
+        let json = serde_json::to_string(&timestamps).unwrap();
+        assert_eq!(
+            json,
+            r#"{"at":"2025-01-02T03:04:05.000Z","until":"2025-01-02T03:04:05.000Z"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Timestamps>(&json).unwrap(),
+            timestamps
+        );
+
+        // Other offsets and precisions are accepted
+        let parsed: Timestamps =
+            serde_json::from_str(r#"{"at":"2025-01-02T05:04:05.5+02:00","until":null}"#).unwrap();
+        assert_eq!(parsed.at, at + chrono::Duration::milliseconds(500));
+    }
+}
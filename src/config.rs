@@ -1,61 +1,781 @@
-fn get_env_var(var_name: &str) -> String {
-    std::env::var(var_name).unwrap_or_else(|_| panic!("{} must be set", var_name))
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use base64::{Engine, engine::general_purpose};
+
+use crate::{
+    api::rate_limit::{DEFAULT_RATE_LIMITS, RateLimitRule},
+    auth::internal::InternalApiToken,
+    common::logging::LogFormat,
+};
+
+/// Size of the database pool when `DB_MAX_CONNECTIONS` is not set.
+const DEFAULT_DB_MAX_CONNECTIONS: u32 = 10;
+
+/// Time a query waits for a pooled connection, and startup for the first one, when
+/// `DB_ACQUIRE_TIMEOUT_SECS` is not set.
+const DEFAULT_DB_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+/// Duration above which statements are logged as slow when `DB_SLOW_QUERY_MS` is not set.
+const DEFAULT_DB_SLOW_QUERY_MS: u64 = 1000;
+
+/// Address the server listens on when `SERVER_ADDRESS` is not set.
+const DEFAULT_SERVER_ADDRESS: &str = "0.0.0.0";
+
+/// Port the server listens on when `SERVER_PORT` is not set.
+const DEFAULT_SERVER_PORT: u16 = 8080;
+
+/// Largest JSON body accepted when `MAX_JSON_PAYLOAD_BYTES` is not set.
+const DEFAULT_MAX_JSON_PAYLOAD_BYTES: usize = 4 * 1024 * 1024;
+
+/// Largest multipart body accepted when `MAX_MULTIPART_TOTAL_BYTES` is not set. Leaves room for
+/// the form fields next to a publication file of the largest accepted size.
+const DEFAULT_MAX_MULTIPART_TOTAL_BYTES: usize = 101 * 1024 * 1024;
+
+/// Lifetime of presigned download URLs when `S3_PRESIGN_EXPIRY_SECS` is not set.
+const DEFAULT_S3_PRESIGN_EXPIRY_SECS: u64 = 5 * 60;
+/// Longest lifetime S3 accepts for a presigned URL, a week.
+const MAX_S3_PRESIGN_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Duration above which storage operations are logged as slow when `S3_SLOW_OPERATION_MS` is not
+/// set.
+const DEFAULT_S3_SLOW_OPERATION_MS: u64 = 1000;
+
+/// Largest publication file accepted when `MAX_PUBLICATION_FILE_BYTES` is not set.
+const DEFAULT_MAX_PUBLICATION_FILE_BYTES: i64 = 100 * 1024 * 1024;
+
+/// Tolerated clock skew when checking the expiry of Privy tokens when `PRIVY_TOKEN_LEEWAY_SECS`
+/// is not set.
+const DEFAULT_PRIVY_TOKEN_LEEWAY_SECS: u64 = 30;
+
+/// How long revoking every session of a user keeps rejecting their older tokens when
+/// `PRIVY_SUBJECT_REVOCATION_SECS` is not set. Covers the lifetime of Privy access tokens.
+const DEFAULT_PRIVY_SUBJECT_REVOCATION_SECS: u64 = 24 * 60 * 60;
+
+/// Time given to in-flight requests, then to background tasks, to finish when the server stops
+/// and `SHUTDOWN_GRACE_SECS` is not set.
+const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 30;
+
+/// Lifetime of the cookie sessions opened on sign-in when `SESSION_TTL_SECS` is not set.
+const DEFAULT_SESSION_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Longest time refreshed cookie sessions last after signing in when `SESSION_MAX_AGE_SECS` is
+/// not set.
+const DEFAULT_SESSION_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Whether a dependency, such as Redis or S3, or an optional feature is used by a deployment.
+/// Disabling a dependency skips its variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Enabled,
+    Disabled,
+}
+
+impl FromStr for Mode {
+    type Err = ();
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "enabled" => Ok(Mode::Enabled),
+            "disabled" => Ok(Mode::Disabled),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabasePoolConfig {
+    pub max_connections: u32,
+    /// Connections kept open even when idle
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// Time after which Postgres cancels a statement, unlimited if not set
+    pub statement_timeout_ms: Option<u64>,
+    pub slow_query_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RedisConfig {
+    pub url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub access_key: String,
+    pub secret_key: String,
+    pub endpoint: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BodyLimitsConfig {
+    pub max_json_payload_bytes: usize,
+    /// Largest multipart body, files included
+    pub max_multipart_total_bytes: usize,
+    /// Directory uploaded files are written to while handled, the system's if not set
+    pub multipart_temp_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
-    pub redis_url: String,
+    pub database_pool: DatabasePoolConfig,
+    /// Redis connection, unless `REDIS_MODE=disabled`
+    pub redis: Option<RedisConfig>,
     pub client_origin: String,
 
-    pub server_address: String,
-    pub server_port: String,
+    /// Addresses the server listens on, each on `server_port`
+    pub server_addresses: Vec<String>,
+    pub server_port: u16,
+    /// Number of worker threads, one per CPU core if not set
+    pub server_workers: Option<usize>,
+    /// Seconds given to in-flight requests, then to background tasks, to finish on shutdown
+    pub shutdown_grace_secs: u64,
     pub server_base_url: String,
+    pub body_limits: BodyLimitsConfig,
 
-    pub s3_access_key: String,
-    pub s3_secret_key: String,
-    pub s3_endpoint: String,
+    /// Object storage, unless `STORAGE_MODE=disabled`
+    pub s3: Option<S3Config>,
+    pub s3_presign_expiry_secs: u64,
+    pub s3_slow_operation_ms: u64,
+    pub user_storage_quota_bytes: Option<i64>,
+    /// Largest publication file accepted, uploaded directly or through an upload intent
+    pub max_publication_file_bytes: i64,
 
     // Privy authentication
     pub privy_app_id: String,
     pub privy_app_secret: String,
-    pub privy_jwt_verification_key: Vec<u8>,
+    /// Key verifying Privy tokens while Privy's key set cannot be fetched
+    pub privy_jwt_verification_key: Option<Vec<u8>>,
+    pub privy_token_leeway_secs: u64,
+    pub privy_subject_revocation_secs: u64,
+
+    // Cookie sessions
+    pub session_ttl_secs: u64,
+    /// Longest time a session and its refreshes last after signing in
+    pub session_max_age_secs: u64,
+    /// Whether session cookies are only sent over HTTPS, which only local development disables
+    pub session_cookie_secure: bool,
+
+    /// Tokens of internal services, such as the indexer or cron jobs
+    pub internal_api_tokens: Vec<InternalApiToken>,
+
+    /// Per-user limits of the classes of expensive endpoints
+    pub rate_limits: Vec<RateLimitRule>,
+
+    /// Whether the OpenAPI document and Swagger UI are served under `/api-docs`
+    pub api_docs: bool,
+    /// Whether development commands, such as `seed`, may run against the database
+    pub dev_mode: bool,
+
+    /// Format of the logs, human-readable unless `LOG_FORMAT=json`
+    pub log_format: LogFormat,
+    /// Sentry project the 5xx responses are reported to, if any
+    pub sentry_dsn: Option<String>,
+}
+
+/// Configuration of the one-shot commands, such as `migrate`, which only read the variables of
+/// what they use so that they run without the server's full environment.
+#[derive(Debug, Clone)]
+pub struct CommandConfig {
+    pub database_url: String,
+    pub database_pool: DatabasePoolConfig,
+    /// Object storage, only read for the commands using it and unless `STORAGE_MODE=disabled`
+    pub s3: Option<S3Config>,
+    pub log_format: LogFormat,
+    /// Whether development commands, such as `seed`, may run against the database
+    pub dev_mode: bool,
+}
+
+/// Every missing or invalid variable found while reading the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigErrors(pub Vec<String>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Invalid configuration, {} problem(s) found:",
+            self.0.len()
+        )?;
+        for error in &self.0 {
+            writeln!(f, "  - {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Reads variables through `lookup`, recording every problem instead of stopping at the first.
+struct Vars<F> {
+    lookup: F,
+    errors: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Vars<F> {
+    fn optional(&self, name: &str) -> Option<String> {
+        (self.lookup)(name).filter(|value| !value.trim().is_empty())
+    }
+
+    fn required(&mut self, name: &str) -> String {
+        self.optional(name).unwrap_or_else(|| {
+            self.errors.push(format!("{name} must be set"));
+            String::new()
+        })
+    }
+
+    /// Parses `name` with `parse`, describing valid values as `expected` when it fails.
+    fn parse_with<T>(
+        &mut self,
+        name: &str,
+        expected: &str,
+        parse: impl FnOnce(&str) -> Result<T, String>,
+    ) -> Option<T> {
+        let value = self.optional(name)?;
+        parse(value.trim())
+            .inspect_err(|reason| {
+                let reason = if reason.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {reason}")
+                };
+                self.errors
+                    .push(format!("{name} must be {expected}, got '{value}'{reason}"));
+            })
+            .ok()
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, expected: &str) -> Option<T> {
+        self.parse_with(name, expected, |value| {
+            value.parse().map_err(|_| String::new())
+        })
+    }
+
+    fn mode(&mut self, name: &str, default: Mode) -> Mode {
+        self.parse(name, "enabled or disabled").unwrap_or(default)
+    }
+
+    fn database_pool(&mut self) -> DatabasePoolConfig {
+        let max_connections =
+            self.parse_with("DB_MAX_CONNECTIONS", "a positive number", |connections| {
+                match connections.parse() {
+                    Ok(0) | Err(_) => Err(String::new()),
+                    Ok(connections) => Ok(connections),
+                }
+            })
+            .unwrap_or(DEFAULT_DB_MAX_CONNECTIONS);
+        let min_connections = self
+            .parse("DB_MIN_CONNECTIONS", "a number of connections")
+            .unwrap_or(0);
+        if min_connections > max_connections {
+            self.errors.push(format!(
+                "DB_MIN_CONNECTIONS must not exceed DB_MAX_CONNECTIONS ({max_connections}), got \
+                 '{min_connections}'"
+            ));
+        }
+        DatabasePoolConfig {
+            max_connections,
+            min_connections,
+            acquire_timeout_secs: self
+                .parse("DB_ACQUIRE_TIMEOUT_SECS", "a number of seconds")
+                .unwrap_or(DEFAULT_DB_ACQUIRE_TIMEOUT_SECS),
+            statement_timeout_ms: self.parse("DB_STATEMENT_TIMEOUT_MS", "a number of milliseconds"),
+            slow_query_ms: self
+                .parse("DB_SLOW_QUERY_MS", "a number of milliseconds")
+                .unwrap_or(DEFAULT_DB_SLOW_QUERY_MS),
+        }
+    }
+
+    fn s3(&mut self) -> Option<S3Config> {
+        match self.mode("STORAGE_MODE", Mode::Enabled) {
+            Mode::Enabled => Some(S3Config {
+                access_key: self.required("S3_ACCESS_KEY"),
+                secret_key: self.required("S3_SECRET_KEY"),
+                endpoint: self.required("S3_ENDPOINT"),
+            }),
+            Mode::Disabled => None,
+        }
+    }
+
+    fn log_format(&mut self) -> LogFormat {
+        self.parse("LOG_FORMAT", "pretty or json")
+            .unwrap_or(LogFormat::Pretty)
+    }
+
+    fn dev_mode(&mut self) -> bool {
+        self.mode("DEV_MODE", Mode::Disabled) == Mode::Enabled
+    }
 }
 
 impl Config {
+    /// Reads the configuration from the environment, exiting with a report of every missing or
+    /// invalid variable if it cannot.
     pub fn init() -> Config {
-        let database_url = get_env_var("DATABASE_URL");
-        let redis_url = get_env_var("REDIS_URL");
-        let client_origin = get_env_var("CLIENT_ORIGIN");
+        Config::from_env().unwrap_or_else(|errors| {
+            eprintln!("{errors}");
+            std::process::exit(1);
+        })
+    }
+
+    pub fn from_env() -> Result<Config, ConfigErrors> {
+        Config::from_lookup(|name| std::env::var(name).ok())
+    }
 
-        let server_address = get_env_var("SERVER_ADDRESS");
-        let server_port = get_env_var("SERVER_PORT");
-        let server_base_url = get_env_var("SERVER_BASE_URL");
+    /// Reads the configuration from the variables returned by `lookup`, so that it can be read
+    /// from a scoped environment in tests.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigErrors> {
+        let mut vars = Vars {
+            lookup,
+            errors: Vec::new(),
+        };
 
-        let s3_access_key = get_env_var("S3_ACCESS_KEY");
-        let s3_secret_key = get_env_var("S3_SECRET_KEY");
-        let s3_endpoint = get_env_var("S3_ENDPOINT");
+        let database_url = vars.required("DATABASE_URL");
+        let database_pool = vars.database_pool();
+        let redis = match vars.mode("REDIS_MODE", Mode::Enabled) {
+            Mode::Enabled => Some(RedisConfig {
+                url: vars.required("REDIS_URL"),
+            }),
+            Mode::Disabled => None,
+        };
+        let client_origin = vars.required("CLIENT_ORIGIN");
+
+        let server_addresses = vars
+            .optional("SERVER_ADDRESS")
+            .unwrap_or_else(|| DEFAULT_SERVER_ADDRESS.to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(str::to_string)
+            .collect();
+        let server_port = vars
+            .parse("SERVER_PORT", "a port number")
+            .unwrap_or(DEFAULT_SERVER_PORT);
+        let server_workers =
+            vars.parse_with(
+                "SERVER_WORKERS",
+                "a positive number",
+                |workers| match workers.parse() {
+                    Ok(0) | Err(_) => Err(String::new()),
+                    Ok(workers) => Ok(workers),
+                },
+            );
+        let shutdown_grace_secs = vars
+            .parse("SHUTDOWN_GRACE_SECS", "a number of seconds")
+            .unwrap_or(DEFAULT_SHUTDOWN_GRACE_SECS);
+        let server_base_url = vars.required("SERVER_BASE_URL");
+        let body_limits = BodyLimitsConfig {
+            max_json_payload_bytes: vars
+                .parse("MAX_JSON_PAYLOAD_BYTES", "a number of bytes")
+                .unwrap_or(DEFAULT_MAX_JSON_PAYLOAD_BYTES),
+            max_multipart_total_bytes: vars
+                .parse("MAX_MULTIPART_TOTAL_BYTES", "a number of bytes")
+                .unwrap_or(DEFAULT_MAX_MULTIPART_TOTAL_BYTES),
+            multipart_temp_dir: vars.optional("MULTIPART_TEMP_DIR").map(PathBuf::from),
+        };
+
+        let s3 = vars.s3();
+        let s3_presign_expiry_secs = vars
+            .parse_with(
+                "S3_PRESIGN_EXPIRY_SECS",
+                &format!("a number of seconds from 1 to {MAX_S3_PRESIGN_EXPIRY_SECS}"),
+                |secs| match secs.parse() {
+                    Ok(secs) if (1..=MAX_S3_PRESIGN_EXPIRY_SECS).contains(&secs) => Ok(secs),
+                    _ => Err(String::new()),
+                },
+            )
+            .unwrap_or(DEFAULT_S3_PRESIGN_EXPIRY_SECS);
+        let s3_slow_operation_ms = vars
+            .parse("S3_SLOW_OPERATION_MS", "a number of milliseconds")
+            .unwrap_or(DEFAULT_S3_SLOW_OPERATION_MS);
+        let user_storage_quota_bytes = vars.parse("USER_STORAGE_QUOTA_BYTES", "a number of bytes");
+        let max_publication_file_bytes = vars
+            .parse("MAX_PUBLICATION_FILE_BYTES", "a number of bytes")
+            .unwrap_or(DEFAULT_MAX_PUBLICATION_FILE_BYTES);
 
         // Privy configuration
-        let privy_app_id = get_env_var("PRIVY_APP_ID");
-        let privy_app_secret = get_env_var("PRIVY_APP_SECRET");
-        let privy_jwt_verification_key = base64::decode(get_env_var("PRIVY_JWT_VERIFICATION_KEY"))
-            .unwrap_or_else(|_| panic!("PRIVY_JWT_VERIFICATION_KEY must be a valid base64 string"))
-            .to_vec();
+        let privy_app_id = vars.required("PRIVY_APP_ID");
+        let privy_app_secret = vars.required("PRIVY_APP_SECRET");
+        let privy_jwt_verification_key =
+            vars.parse_with("PRIVY_JWT_VERIFICATION_KEY", "a base64 string", |key| {
+                general_purpose::STANDARD
+                    .decode(key)
+                    .map_err(|err| err.to_string())
+            });
+        let privy_token_leeway_secs = vars
+            .parse("PRIVY_TOKEN_LEEWAY_SECS", "a number of seconds")
+            .unwrap_or(DEFAULT_PRIVY_TOKEN_LEEWAY_SECS);
+        let privy_subject_revocation_secs = vars
+            .parse("PRIVY_SUBJECT_REVOCATION_SECS", "a number of seconds")
+            .unwrap_or(DEFAULT_PRIVY_SUBJECT_REVOCATION_SECS);
+
+        let session_ttl_secs = vars
+            .parse("SESSION_TTL_SECS", "a number of seconds")
+            .unwrap_or(DEFAULT_SESSION_TTL_SECS);
+        let session_max_age_secs = vars
+            .parse("SESSION_MAX_AGE_SECS", "a number of seconds")
+            .unwrap_or(DEFAULT_SESSION_MAX_AGE_SECS);
+        let session_cookie_secure = vars
+            .parse("SESSION_COOKIE_SECURE", "true or false")
+            .unwrap_or(true);
+
+        let internal_api_tokens = vars
+            .parse_with(
+                "INTERNAL_API_TOKENS",
+                "name:sha256:scopes entries",
+                InternalApiToken::parse_all,
+            )
+            .unwrap_or_default();
+
+        // Configured limits override the default limit of their class
+        let mut rate_limits = RateLimitRule::parse_all(DEFAULT_RATE_LIMITS)
+            .expect("DEFAULT_RATE_LIMITS must be valid");
+        if let Some(rules) = vars.parse_with(
+            "RATE_LIMITS",
+            "class:limit/window_secs entries",
+            RateLimitRule::parse_all,
+        ) {
+            rate_limits.retain(|default| rules.iter().all(|rule| rule.class != default.class));
+            rate_limits.extend(rules);
+        }
+
+        let api_docs = vars.mode("API_DOCS_MODE", Mode::Disabled) == Mode::Enabled;
+        let dev_mode = vars.dev_mode();
+
+        let log_format = vars.log_format();
+        let sentry_dsn = vars.optional("SENTRY_DSN");
 
-        Config {
+        if !vars.errors.is_empty() {
+            return Err(ConfigErrors(vars.errors));
+        }
+
+        Ok(Config {
             database_url,
-            redis_url,
+            database_pool,
+            redis,
             client_origin,
-            server_address,
+            server_addresses,
             server_port,
+            server_workers,
+            shutdown_grace_secs,
             server_base_url,
-            s3_access_key,
-            s3_secret_key,
-            s3_endpoint,
+            body_limits,
+            s3,
+            s3_presign_expiry_secs,
+            s3_slow_operation_ms,
+            user_storage_quota_bytes,
+            max_publication_file_bytes,
             privy_app_id,
             privy_app_secret,
             privy_jwt_verification_key,
+            privy_token_leeway_secs,
+            privy_subject_revocation_secs,
+            session_ttl_secs,
+            session_max_age_secs,
+            session_cookie_secure,
+            internal_api_tokens,
+            rate_limits,
+            api_docs,
+            dev_mode,
+            log_format,
+            sentry_dsn,
+        })
+    }
+}
+
+impl CommandConfig {
+    /// Reads the configuration from the environment, along with the storage variables when
+    /// `storage` is set.
+    pub fn from_env(storage: bool) -> Result<CommandConfig, ConfigErrors> {
+        CommandConfig::from_lookup(|name| std::env::var(name).ok(), storage)
+    }
+
+    pub fn from_lookup(
+        lookup: impl Fn(&str) -> Option<String>,
+        storage: bool,
+    ) -> Result<CommandConfig, ConfigErrors> {
+        let mut vars = Vars {
+            lookup,
+            errors: Vec::new(),
+        };
+
+        let database_url = vars.required("DATABASE_URL");
+        let database_pool = vars.database_pool();
+        let s3 = if storage { vars.s3() } else { None };
+        let log_format = vars.log_format();
+        let dev_mode = vars.dev_mode();
+
+        if !vars.errors.is_empty() {
+            return Err(ConfigErrors(vars.errors));
+        }
+
+        Ok(CommandConfig {
+            database_url,
+            database_pool,
+            s3,
+            log_format,
+            dev_mode,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Environment scoped to a test, read instead of the process environment, which the database
+    /// tests running concurrently rely on.
+    #[derive(Default)]
+    struct TestEnv(HashMap<&'static str, &'static str>);
+
+    impl TestEnv {
+        /// Returns an environment setting every required variable.
+        fn complete() -> Self {
+            TestEnv::default()
+                .set("DATABASE_URL", "postgres://localhost/publish3")
+                .set("REDIS_URL", "redis://localhost:6379")
+                .set("CLIENT_ORIGIN", "http://localhost:3000")
+                .set("SERVER_BASE_URL", "http://localhost:8080")
+                .set("S3_ACCESS_KEY", "minioadmin")
+                .set("S3_SECRET_KEY", "minioadmin")
+                .set("S3_ENDPOINT", "http://localhost:9000")
+                .set("PRIVY_APP_ID", "app")
+                .set("PRIVY_APP_SECRET", "secret")
+        }
+
+        fn set(mut self, name: &'static str, value: &'static str) -> Self {
+            self.0.insert(name, value);
+            self
+        }
+
+        fn unset(mut self, name: &'static str) -> Self {
+            self.0.remove(name);
+            self
+        }
+
+        fn config(&self) -> Result<Config, ConfigErrors> {
+            Config::from_lookup(|name| self.0.get(name).map(|value| value.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = TestEnv::complete().config().unwrap();
+
+        assert_eq!(config.database_pool.max_connections, 10);
+        assert_eq!(config.database_pool.min_connections, 0);
+        assert_eq!(config.database_pool.acquire_timeout_secs, 30);
+        assert_eq!(config.database_pool.statement_timeout_ms, None);
+        assert_eq!(config.database_pool.slow_query_ms, 1000);
+        assert_eq!(config.server_addresses, vec!["0.0.0.0"]);
+        assert_eq!(config.server_port, 8080);
+        assert_eq!(config.server_workers, None);
+        assert_eq!(config.shutdown_grace_secs, 30);
+        assert_eq!(config.body_limits.max_json_payload_bytes, 4 * 1024 * 1024);
+        assert_eq!(
+            config.body_limits.max_multipart_total_bytes,
+            101 * 1024 * 1024
+        );
+        assert_eq!(config.body_limits.multipart_temp_dir, None);
+        assert_eq!(config.redis.unwrap().url, "redis://localhost:6379");
+        assert_eq!(config.s3.unwrap().endpoint, "http://localhost:9000");
+        assert_eq!(config.s3_presign_expiry_secs, 300);
+        assert_eq!(config.max_publication_file_bytes, 100 * 1024 * 1024);
+        assert_eq!(config.session_max_age_secs, 30 * 24 * 60 * 60);
+        assert!(config.session_cookie_secure);
+        assert!(config.internal_api_tokens.is_empty());
+        assert_eq!(config.rate_limits.len(), 1);
+        assert!(!config.api_docs);
+        assert!(!config.dev_mode);
+        assert_eq!(config.log_format, LogFormat::Pretty);
+        assert_eq!(config.sentry_dsn, None);
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let config = TestEnv::complete()
+            .set("DB_MAX_CONNECTIONS", "20")
+            .set("DB_MIN_CONNECTIONS", "2")
+            .set("DB_ACQUIRE_TIMEOUT_SECS", "5")
+            .set("DB_STATEMENT_TIMEOUT_MS", "15000")
+            .set("DB_SLOW_QUERY_MS", "250")
+            .set("SERVER_ADDRESS", "127.0.0.1, ::1")
+            .set("SERVER_PORT", "9090")
+            .set("SERVER_WORKERS", "4")
+            .set("SHUTDOWN_GRACE_SECS", "5")
+            .set("MAX_JSON_PAYLOAD_BYTES", "1024")
+            .set("MAX_MULTIPART_TOTAL_BYTES", "4096")
+            .set("MULTIPART_TEMP_DIR", "/var/tmp/publish3")
+            .set("USER_STORAGE_QUOTA_BYTES", "1024")
+            .set("MAX_PUBLICATION_FILE_BYTES", "2048")
+            .set("RATE_LIMITS", "publish:10/60;purchase:20/3600")
+            .set("API_DOCS_MODE", "enabled")
+            .set("DEV_MODE", "enabled")
+            .set("LOG_FORMAT", "json")
+            .config()
+            .unwrap();
+
+        assert_eq!(config.database_pool.max_connections, 20);
+        assert_eq!(config.database_pool.min_connections, 2);
+        assert_eq!(config.database_pool.acquire_timeout_secs, 5);
+        assert_eq!(config.database_pool.statement_timeout_ms, Some(15000));
+        assert_eq!(config.database_pool.slow_query_ms, 250);
+        assert_eq!(config.server_addresses, vec!["127.0.0.1", "::1"]);
+        assert_eq!(config.server_port, 9090);
+        assert_eq!(config.server_workers, Some(4));
+        assert_eq!(config.shutdown_grace_secs, 5);
+        assert_eq!(config.body_limits.max_json_payload_bytes, 1024);
+        assert_eq!(config.body_limits.max_multipart_total_bytes, 4096);
+        assert_eq!(
+            config.body_limits.multipart_temp_dir,
+            Some(PathBuf::from("/var/tmp/publish3"))
+        );
+        assert_eq!(config.user_storage_quota_bytes, Some(1024));
+        assert_eq!(config.max_publication_file_bytes, 2048);
+        assert_eq!(config.rate_limits.len(), 2);
+        assert!(
+            config
+                .rate_limits
+                .iter()
+                .any(|rule| rule.class == "publish" && rule.limit == 10)
+        );
+        assert!(config.api_docs);
+        assert!(config.dev_mode);
+        assert_eq!(config.log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn test_every_error_is_reported() {
+        let errors = TestEnv::complete()
+            .unset("DATABASE_URL")
+            .unset("PRIVY_APP_ID")
+            .set("SERVER_PORT", "80800")
+            .set("SERVER_WORKERS", "0")
+            .set("SESSION_COOKIE_SECURE", "yes")
+            .set("RATE_LIMITS", "publish")
+            .set("LOG_FORMAT", "text")
+            .config()
+            .unwrap_err();
+
+        assert_eq!(errors.0.len(), 7, "{errors}");
+        assert!(errors.0.contains(&"DATABASE_URL must be set".to_string()));
+        assert!(errors.0.contains(&"PRIVY_APP_ID must be set".to_string()));
+        assert!(
+            errors
+                .0
+                .contains(&"SERVER_PORT must be a port number, got '80800'".to_string())
+        );
+        assert!(
+            errors
+                .0
+                .iter()
+                .any(|error| error.starts_with("RATE_LIMITS must be"))
+        );
+
+        let report = errors.to_string();
+        assert!(report.starts_with("Invalid configuration, 7 problem(s) found:"));
+        assert!(report.contains("  - SERVER_WORKERS must be a positive number, got '0'"));
+        assert!(report.contains("  - SESSION_COOKIE_SECURE must be true or false, got 'yes'"));
+        assert!(report.contains("  - LOG_FORMAT must be pretty or json, got 'text'"));
+    }
+
+    #[test]
+    fn test_database_pool_bounds() {
+        let errors = TestEnv::complete()
+            .set("DB_MAX_CONNECTIONS", "0")
+            .config()
+            .unwrap_err();
+        assert_eq!(
+            errors.0,
+            vec!["DB_MAX_CONNECTIONS must be a positive number, got '0'".to_string()]
+        );
+
+        let errors = TestEnv::complete()
+            .set("DB_MIN_CONNECTIONS", "11")
+            .config()
+            .unwrap_err();
+        assert_eq!(
+            errors.0,
+            vec![
+                "DB_MIN_CONNECTIONS must not exceed DB_MAX_CONNECTIONS (10), got '11'".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_presign_expiry_bounds() {
+        for secs in ["0", "604801"] {
+            let errors = TestEnv::complete()
+                .set("S3_PRESIGN_EXPIRY_SECS", secs)
+                .config()
+                .unwrap_err();
+            assert_eq!(
+                errors.0,
+                vec![format!(
+                    "S3_PRESIGN_EXPIRY_SECS must be a number of seconds from 1 to 604800, got \
+                     '{secs}'"
+                )]
+            );
         }
+
+        let config = TestEnv::complete()
+            .set("S3_PRESIGN_EXPIRY_SECS", "604800")
+            .config()
+            .unwrap();
+        assert_eq!(config.s3_presign_expiry_secs, 604800);
+    }
+
+    #[test]
+    fn test_disabled_sections() {
+        let env = TestEnv::complete()
+            .unset("REDIS_URL")
+            .unset("S3_ACCESS_KEY")
+            .unset("S3_SECRET_KEY")
+            .unset("S3_ENDPOINT");
+
+        let errors = env.config().unwrap_err();
+        assert_eq!(errors.0.len(), 4, "{errors}");
+
+        let config = env
+            .set("REDIS_MODE", "disabled")
+            .set("STORAGE_MODE", "disabled")
+            .config()
+            .unwrap();
+        assert!(config.redis.is_none());
+        assert!(config.s3.is_none());
+
+        let errors = TestEnv::complete()
+            .set("REDIS_MODE", "off")
+            .config()
+            .unwrap_err();
+        assert_eq!(
+            errors.0,
+            vec!["REDIS_MODE must be enabled or disabled, got 'off'".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_command_config() {
+        let env = TestEnv::default().set("DATABASE_URL", "postgres://localhost/publish3");
+        let command_config = |env: &TestEnv, storage| {
+            CommandConfig::from_lookup(
+                |name| env.0.get(name).map(|value| value.to_string()),
+                storage,
+            )
+        };
+
+        // Neither Privy, Redis nor the server's variables are read
+        let config = command_config(&env, false).unwrap();
+        assert_eq!(config.database_url, "postgres://localhost/publish3");
+        assert!(config.s3.is_none());
+        assert!(!config.dev_mode);
+
+        let errors = command_config(&env, true).unwrap_err();
+        assert_eq!(errors.0.len(), 3, "{errors}");
+        let config = command_config(&env.set("STORAGE_MODE", "disabled"), true).unwrap();
+        assert!(config.s3.is_none());
+
+        let errors = command_config(&TestEnv::default(), false).unwrap_err();
+        assert_eq!(errors.0, vec!["DATABASE_URL must be set".to_string()]);
     }
 }
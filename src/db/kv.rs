@@ -0,0 +1,256 @@
+//! Expiring key-value store holding the state the server keeps in Redis: cookie sessions, the
+//! revocation denylist, cached publications and recent views. Each of them prefixes its keys, so
+//! that they can share a store.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use tokio::sync::OnceCell;
+
+use crate::common::zresult::ZResult;
+
+#[async_trait]
+pub trait KeyValueStore: Send + Sync {
+    async fn get(&self, key: &str) -> ZResult<Option<String>>;
+
+    /// Returns the values stored under `keys`, in one round trip.
+    async fn get_all(&self, keys: &[String]) -> ZResult<Vec<Option<String>>>;
+
+    /// Stores `value` under `key` for `ttl`.
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> ZResult<()>;
+
+    /// Stores `value` under `key` for `ttl` unless a value is already stored, returning whether
+    /// it was stored.
+    async fn insert(&self, key: &str, value: &str, ttl: Duration) -> ZResult<bool>;
+
+    async fn delete(&self, key: &str) -> ZResult<()>;
+}
+
+/// Connection to Redis, opened on first use so that the server starts while Redis is down.
+pub struct RedisConnection {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisConnection {
+    pub fn new(client: redis::Client) -> Self {
+        RedisConnection {
+            client,
+            connection: OnceCell::new(),
+        }
+    }
+
+    pub async fn get(&self) -> ZResult<ConnectionManager> {
+        Ok(self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await?
+            .clone())
+    }
+}
+
+/// [KeyValueStore] backed by Redis.
+pub struct RedisKeyValueStore {
+    connection: RedisConnection,
+}
+
+impl RedisKeyValueStore {
+    pub fn new(client: redis::Client) -> Self {
+        RedisKeyValueStore {
+            connection: RedisConnection::new(client),
+        }
+    }
+}
+
+#[async_trait]
+impl KeyValueStore for RedisKeyValueStore {
+    async fn get(&self, key: &str) -> ZResult<Option<String>> {
+        let mut connection = self.connection.get().await?;
+        Ok(redis::cmd("GET")
+            .arg(key)
+            .query_async(&mut connection)
+            .await?)
+    }
+
+    async fn get_all(&self, keys: &[String]) -> ZResult<Vec<Option<String>>> {
+        let mut connection = self.connection.get().await?;
+        Ok(redis::cmd("MGET")
+            .arg(keys)
+            .query_async(&mut connection)
+            .await?)
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> ZResult<()> {
+        let mut connection = self.connection.get().await?;
+        redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async::<()>(&mut connection)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn insert(&self, key: &str, value: &str, ttl: Duration) -> ZResult<bool> {
+        let mut connection = self.connection.get().await?;
+        let stored: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut connection)
+            .await?;
+
+        Ok(stored.is_some())
+    }
+
+    async fn delete(&self, key: &str) -> ZResult<()> {
+        let mut connection = self.connection.get().await?;
+        redis::cmd("DEL")
+            .arg(key)
+            .query_async::<()>(&mut connection)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Number of entries from which [MemoryKeyValueStore] starts purging expired ones.
+const MEMORY_PURGE_THRESHOLD: usize = 1024;
+
+/// In-memory [KeyValueStore] standing in for Redis when `REDIS_MODE=disabled` and in tests.
+///
+/// Expired entries are purged when writes grow the map to twice its size after the last purge,
+/// so that the memory used follows the live entries at an amortized constant cost.
+#[derive(Default)]
+pub struct MemoryKeyValueStore {
+    entries: Mutex<MemoryEntries>,
+}
+
+#[derive(Default)]
+struct MemoryEntries {
+    values: HashMap<String, (String, Instant)>,
+    purge_at: usize,
+}
+
+impl MemoryEntries {
+    fn get(&self, key: &str, now: Instant) -> Option<&String> {
+        self.values
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(value, _)| value)
+    }
+
+    fn set(&mut self, key: &str, value: &str, expires_at: Instant, now: Instant) {
+        if self.values.len() >= self.purge_at.max(MEMORY_PURGE_THRESHOLD) {
+            self.values.retain(|_, (_, expires_at)| *expires_at > now);
+            self.purge_at = self.values.len() * 2;
+        }
+        self.values
+            .insert(key.to_string(), (value.to_string(), expires_at));
+    }
+}
+
+#[async_trait]
+impl KeyValueStore for MemoryKeyValueStore {
+    async fn get(&self, key: &str) -> ZResult<Option<String>> {
+        Ok(self.get_all(&[key.to_string()]).await?.remove(0))
+    }
+
+    async fn get_all(&self, keys: &[String]) -> ZResult<Vec<Option<String>>> {
+        let now = Instant::now();
+        let entries = self.entries.lock().unwrap();
+        Ok(keys
+            .iter()
+            .map(|key| entries.get(key, now).cloned())
+            .collect())
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> ZResult<()> {
+        let now = Instant::now();
+        self.entries.lock().unwrap().set(key, value, now + ttl, now);
+        Ok(())
+    }
+
+    async fn insert(&self, key: &str, value: &str, ttl: Duration) -> ZResult<bool> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        if entries.get(key, now).is_some() {
+            return Ok(false);
+        }
+        entries.set(key, value, now + ttl, now);
+        Ok(true)
+    }
+
+    async fn delete(&self, key: &str) -> ZResult<()> {
+        self.entries.lock().unwrap().values.remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_memory_key_value_store() {
+        let store = MemoryKeyValueStore::default();
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(store.get("a").await.unwrap(), None);
+        store.set("a", "1", ttl).await.unwrap();
+        store.set("a", "2", ttl).await.unwrap();
+        assert_eq!(store.get("a").await.unwrap().as_deref(), Some("2"));
+
+        assert!(!store.insert("a", "3", ttl).await.unwrap());
+        assert!(store.insert("b", "3", ttl).await.unwrap());
+        assert_eq!(
+            store
+                .get_all(&["a".to_string(), "c".to_string(), "b".to_string()])
+                .await
+                .unwrap(),
+            vec![Some("2".to_string()), None, Some("3".to_string())]
+        );
+
+        store.delete("a").await.unwrap();
+        assert_eq!(store.get("a").await.unwrap(), None);
+
+        // Expired entries are gone, and can be inserted again
+        store.set("c", "1", Duration::ZERO).await.unwrap();
+        assert_eq!(store.get("c").await.unwrap(), None);
+        assert!(store.insert("c", "2", ttl).await.unwrap());
+    }
+
+    #[actix_web::test]
+    async fn test_memory_key_value_store_purges_expired_entries() {
+        let store = MemoryKeyValueStore::default();
+        let ttl = Duration::from_secs(60);
+
+        for i in 0..10 * MEMORY_PURGE_THRESHOLD {
+            store
+                .insert(&format!("expired:{i}"), "1", Duration::ZERO)
+                .await
+                .unwrap();
+        }
+        store.set("live", "1", ttl).await.unwrap();
+        assert!(store.entries.lock().unwrap().values.len() <= MEMORY_PURGE_THRESHOLD + 1);
+
+        // Live entries survive purges
+        for i in 0..2 * MEMORY_PURGE_THRESHOLD {
+            store.set(&format!("live:{i}"), "1", ttl).await.unwrap();
+        }
+        assert_eq!(store.get("live").await.unwrap().as_deref(), Some("1"));
+        assert_eq!(
+            store.entries.lock().unwrap().values.len(),
+            2 * MEMORY_PURGE_THRESHOLD + 1
+        );
+    }
+}
@@ -1,2 +1,3 @@
+pub mod kv;
 pub mod sql;
 pub mod s3;
\ No newline at end of file
@@ -1,37 +1,78 @@
 use actix_multipart::form::tempfile::TempFile;
 use actix_web::mime::Mime;
+use async_trait::async_trait;
 use aws_config::Region;
 use aws_sdk_s3::{
     Client,
     config::Credentials,
+    error::ProvideErrorMetadata,
     operation::{
-        create_bucket::CreateBucketOutput, delete_objects::DeleteObjectsOutput,
-        get_object::GetObjectOutput, list_objects_v2::ListObjectsV2Output,
-        put_object::PutObjectOutput,
+        create_bucket::CreateBucketOutput,
+        delete_objects::DeleteObjectsOutput,
+        get_object::GetObjectOutput,
+        head_object::{HeadObjectError, HeadObjectOutput},
+        list_objects_v2::ListObjectsV2Output,
     },
     presigning::{PresignedRequest, PresigningConfig},
-    primitives::ByteStream,
+    primitives::{ByteStream, Length},
     types::{
-        BucketLocationConstraint, CreateBucketConfiguration, Delete, Object, ObjectIdentifier,
+        BucketLocationConstraint, CompletedMultipartUpload, CompletedPart,
+        CreateBucketConfiguration, Delete, Object, ObjectIdentifier,
     },
 };
 use base64::{Engine, engine::general_purpose};
+use chrono::{DateTime, Utc};
+use percent_encoding::{
+    AsciiSet, CONTROLS, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode,
+};
+use serde::Serialize;
 use std::io::Write;
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     time::Duration,
 };
 use tempfile::NamedTempFile;
+use utoipa::ToSchema;
 
 use crate::{
-    common::zresult::{ZError, ZResult},
-    db::s3::{S3Bucket, S3Key},
+    common::{
+        time::rfc3339_millis,
+        zresult::{ZError, ZResult},
+    },
+    config::S3Config,
+    db::s3::{ByteRange, ObjectStore, S3Bucket, S3Key, store::NoSuchBucket, validate_key},
 };
 
+/// Characters escaped when a key is used as a URL path, as in the `x-amz-copy-source` header or
+/// public URLs: everything but unreserved characters and the path separator.
+const KEY_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Characters escaped in user metadata values, which S3 transports as ASCII headers. Non-ASCII
+/// characters are always escaped; `%` is escaped so that values decode back unchanged.
+const METADATA_ENCODE_SET: &AsciiSet = &CONTROLS.add(b'%');
+
+/// Maximum number of keys S3 returns per listing page and accepts per delete_objects request.
+const MAX_KEYS_PER_REQUEST: i32 = 1000;
+
+/// Maximum number of objects returned by a single [S3Client::list_files] call.
+const MAX_LISTED_FILES: usize = 10_000;
+
+/// Size of the parts of multipart uploads. Larger files are uploaded in parts of this size, so
+/// that a slow or failing request only has to send one part again; smaller ones in a single
+/// request. S3 requires parts other than the last to be at least 5 MiB.
+const MULTIPART_PART_SIZE: u64 = 16 * 1024 * 1024;
+
 #[derive(Clone)]
 pub struct S3Client {
     client: Client,
     region: Option<String>,
+    endpoint: Option<String>,
 }
 
 impl S3Client {
@@ -51,7 +92,7 @@ impl S3Client {
             }
         };
 
-        if let Some(endpoint) = endpoint {
+        if let Some(endpoint) = &endpoint {
             config_loader = config_loader.endpoint_url(endpoint)
         }
 
@@ -60,16 +101,23 @@ impl S3Client {
 
         let client = Client::from_conf(config.build());
 
-        S3Client { client, region }
+        S3Client {
+            client,
+            region,
+            endpoint,
+        }
     }
 
-    pub async fn upload_storage_files(
-        &self,
-        files: Vec<TempFile>,
-        path: Option<PathBuf>,
-    ) -> ZResult<()> {
-        self.upload_files(files, path, None, &S3Bucket::Storage)
-            .await
+    /// Returns a client of the configured storage, in its default region.
+    pub async fn from_config(config: &S3Config) -> Self {
+        let credentials = Credentials::new(
+            config.access_key.to_owned(),
+            config.secret_key.to_owned(),
+            None,
+            None,
+            "Publish3",
+        );
+        S3Client::new(credentials, None, Some(config.endpoint.to_owned())).await
     }
 
     pub async fn delete_storage_files(
@@ -81,13 +129,9 @@ impl S3Client {
             .await
     }
 
-    pub async fn retrieve_storage_file(&self, path: &str) -> ZResult<FileResponse> {
-        self.retrieve_file(path, &S3Bucket::Storage).await
-    }
-
     pub async fn get_file_bytes(&self, key: &str, bucket: &S3Bucket) -> ZResult<bytes::Bytes> {
         Ok(self
-            .get_object(key, bucket)
+            .get_object(key, bucket, None)
             .await?
             .body
             .collect()
@@ -95,22 +139,19 @@ impl S3Client {
             .map(|data| data.into_bytes())?)
     }
 
-    pub async fn get_file_url(&self, key: &str, bucket: &S3Bucket) -> ZResult<String> {
-        let presigned_request = self.get_object_presigned(key, bucket).await?;
-        Ok(presigned_request.uri().to_string())
-    }
-
     /// Asynchronously creates the bucket associated to this client upon construction on a new
-    /// tokio runtime.
+    /// tokio runtime. When `public` is true, a policy granting anonymous read access to its
+    /// objects is attached, including to a reused bucket.
     /// Returns:
     /// - Ok(Some(CreateBucketOutput)) in case the bucket was successfully created
     /// - Ok(Some(None)) in case the `reuse_bucket` parameter is true and the bucket already exists
-    ///     and is owned by you
+    ///   and is owned by you
     /// - Error in any other case
     pub async fn create_bucket(
         &self,
         bucket: S3Bucket,
         reuse_bucket: bool,
+        public: bool,
     ) -> ZResult<Option<CreateBucketOutput>> {
         let constraint = self
             .region
@@ -127,7 +168,7 @@ impl S3Client {
             .send()
             .await;
 
-        match result {
+        let output = match result {
                 Ok(output) => Ok(Some(output)),
                 Err(err) => {
                     match err.into_service_error() {
@@ -146,9 +187,65 @@ impl S3Client {
                         , bucket))),
                     }
                 }
-            }
+            }?;
+
+        if public {
+            self.put_public_read_policy(&bucket).await?;
+        }
+
+        Ok(output)
+    }
+
+    /// Returns the stable, unsigned URL of an object in the public bucket.
+    pub fn public_url(&self, key: &S3Key) -> String {
+        let base_url = match &self.endpoint {
+            Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+            None => format!(
+                "https://s3.{}.amazonaws.com",
+                self.region.as_deref().unwrap_or("us-east-1")
+            ),
+        };
+
+        // The client is configured with path-style addressing
+        format!(
+            "{}/{}/{}",
+            base_url,
+            S3Bucket::Public.as_str(),
+            utf8_percent_encode(&key.0, KEY_PATH_ENCODE_SET)
+        )
+    }
+
+    /// Allows anyone to read the objects of `bucket`, but not to list or modify them.
+    async fn put_public_read_policy(&self, bucket: &S3Bucket) -> ZResult<()> {
+        let policy = serde_json::json!({
+            "Version": "2012-10-17",
+            "Statement": [{
+                "Sid": "PublicRead",
+                "Effect": "Allow",
+                "Principal": "*",
+                "Action": ["s3:GetObject"],
+                "Resource": [format!("arn:aws:s3:::{}/*", bucket.as_str())]
+            }]
+        });
+
+        self.client
+            .put_bucket_policy()
+            .bucket(bucket.as_str())
+            .policy(policy.to_string())
+            .send()
+            .await
+            .map_err(|err| {
+                ZError::from(format!(
+                    "Error setting public read policy on bucket '{}': {}",
+                    bucket.as_str(),
+                    err.into_service_error()
+                ))
+            })?;
+
+        Ok(())
     }
 
+    #[allow(dead_code)]
     async fn retrieve_file_as_tempfile(
         &self,
         key: &S3Key,
@@ -157,7 +254,7 @@ impl S3Client {
         let mut tempfile = NamedTempFile::new().unwrap();
 
         let object_output = self
-            .get_object(key.0.as_str(), bucket)
+            .get_object(key.0.as_str(), bucket, None)
             .await
             .map_err(|err| ZError::from(format!("Error retrieving file from S3: {err}")))?;
 
@@ -173,33 +270,6 @@ impl S3Client {
         Ok(tempfile)
     }
 
-    async fn upload_files(
-        &self,
-        files: Vec<TempFile>,
-        path: Option<PathBuf>,
-        root_path: Option<PathBuf>,
-        bucket: &S3Bucket,
-    ) -> ZResult<()> {
-        let upload_futures = files.into_iter().map(|temp_file| {
-            let mut key = root_path.clone().unwrap_or_default();
-            if let Some(path) = &path {
-                key.push(path.to_str().unwrap());
-            }
-            key.push(temp_file.file_name.clone().unwrap());
-            let key = S3Key(key.to_string_lossy().into_owned());
-            async move {
-                self.put_file(&key, bucket, temp_file.file, temp_file.content_type)
-                    .await
-            }
-        });
-
-        futures::future::try_join_all(upload_futures)
-            .await
-            .map_err(|err| ZError::from(format!("Error uploading files to S3: {err}")))?;
-
-        Ok(())
-    }
-
     async fn delete_files(
         &self,
         files: Vec<String>,
@@ -242,9 +312,14 @@ impl S3Client {
         Ok(())
     }
 
-    async fn retrieve_file(&self, key: &str, bucket: &S3Bucket) -> ZResult<FileResponse> {
+    async fn retrieve_file(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        range: Option<ByteRange>,
+    ) -> ZResult<FileResponse> {
         let object_output = self
-            .get_object(key, bucket)
+            .get_object(key, bucket, range)
             .await
             .map_err(|err| ZError::from(format!("Error retrieving file from S3: {err}")))?;
 
@@ -252,6 +327,7 @@ impl S3Client {
         let content_length = object_output.content_length().map(|l| l.to_string());
         let content_disposition = object_output.content_disposition().map(|s| s.to_string());
         let last_modified = object_output.last_modified().map(|dt| dt.to_string());
+        let metadata = decode_metadata(object_output.metadata());
         let body = object_output.body;
 
         Ok(FileResponse {
@@ -260,49 +336,101 @@ impl S3Client {
             content_length,
             content_disposition,
             last_modified,
+            metadata,
         })
     }
 
-    /// Retrieves the object associated to the [key] specified.
-    async fn get_object(&self, key: &str, bucket: &S3Bucket) -> ZResult<GetObjectOutput> {
+    /// Retrieves the object associated to the [key] specified, restricted to `range` if given.
+    async fn get_object(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        range: Option<ByteRange>,
+    ) -> ZResult<GetObjectOutput> {
         Ok(self
             .client
             .get_object()
             .bucket(bucket.as_str())
             .key(key.to_string())
+            .set_range(range.map(|range| range.header_value()))
             .send()
             .await?)
     }
 
-    /// Retrieves the object associated to the [key] specified.
+    /// Retrieves the metadata of the object associated to the [key] specified, mapping a missing
+    /// object to `None`.
+    async fn head_object(&self, key: &str, bucket: &S3Bucket) -> ZResult<Option<HeadObjectOutput>> {
+        let result = self
+            .client
+            .head_object()
+            .bucket(bucket.as_str())
+            .key(key.to_string())
+            .send()
+            .await;
+
+        match result {
+            Ok(output) => Ok(Some(output)),
+            Err(err) => match err.into_service_error() {
+                HeadObjectError::NotFound(_) => Ok(None),
+                err => Err(ZError::from(format!(
+                    "Error retrieving metadata of '{key}' from S3: {err}"
+                ))),
+            },
+        }
+    }
+
+    /// Presigns a GET request for the object associated to the [key] specified, optionally
+    /// overriding the `Content-Disposition` of the response.
     async fn get_object_presigned(
         &self,
         key: &str,
         bucket: &S3Bucket,
+        expires: Duration,
+        content_disposition: Option<&str>,
     ) -> ZResult<PresignedRequest> {
+        let presigning_config = PresigningConfig::builder()
+            .expires_in(expires)
+            .build()
+            .map_err(|err| ZError::from(format!("Invalid presigning configuration: {err}")))?;
+
         Ok(self
             .client
             .get_object()
             .bucket(bucket.as_str())
             .key(key.to_string())
-            .presigned(
-                PresigningConfig::builder()
-                    .expires_in(Duration::from_secs(60 * 5))
-                    .build()
-                    .unwrap(),
-            )
+            .set_response_content_disposition(content_disposition.map(str::to_string))
+            .presigned(presigning_config)
             .await?)
     }
 
+    /// Uploads the file at `path`, in parts of [MULTIPART_PART_SIZE] if it is larger than one.
     async fn put_file(
         &self,
         key: &S3Key,
         bucket: &S3Bucket,
-        file: NamedTempFile,
-        content_type: Option<Mime>,
-    ) -> ZResult<PutObjectOutput> {
+        path: &Path,
+        content_type: Option<&Mime>,
+        metadata: HashMap<String, String>,
+    ) -> ZResult<()> {
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| ZError::from(format!("Failed to read file: {e}")))?
+            .len();
+        if size > MULTIPART_PART_SIZE {
+            return self
+                .put_file_multipart(
+                    key,
+                    bucket,
+                    path,
+                    content_type,
+                    metadata,
+                    part_ranges(size, MULTIPART_PART_SIZE),
+                )
+                .await;
+        }
+
         let body = ByteStream::read_from()
-            .path(file.path())
+            .path(path)
             .build()
             .await
             .map_err(|e| ZError::from(format!("Failed to read file: {e}")))?;
@@ -312,13 +440,144 @@ impl S3Client {
             .put_object()
             .bucket(bucket.as_str())
             .key(key.0.as_str())
+            .set_metadata(Some(encode_metadata(metadata)))
             .body(body);
 
         if let Some(mime) = content_type {
             request = request.content_type(mime.as_ref());
         }
 
-        Ok(request.send().await?)
+        match request.send().await {
+            Ok(_) => Ok(()),
+            Err(err) if err.code() == Some("NoSuchBucket") => {
+                Err(ZError::from(NoSuchBucket(bucket.as_str())))
+            }
+            Err(err) => Err(ZError::from(format!(
+                "Error uploading '{key}' to S3: {}",
+                err.into_service_error()
+            ))),
+        }
+    }
+
+    /// Uploads the file at `path` as a multipart upload of the given parts, aborting the upload
+    /// if any part fails so that S3 does not keep the parts already sent.
+    async fn put_file_multipart(
+        &self,
+        key: &S3Key,
+        bucket: &S3Bucket,
+        path: &Path,
+        content_type: Option<&Mime>,
+        metadata: HashMap<String, String>,
+        parts: Vec<(u64, u64)>,
+    ) -> ZResult<()> {
+        let upload = match self
+            .client
+            .create_multipart_upload()
+            .bucket(bucket.as_str())
+            .key(key.0.as_str())
+            .set_metadata(Some(encode_metadata(metadata)))
+            .set_content_type(content_type.map(|mime| mime.to_string()))
+            .send()
+            .await
+        {
+            Ok(upload) => upload,
+            Err(err) if err.code() == Some("NoSuchBucket") => {
+                return Err(ZError::from(NoSuchBucket(bucket.as_str())));
+            }
+            Err(err) => {
+                return Err(ZError::from(format!(
+                    "Error starting the upload of '{key}' to S3: {}",
+                    err.into_service_error()
+                )));
+            }
+        };
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| ZError::from(format!("No upload id returned for '{key}'")))?;
+
+        let result = self.upload_parts(key, bucket, path, upload_id, parts).await;
+        if result.is_err()
+            && let Err(err) = self
+                .client
+                .abort_multipart_upload()
+                .bucket(bucket.as_str())
+                .key(key.0.as_str())
+                .upload_id(upload_id)
+                .send()
+                .await
+        {
+            tracing::error!(
+                "Error aborting the upload of '{}' to S3: {}",
+                key,
+                err.into_service_error()
+            );
+        }
+        result
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &S3Key,
+        bucket: &S3Bucket,
+        path: &Path,
+        upload_id: &str,
+        parts: Vec<(u64, u64)>,
+    ) -> ZResult<()> {
+        let mut completed_parts = Vec::with_capacity(parts.len());
+        for (index, (offset, length)) in parts.into_iter().enumerate() {
+            let part_number = index as i32 + 1;
+            let body = ByteStream::read_from()
+                .path(path)
+                .offset(offset)
+                .length(Length::Exact(length))
+                .build()
+                .await
+                .map_err(|e| ZError::from(format!("Failed to read file: {e}")))?;
+
+            let part = self
+                .client
+                .upload_part()
+                .bucket(bucket.as_str())
+                .key(key.0.as_str())
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(body)
+                .send()
+                .await
+                .map_err(|err| {
+                    ZError::from(format!(
+                        "Error uploading part {part_number} of '{key}' to S3: {}",
+                        err.into_service_error()
+                    ))
+                })?;
+            completed_parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(part.e_tag().map(str::to_string))
+                    .build(),
+            );
+        }
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(bucket.as_str())
+            .key(key.0.as_str())
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(completed_parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|err| {
+                ZError::from(format!(
+                    "Error completing the upload of '{key}' to S3: {}",
+                    err.into_service_error()
+                ))
+            })?;
+
+        Ok(())
     }
 
     async fn delete_items(
@@ -368,6 +627,9 @@ impl S3Client {
             .bucket(bucket.as_str())
             .delete(delete)
             .customize()
+            // S3 requires an integrity checksum on DeleteObjects, and S3-compatible stores such
+            // as MinIO only accept Content-MD5 rather than the checksums the SDK sends by default.
+            // The XML body is always buffered and non-empty, as empty batches return early.
             .mutate_request(|http_request| {
                 if let Some(bytes) = http_request.body().bytes() {
                     let md5 = md5::compute(bytes);
@@ -412,6 +674,306 @@ impl S3Client {
     }
 }
 
+#[async_trait]
+impl ObjectStore for S3Client {
+    async fn put_file_at(
+        &self,
+        file: &TempFile,
+        key: &S3Key,
+        metadata: HashMap<String, String>,
+    ) -> ZResult<()> {
+        self.put_file(
+            key,
+            &S3Bucket::Storage,
+            file.file.path(),
+            file.content_type.as_ref(),
+            metadata,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_storage_bucket(&self) -> ZResult<()> {
+        self.create_bucket(S3Bucket::Storage, true, false).await?;
+        Ok(())
+    }
+
+    async fn delete_prefix(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<usize> {
+        if prefix.is_empty() {
+            return Err(ZError::from(
+                "Refusing to delete an empty prefix, which would match the whole bucket",
+            ));
+        }
+
+        let mut deleted = 0;
+        let mut continuation_token = None;
+
+        loop {
+            // Listing pages are capped at the delete_objects batch limit, so each page can be
+            // deleted with a single request.
+            let response = self
+                .client
+                .list_objects_v2()
+                .bucket(bucket.as_str())
+                .prefix(prefix)
+                .max_keys(MAX_KEYS_PER_REQUEST)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await?;
+
+            let objects = response.contents().to_vec();
+            if !objects.is_empty() {
+                let output = self.delete_objects(objects, bucket).await?;
+                deleted += output.deleted().len();
+
+                for error in output.errors() {
+                    tracing::error!(
+                        "Error deleting object from S3: key={:?}, code={:?}, message={:?}",
+                        error.key,
+                        error.code,
+                        error.message
+                    );
+                }
+            }
+
+            match response.next_continuation_token() {
+                Some(token) if response.is_truncated().unwrap_or_default() => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        tracing::debug!("Deleted {} objects under prefix '{}'.", deleted, prefix);
+        Ok(deleted)
+    }
+
+    async fn delete_keys(&self, keys: &[String], bucket: &S3Bucket) -> ZResult<Vec<String>> {
+        for key in keys {
+            validate_key(key)?;
+        }
+
+        let mut deleted = vec![];
+
+        for batch in keys.chunks(MAX_KEYS_PER_REQUEST as usize) {
+            let objects = batch
+                .iter()
+                .map(|key| Object::builder().key(key).build())
+                .collect();
+            let output = self.delete_objects(objects, bucket).await?;
+
+            deleted.extend(
+                output
+                    .deleted()
+                    .iter()
+                    .filter_map(|object| object.key().map(str::to_string)),
+            );
+
+            for error in output.errors() {
+                tracing::error!(
+                    "Error deleting object from S3: key={:?}, code={:?}, message={:?}",
+                    error.key,
+                    error.code,
+                    error.message
+                );
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    async fn list_files(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<Vec<S3ObjectInfo>> {
+        let mut files = vec![];
+        let mut continuation_token = None;
+
+        loop {
+            let response = self
+                .client
+                .list_objects_v2()
+                .bucket(bucket.as_str())
+                .prefix(prefix)
+                .max_keys(MAX_KEYS_PER_REQUEST)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await?;
+
+            files.extend(response.contents().iter().map(S3ObjectInfo::from));
+
+            if files.len() >= MAX_LISTED_FILES {
+                tracing::warn!(
+                    "Listing of prefix '{}' truncated to {} objects.",
+                    prefix,
+                    MAX_LISTED_FILES
+                );
+                files.truncate(MAX_LISTED_FILES);
+                break;
+            }
+
+            match response.next_continuation_token() {
+                Some(token) if response.is_truncated().unwrap_or_default() => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(files)
+    }
+
+    async fn get_file(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        range: Option<ByteRange>,
+    ) -> ZResult<FileResponse> {
+        self.retrieve_file(key, bucket, range).await
+    }
+
+    async fn retrieve_storage_file(&self, path: &str) -> ZResult<FileResponse> {
+        self.retrieve_file(path, &S3Bucket::Storage, None).await
+    }
+
+    async fn get_file_url(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        expires: Duration,
+        content_disposition: Option<&str>,
+    ) -> ZResult<String> {
+        let presigned_request = self
+            .get_object_presigned(key, bucket, expires, content_disposition)
+            .await?;
+        Ok(presigned_request.uri().to_string())
+    }
+
+    async fn presign_put(
+        &self,
+        key: &S3Key,
+        bucket: &S3Bucket,
+        content_type: &str,
+        content_length: i64,
+        expires: Duration,
+    ) -> ZResult<String> {
+        let presigning_config = PresigningConfig::builder()
+            .expires_in(expires)
+            .build()
+            .map_err(|err| ZError::from(format!("Invalid presigning configuration: {err}")))?;
+
+        let presigned_request = self
+            .client
+            .put_object()
+            .bucket(bucket.as_str())
+            .key(key.0.as_str())
+            .content_type(content_type)
+            .content_length(content_length)
+            .presigned(presigning_config)
+            .await?;
+
+        Ok(presigned_request.uri().to_string())
+    }
+
+    async fn copy_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()> {
+        let bucket = S3Bucket::Storage;
+        let copy_source = format!(
+            "{}/{}",
+            bucket.as_str(),
+            utf8_percent_encode(&src.0, KEY_PATH_ENCODE_SET)
+        );
+
+        let result = self
+            .client
+            .copy_object()
+            .bucket(bucket.as_str())
+            .copy_source(copy_source)
+            .key(dst.0.as_str())
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => match err.code() {
+                Some("NoSuchKey") | Some("NotFound") => Err(ZError::from(format!(
+                    "Cannot copy '{src}' to '{dst}': source object does not exist"
+                ))),
+                _ => Err(ZError::from(format!(
+                    "Error copying '{src}' to '{dst}' in S3: {}",
+                    err.into_service_error()
+                ))),
+            },
+        }
+    }
+
+    async fn move_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()> {
+        let bucket = S3Bucket::Storage;
+        self.copy_file(src, dst).await?;
+
+        let result = self
+            .client
+            .delete_object()
+            .bucket(bucket.as_str())
+            .key(src.0.as_str())
+            .send()
+            .await;
+
+        if let Err(err) = result {
+            if let Err(rollback_err) = self
+                .client
+                .delete_object()
+                .bucket(bucket.as_str())
+                .key(dst.0.as_str())
+                .send()
+                .await
+            {
+                tracing::error!(
+                    "Error removing '{}' while rolling back its move from '{}': {}",
+                    dst,
+                    src,
+                    rollback_err.into_service_error()
+                );
+            }
+
+            return Err(ZError::from(format!(
+                "Error moving '{src}' to '{dst}' in S3: {}",
+                err.into_service_error()
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn object_exists(&self, key: &str, bucket: &S3Bucket) -> ZResult<bool> {
+        Ok(self.head_object(key, bucket).await?.is_some())
+    }
+
+    async fn get_file_size(&self, key: &str, bucket: &S3Bucket) -> ZResult<Option<i64>> {
+        Ok(self
+            .head_object(key, bucket)
+            .await?
+            .map(|head| head.content_length().unwrap_or_default()))
+    }
+
+    async fn get_file_metadata(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+    ) -> ZResult<Option<HashMap<String, String>>> {
+        Ok(self
+            .head_object(key, bucket)
+            .await?
+            .map(|head| decode_metadata(head.metadata())))
+    }
+
+    async fn head_bucket(&self, bucket: &S3Bucket) -> ZResult<()> {
+        self.client
+            .head_bucket()
+            .bucket(bucket.as_str())
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct FileResponse {
     pub body: aws_sdk_s3::primitives::ByteStream,
@@ -419,4 +981,65 @@ pub struct FileResponse {
     pub content_length: Option<String>,
     pub content_disposition: Option<String>,
     pub last_modified: Option<String>,
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct S3ObjectInfo {
+    pub key: String,
+    pub size: i64,
+    #[serde(with = "rfc3339_millis::option")]
+    pub last_modified: Option<DateTime<Utc>>,
+    pub etag: Option<String>,
+    /// User metadata of the object, which listings do not return and must be fetched separately.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl From<&Object> for S3ObjectInfo {
+    fn from(object: &Object) -> Self {
+        S3ObjectInfo {
+            key: object.key().unwrap_or_default().to_string(),
+            size: object.size().unwrap_or_default(),
+            last_modified: object
+                .last_modified()
+                .and_then(|dt| DateTime::from_timestamp(dt.secs(), dt.subsec_nanos())),
+            etag: object
+                .e_tag()
+                .map(|etag| etag.trim_matches('"').to_string()),
+            metadata: None,
+        }
+    }
+}
+
+fn encode_metadata(metadata: HashMap<String, String>) -> HashMap<String, String> {
+    metadata
+        .into_iter()
+        .map(|(name, value)| {
+            let value = utf8_percent_encode(&value, METADATA_ENCODE_SET).to_string();
+            (name, value)
+        })
+        .collect()
+}
+
+fn decode_metadata(metadata: Option<&HashMap<String, String>>) -> HashMap<String, String> {
+    metadata
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| {
+            let value = percent_decode_str(value).decode_utf8_lossy().into_owned();
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// Splits `size` bytes into consecutive `(offset, length)` parts of `part_size` bytes, the last
+/// one holding the remainder.
+pub(super) fn part_ranges(size: u64, part_size: u64) -> Vec<(u64, u64)> {
+    (0..size.div_ceil(part_size))
+        .map(|index| {
+            let offset = index * part_size;
+            (offset, part_size.min(size - offset))
+        })
+        .collect()
 }
@@ -0,0 +1,115 @@
+use std::{collections::HashMap, time::Duration};
+
+use actix_multipart::form::tempfile::TempFile;
+use async_trait::async_trait;
+
+use crate::{
+    common::zresult::{ZError, ZResult},
+    db::s3::{
+        ByteRange, ObjectStore, S3Bucket, S3Key,
+        client::{FileResponse, S3ObjectInfo},
+        store::StorageUnavailable,
+    },
+};
+
+/// [ObjectStore] of deployments running with `STORAGE_MODE=disabled`, failing every operation
+/// with a [StorageUnavailable] so that endpoints needing storage answer with a 503.
+pub struct DisabledObjectStore;
+
+fn disabled<T>(bucket: &S3Bucket) -> ZResult<T> {
+    Err(ZError::from(StorageUnavailable {
+        bucket: bucket.as_str(),
+        reason: "storage is disabled".to_string(),
+    }))
+}
+
+#[async_trait]
+impl ObjectStore for DisabledObjectStore {
+    async fn put_file_at(
+        &self,
+        _file: &TempFile,
+        _key: &S3Key,
+        _metadata: HashMap<String, String>,
+    ) -> ZResult<()> {
+        disabled(&S3Bucket::Storage)
+    }
+
+    async fn create_storage_bucket(&self) -> ZResult<()> {
+        disabled(&S3Bucket::Storage)
+    }
+
+    async fn delete_prefix(&self, _prefix: &str, bucket: &S3Bucket) -> ZResult<usize> {
+        disabled(bucket)
+    }
+
+    async fn delete_keys(&self, _keys: &[String], bucket: &S3Bucket) -> ZResult<Vec<String>> {
+        disabled(bucket)
+    }
+
+    async fn list_files(&self, _prefix: &str, bucket: &S3Bucket) -> ZResult<Vec<S3ObjectInfo>> {
+        disabled(bucket)
+    }
+
+    async fn get_file(
+        &self,
+        _key: &str,
+        bucket: &S3Bucket,
+        _range: Option<ByteRange>,
+    ) -> ZResult<FileResponse> {
+        disabled(bucket)
+    }
+
+    async fn retrieve_storage_file(&self, _path: &str) -> ZResult<FileResponse> {
+        disabled(&S3Bucket::Storage)
+    }
+
+    async fn get_file_url(
+        &self,
+        _key: &str,
+        bucket: &S3Bucket,
+        _expires: Duration,
+        _content_disposition: Option<&str>,
+    ) -> ZResult<String> {
+        disabled(bucket)
+    }
+
+    async fn presign_put(
+        &self,
+        _key: &S3Key,
+        bucket: &S3Bucket,
+        _content_type: &str,
+        _content_length: i64,
+        _expires: Duration,
+    ) -> ZResult<String> {
+        disabled(bucket)
+    }
+
+    async fn copy_file(&self, _src: &S3Key, _dst: &S3Key) -> ZResult<()> {
+        disabled(&S3Bucket::Storage)
+    }
+
+    async fn move_file(&self, _src: &S3Key, _dst: &S3Key) -> ZResult<()> {
+        disabled(&S3Bucket::Storage)
+    }
+
+    async fn object_exists(&self, _key: &str, bucket: &S3Bucket) -> ZResult<bool> {
+        disabled(bucket)
+    }
+
+    async fn get_file_size(&self, _key: &str, bucket: &S3Bucket) -> ZResult<Option<i64>> {
+        disabled(bucket)
+    }
+
+    async fn get_file_metadata(
+        &self,
+        _key: &str,
+        bucket: &S3Bucket,
+    ) -> ZResult<Option<HashMap<String, String>>> {
+        disabled(bucket)
+    }
+
+    /// Succeeds, as a deployment without storage does not wait for it to become ready.
+    async fn head_bucket(&self, _bucket: &S3Bucket) -> ZResult<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,433 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_multipart::form::tempfile::TempFile;
+use async_trait::async_trait;
+
+use crate::{
+    common::zresult::ZResult,
+    db::s3::{
+        ByteRange, ObjectStore, S3Bucket, S3Key,
+        client::{FileResponse, S3ObjectInfo},
+    },
+};
+
+/// Upper bounds, in seconds, of the buckets of the operation duration histogram.
+const DURATION_BUCKETS: [f64; 10] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Kind of object store operation, used to label metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StorageOperation {
+    Put,
+    Get,
+    Delete,
+    Presign,
+    Copy,
+    Head,
+    List,
+    CreateBucket,
+}
+
+impl StorageOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageOperation::Put => "put",
+            StorageOperation::Get => "get",
+            StorageOperation::Delete => "delete",
+            StorageOperation::Presign => "presign",
+            StorageOperation::Copy => "copy",
+            StorageOperation::Head => "head",
+            StorageOperation::List => "list",
+            StorageOperation::CreateBucket => "create_bucket",
+        }
+    }
+}
+
+/// Outcome of an object store operation, used to label metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    Success,
+    Error,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Error => "error",
+        }
+    }
+}
+
+/// Aggregated measurements of one operation with one outcome.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperationStats {
+    pub count: u64,
+    pub bytes: u64,
+    pub duration_seconds_sum: f64,
+    /// Number of operations that took at most each of [DURATION_BUCKETS], cumulatively.
+    pub duration_buckets: [u64; DURATION_BUCKETS.len()],
+}
+
+/// In-process registry of object store metrics, rendered in the Prometheus text format.
+#[derive(Debug, Default)]
+pub struct StorageMetrics {
+    operations: Mutex<BTreeMap<(StorageOperation, Outcome), OperationStats>>,
+}
+
+impl StorageMetrics {
+    pub fn record(
+        &self,
+        operation: StorageOperation,
+        outcome: Outcome,
+        duration: Duration,
+        bytes: u64,
+    ) {
+        let seconds = duration.as_secs_f64();
+        let mut operations = self.operations.lock().unwrap();
+        let stats = operations.entry((operation, outcome)).or_default();
+
+        stats.count += 1;
+        stats.bytes += bytes;
+        stats.duration_seconds_sum += seconds;
+        for (bucket, bound) in stats.duration_buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+    }
+
+    /// Returns the measurements of `operation` with `outcome`, if it ever happened.
+    pub fn stats(&self, operation: StorageOperation, outcome: Outcome) -> Option<OperationStats> {
+        self.operations
+            .lock()
+            .unwrap()
+            .get(&(operation, outcome))
+            .cloned()
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let operations = self.operations.lock().unwrap();
+        let mut output = String::new();
+
+        output.push_str(
+            "# HELP storage_operations_total Object store operations by operation and outcome.\n\
+             # TYPE storage_operations_total counter\n",
+        );
+        for ((operation, outcome), stats) in operations.iter() {
+            let _ = writeln!(
+                output,
+                "storage_operations_total{{operation=\"{}\",outcome=\"{}\"}} {}",
+                operation.as_str(),
+                outcome.as_str(),
+                stats.count
+            );
+        }
+
+        output.push_str(
+            "# HELP storage_bytes_total Bytes transferred by object store operations.\n\
+             # TYPE storage_bytes_total counter\n",
+        );
+        for ((operation, outcome), stats) in operations.iter() {
+            let _ = writeln!(
+                output,
+                "storage_bytes_total{{operation=\"{}\",outcome=\"{}\"}} {}",
+                operation.as_str(),
+                outcome.as_str(),
+                stats.bytes
+            );
+        }
+
+        output.push_str(
+            "# HELP storage_operation_duration_seconds Duration of object store operations.\n\
+             # TYPE storage_operation_duration_seconds histogram\n",
+        );
+        for ((operation, outcome), stats) in operations.iter() {
+            let labels = format!(
+                "operation=\"{}\",outcome=\"{}\"",
+                operation.as_str(),
+                outcome.as_str()
+            );
+            for (count, bound) in stats.duration_buckets.iter().zip(DURATION_BUCKETS) {
+                let _ = writeln!(
+                    output,
+                    "storage_operation_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                output,
+                "storage_operation_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                stats.count
+            );
+            let _ = writeln!(
+                output,
+                "storage_operation_duration_seconds_sum{{{labels}}} {}",
+                stats.duration_seconds_sum
+            );
+            let _ = writeln!(
+                output,
+                "storage_operation_duration_seconds_count{{{labels}}} {}",
+                stats.count
+            );
+        }
+
+        output
+    }
+}
+
+/// Returns the directory part of `key`, which identifies the publication an object belongs to
+/// without exposing its file name.
+fn key_prefix(key: &str) -> &str {
+    key.rfind('/').map_or("", |index| &key[..=index])
+}
+
+/// [ObjectStore] recording the duration, byte count and outcome of every operation of the
+/// wrapped store into [StorageMetrics], and warning about operations slower than a threshold.
+pub struct InstrumentedObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    metrics: Arc<StorageMetrics>,
+    slow_threshold: Duration,
+}
+
+impl InstrumentedObjectStore {
+    pub fn new(
+        inner: Arc<dyn ObjectStore>,
+        metrics: Arc<StorageMetrics>,
+        slow_threshold: Duration,
+    ) -> Self {
+        InstrumentedObjectStore {
+            inner,
+            metrics,
+            slow_threshold,
+        }
+    }
+
+    /// Runs `operation`, recording it under `kind`. `key` is only logged through its prefix, and
+    /// `bytes` extracts the number of transferred bytes from a successful result.
+    async fn observe<T>(
+        &self,
+        kind: StorageOperation,
+        key: &str,
+        bytes: impl FnOnce(&T) -> u64,
+        operation: impl Future<Output = ZResult<T>>,
+    ) -> ZResult<T> {
+        let started = Instant::now();
+        let result = operation.await;
+        let elapsed = started.elapsed();
+
+        let (outcome, transferred) = match &result {
+            Ok(value) => (Outcome::Success, bytes(value)),
+            Err(_) => (Outcome::Error, 0),
+        };
+        self.metrics.record(kind, outcome, elapsed, transferred);
+
+        if elapsed > self.slow_threshold {
+            tracing::warn!(
+                "Slow storage operation: {} under '{}' took {}ms ({})",
+                kind.as_str(),
+                key_prefix(key),
+                elapsed.as_millis(),
+                outcome.as_str()
+            );
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl ObjectStore for InstrumentedObjectStore {
+    async fn put_file_at(
+        &self,
+        file: &TempFile,
+        key: &S3Key,
+        metadata: HashMap<String, String>,
+    ) -> ZResult<()> {
+        self.observe(
+            StorageOperation::Put,
+            &key.0,
+            |_| file.size as u64,
+            self.inner.put_file_at(file, key, metadata),
+        )
+        .await
+    }
+
+    async fn create_storage_bucket(&self) -> ZResult<()> {
+        self.observe(
+            StorageOperation::CreateBucket,
+            "",
+            |_| 0,
+            self.inner.create_storage_bucket(),
+        )
+        .await
+    }
+
+    async fn delete_prefix(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<usize> {
+        self.observe(
+            StorageOperation::Delete,
+            prefix,
+            |_| 0,
+            self.inner.delete_prefix(prefix, bucket),
+        )
+        .await
+    }
+
+    async fn delete_keys(&self, keys: &[String], bucket: &S3Bucket) -> ZResult<Vec<String>> {
+        self.observe(
+            StorageOperation::Delete,
+            keys.first().map(String::as_str).unwrap_or_default(),
+            |_| 0,
+            self.inner.delete_keys(keys, bucket),
+        )
+        .await
+    }
+
+    async fn list_files(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<Vec<S3ObjectInfo>> {
+        self.observe(
+            StorageOperation::List,
+            prefix,
+            |_| 0,
+            self.inner.list_files(prefix, bucket),
+        )
+        .await
+    }
+
+    async fn get_file(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        range: Option<ByteRange>,
+    ) -> ZResult<FileResponse> {
+        self.observe(
+            StorageOperation::Get,
+            key,
+            response_bytes,
+            self.inner.get_file(key, bucket, range),
+        )
+        .await
+    }
+
+    async fn retrieve_storage_file(&self, path: &str) -> ZResult<FileResponse> {
+        self.observe(
+            StorageOperation::Get,
+            path,
+            response_bytes,
+            self.inner.retrieve_storage_file(path),
+        )
+        .await
+    }
+
+    async fn get_file_url(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        expires: Duration,
+        content_disposition: Option<&str>,
+    ) -> ZResult<String> {
+        self.observe(
+            StorageOperation::Presign,
+            key,
+            |_| 0,
+            self.inner
+                .get_file_url(key, bucket, expires, content_disposition),
+        )
+        .await
+    }
+
+    async fn presign_put(
+        &self,
+        key: &S3Key,
+        bucket: &S3Bucket,
+        content_type: &str,
+        content_length: i64,
+        expires: Duration,
+    ) -> ZResult<String> {
+        self.observe(
+            StorageOperation::Presign,
+            &key.0,
+            |_| 0,
+            self.inner
+                .presign_put(key, bucket, content_type, content_length, expires),
+        )
+        .await
+    }
+
+    async fn copy_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()> {
+        self.observe(
+            StorageOperation::Copy,
+            &src.0,
+            |_| 0,
+            self.inner.copy_file(src, dst),
+        )
+        .await
+    }
+
+    async fn move_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()> {
+        self.observe(
+            StorageOperation::Copy,
+            &src.0,
+            |_| 0,
+            self.inner.move_file(src, dst),
+        )
+        .await
+    }
+
+    async fn object_exists(&self, key: &str, bucket: &S3Bucket) -> ZResult<bool> {
+        self.observe(
+            StorageOperation::Head,
+            key,
+            |_| 0,
+            self.inner.object_exists(key, bucket),
+        )
+        .await
+    }
+
+    async fn get_file_size(&self, key: &str, bucket: &S3Bucket) -> ZResult<Option<i64>> {
+        self.observe(
+            StorageOperation::Head,
+            key,
+            |_| 0,
+            self.inner.get_file_size(key, bucket),
+        )
+        .await
+    }
+
+    async fn get_file_metadata(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+    ) -> ZResult<Option<HashMap<String, String>>> {
+        self.observe(
+            StorageOperation::Head,
+            key,
+            |_| 0,
+            self.inner.get_file_metadata(key, bucket),
+        )
+        .await
+    }
+
+    async fn head_bucket(&self, bucket: &S3Bucket) -> ZResult<()> {
+        self.observe(
+            StorageOperation::Head,
+            "",
+            |_| 0,
+            self.inner.head_bucket(bucket),
+        )
+        .await
+    }
+}
+
+/// Bytes served by a retrieval, as announced by storage since the body is streamed afterwards.
+fn response_bytes(response: &FileResponse) -> u64 {
+    response
+        .content_length
+        .as_deref()
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,344 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use actix_multipart::form::tempfile::TempFile;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::{DateTime, Utc};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+
+use crate::{
+    common::zresult::{ZError, ZResult},
+    db::s3::{
+        ByteRange, ObjectStore, S3Bucket, S3Key,
+        client::{FileResponse, S3ObjectInfo},
+        store::NoSuchBucket,
+        validate_key,
+    },
+};
+
+/// Base of the fake presigned URLs handed out by [MockObjectStore].
+const MOCK_URL_BASE: &str = "https://mock-object-store";
+
+#[derive(Debug, Clone)]
+pub struct MockObject {
+    pub bytes: Vec<u8>,
+    pub content_type: Option<String>,
+    pub metadata: HashMap<String, String>,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl MockObject {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        MockObject {
+            bytes: bytes.into(),
+            content_type: None,
+            metadata: HashMap::new(),
+            last_modified: Utc::now(),
+        }
+    }
+
+    fn info(&self, key: &str) -> S3ObjectInfo {
+        S3ObjectInfo {
+            key: key.to_string(),
+            size: self.bytes.len() as i64,
+            last_modified: Some(self.last_modified),
+            etag: Some(format!("{:x}", md5::compute(&self.bytes))),
+            metadata: None,
+        }
+    }
+}
+
+/// In-memory [ObjectStore] standing in for S3 in tests, so that handlers touching storage run
+/// without a MinIO instance. Presigned URLs are fake and cannot be fetched.
+#[derive(Default)]
+pub struct MockObjectStore {
+    objects: Mutex<BTreeMap<(&'static str, String), MockObject>>,
+    unavailable: AtomicBool,
+    failing_copies: AtomicBool,
+    missing_bucket: AtomicBool,
+    failing_bucket_creation: AtomicBool,
+    bucket_creations: AtomicUsize,
+}
+
+impl MockObjectStore {
+    pub fn insert(&self, bucket: S3Bucket, key: &str, object: MockObject) {
+        self.objects
+            .lock()
+            .unwrap()
+            .insert((bucket.as_str(), key.to_string()), object);
+    }
+
+    pub fn get(&self, bucket: S3Bucket, key: &str) -> Option<MockObject> {
+        self.objects
+            .lock()
+            .unwrap()
+            .get(&(bucket.as_str(), key.to_string()))
+            .cloned()
+    }
+
+    /// Returns the keys of every object in `bucket`, in lexicographic order.
+    pub fn keys(&self, bucket: S3Bucket) -> Vec<String> {
+        self.objects
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|(object_bucket, _)| *object_bucket == bucket.as_str())
+            .map(|(_, key)| key.clone())
+            .collect()
+    }
+
+    /// Makes bucket checks fail, as they would with an unreachable S3 endpoint.
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.unavailable.store(unavailable, Ordering::SeqCst);
+    }
+
+    /// Makes copies, and therefore moves, fail without touching any object.
+    pub fn set_failing_copies(&self, failing: bool) {
+        self.failing_copies.store(failing, Ordering::SeqCst);
+    }
+
+    /// Makes uploads fail as they would once the storage bucket has been deleted, until
+    /// [ObjectStore::create_storage_bucket] recreates it.
+    pub fn set_missing_bucket(&self, missing: bool) {
+        self.missing_bucket.store(missing, Ordering::SeqCst);
+    }
+
+    /// Makes [ObjectStore::create_storage_bucket] fail, leaving a missing bucket missing.
+    pub fn set_failing_bucket_creation(&self, failing: bool) {
+        self.failing_bucket_creation
+            .store(failing, Ordering::SeqCst);
+    }
+
+    /// Returns how many times the storage bucket was asked to be created.
+    pub fn bucket_creations(&self) -> usize {
+        self.bucket_creations.load(Ordering::SeqCst)
+    }
+
+    fn presigned_url(key: &str, bucket: &S3Bucket, method: &str, expires: Duration) -> String {
+        format!(
+            "{}/{}/{}?method={}&expires_in={}",
+            MOCK_URL_BASE,
+            bucket.as_str(),
+            key,
+            method,
+            expires.as_secs()
+        )
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MockObjectStore {
+    async fn put_file_at(
+        &self,
+        file: &TempFile,
+        key: &S3Key,
+        metadata: HashMap<String, String>,
+    ) -> ZResult<()> {
+        if self.missing_bucket.load(Ordering::SeqCst) {
+            return Err(ZError::from(NoSuchBucket(S3Bucket::Storage.as_str())));
+        }
+
+        let object = MockObject {
+            bytes: std::fs::read(file.file.path())?,
+            content_type: file.content_type.as_ref().map(|mime| mime.to_string()),
+            metadata,
+            last_modified: Utc::now(),
+        };
+        self.insert(S3Bucket::Storage, &key.0, object);
+
+        Ok(())
+    }
+
+    async fn create_storage_bucket(&self) -> ZResult<()> {
+        self.bucket_creations.fetch_add(1, Ordering::SeqCst);
+        if self.failing_bucket_creation.load(Ordering::SeqCst) {
+            return Err(ZError::from("Bucket creation is failing"));
+        }
+
+        self.missing_bucket.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn delete_prefix(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<usize> {
+        if prefix.is_empty() {
+            return Err(ZError::from(
+                "Refusing to delete an empty prefix, which would match the whole bucket",
+            ));
+        }
+
+        let mut objects = self.objects.lock().unwrap();
+        let count = objects.len();
+        objects.retain(|(object_bucket, key), _| {
+            *object_bucket != bucket.as_str() || !key.starts_with(prefix)
+        });
+
+        Ok(count - objects.len())
+    }
+
+    async fn delete_keys(&self, keys: &[String], bucket: &S3Bucket) -> ZResult<Vec<String>> {
+        for key in keys {
+            validate_key(key)?;
+        }
+
+        let mut objects = self.objects.lock().unwrap();
+
+        Ok(keys
+            .iter()
+            .filter(|key| {
+                objects
+                    .remove(&(bucket.as_str(), key.to_string()))
+                    .is_some()
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn list_files(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<Vec<S3ObjectInfo>> {
+        Ok(self
+            .objects
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((object_bucket, key), _)| {
+                *object_bucket == bucket.as_str() && key.starts_with(prefix)
+            })
+            .map(|((_, key), object)| object.info(key))
+            .collect())
+    }
+
+    async fn get_file(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        range: Option<ByteRange>,
+    ) -> ZResult<FileResponse> {
+        let mut object = self.get(*bucket, key).ok_or_else(|| {
+            ZError::from(format!("Error retrieving file from S3: NoSuchKey '{key}'"))
+        })?;
+
+        if let Some(range) = range {
+            let size = object.bytes.len() as u64;
+            if range.start > range.end || range.start >= size {
+                return Err(ZError::from(format!(
+                    "Error retrieving file from S3: InvalidRange '{key}'"
+                )));
+            }
+            let end = range.end.min(size - 1);
+            object.bytes = object.bytes[range.start as usize..=end as usize].to_vec();
+        }
+
+        Ok(FileResponse {
+            content_type: object.content_type,
+            content_length: Some(object.bytes.len().to_string()),
+            content_disposition: None,
+            last_modified: Some(object.last_modified.to_string()),
+            metadata: object.metadata,
+            body: ByteStream::from(object.bytes),
+        })
+    }
+
+    async fn retrieve_storage_file(&self, path: &str) -> ZResult<FileResponse> {
+        self.get_file(path, &S3Bucket::Storage, None).await
+    }
+
+    async fn get_file_url(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        expires: Duration,
+        content_disposition: Option<&str>,
+    ) -> ZResult<String> {
+        let url = Self::presigned_url(key, bucket, "GET", expires);
+
+        Ok(match content_disposition {
+            Some(content_disposition) => format!(
+                "{}&response-content-disposition={}",
+                url,
+                utf8_percent_encode(content_disposition, NON_ALPHANUMERIC)
+            ),
+            None => url,
+        })
+    }
+
+    async fn presign_put(
+        &self,
+        key: &S3Key,
+        bucket: &S3Bucket,
+        _content_type: &str,
+        _content_length: i64,
+        expires: Duration,
+    ) -> ZResult<String> {
+        Ok(Self::presigned_url(&key.0, bucket, "PUT", expires))
+    }
+
+    async fn copy_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()> {
+        if self.failing_copies.load(Ordering::SeqCst) {
+            return Err(ZError::from(format!(
+                "Error copying '{src}' to '{dst}' in S3: copies are failing"
+            )));
+        }
+
+        let object = self.get(S3Bucket::Storage, &src.0).ok_or_else(|| {
+            ZError::from(format!(
+                "Cannot copy '{src}' to '{dst}': source object does not exist"
+            ))
+        })?;
+
+        self.insert(
+            S3Bucket::Storage,
+            &dst.0,
+            MockObject {
+                last_modified: Utc::now(),
+                ..object
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn move_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()> {
+        self.copy_file(src, dst).await?;
+        self.objects
+            .lock()
+            .unwrap()
+            .remove(&(S3Bucket::Storage.as_str(), src.0.clone()));
+
+        Ok(())
+    }
+
+    async fn object_exists(&self, key: &str, bucket: &S3Bucket) -> ZResult<bool> {
+        Ok(self.get(*bucket, key).is_some())
+    }
+
+    async fn get_file_size(&self, key: &str, bucket: &S3Bucket) -> ZResult<Option<i64>> {
+        Ok(self
+            .get(*bucket, key)
+            .map(|object| object.bytes.len() as i64))
+    }
+
+    async fn get_file_metadata(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+    ) -> ZResult<Option<HashMap<String, String>>> {
+        Ok(self.get(*bucket, key).map(|object| object.metadata))
+    }
+
+    async fn head_bucket(&self, bucket: &S3Bucket) -> ZResult<()> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(ZError::from(format!(
+                "Bucket '{}' is unreachable",
+                bucket.as_str()
+            )));
+        }
+
+        Ok(())
+    }
+}
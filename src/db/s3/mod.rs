@@ -1,18 +1,35 @@
 pub mod client;
+pub mod disabled;
+pub mod metrics;
+#[cfg(test)]
+pub mod mock;
+pub mod store;
 
-use crate::db::s3::client::S3Client;
+pub use store::ObjectStore;
+
+use std::time::Duration;
+
+use crate::{
+    common::zresult::ZResult,
+    db::sql::models::{Publication, PublicationFile},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum S3Bucket {
+    /// Private files, such as publication PDFs, only reachable through presigned URLs
     Storage,
+    /// Publicly readable assets, such as cover images and avatars, served from stable URLs
+    Public,
 }
 
 impl S3Bucket {
     pub const STORAGE: &'static str = "storage";
+    pub const PUBLIC: &'static str = "public";
 
     pub fn as_str(&self) -> &'static str {
         match self {
             S3Bucket::Storage => Self::STORAGE,
+            S3Bucket::Public => Self::PUBLIC,
         }
     }
 }
@@ -23,8 +40,73 @@ impl From<S3Bucket> for &'static str {
     }
 }
 
-pub trait S3Contents {
-    fn load_s3_contents(&self, s3_client: &S3Client) -> impl Future<Output = Self>;
+/// Inclusive range of bytes of a stored object, as in an HTTP `Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Number of bytes in the range, which is never empty.
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Formats the range as the value of a `Range` request header.
+    pub fn header_value(&self) -> String {
+        format!("bytes={}-{}", self.start, self.end)
+    }
+}
+
+/// Records whose stored objects can be exposed to clients through presigned URLs.
+pub trait S3Contents: Sized {
+    /// Returns a copy of the record with presigned URLs, valid for `expires`, for each of its
+    /// stored objects. URL fields of objects without a key are left as `None`.
+    fn load_s3_contents(
+        &self,
+        object_store: &dyn ObjectStore,
+        expires: Duration,
+    ) -> impl Future<Output = ZResult<Self>>;
+}
+
+impl S3Contents for Publication {
+    async fn load_s3_contents(
+        &self,
+        object_store: &dyn ObjectStore,
+        expires: Duration,
+    ) -> ZResult<Self> {
+        let file_url = match self.s3key.as_deref() {
+            Some(s3key) if !s3key.is_empty() => Some(
+                object_store
+                    .get_file_url(s3key, &S3Bucket::Storage, expires, None)
+                    .await?,
+            ),
+            _ => None,
+        };
+
+        Ok(Publication {
+            file_url,
+            ..self.clone()
+        })
+    }
+}
+
+impl S3Contents for PublicationFile {
+    async fn load_s3_contents(
+        &self,
+        object_store: &dyn ObjectStore,
+        expires: Duration,
+    ) -> ZResult<Self> {
+        let file_url = object_store
+            .get_file_url(&self.s3key, &S3Bucket::Storage, expires, None)
+            .await?;
+
+        Ok(PublicationFile {
+            file_url: Some(file_url),
+            ..self.clone()
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -41,3 +123,28 @@ impl std::fmt::Display for S3Key {
         write!(f, "{}", self.0)
     }
 }
+
+/// Key rejected before reaching S3, as it could address objects other than the intended one.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidKey {
+    #[error("S3 key is empty")]
+    Empty,
+    #[error("S3 key '{0}' contains a '..' segment")]
+    ParentSegment(String),
+}
+
+/// Checks that `key` names a single object: it must not be empty nor contain `..` segments,
+/// which some S3-compatible stores resolve like paths.
+pub fn validate_key(key: &str) -> Result<(), InvalidKey> {
+    if key.is_empty() {
+        return Err(InvalidKey::Empty);
+    }
+    if key.split('/').any(|segment| segment == "..") {
+        return Err(InvalidKey::ParentSegment(key.to_string()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
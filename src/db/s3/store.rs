@@ -0,0 +1,158 @@
+use std::{collections::HashMap, time::Duration};
+
+use actix_multipart::form::tempfile::TempFile;
+use async_trait::async_trait;
+
+use crate::{
+    common::zresult::{ZError, ZResult},
+    db::s3::{
+        ByteRange, S3Bucket, S3Key,
+        client::{FileResponse, S3ObjectInfo},
+    },
+};
+
+/// Returned by [ObjectStore::put_file_at] when the bucket written to does not exist.
+#[derive(Debug, thiserror::Error)]
+#[error("Bucket '{0}' does not exist")]
+pub struct NoSuchBucket(pub &'static str);
+
+/// Returned by [ObjectStore::store_file_at] when the storage bucket is missing and could not be
+/// recreated, so that handlers can answer with a 503 rather than a 500.
+#[derive(Debug, thiserror::Error)]
+#[error("Storage bucket '{bucket}' is unavailable: {reason}")]
+pub struct StorageUnavailable {
+    pub bucket: &'static str,
+    pub reason: String,
+}
+
+/// Object storage operations used by the API. [crate::db::s3::client::S3Client] implements them
+/// against S3, while tests use an in-memory store.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Uploads `file` to the storage bucket under exactly `key` with the given user metadata,
+    /// returning the key the object was stored under. Callers are responsible for building a
+    /// safe key, see [crate::common::filename::sanitize_filename].
+    ///
+    /// If the bucket has disappeared since startup, it is recreated once and the upload retried.
+    /// When that fails too, the error is a [StorageUnavailable].
+    async fn store_file_at(
+        &self,
+        file: &TempFile,
+        key: S3Key,
+        metadata: HashMap<String, String>,
+    ) -> ZResult<S3Key> {
+        let err = match self.put_file_at(file, &key, metadata.clone()).await {
+            Ok(()) => return Ok(key),
+            Err(err) => err,
+        };
+        let Some(NoSuchBucket(bucket)) = err.downcast_ref::<NoSuchBucket>() else {
+            return Err(err);
+        };
+
+        tracing::warn!("Bucket '{}' is missing, attempting to recreate it", bucket);
+        if let Err(err) = self.create_storage_bucket().await {
+            return Err(ZError::from(StorageUnavailable {
+                bucket,
+                reason: format!("recreating it failed: {err}"),
+            }));
+        }
+
+        match self.put_file_at(file, &key, metadata).await {
+            Ok(()) => Ok(key),
+            Err(err) if err.is::<NoSuchBucket>() => Err(ZError::from(StorageUnavailable {
+                bucket,
+                reason: "still missing after being recreated".to_string(),
+            })),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Uploads `file` to the storage bucket under `key`, failing with a [NoSuchBucket] if the
+    /// bucket does not exist. Prefer [ObjectStore::store_file_at], which recovers from that.
+    async fn put_file_at(
+        &self,
+        file: &TempFile,
+        key: &S3Key,
+        metadata: HashMap<String, String>,
+    ) -> ZResult<()>;
+
+    /// Creates the storage bucket, succeeding if it already exists.
+    async fn create_storage_bucket(&self) -> ZResult<()>;
+
+    /// Deletes every object whose key starts with `prefix`, however many there are. Returns the
+    /// number of deleted objects.
+    async fn delete_prefix(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<usize>;
+
+    /// Deletes the object under exactly `key`, returning whether it was deleted. Fails with an
+    /// [crate::db::s3::InvalidKey] before contacting storage if the key is not valid.
+    async fn delete_file(&self, key: &S3Key, bucket: &S3Bucket) -> ZResult<bool> {
+        let deleted = self
+            .delete_keys(std::slice::from_ref(&key.0), bucket)
+            .await?;
+        Ok(!deleted.is_empty())
+    }
+
+    /// Deletes the objects stored under exactly `keys`. Returns the keys that were deleted;
+    /// failures are logged. Nothing is deleted if any key fails [crate::db::s3::validate_key].
+    async fn delete_keys(&self, keys: &[String], bucket: &S3Bucket) -> ZResult<Vec<String>>;
+
+    /// Lists the objects whose key starts with `prefix`, up to an implementation defined cap.
+    async fn list_files(&self, prefix: &str, bucket: &S3Bucket) -> ZResult<Vec<S3ObjectInfo>>;
+
+    /// Retrieves the object stored under `key`, or only the bytes within `range` when given.
+    async fn get_file(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        range: Option<ByteRange>,
+    ) -> ZResult<FileResponse>;
+
+    async fn retrieve_storage_file(&self, path: &str) -> ZResult<FileResponse>;
+
+    /// Presigns a GET request for the object stored under `key`, valid for `expires`. When given,
+    /// `content_disposition` overrides the `Content-Disposition` header of the response.
+    async fn get_file_url(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+        expires: Duration,
+        content_disposition: Option<&str>,
+    ) -> ZResult<String>;
+
+    /// Presigns a PUT request so that clients can upload an object of exactly `content_length`
+    /// bytes directly to the bucket, without routing the file through the server.
+    async fn presign_put(
+        &self,
+        key: &S3Key,
+        bucket: &S3Bucket,
+        content_type: &str,
+        content_length: i64,
+        expires: Duration,
+    ) -> ZResult<String>;
+
+    /// Copies the object stored under `src` to `dst` within the storage bucket. Fails with a
+    /// dedicated error if there is no object under `src`.
+    async fn copy_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()>;
+
+    /// Moves the object stored under `src` to `dst` within the storage bucket. If the original
+    /// cannot be deleted after copying, the copy is removed so that the object stays under `src`.
+    async fn move_file(&self, src: &S3Key, dst: &S3Key) -> ZResult<()>;
+
+    /// Checks whether an object is stored under `key`.
+    async fn object_exists(&self, key: &str, bucket: &S3Bucket) -> ZResult<bool>;
+
+    /// Returns the size in bytes of the object stored under `key`, or `None` if there is no such
+    /// object.
+    async fn get_file_size(&self, key: &str, bucket: &S3Bucket) -> ZResult<Option<i64>>;
+
+    /// Returns the user metadata of the object stored under `key`, or `None` if there is no such
+    /// object.
+    async fn get_file_metadata(
+        &self,
+        key: &str,
+        bucket: &S3Bucket,
+    ) -> ZResult<Option<HashMap<String, String>>>;
+
+    /// Checks that `bucket` exists and is accessible.
+    async fn head_bucket(&self, bucket: &S3Bucket) -> ZResult<()>;
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod unit_tests {
+    use aws_sdk_s3::config::Credentials;
+
+    use crate::db::s3::{
+        InvalidKey, ObjectStore, S3Bucket, S3Key,
+        client::{S3Client, part_ranges},
+        mock::{MockObject, MockObjectStore},
+        validate_key,
+    };
+
+    async fn create_test_client(region: Option<&str>, endpoint: Option<&str>) -> S3Client {
+        let credentials =
+            Credentials::new("test_access_key", "test_secret_key", None, None, "Test");
+        S3Client::new(
+            credentials,
+            region.map(str::to_string),
+            endpoint.map(str::to_string),
+        )
+        .await
+    }
+
+    #[actix_web::test]
+    async fn test_public_url_with_path_style_endpoint() {
+        let s3_client = create_test_client(None, Some("http://localhost:9000/")).await;
+
+        assert_eq!(
+            s3_client.public_url(&S3Key("avatars/user.png".to_string())),
+            "http://localhost:9000/public/avatars/user.png"
+        );
+        assert_eq!(
+            s3_client.public_url(&S3Key("covers/my cover#1.png".to_string())),
+            "http://localhost:9000/public/covers/my%20cover%231.png"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_public_url_without_endpoint() {
+        let s3_client = create_test_client(Some("eu-west-1"), None).await;
+
+        assert_eq!(
+            s3_client.public_url(&S3Key("covers/cover.png".to_string())),
+            "https://s3.eu-west-1.amazonaws.com/public/covers/cover.png"
+        );
+    }
+
+    #[test]
+    fn test_part_ranges() {
+        assert_eq!(part_ranges(10, 4), vec![(0, 4), (4, 4), (8, 2)]);
+        assert_eq!(part_ranges(8, 4), vec![(0, 4), (4, 4)]);
+        assert_eq!(part_ranges(3, 4), vec![(0, 3)]);
+        let parts = part_ranges(200 * 1024 * 1024 + 1, 16 * 1024 * 1024);
+        assert_eq!(parts.len(), 13);
+        assert_eq!(parts[12], (192 * 1024 * 1024, 8 * 1024 * 1024 + 1));
+    }
+
+    #[test]
+    fn test_validate_key() {
+        assert_eq!(validate_key("publications/id/paper.pdf"), Ok(()));
+        assert_eq!(validate_key("publications/id/paper..v2.pdf"), Ok(()));
+        assert_eq!(validate_key(""), Err(InvalidKey::Empty));
+        for key in ["..", "publications/../secret.pdf", "publications/id/.."] {
+            assert_eq!(
+                validate_key(key),
+                Err(InvalidKey::ParentSegment(key.to_string()))
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_delete_file_rejects_invalid_keys() {
+        let object_store = MockObjectStore::default();
+        object_store.insert(
+            S3Bucket::Storage,
+            "publications/id/paper.pdf",
+            MockObject::new("%PDF"),
+        );
+
+        let err = object_store
+            .delete_file(
+                &S3Key("publications/id/../id/paper.pdf".to_string()),
+                &S3Bucket::Storage,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.is::<InvalidKey>());
+
+        let err = object_store
+            .delete_keys(
+                &["publications/id/paper.pdf".to_string(), String::new()],
+                &S3Bucket::Storage,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<InvalidKey>(), Some(&InvalidKey::Empty));
+        assert!(
+            object_store
+                .get(S3Bucket::Storage, "publications/id/paper.pdf")
+                .is_some()
+        );
+
+        let deleted = object_store
+            .delete_file(
+                &S3Key("publications/id/paper.pdf".to_string()),
+                &S3Bucket::Storage,
+            )
+            .await
+            .unwrap();
+        assert!(deleted);
+        assert!(object_store.keys(S3Bucket::Storage).is_empty());
+    }
+
+    #[test]
+    fn test_bucket_names() {
+        assert_eq!(S3Bucket::Storage.as_str(), "storage");
+        assert_eq!(S3Bucket::Public.as_str(), "public");
+    }
+}
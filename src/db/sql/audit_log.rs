@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+
+use crate::{
+    common::pagination::PageQuery,
+    db::sql::{
+        SqlClient,
+        models::{AuditLogEntry, NewAuditLogEntry},
+    },
+};
+
+#[async_trait]
+pub trait AuditLogOperations {
+    async fn create_audit_log_entry(
+        &self,
+        new_entry: &NewAuditLogEntry,
+    ) -> Result<AuditLogEntry, sqlx::Error>;
+
+    /// Lists entries, most recent first, optionally only those of `actor` or about an entity of
+    /// `entity_type`, with `entity_id` if given.
+    async fn list_audit_log(
+        &self,
+        actor: Option<&str>,
+        entity_type: Option<&str>,
+        entity_id: Option<&str>,
+        page: PageQuery,
+    ) -> Result<Vec<AuditLogEntry>, sqlx::Error>;
+
+    async fn count_audit_log(
+        &self,
+        actor: Option<&str>,
+        entity_type: Option<&str>,
+        entity_id: Option<&str>,
+    ) -> Result<i64, sqlx::Error>;
+}
+
+#[async_trait]
+impl AuditLogOperations for SqlClient {
+    async fn create_audit_log_entry(
+        &self,
+        new_entry: &NewAuditLogEntry,
+    ) -> Result<AuditLogEntry, sqlx::Error> {
+        sqlx::query_as::<_, AuditLogEntry>(
+            r#"
+            INSERT INTO audit_log (actor, method, path, entity_type, entity_id, status, request_id, impersonator)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, actor, method, path, entity_type, entity_id, status, request_id, impersonator, created_at
+            "#,
+        )
+        .bind(&new_entry.actor)
+        .bind(&new_entry.method)
+        .bind(&new_entry.path)
+        .bind(&new_entry.entity_type)
+        .bind(&new_entry.entity_id)
+        .bind(new_entry.status)
+        .bind(&new_entry.request_id)
+        .bind(&new_entry.impersonator)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn list_audit_log(
+        &self,
+        actor: Option<&str>,
+        entity_type: Option<&str>,
+        entity_id: Option<&str>,
+        page: PageQuery,
+    ) -> Result<Vec<AuditLogEntry>, sqlx::Error> {
+        sqlx::query_as::<_, AuditLogEntry>(
+            r#"
+            SELECT id, actor, method, path, entity_type, entity_id, status, request_id, impersonator,
+                created_at
+            FROM audit_log
+            WHERE ($1::VARCHAR IS NULL OR actor = $1)
+                AND ($2::VARCHAR IS NULL OR entity_type = $2)
+                AND ($3::VARCHAR IS NULL OR entity_id = $3)
+            ORDER BY created_at DESC, id
+            LIMIT $4 OFFSET $5
+            "#,
+        )
+        .bind(actor)
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(page.limit())
+        .bind(page.offset())
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn count_audit_log(
+        &self,
+        actor: Option<&str>,
+        entity_type: Option<&str>,
+        entity_id: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM audit_log
+            WHERE ($1::VARCHAR IS NULL OR actor = $1)
+                AND ($2::VARCHAR IS NULL OR entity_type = $2)
+                AND ($3::VARCHAR IS NULL OR entity_id = $3)
+            "#,
+        )
+        .bind(actor)
+        .bind(entity_type)
+        .bind(entity_id)
+        .fetch_one(&self.db)
+        .await
+    }
+}
@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 use sqlx::postgres::PgQueryResult;
 
-use crate::db::sql::{PrivyId, SqlClient, models::Author};
+use crate::{
+    common::pagination::PageQuery,
+    db::sql::{PrivyId, SqlClient, models::Author},
+};
 
 #[async_trait]
 pub trait AuthorOperations {
@@ -14,17 +17,12 @@ pub trait AuthorOperations {
 
     async fn get_author_by_email(&self, email: &str) -> Result<Author, sqlx::Error>;
 
-    async fn list_authors(
-        &self,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<Author>, sqlx::Error>;
+    async fn list_authors(&self, page: PageQuery) -> Result<(Vec<Author>, i64), sqlx::Error>;
 
     async fn search_authors_by_name(
         &self,
         name_query: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
+        page: PageQuery,
     ) -> Result<Vec<Author>, sqlx::Error>;
 
     async fn update_author(
@@ -89,38 +87,27 @@ impl AuthorOperations for SqlClient {
         .await
     }
 
-    async fn list_authors(
-        &self,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<Author>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
-
-        sqlx::query_as::<_, Author>(
+    async fn list_authors(&self, page: PageQuery) -> Result<(Vec<Author>, i64), sqlx::Error> {
+        let query = sqlx::query(
             r#"
-            SELECT privy_id, name, email, affiliation, created_at, updated_at
+            SELECT privy_id, name, email, affiliation, created_at, updated_at, COUNT(*) OVER() AS total_count
             FROM authors 
             ORDER BY name ASC
             LIMIT $1 OFFSET $2
             "#,
         )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&self.db)
-        .await
+        .bind(page.limit())
+        .bind(page.offset());
+
+        self.fetch_page(query, page.offset(), self.count_authors())
+            .await
     }
 
     async fn search_authors_by_name(
         &self,
         name_query: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
+        page: PageQuery,
     ) -> Result<Vec<Author>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
         let search_pattern = format!("%{}%", name_query);
 
         sqlx::query_as::<_, Author>(
@@ -133,8 +120,8 @@ impl AuthorOperations for SqlClient {
             "#,
         )
         .bind(search_pattern)
-        .bind(limit)
-        .bind(offset)
+        .bind(page.limit())
+        .bind(page.offset())
         .fetch_all(&self.db)
         .await
     }
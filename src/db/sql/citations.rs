@@ -1,9 +1,20 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use sqlx::postgres::PgQueryResult;
 use uuid::Uuid;
 
+use crate::common::pagination::PageQuery;
 use crate::db::sql::{models::Citation, SqlClient};
 
+/// Query counting the citations of a publication, whose plan the tests check to use the index on
+/// `cited_publication_id`. Citations made by deleted publications are not counted.
+pub(super) const COUNT_CITATIONS_TO_PUBLICATION_QUERY: &str = r#"
+    SELECT COUNT(*) FROM citations c
+    INNER JOIN publications p ON p.id = c.citing_publication_id
+    WHERE c.cited_publication_id = $1 AND p.deleted_at IS NULL
+"#;
+
 #[async_trait]
 pub trait CitationOperations {
     async fn create_citation(&self, new_citation: &super::models::NewCitation) -> Result<Citation, sqlx::Error>;
@@ -18,22 +29,19 @@ pub trait CitationOperations {
     
     async fn list_citations(
         &self, 
-        page: Option<i64>, 
-        limit: Option<i64>
-    ) -> Result<Vec<Citation>, sqlx::Error>;
+        page: PageQuery
+    ) -> Result<(Vec<Citation>, i64), sqlx::Error>;
     
     async fn list_citations_from_publication(
         &self,
         citing_publication_id: Uuid,
-        page: Option<i64>,
-        limit: Option<i64>
+        page: PageQuery
     ) -> Result<Vec<Citation>, sqlx::Error>;
     
     async fn list_citations_to_publication(
         &self,
         cited_publication_id: Uuid,
-        page: Option<i64>,
-        limit: Option<i64>
+        page: PageQuery
     ) -> Result<Vec<Citation>, sqlx::Error>;
     
     async fn update_citation(
@@ -49,11 +57,16 @@ pub trait CitationOperations {
         cited_publication_id: Uuid,
     ) -> Result<PgQueryResult, sqlx::Error>;
     
+    /// Counts every citation, as listed by [CitationOperations::list_citations], including those
+    /// made by deleted publications.
     async fn count_citations(&self) -> Result<i64, sqlx::Error>;
     
     async fn count_citations_from_publication(&self, citing_publication_id: Uuid) -> Result<i64, sqlx::Error>;
     
     async fn count_citations_to_publication(&self, cited_publication_id: Uuid) -> Result<i64, sqlx::Error>;
+    
+    /// Counts the citations of each of the publications, those without any being left out.
+    async fn count_citations_to_publications(&self, cited_publication_ids: &[Uuid]) -> Result<HashMap<Uuid, i64>, sqlx::Error>;
 }
 
 #[async_trait]
@@ -105,37 +118,27 @@ impl CitationOperations for SqlClient {
     
     async fn list_citations(
         &self, 
-        page: Option<i64>, 
-        limit: Option<i64>
-    ) -> Result<Vec<Citation>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
-        
-        sqlx::query_as::<_, Citation>(
+        page: PageQuery
+    ) -> Result<(Vec<Citation>, i64), sqlx::Error> {
+        let query = sqlx::query(
             r#"
-            SELECT id, citing_publication_id, cited_publication_id, created_at
+            SELECT id, citing_publication_id, cited_publication_id, created_at, COUNT(*) OVER() AS total_count
             FROM citations 
             ORDER BY created_at DESC
             LIMIT $1 OFFSET $2
             "#,
         )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&self.db)
-        .await
+        .bind(page.limit())
+        .bind(page.offset());
+
+        self.fetch_page(query, page.offset(), self.count_citations()).await
     }
     
     async fn list_citations_from_publication(
         &self,
         citing_publication_id: Uuid,
-        page: Option<i64>,
-        limit: Option<i64>
+        page: PageQuery
     ) -> Result<Vec<Citation>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
-        
         sqlx::query_as::<_, Citation>(
             r#"
             SELECT id, citing_publication_id, cited_publication_id, created_at
@@ -146,8 +149,8 @@ impl CitationOperations for SqlClient {
             "#,
         )
         .bind(citing_publication_id)
-        .bind(limit)
-        .bind(offset)
+        .bind(page.limit())
+        .bind(page.offset())
         .fetch_all(&self.db)
         .await
     }
@@ -155,13 +158,8 @@ impl CitationOperations for SqlClient {
     async fn list_citations_to_publication(
         &self,
         cited_publication_id: Uuid,
-        page: Option<i64>,
-        limit: Option<i64>
+        page: PageQuery
     ) -> Result<Vec<Citation>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
-        
         sqlx::query_as::<_, Citation>(
             r#"
             SELECT id, citing_publication_id, cited_publication_id, created_at
@@ -172,15 +170,15 @@ impl CitationOperations for SqlClient {
             "#,
         )
         .bind(cited_publication_id)
-        .bind(limit)
-        .bind(offset)
+        .bind(page.limit())
+        .bind(page.offset())
         .fetch_all(&self.db)
         .await
     }
     
     async fn update_citation(
         &self,
-        citation_id: Uuid,
+        _citation_id: Uuid,
     ) -> Result<PgQueryResult, sqlx::Error> {
         // Citations have no fields to update, just return empty result
         Ok(PgQueryResult::default())
@@ -219,7 +217,11 @@ impl CitationOperations for SqlClient {
     
     async fn count_citations_from_publication(&self, citing_publication_id: Uuid) -> Result<i64, sqlx::Error> {
         sqlx::query_scalar(
-            "SELECT COUNT(*) FROM citations WHERE citing_publication_id = $1",
+            r#"
+            SELECT COUNT(*) FROM citations c
+            INNER JOIN publications p ON p.id = c.citing_publication_id
+            WHERE c.citing_publication_id = $1 AND p.deleted_at IS NULL
+            "#,
         )
         .bind(citing_publication_id)
         .fetch_one(&self.db)
@@ -227,11 +229,26 @@ impl CitationOperations for SqlClient {
     }
     
     async fn count_citations_to_publication(&self, cited_publication_id: Uuid) -> Result<i64, sqlx::Error> {
-        sqlx::query_scalar(
-            "SELECT COUNT(*) FROM citations WHERE cited_publication_id = $1",
-        )
+        sqlx::query_scalar(COUNT_CITATIONS_TO_PUBLICATION_QUERY)
         .bind(cited_publication_id)
         .fetch_one(&self.db)
         .await
     }
+    
+    async fn count_citations_to_publications(&self, cited_publication_ids: &[Uuid]) -> Result<HashMap<Uuid, i64>, sqlx::Error> {
+        let counts: Vec<(Uuid, i64)> = sqlx::query_as(
+            r#"
+            SELECT c.cited_publication_id, COUNT(*)
+            FROM citations c
+            INNER JOIN publications p ON p.id = c.citing_publication_id
+            WHERE c.cited_publication_id = ANY($1) AND p.deleted_at IS NULL
+            GROUP BY c.cited_publication_id
+            "#,
+        )
+        .bind(cited_publication_ids)
+        .fetch_all(&self.db)
+        .await?;
+        
+        Ok(counts.into_iter().collect())
+    }
 }
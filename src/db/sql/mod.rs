@@ -1,19 +1,34 @@
-use sqlx::PgPool;
+use std::{future::Future, time::Duration};
+
+use sqlx::{
+    ConnectOptions, Executor, FromRow, PgPool, Postgres, Row,
+    postgres::{PgArguments, PgConnectOptions, PgPoolOptions, PgRow},
+    query::Query,
+};
+use tracing::log::LevelFilter;
+
+use crate::config::DatabasePoolConfig;
 
 pub mod models;
 pub use models::*;
 
+pub mod audit_log;
 pub mod authors;
 pub mod citations;
 pub mod publication_authors;
+pub mod publication_files;
 pub mod publications;
 pub mod users;
+pub mod views;
 
+pub use audit_log::AuditLogOperations;
 pub use authors::AuthorOperations;
 pub use citations::CitationOperations;
 pub use publication_authors::PublicationAuthorOperations;
+pub use publication_files::PublicationFileOperations;
 pub use publications::PublicationOperations;
 pub use users::UserOperations;
+pub use views::ViewOperations;
 
 pub struct SqlClient {
     pub db: sqlx::PgPool,
@@ -23,6 +38,75 @@ impl SqlClient {
     pub async fn new(pool: PgPool) -> Self {
         Self { db: pool }
     }
+
+    /// Runs a trivial query to check that the database is reachable.
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.db).await?;
+        Ok(())
+    }
+
+    /// Runs `query`, which selects a page of rows along with `COUNT(*) OVER() AS total_count`,
+    /// returning the rows and the number of rows matching the query.
+    ///
+    /// A page past the last one has no row carrying the total, which is then taken from `count`,
+    /// only awaited in that case.
+    async fn fetch_page<T>(
+        &self,
+        query: Query<'_, Postgres, PgArguments>,
+        offset: i64,
+        count: impl Future<Output = Result<i64, sqlx::Error>>,
+    ) -> Result<(Vec<T>, i64), sqlx::Error>
+    where
+        T: for<'r> FromRow<'r, PgRow>,
+    {
+        let rows = query.fetch_all(&self.db).await?;
+        let total_count = match rows.first() {
+            Some(row) => row.try_get("total_count")?,
+            None if offset > 0 => count.await?,
+            None => 0,
+        };
+        let items = rows.iter().map(T::from_row).collect::<Result<_, _>>()?;
+        Ok((items, total_count))
+    }
+}
+
+/// Connects the pool of `url`, failing with [sqlx::Error::PoolTimedOut] if no connection could
+/// be opened within the acquire timeout.
+pub async fn connect(url: &str, config: &DatabasePoolConfig) -> Result<PgPool, sqlx::Error> {
+    let options: PgConnectOptions = url.parse()?;
+    pool_options(config)
+        .connect_with(connect_options(options, config))
+        .await
+}
+
+/// Returns the options of a pool sized as configured, whose connections apply the statement
+/// timeout.
+pub fn pool_options(config: &DatabasePoolConfig) -> PgPoolOptions {
+    let statement_timeout_ms = config.statement_timeout_ms;
+    PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .after_connect(move |conn, _| {
+            Box::pin(async move {
+                if let Some(timeout) = statement_timeout_ms {
+                    conn.execute(format!("SET statement_timeout = {timeout}").as_str())
+                        .await?;
+                }
+                Ok(())
+            })
+        })
+}
+
+/// Logs statements slower than the configured threshold as warnings, with a summary made of the
+/// first words of their SQL, and the others at debug level.
+pub fn connect_options(options: PgConnectOptions, config: &DatabasePoolConfig) -> PgConnectOptions {
+    options
+        .log_statements(LevelFilter::Debug)
+        .log_slow_statements(
+            LevelFilter::Warn,
+            Duration::from_millis(config.slow_query_ms),
+        )
 }
 
 pub type PrivyId = String;
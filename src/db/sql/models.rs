@@ -1,51 +1,163 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::db::sql::PrivyId;
+use crate::{common::time::rfc3339_millis, db::sql::PrivyId};
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct User {
+    #[schema(value_type = String)]
     pub privy_id: PrivyId,
+    pub is_admin: bool,
+    #[serde(with = "rfc3339_millis")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "rfc3339_millis")]
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// Bytes of files stored by a user, across their publications.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StorageUsage {
+    #[schema(value_type = String)]
+    pub privy_id: PrivyId,
+    pub publication_bytes: i64,
+    pub supplementary_bytes: i64,
+    pub total_bytes: i64,
+}
+
+/// Change made through the API by an authenticated caller.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    /// Privy id of the user, or `internal:<name>` for internal services
+    pub actor: String,
+    pub method: String,
+    pub path: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub status: i16,
+    pub request_id: Option<String>,
+    /// Privy id of the admin who made the request as `actor`, if any
+    pub impersonator: Option<String>,
+    #[serde(with = "rfc3339_millis")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Author {
+    #[schema(value_type = String)]
     pub privy_id: PrivyId,
     pub name: String,
     pub email: Option<String>,
     pub affiliation: Option<String>,
+    #[serde(with = "rfc3339_millis")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "rfc3339_millis")]
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Publication {
     pub id: Uuid,
+    #[schema(value_type = Option<String>)]
     pub user_id: Option<PrivyId>,
     pub title: String,
     pub about: Option<String>,
     pub tags: Vec<String>,
     pub s3key: Option<String>,
+    pub paper_hash: Option<String>,
+    pub file_sha256: Option<String>,
+    pub file_size: Option<i64>,
+    #[serde(with = "rfc3339_millis")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "rfc3339_millis")]
     pub updated_at: DateTime<Utc>,
+    /// Presigned download URL of the file, filled in by [crate::db::s3::S3Contents]
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_url: Option<String>,
+}
+
+/// Publication found by a title search, with its relevance to the query.
+#[derive(Debug, Clone, FromRow)]
+pub struct TitleMatch {
+    #[sqlx(flatten)]
+    pub publication: Publication,
+    /// 2 and above for a title equal to the query, 1 and above for a title starting with it, plus
+    /// the trigram similarity of the title to the query
+    pub score: f32,
+}
+
+/// Publication ranked by the citations it recently received.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct TrendingPublication {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub publication: Publication,
+    /// Citations received within the window
+    pub recent_citations: i64,
+    /// Sum of the recent citations, each weighing less the older it is
+    pub score: f64,
+}
+
+/// Publication on either end of a citation, reduced to its id once deleted so that the citation
+/// still resolves.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum CitedPublication {
+    Publication(Box<Publication>),
+    Deleted(DeletedPublication),
+}
+
+/// Tombstone of a deleted publication, serialized as `{"id": ..., "deleted": true}`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeletedPublication {
+    pub id: Uuid,
+    pub deleted: bool,
+}
+
+impl CitedPublication {
+    pub fn id(&self) -> Uuid {
+        match self {
+            CitedPublication::Publication(publication) => publication.id,
+            CitedPublication::Deleted(deleted) => deleted.id,
+        }
+    }
+}
+
+/// Supplementary file, such as a dataset or code archive, attached to a publication.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PublicationFile {
+    pub id: Uuid,
+    pub publication_id: Uuid,
+    pub s3key: String,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    #[serde(with = "rfc3339_millis")]
+    pub created_at: DateTime<Utc>,
+    /// Presigned download URL of the file, filled in by [crate::db::s3::S3Contents]
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct PublicationAuthor {
     pub publication_id: Uuid,
+    #[schema(value_type = String)]
     pub author_id: PrivyId, // Now references authors(privy_id) as VARCHAR
     pub author_order: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Citation {
     pub id: Uuid,
     pub citing_publication_id: Uuid,
     pub cited_publication_id: Uuid,
+    #[serde(with = "rfc3339_millis")]
     pub created_at: DateTime<Utc>,
 }
 
@@ -70,6 +182,30 @@ pub struct NewPublication {
     pub about: Option<String>,
     pub tags: Option<Vec<String>>,
     pub s3key: Option<String>,
+    pub paper_hash: Option<String>,
+    pub file_sha256: Option<String>,
+    pub file_size: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewAuditLogEntry {
+    pub actor: String,
+    pub method: String,
+    pub path: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub status: i16,
+    pub request_id: Option<String>,
+    pub impersonator: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPublicationFile {
+    pub publication_id: Uuid,
+    pub s3key: String,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
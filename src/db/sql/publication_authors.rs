@@ -1,8 +1,23 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use sqlx::postgres::PgQueryResult;
 use uuid::Uuid;
 
-use crate::db::sql::{PrivyId, SqlClient, models::PublicationAuthor};
+use crate::{
+    common::pagination::PageQuery,
+    db::sql::{
+        PrivyId, SqlClient,
+        models::{Author, PublicationAuthor},
+    },
+};
+
+#[derive(sqlx::FromRow)]
+struct PublicationAuthorProfile {
+    publication_id: Uuid,
+    #[sqlx(flatten)]
+    author: Author,
+}
 
 #[async_trait]
 pub trait PublicationAuthorOperations {
@@ -31,11 +46,23 @@ pub trait PublicationAuthorOperations {
         publication_id: Uuid,
     ) -> Result<Vec<PublicationAuthor>, sqlx::Error>;
 
+    /// Returns the profiles of the authors of a publication, in author order.
+    async fn get_publication_author_profiles(
+        &self,
+        publication_id: Uuid,
+    ) -> Result<Vec<Author>, sqlx::Error>;
+
+    /// Returns the profiles of the authors of each of the publications, in author order, those
+    /// without authors being left out.
+    async fn get_publications_author_profiles(
+        &self,
+        publication_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<Author>>, sqlx::Error>;
+
     async fn get_author_publications(
         &self,
         author_id: &PrivyId,
-        page: Option<i64>,
-        limit: Option<i64>,
+        page: PageQuery,
     ) -> Result<Vec<super::models::Publication>, sqlx::Error>;
 
     async fn publication_has_author(
@@ -144,29 +171,69 @@ impl PublicationAuthorOperations for SqlClient {
         .await
     }
 
+    async fn get_publication_author_profiles(
+        &self,
+        publication_id: Uuid,
+    ) -> Result<Vec<Author>, sqlx::Error> {
+        sqlx::query_as::<_, Author>(
+            r#"
+            SELECT a.privy_id, a.name, a.email, a.affiliation, a.created_at, a.updated_at
+            FROM authors a
+            INNER JOIN publication_authors pa ON a.privy_id = pa.author_id
+            WHERE pa.publication_id = $1
+            ORDER BY pa.author_order ASC
+            "#,
+        )
+        .bind(publication_id)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn get_publications_author_profiles(
+        &self,
+        publication_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<Author>>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, PublicationAuthorProfile>(
+            r#"
+            SELECT pa.publication_id, a.privy_id, a.name, a.email, a.affiliation, a.created_at, a.updated_at
+            FROM authors a
+            INNER JOIN publication_authors pa ON a.privy_id = pa.author_id
+            WHERE pa.publication_id = ANY($1)
+            ORDER BY pa.publication_id, pa.author_order ASC
+            "#,
+        )
+        .bind(publication_ids)
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut authors: HashMap<Uuid, Vec<Author>> = HashMap::new();
+        for row in rows {
+            authors
+                .entry(row.publication_id)
+                .or_default()
+                .push(row.author);
+        }
+        Ok(authors)
+    }
+
     async fn get_author_publications(
         &self,
         author_id: &PrivyId,
-        page: Option<i64>,
-        limit: Option<i64>,
+        page: PageQuery,
     ) -> Result<Vec<super::models::Publication>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
-
         sqlx::query_as::<_, super::models::Publication>(
             r#"
-            SELECT p.id, p.user_id, p.title, p.about, p.tags, p.s3key, p.created_at, p.updated_at
+            SELECT p.id, p.user_id, p.title, p.about, p.tags, p.s3key, p.paper_hash, p.file_sha256, p.file_size, p.created_at, p.updated_at
             FROM publications p
             INNER JOIN publication_authors pa ON p.id = pa.publication_id
-            WHERE pa.author_id = $1
+            WHERE pa.author_id = $1 AND p.deleted_at IS NULL
             ORDER BY p.created_at DESC
             LIMIT $2 OFFSET $3
             "#,
         )
         .bind(author_id)
-        .bind(limit)
-        .bind(offset)
+        .bind(page.limit())
+        .bind(page.offset())
         .fetch_all(&self.db)
         .await
     }
@@ -199,20 +266,19 @@ impl PublicationAuthorOperations for SqlClient {
             .execute(&mut *tx)
             .await?;
 
-        // Add new authors with order
-        for (index, author_id) in author_ids.iter().enumerate() {
-            sqlx::query(
-                r#"
-                INSERT INTO publication_authors (publication_id, author_id, author_order)
-                VALUES ($1, $2, $3)
-                "#,
-            )
-            .bind(publication_id)
-            .bind(author_id)
-            .bind((index + 1) as i32)
-            .execute(&mut *tx)
-            .await?;
-        }
+        // Add new authors with order, in a single statement
+        let author_orders: Vec<i32> = (1..=author_ids.len() as i32).collect();
+        sqlx::query(
+            r#"
+            INSERT INTO publication_authors (publication_id, author_id, author_order)
+            SELECT $1, a, o FROM UNNEST($2::text[], $3::int[]) AS t(a, o)
+            "#,
+        )
+        .bind(publication_id)
+        .bind(author_ids)
+        .bind(&author_orders)
+        .execute(&mut *tx)
+        .await?;
 
         tx.commit().await?;
         Ok(())
@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use sqlx::postgres::PgQueryResult;
+use uuid::Uuid;
+
+use crate::db::sql::{
+    SqlClient,
+    models::{NewPublicationFile, PublicationFile},
+};
+
+#[async_trait]
+pub trait PublicationFileOperations {
+    async fn create_publication_file(
+        &self,
+        new_file: &NewPublicationFile,
+    ) -> Result<PublicationFile, sqlx::Error>;
+
+    /// Returns the file `file_id` of the publication, failing with `RowNotFound` if it belongs to
+    /// another publication.
+    async fn get_publication_file(
+        &self,
+        publication_id: Uuid,
+        file_id: Uuid,
+    ) -> Result<PublicationFile, sqlx::Error>;
+
+    async fn list_publication_files(
+        &self,
+        publication_id: Uuid,
+    ) -> Result<Vec<PublicationFile>, sqlx::Error>;
+
+    async fn delete_publication_file(&self, file_id: Uuid) -> Result<PgQueryResult, sqlx::Error>;
+}
+
+#[async_trait]
+impl PublicationFileOperations for SqlClient {
+    async fn create_publication_file(
+        &self,
+        new_file: &NewPublicationFile,
+    ) -> Result<PublicationFile, sqlx::Error> {
+        sqlx::query_as::<_, PublicationFile>(
+            r#"
+            INSERT INTO publication_files (publication_id, s3key, filename, content_type, size)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, publication_id, s3key, filename, content_type, size, created_at
+            "#,
+        )
+        .bind(new_file.publication_id)
+        .bind(&new_file.s3key)
+        .bind(&new_file.filename)
+        .bind(&new_file.content_type)
+        .bind(new_file.size)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn get_publication_file(
+        &self,
+        publication_id: Uuid,
+        file_id: Uuid,
+    ) -> Result<PublicationFile, sqlx::Error> {
+        sqlx::query_as::<_, PublicationFile>(
+            r#"
+            SELECT id, publication_id, s3key, filename, content_type, size, created_at
+            FROM publication_files
+            WHERE id = $1 AND publication_id = $2
+            "#,
+        )
+        .bind(file_id)
+        .bind(publication_id)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn list_publication_files(
+        &self,
+        publication_id: Uuid,
+    ) -> Result<Vec<PublicationFile>, sqlx::Error> {
+        sqlx::query_as::<_, PublicationFile>(
+            r#"
+            SELECT id, publication_id, s3key, filename, content_type, size, created_at
+            FROM publication_files
+            WHERE publication_id = $1
+            ORDER BY created_at, filename
+            "#,
+        )
+        .bind(publication_id)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn delete_publication_file(&self, file_id: Uuid) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query("DELETE FROM publication_files WHERE id = $1")
+            .bind(file_id)
+            .execute(&self.db)
+            .await
+    }
+}
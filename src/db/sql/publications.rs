@@ -1,8 +1,91 @@
 use async_trait::async_trait;
-use sqlx::postgres::PgQueryResult;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, postgres::PgQueryResult};
 use uuid::Uuid;
 
-use crate::db::sql::{SqlClient, models::Publication};
+use crate::{
+    common::pagination::{Cursor, PageQuery},
+    db::sql::{
+        PrivyId, PublicationAuthorOperations, SqlClient,
+        models::{
+            CitedPublication, DeletedPublication, Publication, TitleMatch, TrendingPublication,
+        },
+    },
+};
+
+/// Queries whose plans the tests check to use an index, so that editing them into a form the
+/// index does not support fails.
+pub(super) const SEARCH_BY_TITLE_QUERY: &str = r#"
+    SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at,
+        (CASE
+            WHEN LOWER(title) = LOWER($4) THEN 2
+            WHEN title ILIKE $4 || '%' THEN 1
+            ELSE 0
+        END + similarity(title, $4))::REAL AS score,
+        COUNT(*) OVER() AS total_count
+    FROM publications
+    WHERE title ILIKE $1 AND deleted_at IS NULL
+    ORDER BY score DESC, created_at DESC, id
+    LIMIT $2 OFFSET $3
+"#;
+
+pub(super) const SEARCH_QUERY: &str = r#"
+    SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at,
+        COUNT(*) OVER() AS total_count
+    FROM publications, websearch_to_tsquery('english', $1) AS query
+    WHERE search_document @@ query AND deleted_at IS NULL
+    ORDER BY ts_rank(search_document, query) DESC, created_at DESC, id
+    LIMIT $2 OFFSET $3
+"#;
+
+pub(super) const LIST_AFTER_QUERY: &str = r#"
+    SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at
+    FROM publications
+    WHERE (created_at, id) < ($1, $2) AND deleted_at IS NULL
+    ORDER BY created_at DESC, id DESC
+    LIMIT $3
+"#;
+
+/// Number of days after which a citation weighs half as much in the trending score.
+const TRENDING_HALF_LIFE_DAYS: f64 = 2.0;
+
+pub(super) const TRENDING_QUERY: &str = r#"
+    WITH trending AS (
+        SELECT c.cited_publication_id AS id, COUNT(*) AS recent_citations,
+            SUM(POWER(0.5, EXTRACT(EPOCH FROM NOW() - c.created_at) / 86400 / $3))::DOUBLE PRECISION AS score
+        FROM citations c
+        INNER JOIN publications citing ON citing.id = c.citing_publication_id
+        WHERE c.created_at > NOW() - make_interval(days => $1) AND citing.deleted_at IS NULL
+        GROUP BY c.cited_publication_id
+    )
+    SELECT p.id, p.user_id, p.title, p.about, p.tags, p.s3key, p.paper_hash, p.file_sha256, p.file_size, p.created_at, p.updated_at,
+        COALESCE(t.recent_citations, 0) AS recent_citations, COALESCE(t.score, 0) AS score
+    FROM publications p
+    LEFT JOIN trending t ON t.id = p.id
+    WHERE p.deleted_at IS NULL
+        AND (t.id IS NOT NULL OR p.id IN (
+            SELECT id FROM publications WHERE deleted_at IS NULL
+            ORDER BY created_at DESC, id DESC LIMIT $2
+        ))
+    ORDER BY score DESC, p.created_at DESC, p.id DESC
+    LIMIT $2
+"#;
+
+pub(super) const SEARCH_BY_TAG_QUERY: &str = r#"
+    SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at,
+        COUNT(*) OVER() AS total_count
+    FROM publications
+    WHERE tags @> ARRAY[$1] AND deleted_at IS NULL
+    ORDER BY created_at DESC
+    LIMIT $2 OFFSET $3
+"#;
+
+#[derive(sqlx::FromRow)]
+struct CitedPublicationRow {
+    #[sqlx(flatten)]
+    publication: Publication,
+    deleted: bool,
+}
 
 #[async_trait]
 pub trait PublicationOperations {
@@ -11,34 +94,80 @@ pub trait PublicationOperations {
         new_publication: &super::models::NewPublication,
     ) -> Result<Publication, sqlx::Error>;
 
+    /// Creates the publication `publication_id` with its authors, in the given order, and the
+    /// publications it cites, in a single transaction: nothing is created if any of them fails.
+    ///
+    /// The id is chosen by the caller, so that the file of the publication is stored in its
+    /// `publications/<id>/` directory before the publication is created.
+    async fn create_publication_full(
+        &self,
+        publication_id: Uuid,
+        new_publication: &super::models::NewPublication,
+        author_ids: &[PrivyId],
+        cited_publication_ids: &[Uuid],
+    ) -> Result<Publication, sqlx::Error>;
+
     async fn get_publication(&self, publication_id: Uuid) -> Result<Publication, sqlx::Error>;
 
+    /// Returns the publications among `publication_ids`, in no particular order, missing and
+    /// deleted ones being left out.
+    async fn get_publications_by_ids(
+        &self,
+        publication_ids: &[Uuid],
+    ) -> Result<Vec<Publication>, sqlx::Error>;
+
     async fn list_publications(
         &self,
-        page: Option<i64>,
-        limit: Option<i64>,
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error>;
+
+    /// Returns up to `limit` publications created before the one at `cursor`, in the order of
+    /// [PublicationOperations::list_publications].
+    async fn list_publications_after(
+        &self,
+        cursor: Cursor,
+        limit: i64,
     ) -> Result<Vec<Publication>, sqlx::Error>;
 
     async fn list_publications_by_user(
         &self,
         user_id: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<Publication>, sqlx::Error>;
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error>;
 
+    /// Returns the publications whose title contains `title_query`, most relevant first: exact
+    /// titles, then titles starting with it, then by similarity. The number of matching
+    /// publications is returned along.
     async fn search_publications_by_title(
         &self,
         title_query: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<Publication>, sqlx::Error>;
+        page: PageQuery,
+    ) -> Result<(Vec<TitleMatch>, i64), sqlx::Error>;
 
+    /// Returns the publications tagged with `tag`, most recent first, along with the number of
+    /// publications tagged with it.
     async fn search_publications_by_tag(
         &self,
         tag: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<Publication>, sqlx::Error>;
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error>;
+
+    /// Returns the publications matching the words of `query` in their title, tags or abstract,
+    /// most relevant first, along with the number of matching publications. Words are stemmed, and
+    /// the query may quote phrases, exclude words with `-` and combine them with `or`.
+    async fn search_publications(
+        &self,
+        query: &str,
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error>;
+
+    /// Returns up to `limit` publications ranked by the citations they received within the last
+    /// `window_days`, recent ones weighing more, then the most recent publications.
+    async fn get_trending_publications(
+        &self,
+        window_days: i32,
+        limit: i64,
+    ) -> Result<Vec<TrendingPublication>, sqlx::Error>;
 
     async fn update_publication(
         &self,
@@ -50,8 +179,45 @@ pub trait PublicationOperations {
         s3key: Option<&str>,
     ) -> Result<PgQueryResult, sqlx::Error>;
 
+    /// Records the digests and size of a publication's newly stored file.
+    async fn set_publication_file_details(
+        &self,
+        publication_id: Uuid,
+        paper_hash: &str,
+        file_sha256: &str,
+        file_size: i64,
+    ) -> Result<PgQueryResult, sqlx::Error>;
+
+    /// Marks the publication as deleted, hiding it from every read but the citations of it.
     async fn delete_publication(&self, publication_id: Uuid) -> Result<PgQueryResult, sqlx::Error>;
 
+    /// Undoes [PublicationOperations::delete_publication], failing with
+    /// [sqlx::Error::RowNotFound] unless the publication was deleted after `deleted_after`.
+    async fn restore_publication(
+        &self,
+        publication_id: Uuid,
+        deleted_after: DateTime<Utc>,
+    ) -> Result<Publication, sqlx::Error>;
+
+    /// Returns which of the given `publications/<id>/` storage directories are still in use,
+    /// either as a publication's own directory or as the directory of its file. Publications
+    /// deleted before `deleted_before` no longer use theirs.
+    async fn get_referenced_storage_directories(
+        &self,
+        directory_ids: &[Uuid],
+        deleted_before: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>, sqlx::Error>;
+
+    /// Returns whether a publication, deleted or not, has its file stored under `s3key`.
+    async fn is_s3key_referenced(&self, s3key: &str) -> Result<bool, sqlx::Error>;
+
+    /// Returns the publications whose file is not stored in their own `publications/<id>/`
+    /// directory, as was the case for files uploaded before keys followed the publication id.
+    /// Deleted publications are left out, as they cannot be updated until restored.
+    async fn get_publications_with_noncanonical_s3keys(
+        &self,
+    ) -> Result<Vec<Publication>, sqlx::Error>;
+
     async fn count_publications(&self) -> Result<i64, sqlx::Error>;
 
     async fn count_publications_by_user(&self, user_id: &str) -> Result<i64, sqlx::Error>;
@@ -61,42 +227,99 @@ pub trait PublicationOperations {
         publication_id: Uuid,
     ) -> Result<Vec<super::models::Author>, sqlx::Error>;
 
+    /// Returns the publications cited by the publication, deleted ones as tombstones, so that its
+    /// references still resolve.
     async fn get_publication_citations(
         &self,
         publication_id: Uuid,
-    ) -> Result<Vec<super::models::Citation>, sqlx::Error>;
+    ) -> Result<Vec<CitedPublication>, sqlx::Error>;
 
+    /// Returns the publications citing the publication, deleted ones being left out as their
+    /// citations no longer count.
     async fn get_cited_by(&self, publication_id: Uuid) -> Result<Vec<Publication>, sqlx::Error>;
 }
 
+/// Inserts a publication, with a generated id unless `publication_id` is given.
+async fn insert_publication<'e>(
+    executor: impl PgExecutor<'e>,
+    publication_id: Option<Uuid>,
+    new_publication: &super::models::NewPublication,
+) -> Result<Publication, sqlx::Error> {
+    sqlx::query_as::<_, Publication>(
+        r#"
+        INSERT INTO publications (id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size)
+        VALUES (COALESCE($1, uuid_generate_v4()), $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at
+        "#,
+    )
+    .bind(publication_id)
+    .bind(&new_publication.user_id)
+    .bind(&new_publication.title)
+    .bind(&new_publication.about)
+    .bind(new_publication.tags.as_deref().unwrap_or(&[]))
+    .bind(&new_publication.s3key)
+    .bind(&new_publication.paper_hash)
+    .bind(&new_publication.file_sha256)
+    .bind(new_publication.file_size)
+    .fetch_one(executor)
+    .await
+}
+
 #[async_trait]
 impl PublicationOperations for SqlClient {
     async fn create_publication(
         &self,
         new_publication: &super::models::NewPublication,
     ) -> Result<Publication, sqlx::Error> {
-        sqlx::query_as::<_, Publication>(
+        insert_publication(&self.db, None, new_publication).await
+    }
+
+    async fn create_publication_full(
+        &self,
+        publication_id: Uuid,
+        new_publication: &super::models::NewPublication,
+        author_ids: &[PrivyId],
+        cited_publication_ids: &[Uuid],
+    ) -> Result<Publication, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+
+        let publication =
+            insert_publication(&mut *tx, Some(publication_id), new_publication).await?;
+
+        let author_orders: Vec<i32> = (1..=author_ids.len() as i32).collect();
+        sqlx::query(
             r#"
-            INSERT INTO publications (user_id, title, about, tags, s3key)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, user_id, title, about, tags, s3key, created_at, updated_at
+            INSERT INTO publication_authors (publication_id, author_id, author_order)
+            SELECT $1, a, o FROM UNNEST($2::text[], $3::int[]) AS t(a, o)
             "#,
         )
-        .bind(&new_publication.user_id)
-        .bind(&new_publication.title)
-        .bind(&new_publication.about)
-        .bind(new_publication.tags.as_deref().unwrap_or(&[]))
-        .bind(&new_publication.s3key)
-        .fetch_one(&self.db)
-        .await
+        .bind(publication.id)
+        .bind(author_ids)
+        .bind(&author_orders)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO citations (citing_publication_id, cited_publication_id)
+            SELECT DISTINCT $1, c FROM UNNEST($2::uuid[]) AS t(c)
+            "#,
+        )
+        .bind(publication.id)
+        .bind(cited_publication_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(publication)
     }
 
     async fn get_publication(&self, publication_id: Uuid) -> Result<Publication, sqlx::Error> {
         sqlx::query_as::<_, Publication>(
             r#"
-            SELECT id, user_id, title, about, tags, s3key, created_at, updated_at
+            SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at
             FROM publications 
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
         )
         .bind(publication_id)
@@ -104,106 +327,152 @@ impl PublicationOperations for SqlClient {
         .await
     }
 
-    async fn list_publications(
+    async fn get_publications_by_ids(
         &self,
-        page: Option<i64>,
-        limit: Option<i64>,
+        publication_ids: &[Uuid],
     ) -> Result<Vec<Publication>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
-
         sqlx::query_as::<_, Publication>(
             r#"
-            SELECT id, user_id, title, about, tags, s3key, created_at, updated_at
-            FROM publications 
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
+            SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at
+            FROM publications
+            WHERE id = ANY($1) AND deleted_at IS NULL
             "#,
         )
-        .bind(limit)
-        .bind(offset)
+        .bind(publication_ids)
         .fetch_all(&self.db)
         .await
     }
 
-    async fn list_publications_by_user(
+    async fn list_publications(
         &self,
-        user_id: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error> {
+        let query = sqlx::query(
+            r#"
+            SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at, COUNT(*) OVER() AS total_count
+            FROM publications 
+            WHERE deleted_at IS NULL
+            ORDER BY created_at DESC, id DESC
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(page.limit())
+        .bind(page.offset());
+
+        self.fetch_page(query, page.offset(), self.count_publications())
+            .await
+    }
+
+    async fn list_publications_after(
+        &self,
+        cursor: Cursor,
+        limit: i64,
     ) -> Result<Vec<Publication>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
+        sqlx::query_as::<_, Publication>(LIST_AFTER_QUERY)
+            .bind(cursor.created_at)
+            .bind(cursor.id)
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await
+    }
 
-        sqlx::query_as::<_, Publication>(
+    async fn list_publications_by_user(
+        &self,
+        user_id: &str,
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error> {
+        let query = sqlx::query(
             r#"
-            SELECT id, user_id, title, about, tags, s3key, created_at, updated_at
+            SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at, COUNT(*) OVER() AS total_count
             FROM publications 
-            WHERE user_id = $1
+            WHERE user_id = $1 AND deleted_at IS NULL
             ORDER BY created_at DESC
             LIMIT $2 OFFSET $3
             "#,
         )
         .bind(user_id)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&self.db)
+        .bind(page.limit())
+        .bind(page.offset());
+
+        self.fetch_page(
+            query,
+            page.offset(),
+            self.count_publications_by_user(user_id),
+        )
         .await
     }
 
     async fn search_publications_by_title(
         &self,
         title_query: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<Publication>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
+        page: PageQuery,
+    ) -> Result<(Vec<TitleMatch>, i64), sqlx::Error> {
         let search_pattern = format!("%{}%", title_query);
 
-        sqlx::query_as::<_, Publication>(
-            r#"
-            SELECT id, user_id, title, about, tags, s3key, created_at, updated_at
-            FROM publications 
-            WHERE title ILIKE $1
-            ORDER BY title ASC
-            LIMIT $2 OFFSET $3
-            "#,
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM publications WHERE title ILIKE $1 AND deleted_at IS NULL",
         )
-        .bind(search_pattern)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&self.db)
-        .await
+        .bind(search_pattern.clone())
+        .fetch_one(&self.db);
+        let search = sqlx::query(SEARCH_BY_TITLE_QUERY)
+            .bind(search_pattern)
+            .bind(page.limit())
+            .bind(page.offset())
+            .bind(title_query);
+
+        self.fetch_page(search, page.offset(), count).await
+    }
+
+    async fn get_trending_publications(
+        &self,
+        window_days: i32,
+        limit: i64,
+    ) -> Result<Vec<TrendingPublication>, sqlx::Error> {
+        sqlx::query_as::<_, TrendingPublication>(TRENDING_QUERY)
+            .bind(window_days)
+            .bind(limit)
+            .bind(TRENDING_HALF_LIFE_DAYS)
+            .fetch_all(&self.db)
+            .await
     }
 
     async fn search_publications_by_tag(
         &self,
         tag: &str,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<Publication>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM publications WHERE tags @> ARRAY[$1] AND deleted_at IS NULL",
+        )
+        .bind(tag)
+        .fetch_one(&self.db);
+        let search = sqlx::query(SEARCH_BY_TAG_QUERY)
+            .bind(tag)
+            .bind(page.limit())
+            .bind(page.offset());
 
-        sqlx::query_as::<_, Publication>(
+        self.fetch_page(search, page.offset(), count).await
+    }
+
+    async fn search_publications(
+        &self,
+        query: &str,
+        page: PageQuery,
+    ) -> Result<(Vec<Publication>, i64), sqlx::Error> {
+        let count = sqlx::query_scalar(
             r#"
-            SELECT id, user_id, title, about, tags, s3key, created_at, updated_at
-            FROM publications 
-            WHERE $1 = ANY(tags)
-            ORDER BY created_at DESC
-            LIMIT $2 OFFSET $3
+            SELECT COUNT(*) FROM publications
+            WHERE search_document @@ websearch_to_tsquery('english', $1) AND deleted_at IS NULL
             "#,
         )
-        .bind(tag)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&self.db)
-        .await
+        .bind(query)
+        .fetch_one(&self.db);
+        let search = sqlx::query(SEARCH_QUERY)
+            .bind(query)
+            .bind(page.limit())
+            .bind(page.offset());
+
+        self.fetch_page(search, page.offset(), count).await
     }
 
     async fn update_publication(
@@ -224,7 +493,7 @@ impl PublicationOperations for SqlClient {
             tags = COALESCE($4, tags),
             s3key = COALESCE($5, s3key),
             updated_at = NOW()
-            WHERE id = $6
+            WHERE id = $6 AND deleted_at IS NULL
             "#,
         )
         .bind(user_id)
@@ -237,68 +506,169 @@ impl PublicationOperations for SqlClient {
         .await
     }
 
+    async fn set_publication_file_details(
+        &self,
+        publication_id: Uuid,
+        paper_hash: &str,
+        file_sha256: &str,
+        file_size: i64,
+    ) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE publications SET
+            paper_hash = $1,
+            file_sha256 = $2,
+            file_size = $3,
+            updated_at = NOW()
+            WHERE id = $4 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(paper_hash)
+        .bind(file_sha256)
+        .bind(file_size)
+        .bind(publication_id)
+        .execute(&self.db)
+        .await
+    }
+
     async fn delete_publication(&self, publication_id: Uuid) -> Result<PgQueryResult, sqlx::Error> {
-        sqlx::query("DELETE FROM publications WHERE id = $1")
-            .bind(publication_id)
-            .execute(&self.db)
+        sqlx::query(
+            "UPDATE publications SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(publication_id)
+        .execute(&self.db)
+        .await
+    }
+
+    async fn restore_publication(
+        &self,
+        publication_id: Uuid,
+        deleted_after: DateTime<Utc>,
+    ) -> Result<Publication, sqlx::Error> {
+        sqlx::query_as::<_, Publication>(
+            r#"
+            UPDATE publications SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at > $2
+            RETURNING id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at
+            "#,
+        )
+        .bind(publication_id)
+        .bind(deleted_after)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn get_referenced_storage_directories(
+        &self,
+        directory_ids: &[Uuid],
+        deleted_before: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let directory_names: Vec<String> = directory_ids.iter().map(Uuid::to_string).collect();
+
+        sqlx::query_scalar(
+            r#"
+            SELECT id FROM publications
+            WHERE id = ANY($1) AND (deleted_at IS NULL OR deleted_at >= $3)
+            UNION
+            SELECT split_part(s3key, '/', 2)::uuid FROM publications
+            WHERE split_part(s3key, '/', 1) = 'publications'
+            AND split_part(s3key, '/', 2) = ANY($2)
+            AND (deleted_at IS NULL OR deleted_at >= $3)
+            "#,
+        )
+        .bind(directory_ids)
+        .bind(&directory_names)
+        .bind(deleted_before)
+        .fetch_all(&self.db)
+        .await
+    }
+
+    async fn is_s3key_referenced(&self, s3key: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM publications WHERE s3key = $1)")
+            .bind(s3key)
+            .fetch_one(&self.db)
             .await
     }
 
+    async fn get_publications_with_noncanonical_s3keys(
+        &self,
+    ) -> Result<Vec<Publication>, sqlx::Error> {
+        sqlx::query_as::<_, Publication>(
+            r#"
+            SELECT id, user_id, title, about, tags, s3key, paper_hash, file_sha256, file_size, created_at, updated_at
+            FROM publications
+            WHERE s3key IS NOT NULL AND s3key <> ''
+            AND NOT starts_with(s3key, 'publications/' || id::text || '/')
+            AND deleted_at IS NULL
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
     async fn count_publications(&self) -> Result<i64, sqlx::Error> {
-        sqlx::query_scalar("SELECT COUNT(*) FROM publications")
+        sqlx::query_scalar("SELECT COUNT(*) FROM publications WHERE deleted_at IS NULL")
             .fetch_one(&self.db)
             .await
     }
 
     async fn count_publications_by_user(&self, user_id: &str) -> Result<i64, sqlx::Error> {
-        sqlx::query_scalar("SELECT COUNT(*) FROM publications WHERE user_id = $1")
-            .bind(user_id)
-            .fetch_one(&self.db)
-            .await
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM publications WHERE user_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await
     }
 
     async fn get_publication_authors(
         &self,
         publication_id: Uuid,
     ) -> Result<Vec<super::models::Author>, sqlx::Error> {
-        sqlx::query_as::<_, super::models::Author>(
-            r#"
-            SELECT a.id, a.name, a.email, a.affiliation, a.created_at, a.updated_at
-            FROM authors a
-            INNER JOIN publication_authors pa ON a.id = pa.author_id
-            WHERE pa.publication_id = $1
-            ORDER BY pa.author_order ASC
-            "#,
-        )
-        .bind(publication_id)
-        .fetch_all(&self.db)
-        .await
+        self.get_publication_author_profiles(publication_id).await
     }
 
     async fn get_publication_citations(
         &self,
         publication_id: Uuid,
-    ) -> Result<Vec<super::models::Citation>, sqlx::Error> {
-        sqlx::query_as::<_, super::models::Citation>(
+    ) -> Result<Vec<CitedPublication>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, CitedPublicationRow>(
             r#"
-            SELECT id, citing_publication_id, cited_publication_id, created_at
-            FROM citations 
-            WHERE citing_publication_id = $1 OR cited_publication_id = $1
-            ORDER BY created_at DESC
+            SELECT p.id, p.user_id, p.title, p.about, p.tags, p.s3key, p.paper_hash, p.file_sha256, p.file_size, p.created_at, p.updated_at,
+                p.deleted_at IS NOT NULL AS deleted
+            FROM publications p
+            INNER JOIN citations c ON p.id = c.cited_publication_id
+            WHERE c.citing_publication_id = $1
+            ORDER BY c.created_at DESC
             "#,
         )
         .bind(publication_id)
         .fetch_all(&self.db)
-        .await
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                if row.deleted {
+                    CitedPublication::Deleted(DeletedPublication {
+                        id: row.publication.id,
+                        deleted: true,
+                    })
+                } else {
+                    CitedPublication::Publication(Box::new(row.publication))
+                }
+            })
+            .collect())
     }
 
     async fn get_cited_by(&self, publication_id: Uuid) -> Result<Vec<Publication>, sqlx::Error> {
         sqlx::query_as::<_, Publication>(
             r#"
-            SELECT p.id, p.user_id, p.title, p.about, p.tags, p.s3key, p.created_at, p.updated_at
+            SELECT p.id, p.user_id, p.title, p.about, p.tags, p.s3key, p.paper_hash, p.file_sha256, p.file_size, p.created_at, p.updated_at
             FROM publications p
             INNER JOIN citations c ON p.id = c.citing_publication_id
-            WHERE c.cited_publication_id = $1
+            WHERE c.cited_publication_id = $1 AND p.deleted_at IS NULL
             ORDER BY c.created_at DESC
             "#,
         )
@@ -3,10 +3,25 @@
 
 #[cfg(test)]
 mod integration_tests {
-    use crate::db::sql::{
-        AuthorOperations, CitationOperations, PublicationAuthorOperations, PublicationOperations,
-        SqlClient, UserOperations,
-        models::{NewAuthor, NewCitation, NewPublication, NewUser},
+    use chrono::{TimeDelta, Utc};
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use crate::{
+        common::pagination::{Cursor, PageQuery},
+        config::DatabasePoolConfig,
+        db::sql::{
+            self as sql, AuthorOperations, CitationOperations, PublicationAuthorOperations,
+            PublicationFileOperations, PublicationOperations, SqlClient, UserOperations,
+            ViewOperations,
+            citations::COUNT_CITATIONS_TO_PUBLICATION_QUERY,
+            models::{
+                CitedPublication, NewAuthor, NewCitation, NewPublication, NewPublicationFile,
+                NewUser,
+            },
+            publications::{
+                LIST_AFTER_QUERY, SEARCH_BY_TAG_QUERY, SEARCH_BY_TITLE_QUERY, SEARCH_QUERY,
+            },
+        },
     };
     use uuid::Uuid;
 
@@ -31,6 +46,9 @@ mod integration_tests {
                 about: Some("Test description".to_string()),
                 tags: Some(vec!["test".to_string()]),
                 s3key: None,
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await?;
         Ok(publication)
@@ -77,17 +95,148 @@ mod integration_tests {
             sql_client.create_user(&new_user).await?;
         }
 
-        let users = sql_client.list_users(Some(1), Some(10)).await?;
+        let (users, total_count) = sql_client
+            .list_users(PageQuery::new(1, 10).unwrap())
+            .await?;
         assert_eq!(users.len(), 3);
+        assert_eq!(total_count, 3);
 
         let count = sql_client.count_users().await?;
         assert_eq!(count, 3);
 
+        // Past the last page, the total is still that of the whole table
+        let (users, total_count) = sql_client
+            .list_users(PageQuery::new(2, 10).unwrap())
+            .await?;
+        assert!(users.is_empty());
+        assert_eq!(total_count, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_get_referenced_storage_directories(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let user_privy_id = create_test_user(&sql_client, "storage").await?;
+
+        let upload_directory = Uuid::new_v4();
+        let publication = sql_client
+            .create_publication(&NewPublication {
+                user_id: user_privy_id,
+                title: "Stored Publication".to_string(),
+                about: None,
+                tags: None,
+                s3key: Some(format!("publications/{}/paper.pdf", upload_directory)),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
+            })
+            .await?;
+        let orphaned_directory = Uuid::new_v4();
+
+        let directory_ids = [publication.id, upload_directory, orphaned_directory];
+        let retention_cutoff = Utc::now() - TimeDelta::days(30);
+
+        let mut referenced = sql_client
+            .get_referenced_storage_directories(&directory_ids, retention_cutoff)
+            .await?;
+        referenced.sort();
+
+        let mut expected = vec![publication.id, upload_directory];
+        expected.sort();
+        assert_eq!(referenced, expected);
+
+        // A deleted publication keeps its directories until deleted before the cutoff
+        sql_client.delete_publication(publication.id).await?;
+        let mut referenced = sql_client
+            .get_referenced_storage_directories(&directory_ids, retention_cutoff)
+            .await?;
+        referenced.sort();
+        assert_eq!(referenced, expected);
+        assert!(
+            sql_client
+                .get_referenced_storage_directories(
+                    &directory_ids,
+                    Utc::now() + TimeDelta::minutes(1)
+                )
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_storage_usage(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let heavy_user = create_test_user(&sql_client, "heavy").await?;
+        let light_user = create_test_user(&sql_client, "light").await?;
+        let idle_user = create_test_user(&sql_client, "idle").await?;
+
+        for (user, file_size) in [(&heavy_user, 1000), (&heavy_user, 500), (&light_user, 300)] {
+            let publication = sql_client
+                .create_publication(&NewPublication {
+                    user_id: user.clone(),
+                    title: "Sized Publication".to_string(),
+                    about: None,
+                    tags: None,
+                    s3key: Some(format!("publications/{}/paper.pdf", Uuid::new_v4())),
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: Some(file_size),
+                })
+                .await?;
+            sql_client
+                .create_publication_file(&NewPublicationFile {
+                    publication_id: publication.id,
+                    s3key: format!("publications/{}/supplementary/data.csv", publication.id),
+                    filename: "data.csv".to_string(),
+                    content_type: "text/csv".to_string(),
+                    size: 10,
+                })
+                .await?;
+        }
+        // Publications without a file count for nothing
+        create_test_publication(&sql_client, &idle_user, None).await?;
+
+        assert_eq!(sql_client.get_storage_usage(&heavy_user).await?, 1520);
+        assert_eq!(sql_client.get_storage_usage(&light_user).await?, 310);
+        assert_eq!(sql_client.get_storage_usage(&idle_user).await?, 0);
+
+        let usage = sql_client.list_storage_usage(10).await?;
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].privy_id, heavy_user);
+        assert_eq!(usage[0].publication_bytes, 1500);
+        assert_eq!(usage[0].supplementary_bytes, 20);
+        assert_eq!(usage[0].total_bytes, 1520);
+        assert_eq!(usage[1].privy_id, light_user);
+
+        let usage = sql_client.list_storage_usage(1).await?;
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].privy_id, heavy_user);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_set_user_admin(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+
+        let privy_id = create_test_user(&sql_client, "admin").await?;
+        assert!(!sql_client.get_user(privy_id.clone()).await?.is_admin);
+
+        let result = sql_client.set_user_admin(&privy_id, true).await?;
+        assert_eq!(result.rows_affected(), 1);
+        assert!(sql_client.get_user(privy_id.clone()).await?.is_admin);
+
+        sql_client.set_user_admin(&privy_id, false).await?;
+        assert!(!sql_client.get_user(privy_id).await?.is_admin);
+
         Ok(())
     }
 
     #[sqlx::test]
-    async fn test_user_email_exists(pool: sqlx::PgPool) -> sqlx::Result<()> {
+    async fn test_user_email_exists(_pool: sqlx::PgPool) -> sqlx::Result<()> {
         // This test is no longer relevant since we don't store email in users table
         // We'll skip it for now
         Ok(())
@@ -135,8 +284,11 @@ mod integration_tests {
             create_test_author(&sql_client, &user_privy_id).await?;
         }
 
-        let authors = sql_client.list_authors(Some(1), Some(10)).await?;
-        assert_eq!(authors.len(), 3);
+        let (authors, total_count) = sql_client
+            .list_authors(PageQuery::new(1, 2).unwrap())
+            .await?;
+        assert_eq!(authors.len(), 2);
+        assert_eq!(total_count, 3);
 
         let count = sql_client.count_authors().await?;
         assert_eq!(count, 3);
@@ -208,8 +360,11 @@ mod integration_tests {
             sql_client.create_citation(&new_citation).await?;
         }
 
-        let citations = sql_client.list_citations(Some(1), Some(10)).await?;
+        let (citations, total_count) = sql_client
+            .list_citations(PageQuery::new(1, 10).unwrap())
+            .await?;
         assert_eq!(citations.len(), 3);
+        assert_eq!(total_count, 3);
 
         let count = sql_client.count_citations().await?;
         assert_eq!(count, 3);
@@ -262,6 +417,45 @@ mod integration_tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn test_get_publication_author_profiles(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+
+        let pub_user_privy_id = create_test_user(&sql_client, "profiles_pub").await?;
+        let first_privy_id = create_test_user(&sql_client, "profiles_first").await?;
+        let second_privy_id = create_test_user(&sql_client, "profiles_second").await?;
+
+        let publication =
+            create_test_publication(&sql_client, &pub_user_privy_id, Some("Profiles")).await?;
+        let first = create_test_author(&sql_client, &first_privy_id).await?;
+        let second = create_test_author(&sql_client, &second_privy_id).await?;
+        sql_client
+            .set_publication_authors(
+                publication.id,
+                &[second.privy_id.clone(), first.privy_id.clone()],
+            )
+            .await?;
+
+        // Authors are joined on their Privy id and returned in author order
+        let authors =
+            PublicationOperations::get_publication_authors(&sql_client, publication.id).await?;
+        assert_eq!(authors.len(), 2);
+        assert_eq!(authors[0].privy_id, second.privy_id);
+        assert_eq!(authors[0].name, second.name);
+        assert_eq!(authors[0].email, second.email);
+        assert_eq!(authors[1].privy_id, first.privy_id);
+
+        let other =
+            create_test_publication(&sql_client, &pub_user_privy_id, Some("No authors")).await?;
+        assert!(
+            PublicationOperations::get_publication_authors(&sql_client, other.id)
+                .await?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn test_set_publication_authors(pool: sqlx::PgPool) -> sqlx::Result<()> {
         let sql_client = SqlClient::new(pool.clone()).await;
@@ -296,13 +490,160 @@ mod integration_tests {
             assert_eq!(pub_author.author_order, (i + 1) as i32);
         }
 
+        // Setting the authors again replaces them, and an empty list clears them
+        sql_client
+            .set_publication_authors(publication.id, &author_privy_ids[1..])
+            .await?;
+        let pub_authors =
+            PublicationAuthorOperations::get_publication_authors(&sql_client, publication.id)
+                .await?;
+        assert_eq!(pub_authors.len(), 2);
+        assert_eq!(pub_authors[0].author_id, author_privy_ids[1]);
+        assert_eq!(pub_authors[0].author_order, 1);
+
+        sql_client
+            .set_publication_authors(publication.id, &[])
+            .await?;
+        assert_eq!(
+            sql_client
+                .count_authors_for_publication(publication.id)
+                .await?,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_create_publication_full(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+
+        let user_privy_id = create_test_user(&sql_client, "full_user").await?;
+        let author = create_test_author(&sql_client, &user_privy_id).await?;
+        let co_author_privy_id = create_test_user(&sql_client, "full_co_author").await?;
+        let co_author = create_test_author(&sql_client, &co_author_privy_id).await?;
+        let cited = create_test_publication(&sql_client, &user_privy_id, Some("Cited")).await?;
+        let new_publication = NewPublication {
+            user_id: user_privy_id.clone(),
+            title: "Complete".to_string(),
+            about: None,
+            tags: None,
+            s3key: None,
+            paper_hash: None,
+            file_sha256: None,
+            file_size: None,
+        };
+
+        // A missing author or cited publication leaves no row behind
+        let missing_author = vec![author.privy_id.clone(), "did:privy:missing".to_string()];
+        let result = sql_client
+            .create_publication_full(
+                Uuid::new_v4(),
+                &new_publication,
+                &missing_author,
+                &[cited.id],
+            )
+            .await;
+        assert!(matches!(result, Err(sqlx::Error::Database(_))));
+        let result = sql_client
+            .create_publication_full(
+                Uuid::new_v4(),
+                &new_publication,
+                std::slice::from_ref(&author.privy_id),
+                &[Uuid::new_v4()],
+            )
+            .await;
+        assert!(matches!(result, Err(sqlx::Error::Database(_))));
+        assert_eq!(sql_client.count_publications().await?, 1);
+        assert_eq!(sql_client.count_citations().await?, 0);
+        assert_eq!(
+            sql_client
+                .count_publications_for_author(&author.privy_id)
+                .await?,
+            0
+        );
+
+        let author_ids = vec![co_author.privy_id.clone(), author.privy_id.clone()];
+        let publication_id = Uuid::new_v4();
+        let publication = sql_client
+            .create_publication_full(publication_id, &new_publication, &author_ids, &[cited.id])
+            .await?;
+        assert_eq!(publication.id, publication_id);
+        assert_eq!(publication.title, "Complete");
+        let pub_authors =
+            PublicationAuthorOperations::get_publication_authors(&sql_client, publication.id)
+                .await?;
+        let orders: Vec<(&str, i32)> = pub_authors
+            .iter()
+            .map(|pub_author| (pub_author.author_id.as_str(), pub_author.author_order))
+            .collect();
+        assert_eq!(
+            orders,
+            [
+                (co_author.privy_id.as_str(), 1),
+                (author.privy_id.as_str(), 2)
+            ]
+        );
+        assert!(
+            sql_client
+                .get_citation_by_publications(publication.id, cited.id)
+                .await?
+                .is_some()
+        );
+
+        // Without authors or citations, only the publication is created
+        let publication = sql_client
+            .create_publication_full(Uuid::new_v4(), &new_publication, &[], &[])
+            .await?;
+        assert_eq!(
+            sql_client
+                .count_authors_for_publication(publication.id)
+                .await?,
+            0
+        );
+        assert_eq!(sql_client.count_publications().await?, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_set_many_publication_authors(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+
+        let pub_user_privy_id = create_test_user(&sql_client, "pub_user").await?;
+        let publication =
+            create_test_publication(&sql_client, &pub_user_privy_id, Some("Collaboration")).await?;
+
+        let mut author_privy_ids = Vec::new();
+        for i in 0..50 {
+            let author_user_privy_id =
+                create_test_user(&sql_client, &format!("author{}", i)).await?;
+            let author = create_test_author(&sql_client, &author_user_privy_id).await?;
+            author_privy_ids.push(author.privy_id);
+        }
+        // Orders follow the given list, not the order the authors were created in
+        author_privy_ids.reverse();
+
+        sql_client
+            .set_publication_authors(publication.id, &author_privy_ids)
+            .await?;
+
+        let pub_authors =
+            PublicationAuthorOperations::get_publication_authors(&sql_client, publication.id)
+                .await?;
+        assert_eq!(pub_authors.len(), 50);
+        for (i, pub_author) in pub_authors.iter().enumerate() {
+            assert_eq!(pub_author.author_id, author_privy_ids[i]);
+            assert_eq!(pub_author.author_order, (i + 1) as i32);
+        }
+
         Ok(())
     }
 
     #[sqlx::test]
     async fn test_search_publications_by_title(pool: sqlx::PgPool) -> sqlx::Result<()> {
         let sql_client = SqlClient::new(pool.clone()).await;
-        let publications = vec![
+        let publications = [
             "Machine Learning Advances",
             "Deep Learning Research",
             "Artificial Intelligence Review",
@@ -315,32 +656,155 @@ mod integration_tests {
             create_test_publication(&sql_client, &user_privy_id, Some(title)).await?;
         }
 
-        let machine_pubs = sql_client
-            .search_publications_by_title("Machine", Some(1), Some(10))
+        let (machine_pubs, total) = sql_client
+            .search_publications_by_title("Machine", PageQuery::new(1, 10).unwrap())
             .await?;
 
         assert_eq!(machine_pubs.len(), 2);
+        assert_eq!(total, 2);
         for pub_item in &machine_pubs {
-            assert!(pub_item.title.contains("Machine"));
+            assert!(pub_item.publication.title.contains("Machine"));
         }
 
-        let learning_pubs = sql_client
-            .search_publications_by_title("Learning", Some(1), Some(10))
+        let (learning_pubs, _) = sql_client
+            .search_publications_by_title("Learning", PageQuery::new(1, 10).unwrap())
             .await?;
 
         assert_eq!(learning_pubs.len(), 2);
         for pub_item in &learning_pubs {
-            assert!(pub_item.title.contains("Learning"));
+            assert!(pub_item.publication.title.contains("Learning"));
         }
 
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn test_search_publications_by_title_relevance(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        // Created in the reverse order of their relevance, and alphabetically first for the
+        // least relevant ones
+        let titles = [
+            "A graph primer on very sparse structures",
+            "Hypergraphs",
+            "Graph theory",
+            "Graph",
+        ];
+        for (index, title) in titles.iter().enumerate() {
+            let user_privy_id =
+                create_test_user(&sql_client, &format!("relevance{}", index)).await?;
+            create_test_publication(&sql_client, &user_privy_id, Some(title)).await?;
+        }
+
+        let (matches, _) = sql_client
+            .search_publications_by_title("graph", PageQuery::new(1, 10).unwrap())
+            .await?;
+        let found: Vec<&str> = matches
+            .iter()
+            .map(|found| found.publication.title.as_str())
+            .collect();
+        assert_eq!(
+            found,
+            [
+                "Graph",
+                "Graph theory",
+                "Hypergraphs",
+                "A graph primer on very sparse structures"
+            ]
+        );
+        assert!(matches[0].score >= 2.0);
+        assert!((1.0..2.0).contains(&matches[1].score));
+        assert!(matches[2].score < 1.0);
+        assert!(matches[2].score > matches[3].score);
+
+        // Pages follow the same order
+        let mut paged = Vec::new();
+        for page in 1..=2 {
+            let (matches, total) = sql_client
+                .search_publications_by_title("graph", PageQuery::new(page, 2).unwrap())
+                .await?;
+            assert_eq!(total, 4);
+            paged.extend(matches.into_iter().map(|found| found.publication.title));
+        }
+        assert_eq!(paged, found);
+        // Pages past the last one still count the matches
+        let (matches, total) = sql_client
+            .search_publications_by_title("graph", PageQuery::new(3, 2).unwrap())
+            .await?;
+        assert_eq!((matches.len(), total), (0, 4));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_search_publications(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let user_privy_id = create_test_user(&sql_client, "full_text").await?;
+        let publications = [
+            ("Learn to rank", "Ranking search results.", vec!["search"]),
+            (
+                "Graph neural networks",
+                "Machine learning on graphs.",
+                vec!["ml"],
+            ),
+            (
+                "Consensus protocols",
+                "Byzantine agreement.",
+                vec!["distributed-systems", "learning"],
+            ),
+        ];
+        for (title, about, tags) in publications {
+            sql_client
+                .create_publication(&NewPublication {
+                    user_id: user_privy_id.clone(),
+                    title: title.to_string(),
+                    about: Some(about.to_string()),
+                    tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+                    s3key: None,
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
+                })
+                .await?;
+        }
+        let search = async |query: &str, page: PageQuery| -> sqlx::Result<(Vec<String>, i64)> {
+            let (found, total) = sql_client.search_publications(query, page).await?;
+            Ok((found.into_iter().map(|found| found.title).collect(), total))
+        };
+        let first_page = PageQuery::default();
+
+        // Stemmed words match in the title, abstract and tags, title matches ranking first
+        let (found, total) = search("learning", first_page).await?;
+        assert_eq!(total, 3);
+        assert_eq!(found[0], "Learn to rank");
+        assert_eq!(found.len(), 3);
+
+        // Every word must match, in any field
+        let (found, total) = search("machine graphs", first_page).await?;
+        assert_eq!(found, ["Graph neural networks"]);
+        assert_eq!(total, 1);
+        let (found, _) = search("byzantine learning", first_page).await?;
+        assert_eq!(found, ["Consensus protocols"]);
+
+        let (found, total) = search("quantum", first_page).await?;
+        assert!(found.is_empty());
+        assert_eq!(total, 0);
+
+        // A page past the last one still reports the total
+        let (found, total) = search("learning", PageQuery::new(2, 2).unwrap()).await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(total, 3);
+        let (found, total) = search("learning", PageQuery::new(3, 2).unwrap()).await?;
+        assert!(found.is_empty());
+        assert_eq!(total, 3);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn test_search_publications_by_tag(pool: sqlx::PgPool) -> sqlx::Result<()> {
         let sql_client = SqlClient::new(pool.clone()).await;
 
-        let publications = vec![
+        let publications = [
             ("Paper 1", vec!["ai".to_string(), "ml".to_string()]),
             ("Paper 2", vec!["ml".to_string(), "dl".to_string()]),
             ("Paper 3", vec!["ai".to_string(), "cv".to_string()]),
@@ -357,24 +821,28 @@ mod integration_tests {
                     about: Some("Test description".to_string()),
                     tags: Some(tags.clone()),
                     s3key: None,
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
                 })
                 .await?;
         }
 
-        let ai_pubs = sql_client
-            .search_publications_by_tag("ai", Some(1), Some(10))
+        let (ai_pubs, total) = sql_client
+            .search_publications_by_tag("ai", PageQuery::new(1, 10).unwrap())
             .await?;
 
         assert_eq!(ai_pubs.len(), 2);
+        assert_eq!(total, 2);
         for pub_item in &ai_pubs {
             assert!(pub_item.tags.contains(&"ai".to_string()));
         }
 
-        let ml_pubs = sql_client
-            .search_publications_by_tag("ml", Some(1), Some(10))
+        let (ml_pubs, total) = sql_client
+            .search_publications_by_tag("ml", PageQuery::new(1, 1).unwrap())
             .await?;
 
-        assert_eq!(ml_pubs.len(), 2);
+        assert_eq!((ml_pubs.len(), total), (1, 2));
         for pub_item in &ml_pubs {
             assert!(pub_item.tags.contains(&"ml".to_string()));
         }
@@ -401,6 +869,9 @@ mod integration_tests {
                     about: None,
                     tags: None,
                     s3key: None,
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
                 })
                 .await?;
             publications.push(publication);
@@ -418,7 +889,8 @@ mod integration_tests {
         let pub1_citations = sql_client
             .get_publication_citations(publications[1].id)
             .await?;
-        assert_eq!(pub1_citations.len(), 2);
+        assert_eq!(pub1_citations.len(), 1);
+        assert_eq!(pub1_citations[0].id(), publications[2].id);
 
         let cited_by = sql_client.get_cited_by(publications[2].id).await?;
         assert_eq!(cited_by.len(), 1);
@@ -427,6 +899,310 @@ mod integration_tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn test_soft_deleted_publications(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let user_privy_id = create_test_user(&sql_client, "soft_delete").await?;
+        create_test_author(&sql_client, &user_privy_id).await?;
+
+        let kept = create_test_publication(&sql_client, &user_privy_id, Some("Kept paper")).await?;
+        let deleted =
+            create_test_publication(&sql_client, &user_privy_id, Some("Deleted paper")).await?;
+        for publication in [&kept, &deleted] {
+            sql_client
+                .set_publication_authors(publication.id, std::slice::from_ref(&user_privy_id))
+                .await?;
+        }
+        // Each cites the other
+        for (citing, cited) in [(&kept, &deleted), (&deleted, &kept)] {
+            sql_client
+                .create_citation(&NewCitation {
+                    citing_publication_id: citing.id,
+                    cited_publication_id: cited.id,
+                })
+                .await?;
+        }
+
+        let result = sql_client.delete_publication(deleted.id).await?;
+        assert_eq!(result.rows_affected(), 1);
+        // Deleting it again finds nothing
+        let result = sql_client.delete_publication(deleted.id).await?;
+        assert_eq!(result.rows_affected(), 0);
+
+        assert!(matches!(
+            sql_client.get_publication(deleted.id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        let (listed, total) = sql_client.list_publications(PageQuery::default()).await?;
+        assert_eq!(total, 1);
+        assert_eq!(
+            listed.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![kept.id]
+        );
+        assert_eq!(sql_client.count_publications().await?, 1);
+        assert_eq!(
+            sql_client
+                .count_publications_by_user(&user_privy_id)
+                .await?,
+            1
+        );
+        let (by_user, _) = sql_client
+            .list_publications_by_user(&user_privy_id, PageQuery::default())
+            .await?;
+        assert_eq!(by_user.len(), 1);
+        let cursor = Cursor {
+            created_at: chrono::Utc::now() + chrono::Duration::days(1),
+            id: Uuid::max(),
+        };
+        let after = sql_client.list_publications_after(cursor, 10).await?;
+        assert_eq!(
+            after.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![kept.id]
+        );
+        assert_eq!(
+            sql_client
+                .search_publications_by_title("Deleted", PageQuery::default())
+                .await?
+                .1,
+            0
+        );
+        let (tagged, total) = sql_client
+            .search_publications_by_tag("test", PageQuery::default())
+            .await?;
+        assert_eq!((tagged.len(), total), (1, 1));
+        let (found, total) = sql_client
+            .search_publications("paper", PageQuery::default())
+            .await?;
+        assert_eq!((found.len(), total), (1, 1));
+        let trending = sql_client.get_trending_publications(7, 10).await?;
+        assert_eq!(
+            trending
+                .iter()
+                .map(|t| t.publication.id)
+                .collect::<Vec<_>>(),
+            vec![kept.id]
+        );
+        // Citations made by the deleted publication no longer count
+        assert_eq!(trending[0].recent_citations, 0);
+        assert_eq!(sql_client.count_citations_to_publication(kept.id).await?, 0);
+        assert!(
+            sql_client
+                .count_citations_to_publications(&[kept.id])
+                .await?
+                .is_empty()
+        );
+        assert_eq!(
+            sql_client
+                .count_citations_from_publication(deleted.id)
+                .await?,
+            0
+        );
+        let by_author = PublicationAuthorOperations::get_author_publications(
+            &sql_client,
+            &user_privy_id,
+            PageQuery::default(),
+        )
+        .await?;
+        assert_eq!(by_author.len(), 1);
+        let updated = sql_client
+            .update_publication(deleted.id, None, Some("Edited"), None, None, None)
+            .await?;
+        assert_eq!(updated.rows_affected(), 0);
+
+        // Citations survive, the deleted cited publication resolving to a tombstone
+        let citations = sql_client.get_publication_citations(kept.id).await?;
+        assert_eq!(citations.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&citations[0]).unwrap(),
+            serde_json::json!({ "id": deleted.id, "deleted": true })
+        );
+        assert!(sql_client.get_cited_by(kept.id).await?.is_empty());
+
+        // Publications deleted before the retention cutoff can no longer be restored
+        assert!(matches!(
+            sql_client
+                .restore_publication(deleted.id, Utc::now() + TimeDelta::minutes(1))
+                .await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        let restored = sql_client
+            .restore_publication(deleted.id, Utc::now() - TimeDelta::days(30))
+            .await?;
+        assert_eq!(restored.title, "Deleted paper");
+        assert_eq!(sql_client.count_citations_to_publication(kept.id).await?, 1);
+        assert_eq!(sql_client.count_publications().await?, 2);
+        assert!(matches!(
+            sql_client.get_publication_citations(kept.id).await?[0],
+            CitedPublication::Publication(_)
+        ));
+        assert_eq!(sql_client.get_cited_by(kept.id).await?.len(), 1);
+        // Only deleted publications can be restored
+        assert!(matches!(
+            sql_client
+                .restore_publication(deleted.id, Utc::now() - TimeDelta::days(30))
+                .await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_batch_publication_queries(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let first_author = create_test_user(&sql_client, "batch_first").await?;
+        let second_author = create_test_user(&sql_client, "batch_second").await?;
+        for privy_id in [&first_author, &second_author] {
+            create_test_author(&sql_client, privy_id).await?;
+        }
+
+        let cited = create_test_publication(&sql_client, &first_author, Some("Cited")).await?;
+        let citing = create_test_publication(&sql_client, &first_author, Some("Citing")).await?;
+        let deleted = create_test_publication(&sql_client, &first_author, Some("Deleted")).await?;
+        sql_client
+            .set_publication_authors(cited.id, &[second_author.clone(), first_author.clone()])
+            .await?;
+        sql_client
+            .create_citation(&NewCitation {
+                citing_publication_id: citing.id,
+                cited_publication_id: cited.id,
+            })
+            .await?;
+        sql_client.record_view(cited.id, None).await?;
+        sql_client.record_view(cited.id, None).await?;
+        sql_client.record_view(citing.id, None).await?;
+        sql_client.delete_publication(deleted.id).await?;
+
+        let ids = [cited.id, citing.id, deleted.id, Uuid::new_v4()];
+        let mut found: Vec<Uuid> = sql_client
+            .get_publications_by_ids(&ids)
+            .await?
+            .into_iter()
+            .map(|publication| publication.id)
+            .collect();
+        found.sort();
+        let mut expected = vec![cited.id, citing.id];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        let authors = sql_client.get_publications_author_profiles(&ids).await?;
+        assert_eq!(authors.len(), 1);
+        assert_eq!(
+            authors[&cited.id]
+                .iter()
+                .map(|author| &author.privy_id)
+                .collect::<Vec<_>>(),
+            vec![&second_author, &first_author]
+        );
+
+        let citation_counts = sql_client.count_citations_to_publications(&ids).await?;
+        assert_eq!(citation_counts, [(cited.id, 1)].into());
+        let view_counts = sql_client.get_view_counts(&ids).await?;
+        assert_eq!(view_counts, [(cited.id, 2), (citing.id, 1)].into());
+
+        assert!(sql_client.get_publications_by_ids(&[]).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_get_trending_publications(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let user_privy_id = create_test_user(&sql_client, "trending").await?;
+
+        let mut publications = vec![];
+        for i in 0..6 {
+            let publication =
+                create_test_publication(&sql_client, &user_privy_id, Some(&format!("Paper {i}")))
+                    .await?;
+            publications.push(publication.id);
+        }
+        let ranked = async |window_days, limit| -> sqlx::Result<Vec<(Uuid, i64)>> {
+            Ok(sql_client
+                .get_trending_publications(window_days, limit)
+                .await?
+                .into_iter()
+                .map(|trending| (trending.publication.id, trending.recent_citations))
+                .collect())
+        };
+        let p = &publications;
+
+        // Without citations, the newest publications come first
+        assert_eq!(ranked(7, 3).await?, vec![(p[5], 0), (p[4], 0), (p[3], 0)]);
+
+        // 1 is cited twice an hour ago, 2 three times six days ago and 3 once a month ago
+        for (cited, citing, hours_ago) in [
+            (1, &[0, 4][..], 1),
+            (2, &[0, 4, 5][..], 6 * 24),
+            (3, &[0][..], 30 * 24),
+        ] {
+            for &citing in citing {
+                sql_client
+                    .create_citation(&NewCitation {
+                        citing_publication_id: p[citing],
+                        cited_publication_id: p[cited],
+                    })
+                    .await?;
+            }
+            sqlx::query(
+                "UPDATE citations SET created_at = NOW() - make_interval(hours => $1) WHERE cited_publication_id = $2",
+            )
+            .bind(hours_ago)
+            .bind(p[cited])
+            .execute(&pool)
+            .await?;
+        }
+
+        // Recent citations outweigh older ones, and newer publications follow
+        assert_eq!(ranked(7, 3).await?, vec![(p[1], 2), (p[2], 3), (p[5], 0)]);
+        assert_eq!(
+            ranked(60, 6).await?,
+            vec![
+                (p[1], 2),
+                (p[2], 3),
+                (p[3], 1),
+                (p[5], 0),
+                (p[4], 0),
+                (p[0], 0)
+            ]
+        );
+        assert_eq!(ranked(1, 3).await?, vec![(p[1], 2), (p[5], 0), (p[4], 0)]);
+
+        let scores: Vec<f64> = sql_client
+            .get_trending_publications(60, 3)
+            .await?
+            .iter()
+            .map(|trending| trending.score)
+            .collect();
+        assert!(scores[0] > 1.9 && scores[0] <= 2.0, "{scores:?}");
+        assert!(scores[2] > 0.0 && scores[2] < 0.001, "{scores:?}");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_view_operations(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let user_privy_id = create_test_user(&sql_client, "viewer").await?;
+        let publication = create_test_publication(&sql_client, &user_privy_id, None).await?;
+        let other = create_test_publication(&sql_client, &user_privy_id, None).await?;
+
+        assert_eq!(sql_client.get_view_count(publication.id).await?, 0);
+        sql_client
+            .record_view(publication.id, Some(&user_privy_id))
+            .await?;
+        sql_client.record_view(publication.id, None).await?;
+        sql_client.record_view(other.id, None).await?;
+        assert_eq!(sql_client.get_view_count(publication.id).await?, 2);
+
+        // Views are kept while the publication is deleted, for when it is restored
+        sql_client.delete_publication(publication.id).await?;
+        assert_eq!(sql_client.get_view_count(publication.id).await?, 2);
+        assert_eq!(sql_client.get_view_count(other.id).await?, 1);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn test_author_publications_relationship(pool: sqlx::PgPool) -> sqlx::Result<()> {
         let sql_client = SqlClient::new(pool.clone()).await;
@@ -436,8 +1212,7 @@ mod integration_tests {
         };
         sql_client.create_user(&new_user).await?;
 
-        let author =
-            create_test_author(&sql_client, &"test_author_relationship".to_string()).await?;
+        let author = create_test_author(&sql_client, "test_author_relationship").await?;
 
         let mut publications = Vec::new();
         for i in 0..5 {
@@ -453,19 +1228,22 @@ mod integration_tests {
                     about: None,
                     tags: None,
                     s3key: None,
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
                 })
                 .await?;
             publications.push(publication);
         }
 
-        for i in 0..3 {
+        for (i, publication) in publications.iter().take(3).enumerate() {
             sql_client
-                .add_author_to_publication(publications[i].id, &author.privy_id, Some(i as i32))
+                .add_author_to_publication(publication.id, &author.privy_id, Some(i as i32))
                 .await?;
         }
 
         let author_pubs = sql_client
-            .get_author_publications(&author.privy_id, Some(1), Some(10))
+            .get_author_publications(&author.privy_id, PageQuery::new(1, 10).unwrap())
             .await?;
 
         assert_eq!(author_pubs.len(), 3);
@@ -520,6 +1298,9 @@ mod integration_tests {
             about: Some("This is a test publication".to_string()),
             tags: Some(vec!["test".to_string(), "ai".to_string()]),
             s3key: Some("s3://bucket/key.pdf".to_string()),
+            paper_hash: Some("ab".repeat(32)),
+            file_sha256: Some("cd".repeat(32)),
+            file_size: Some(1024),
         };
 
         let publication = sql_client.create_publication(&new_publication).await?;
@@ -530,10 +1311,22 @@ mod integration_tests {
         );
         assert_eq!(publication.tags, vec!["test".to_string(), "ai".to_string()]);
         assert_eq!(publication.s3key, Some("s3://bucket/key.pdf".to_string()));
+        assert_eq!(publication.paper_hash, Some("ab".repeat(32)));
+        assert_eq!(publication.file_sha256, Some("cd".repeat(32)));
+        assert_eq!(publication.file_size, Some(1024));
 
+        // Every column written on creation is read back
         let retrieved_publication = sql_client.get_publication(publication.id).await?;
         assert_eq!(retrieved_publication.id, publication.id);
+        assert_eq!(retrieved_publication.user_id, Some(user_privy_id.clone()));
         assert_eq!(retrieved_publication.title, "Test Publication");
+        assert_eq!(retrieved_publication.about, publication.about);
+        assert_eq!(retrieved_publication.tags, publication.tags);
+        assert_eq!(retrieved_publication.s3key, publication.s3key);
+        assert_eq!(retrieved_publication.paper_hash, publication.paper_hash);
+        assert_eq!(retrieved_publication.file_sha256, publication.file_sha256);
+        assert_eq!(retrieved_publication.file_size, Some(1024));
+        assert_eq!(retrieved_publication.created_at, publication.created_at);
 
         let update_result = sql_client
             .update_publication(
@@ -562,6 +1355,16 @@ mod integration_tests {
             Some("s3://bucket/updated.pdf".to_string())
         );
 
+        let hashes_result = sql_client
+            .set_publication_file_details(publication.id, &"ef".repeat(32), &"01".repeat(32), 2048)
+            .await?;
+        assert_eq!(hashes_result.rows_affected(), 1);
+
+        let rehashed_publication = sql_client.get_publication(publication.id).await?;
+        assert_eq!(rehashed_publication.paper_hash, Some("ef".repeat(32)));
+        assert_eq!(rehashed_publication.file_sha256, Some("01".repeat(32)));
+        assert_eq!(rehashed_publication.file_size, Some(2048));
+
         let delete_result = sql_client.delete_publication(publication.id).await?;
         assert!(delete_result.rows_affected() > 0);
 
@@ -596,15 +1399,19 @@ mod integration_tests {
                     about: None,
                     tags: None,
                     s3key: None,
+                    paper_hash: None,
+                    file_sha256: None,
+                    file_size: None,
                 })
                 .await?;
         }
 
-        let user_publications = sql_client
-            .list_publications_by_user(&user_privy_id, Some(1), Some(10))
+        let (user_publications, total_count) = sql_client
+            .list_publications_by_user(&user_privy_id, PageQuery::new(1, 10).unwrap())
             .await?;
 
         assert_eq!(user_publications.len(), 3);
+        assert_eq!(total_count, 3);
         for pub_item in &user_publications {
             assert_eq!(pub_item.user_id, Some(user_privy_id.clone()));
             assert!(pub_item.title.starts_with("User Publication"));
@@ -632,11 +1439,17 @@ mod integration_tests {
             .await?;
         }
 
-        let page1 = sql_client.list_publications(Some(1), Some(3)).await?;
+        let (page1, total_count) = sql_client
+            .list_publications(PageQuery::new(1, 3).unwrap())
+            .await?;
         assert_eq!(page1.len(), 3);
+        assert_eq!(total_count, 5);
 
-        let page2 = sql_client.list_publications(Some(2), Some(3)).await?;
+        let (page2, total_count) = sql_client
+            .list_publications(PageQuery::new(2, 3).unwrap())
+            .await?;
         assert_eq!(page2.len(), 2);
+        assert_eq!(total_count, 5);
 
         let total_count = sql_client.count_publications().await?;
         assert_eq!(total_count, 5);
@@ -644,6 +1457,85 @@ mod integration_tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn test_list_publications_after(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        let user_privy_id = create_test_user(&sql_client, "cursor").await?;
+
+        let mut existing = vec![];
+        for i in 0..7 {
+            let publication =
+                create_test_publication(&sql_client, &user_privy_id, Some(&format!("Old {i}")))
+                    .await?;
+            existing.push(publication.id);
+        }
+        // Publications created at the same time are ordered by id
+        sqlx::query("UPDATE publications SET created_at = (SELECT MIN(created_at) FROM publications) WHERE id = ANY($1)")
+            .bind(&existing[..4])
+            .execute(&pool)
+            .await?;
+
+        // Publications keep being created while the listing is walked
+        let inserts = async {
+            for i in 0..10 {
+                create_test_publication(&sql_client, &user_privy_id, Some(&format!("New {i}")))
+                    .await?;
+            }
+            Ok::<_, sqlx::Error>(())
+        };
+        let walk = async {
+            let (first_page, _) = sql_client
+                .list_publications(PageQuery::new(1, 2).unwrap())
+                .await?;
+            let mut walked: Vec<_> = first_page
+                .iter()
+                .map(|publication| publication.id)
+                .collect();
+            let mut cursor = first_page.last().map(|last| Cursor {
+                created_at: last.created_at,
+                id: last.id,
+            });
+            while let Some(after) = cursor {
+                let page = sql_client.list_publications_after(after, 2).await?;
+                for publication in &page {
+                    assert!(
+                        (publication.created_at, publication.id) < (after.created_at, after.id),
+                        "{} is not after the cursor",
+                        publication.title
+                    );
+                }
+                walked.extend(page.iter().map(|publication| publication.id));
+                cursor = page.last().map(|last| Cursor {
+                    created_at: last.created_at,
+                    id: last.id,
+                });
+            }
+            Ok::<_, sqlx::Error>(walked)
+        };
+        let ((), walked) = futures::try_join!(inserts, walk)?;
+
+        // Every publication created before the walk is listed once, none is repeated
+        let unique: std::collections::HashSet<_> = walked.iter().collect();
+        assert_eq!(unique.len(), walked.len());
+        for id in &existing {
+            assert!(walked.contains(id), "{id} was skipped");
+        }
+
+        // The walk follows the order of the pages
+        let (all, total_count) = sql_client
+            .list_publications(PageQuery::new(1, 100).unwrap())
+            .await?;
+        assert_eq!(total_count, 17);
+        let listed: Vec<_> = all
+            .iter()
+            .map(|publication| publication.id)
+            .filter(|id| walked.contains(id))
+            .collect();
+        assert_eq!(listed, walked);
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn test_publication_pagination_edge_cases(pool: sqlx::PgPool) -> sqlx::Result<()> {
         let sql_client = SqlClient::new(pool.clone()).await;
@@ -658,17 +1550,28 @@ mod integration_tests {
             .await?;
         }
 
-        let default_page = sql_client.list_publications(None, None).await?;
+        let (default_page, total_count) =
+            sql_client.list_publications(PageQuery::default()).await?;
         assert_eq!(default_page.len(), 20);
+        assert_eq!(total_count, 25);
 
-        let empty_page = sql_client.list_publications(Some(10), Some(10)).await?;
+        // An empty page has no row to carry the total, which is counted separately
+        let (empty_page, total_count) = sql_client
+            .list_publications(PageQuery::new(10, 10).unwrap())
+            .await?;
         assert!(empty_page.is_empty());
+        assert_eq!(total_count, 25);
 
-        let small_page = sql_client.list_publications(Some(1), Some(5)).await?;
+        let (small_page, _) = sql_client
+            .list_publications(PageQuery::new(1, 5).unwrap())
+            .await?;
         assert_eq!(small_page.len(), 5);
 
-        let large_page = sql_client.list_publications(Some(1), Some(100)).await?;
+        let (large_page, total_count) = sql_client
+            .list_publications(PageQuery::new(1, 100).unwrap())
+            .await?;
         assert_eq!(large_page.len(), 25);
+        assert_eq!(total_count, 25);
 
         Ok(())
     }
@@ -686,6 +1589,9 @@ mod integration_tests {
                 about: Some("Original description".to_string()),
                 tags: Some(vec!["original".to_string()]),
                 s3key: Some("s3://original.pdf".to_string()),
+                paper_hash: None,
+                file_sha256: None,
+                file_size: None,
             })
             .await?;
 
@@ -752,4 +1658,130 @@ mod integration_tests {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn test_statement_timeout(
+        _pool_options: PgPoolOptions,
+        connect_options: PgConnectOptions,
+    ) -> sqlx::Result<()> {
+        let config = DatabasePoolConfig {
+            max_connections: 1,
+            min_connections: 0,
+            acquire_timeout_secs: 5,
+            statement_timeout_ms: Some(100),
+            slow_query_ms: 1000,
+        };
+        let pool = sql::pool_options(&config)
+            .connect_with(sql::connect_options(connect_options, &config))
+            .await?;
+
+        let err = sqlx::query("SELECT pg_sleep(1)")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+        // query_canceled
+        assert_eq!(
+            err.as_database_error()
+                .and_then(|err| err.code())
+                .as_deref(),
+            Some("57014")
+        );
+
+        sqlx::query("SELECT pg_sleep(0.01)").execute(&pool).await?;
+        Ok(())
+    }
+
+    /// Collects the names of the indexes scanned by a node of a JSON query plan and its children.
+    fn plan_indexes(plan: &serde_json::Value, indexes: &mut Vec<String>) {
+        if let Some(index) = plan["Index Name"].as_str() {
+            indexes.push(index.to_string());
+        }
+        for child in plan["Plans"].as_array().into_iter().flatten() {
+            plan_indexes(child, indexes);
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_queries_use_indexes(pool: sqlx::PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool.clone()).await;
+        crate::seed::seed(&sql_client, 10).await?;
+
+        let mut tx = pool.begin().await?;
+        // Seeded tables are small enough for a sequential scan to be cheaper, which the planner
+        // must only fall back to when no index supports the query
+        sqlx::query("SET LOCAL enable_seqscan = off")
+            .execute(&mut *tx)
+            .await?;
+
+        let mut indexes_of = async |query: sqlx::query::Query<'_, _, _>| -> sqlx::Result<_> {
+            let plan: serde_json::Value = sqlx::Row::try_get(&query.fetch_one(&mut *tx).await?, 0)?;
+            let mut indexes = vec![];
+            plan_indexes(&plan[0]["Plan"], &mut indexes);
+            Ok(indexes)
+        };
+
+        let explain = |query: &str| format!("EXPLAIN (FORMAT JSON) {query}");
+
+        let tag_query = explain(SEARCH_BY_TAG_QUERY);
+        let indexes = indexes_of(
+            sqlx::query(&tag_query)
+                .bind("cryptography")
+                .bind(20i64)
+                .bind(0i64),
+        )
+        .await?;
+        assert!(
+            indexes.contains(&"idx_publications_tags".to_string()),
+            "{indexes:?}"
+        );
+
+        let title_query = explain(SEARCH_BY_TITLE_QUERY);
+        let indexes = indexes_of(
+            sqlx::query(&title_query)
+                .bind("%consensus%")
+                .bind(20i64)
+                .bind(0i64)
+                .bind("consensus"),
+        )
+        .await?;
+        assert!(
+            indexes.contains(&"idx_publications_title_trgm".to_string()),
+            "{indexes:?}"
+        );
+
+        let search_query = explain(SEARCH_QUERY);
+        let indexes = indexes_of(
+            sqlx::query(&search_query)
+                .bind("consensus")
+                .bind(20i64)
+                .bind(0i64),
+        )
+        .await?;
+        assert!(
+            indexes.contains(&"idx_publications_search_document".to_string()),
+            "{indexes:?}"
+        );
+
+        let list_query = explain(LIST_AFTER_QUERY);
+        let indexes = indexes_of(
+            sqlx::query(&list_query)
+                .bind(chrono::Utc::now())
+                .bind(Uuid::new_v4())
+                .bind(20i64),
+        )
+        .await?;
+        assert!(
+            indexes.contains(&"idx_publications_created_at".to_string()),
+            "{indexes:?}"
+        );
+
+        let count_query = explain(COUNT_CITATIONS_TO_PUBLICATION_QUERY);
+        let indexes = indexes_of(sqlx::query(&count_query).bind(Uuid::new_v4())).await?;
+        assert!(
+            indexes.contains(&"idx_citations_cited_publication_id".to_string()),
+            "{indexes:?}"
+        );
+
+        Ok(())
+    }
 }
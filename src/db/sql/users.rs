@@ -1,7 +1,13 @@
 use async_trait::async_trait;
 use sqlx::postgres::PgQueryResult;
 
-use crate::db::sql::{PrivyId, SqlClient, models::User};
+use crate::{
+    common::pagination::PageQuery,
+    db::sql::{
+        PrivyId, SqlClient,
+        models::{StorageUsage, User},
+    },
+};
 
 #[async_trait]
 pub trait UserOperations {
@@ -9,17 +15,26 @@ pub trait UserOperations {
 
     async fn get_user(&self, privy_id: PrivyId) -> Result<User, sqlx::Error>;
 
-    async fn list_users(
-        &self,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<User>, sqlx::Error>;
+    async fn list_users(&self, page: PageQuery) -> Result<(Vec<User>, i64), sqlx::Error>;
 
     async fn delete_user(&self, privy_id: PrivyId) -> Result<PgQueryResult, sqlx::Error>;
 
     async fn count_users(&self) -> Result<i64, sqlx::Error>;
 
     async fn get_user_by_privy_id(&self, privy_id: PrivyId) -> Result<User, sqlx::Error>;
+
+    async fn set_user_admin(
+        &self,
+        privy_id: &PrivyId,
+        is_admin: bool,
+    ) -> Result<PgQueryResult, sqlx::Error>;
+
+    /// Returns the number of bytes of files stored for the user's publications, main files and
+    /// supplementary files alike.
+    async fn get_storage_usage(&self, privy_id: &str) -> Result<i64, sqlx::Error>;
+
+    /// Returns the `limit` users storing the most bytes, largest first.
+    async fn list_storage_usage(&self, limit: i64) -> Result<Vec<StorageUsage>, sqlx::Error>;
 }
 
 #[async_trait]
@@ -30,7 +45,7 @@ impl UserOperations for SqlClient {
             INSERT INTO users 
             (privy_id)
             VALUES ($1)
-            RETURNING privy_id, created_at, updated_at
+            RETURNING privy_id, is_admin, created_at, updated_at
             "#,
         )
         .bind(&new_user.privy_id)
@@ -41,7 +56,7 @@ impl UserOperations for SqlClient {
     async fn get_user(&self, privy_id: PrivyId) -> Result<User, sqlx::Error> {
         sqlx::query_as::<_, User>(
             r#"
-            SELECT privy_id, created_at, updated_at
+            SELECT privy_id, is_admin, created_at, updated_at
             FROM users 
             WHERE privy_id = $1
             "#,
@@ -51,27 +66,20 @@ impl UserOperations for SqlClient {
         .await
     }
 
-    async fn list_users(
-        &self,
-        page: Option<i64>,
-        limit: Option<i64>,
-    ) -> Result<Vec<User>, sqlx::Error> {
-        let page = page.unwrap_or(1);
-        let limit = limit.unwrap_or(20);
-        let offset = (page - 1) * limit;
-
-        sqlx::query_as::<_, User>(
+    async fn list_users(&self, page: PageQuery) -> Result<(Vec<User>, i64), sqlx::Error> {
+        let query = sqlx::query(
             r#"
-            SELECT privy_id, created_at, updated_at
+            SELECT privy_id, is_admin, created_at, updated_at, COUNT(*) OVER() AS total_count
             FROM users 
             ORDER BY created_at DESC
             LIMIT $1 OFFSET $2
             "#,
         )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&self.db)
-        .await
+        .bind(page.limit())
+        .bind(page.offset());
+
+        self.fetch_page(query, page.offset(), self.count_users())
+            .await
     }
 
     async fn delete_user(&self, privy_id: PrivyId) -> Result<PgQueryResult, sqlx::Error> {
@@ -91,4 +99,64 @@ impl UserOperations for SqlClient {
         // This is now the same as get_user, but we keep it for API compatibility
         self.get_user(privy_id).await
     }
+
+    async fn set_user_admin(
+        &self,
+        privy_id: &PrivyId,
+        is_admin: bool,
+    ) -> Result<PgQueryResult, sqlx::Error> {
+        sqlx::query("UPDATE users SET is_admin = $1, updated_at = NOW() WHERE privy_id = $2")
+            .bind(is_admin)
+            .bind(privy_id)
+            .execute(&self.db)
+            .await
+    }
+
+    async fn get_storage_usage(&self, privy_id: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT (
+                COALESCE((SELECT SUM(file_size) FROM publications WHERE user_id = $1), 0)
+                + COALESCE((
+                    SELECT SUM(f.size) FROM publication_files f
+                    JOIN publications p ON p.id = f.publication_id
+                    WHERE p.user_id = $1
+                ), 0)
+            )::BIGINT
+            "#,
+        )
+        .bind(privy_id)
+        .fetch_one(&self.db)
+        .await
+    }
+
+    async fn list_storage_usage(&self, limit: i64) -> Result<Vec<StorageUsage>, sqlx::Error> {
+        sqlx::query_as::<_, StorageUsage>(
+            r#"
+            WITH publication_usage AS (
+                SELECT user_id, SUM(file_size)::BIGINT AS bytes
+                FROM publications
+                GROUP BY user_id
+            ), supplementary_usage AS (
+                SELECT p.user_id, SUM(f.size)::BIGINT AS bytes
+                FROM publication_files f
+                JOIN publications p ON p.id = f.publication_id
+                GROUP BY p.user_id
+            )
+            SELECT u.privy_id,
+                COALESCE(pu.bytes, 0) AS publication_bytes,
+                COALESCE(su.bytes, 0) AS supplementary_bytes,
+                COALESCE(pu.bytes, 0) + COALESCE(su.bytes, 0) AS total_bytes
+            FROM users u
+            LEFT JOIN publication_usage pu ON pu.user_id = u.privy_id
+            LEFT JOIN supplementary_usage su ON su.user_id = u.privy_id
+            WHERE COALESCE(pu.bytes, 0) + COALESCE(su.bytes, 0) > 0
+            ORDER BY total_bytes DESC, u.privy_id
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+    }
 }
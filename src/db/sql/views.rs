@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::db::sql::{PrivyId, SqlClient};
+
+#[async_trait]
+pub trait ViewOperations {
+    /// Records a view of the publication, by `viewer` unless anonymous.
+    async fn record_view(
+        &self,
+        publication_id: Uuid,
+        viewer: Option<&PrivyId>,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn get_view_count(&self, publication_id: Uuid) -> Result<i64, sqlx::Error>;
+
+    /// Counts the views of each of the publications, those without any being left out.
+    async fn get_view_counts(
+        &self,
+        publication_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, i64>, sqlx::Error>;
+}
+
+#[async_trait]
+impl ViewOperations for SqlClient {
+    async fn record_view(
+        &self,
+        publication_id: Uuid,
+        viewer: Option<&PrivyId>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO publication_views (publication_id, viewer_id) VALUES ($1, $2)")
+            .bind(publication_id)
+            .bind(viewer)
+            .execute(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_view_count(&self, publication_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM publication_views WHERE publication_id = $1")
+            .bind(publication_id)
+            .fetch_one(&self.db)
+            .await
+    }
+
+    async fn get_view_counts(
+        &self,
+        publication_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, i64>, sqlx::Error> {
+        let counts: Vec<(Uuid, i64)> = sqlx::query_as(
+            r#"
+            SELECT publication_id, COUNT(*)
+            FROM publication_views
+            WHERE publication_id = ANY($1)
+            GROUP BY publication_id
+            "#,
+        )
+        .bind(publication_ids)
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(counts.into_iter().collect())
+    }
+}
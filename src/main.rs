@@ -1,33 +1,88 @@
-use std::sync::Arc;
+use std::{
+    process::ExitCode,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::{
+    api::{
+        publications::{
+            cache::{PUBLICATION_CACHE_TTL, PublicationCache},
+            upload_intents::{UPLOAD_INTENT_TTL, UploadIntents},
+            views::{VIEW_DEDUPLICATION_WINDOW, ViewDeduplication},
+        },
+        rate_limit::{MemoryRateLimitStore, RateLimitStore, RateLimiter, RedisRateLimitStore},
+    },
+    auth::{internal::InternalApiToken, revocation::SessionRevocation, session::CookieSessions},
+    cli::{Cli, Command},
+    common::{
+        error_reporting, logging,
+        shutdown::{self, TaskRegistry},
+        tasks::TaskSupervisor,
+    },
     config::Config,
     db::{
-        s3::{S3Bucket, client::S3Client},
+        kv::{KeyValueStore, MemoryKeyValueStore, RedisKeyValueStore},
+        s3::{
+            ObjectStore, S3Bucket,
+            client::S3Client,
+            disabled::DisabledObjectStore,
+            metrics::{InstrumentedObjectStore, StorageMetrics},
+        },
         sql::SqlClient,
     },
 };
 use actix_cors::Cors;
 use actix_web::{App, HttpServer, http::header, middleware, web};
-use aws_sdk_s3::config::Credentials;
+use clap::Parser;
 use dotenv::dotenv;
 use lazy_static::lazy_static;
 use privy_rs::PrivyClient;
 use redis::Client;
-use sqlx::postgres::PgPoolOptions;
-use tracing::level_filters::LevelFilter;
-use tracing_subscriber::EnvFilter;
 
 pub mod api;
 pub mod auth;
+pub mod cli;
 pub mod common;
 pub mod config;
 pub mod db;
+pub mod seed;
+pub mod server;
 
 pub struct AppState {
     sql_client: Arc<SqlClient>,
-    redis_client: Client,
-    s3_client: Arc<S3Client>,
+    /// Redis connection, unless it is disabled
+    redis_client: Option<Client>,
+    object_store: Arc<dyn ObjectStore>,
+    /// Measurements of the operations of `object_store`, exposed on `/metrics`
+    storage_metrics: Arc<StorageMetrics>,
+    /// Public URL of the server, which exported citations link to
+    server_base_url: String,
+    /// Lifetime of the presigned download URLs handed out to clients
+    presign_expiry: Duration,
+    /// Maximum number of bytes of files each user may store, unlimited if not set
+    storage_quota: Option<i64>,
+    /// Largest publication file accepted
+    max_publication_file_size: i64,
+    /// Denylist of logged out and revoked sessions
+    session_revocation: Arc<SessionRevocation>,
+    /// Cookie sessions opened on sign-in, authenticating requests without a token
+    sessions: Arc<CookieSessions>,
+    /// Tokens accepted from internal services by [auth::RequireScope]
+    internal_tokens: Arc<[InternalApiToken]>,
+    /// Per-user limits of expensive endpoints, applied by [api::rate_limit::RateLimit]
+    rate_limiter: Arc<RateLimiter>,
+    /// Work spawned by requests that must finish before the process exits, such as audit log
+    /// writes
+    background_tasks: Arc<TaskRegistry>,
+    /// Long-lived tasks restarted when they fail, whose status is exposed on `/admin/tasks`
+    supervisor: Arc<TaskSupervisor>,
+    /// Publications served by `GET /publications/{id}`, kept for a short time
+    publication_cache: Arc<PublicationCache>,
+    /// Recent views of publications by each user, counted once
+    view_deduplication: Arc<ViewDeduplication>,
+    /// Keys of the files uploaded directly to S3, with the users they were issued to
+    upload_intents: Arc<UploadIntents>,
 }
 
 lazy_static! {
@@ -35,76 +90,178 @@ lazy_static! {
 }
 
 #[actix_web::main]
-async fn main() -> std::io::Result<()> {
+async fn main() -> ExitCode {
     dotenv().ok();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::builder()
-                .with_default_directive(LevelFilter::INFO.into())
-                .from_env_lossy(),
-        )
-        .init();
-
-    let client = PrivyClient::new_from_env().unwrap();
-
-    let pool = match PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&CONFIG.database_url)
-        .await
-    {
-        Ok(pool) => {
-            println!("✅Connection to the database is successful!");
-            pool
-        }
-        Err(err) => {
-            println!("🔥 Failed to connect to the database: {}", err);
-            std::process::exit(1);
-        }
+    match Cli::parse().command.unwrap_or(Command::Serve) {
+        Command::Serve => match serve().await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                tracing::error!("Server failed: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Task(task) => cli::run(task).await,
+    }
+}
+
+/// Serves the API until the process is asked to stop. The full configuration is only read here,
+/// the other commands reading the variables they use.
+async fn serve() -> std::io::Result<()> {
+    logging::init(CONFIG.log_format);
+    let _error_reporting = error_reporting::init(CONFIG.sentry_dsn.as_deref());
+
+    // Built from the validated configuration, as `PrivyClient::new_from_env` reads the
+    // `PRIVY_TEST_*` variables of the SDK's own tests
+    let _privy_client =
+        match PrivyClient::new(CONFIG.privy_app_id.clone(), CONFIG.privy_app_secret.clone()) {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::error!("Failed to create the Privy client: {}", err);
+                std::process::exit(1);
+            }
+        };
+
+    let Some(pool) = cli::connect_database(&CONFIG.database_url, &CONFIG.database_pool).await
+    else {
+        std::process::exit(1);
     };
 
     let sql_client = Arc::new(SqlClient::new(pool).await);
 
-    let redis_client = match Client::open(CONFIG.redis_url.to_owned()) {
-        Ok(client) => {
-            println!("✅Connection to the redis is successful!");
-            client
+    let redis_client =
+        CONFIG
+            .redis
+            .as_ref()
+            .map(|redis| match Client::open(redis.url.to_owned()) {
+                Ok(client) => {
+                    tracing::info!("Connected to Redis");
+                    client
+                }
+                Err(e) => {
+                    tracing::error!("Error connecting to Redis: {}", e);
+                    std::process::exit(1);
+                }
+            });
+
+    let s3_store: Arc<dyn ObjectStore> = match &CONFIG.s3 {
+        Some(s3) => {
+            let s3_client = Arc::new(S3Client::from_config(s3).await);
+
+            s3_client
+                .create_bucket(S3Bucket::Storage, true, false)
+                .await
+                .unwrap();
+
+            s3_client
+                .create_bucket(S3Bucket::Public, true, true)
+                .await
+                .unwrap();
+
+            s3_client
         }
-        Err(e) => {
-            println!("🔥 Error connecting to Redis: {}", e);
-            std::process::exit(1);
+        None => {
+            tracing::warn!("Storage is disabled, file operations will fail");
+            Arc::new(DisabledObjectStore)
         }
     };
 
-    let s3_credentials = Credentials::new(
-        CONFIG.s3_access_key.to_owned(),
-        CONFIG.s3_secret_key.to_owned(),
-        None,
-        None,
-        "Publish3",
-    );
+    let storage_metrics = Arc::new(StorageMetrics::default());
+    let object_store: Arc<dyn ObjectStore> = Arc::new(InstrumentedObjectStore::new(
+        s3_store,
+        storage_metrics.clone(),
+        Duration::from_millis(CONFIG.s3_slow_operation_ms),
+    ));
 
-    let s3_client =
-        Arc::new(S3Client::new(s3_credentials, None, Some(CONFIG.s3_endpoint.to_owned())).await);
+    auth::privy::load_verification_keys().await;
+    // Without Redis, revocations, sessions and rate limits only live as long as the process
+    if redis_client.is_none() {
+        tracing::warn!(
+            "Redis is disabled, sessions, rate limits and cached publications are kept in memory"
+        );
+    }
+    let kv_store: Arc<dyn KeyValueStore> = match &redis_client {
+        Some(client) => Arc::new(RedisKeyValueStore::new(client.clone())),
+        None => Arc::new(MemoryKeyValueStore::default()),
+    };
+    let rate_limit_store: Arc<dyn RateLimitStore> = match &redis_client {
+        Some(client) => Arc::new(RedisRateLimitStore::new(client.clone())),
+        None => Arc::new(MemoryRateLimitStore::default()),
+    };
 
-    s3_client
-        .create_bucket(S3Bucket::Storage, true)
-        .await
-        .unwrap();
+    let session_revocation = Arc::new(SessionRevocation::new(
+        kv_store.clone(),
+        Duration::from_secs(CONFIG.privy_token_leeway_secs),
+        Duration::from_secs(CONFIG.privy_subject_revocation_secs),
+    ));
 
-    let address = format!("{}:{}", CONFIG.server_address, CONFIG.server_port);
+    let sessions = Arc::new(CookieSessions::new(
+        kv_store.clone(),
+        Duration::from_secs(CONFIG.session_ttl_secs),
+        Duration::from_secs(CONFIG.session_max_age_secs),
+        CONFIG.session_cookie_secure,
+    ));
 
-    tracing::info!("starting HTTP server at http://{address}");
-    HttpServer::new(move || {
+    let internal_tokens: Arc<[InternalApiToken]> = CONFIG.internal_api_tokens.clone().into();
+
+    let rate_limiter = Arc::new(RateLimiter::new(
+        rate_limit_store,
+        CONFIG.rate_limits.clone(),
+    ));
+
+    // Cached publications carry presigned URLs, which must not expire before the entries do
+    let publication_cache = Arc::new(PublicationCache::new(
+        kv_store.clone(),
+        PUBLICATION_CACHE_TTL.min(Duration::from_secs(CONFIG.s3_presign_expiry_secs)),
+    ));
+
+    let view_deduplication = Arc::new(ViewDeduplication::new(
+        kv_store.clone(),
+        VIEW_DEDUPLICATION_WINDOW,
+    ));
+
+    let upload_intents = Arc::new(UploadIntents::new(kv_store, UPLOAD_INTENT_TTL));
+
+    // Spawned on the main runtime, which outlives the workers' so that tasks survive them
+    let background_tasks = Arc::new(TaskRegistry::new(tokio::runtime::Handle::current()));
+    let supervisor = Arc::new(TaskSupervisor::new(background_tasks.clone()));
+
+    let listeners = match server::bind_listeners(&CONFIG.server_addresses, CONFIG.server_port) {
+        Ok(listeners) => listeners,
+        Err(err) => {
+            tracing::error!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let app_background_tasks = background_tasks.clone();
+    let mut server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(AppState {
                 sql_client: sql_client.clone(),
                 redis_client: redis_client.clone(),
-                s3_client: s3_client.clone(),
+                object_store: object_store.clone(),
+                storage_metrics: storage_metrics.clone(),
+                server_base_url: CONFIG.server_base_url.clone(),
+                presign_expiry: Duration::from_secs(CONFIG.s3_presign_expiry_secs),
+                storage_quota: CONFIG.user_storage_quota_bytes,
+                max_publication_file_size: CONFIG.max_publication_file_bytes,
+                session_revocation: session_revocation.clone(),
+                sessions: sessions.clone(),
+                internal_tokens: internal_tokens.clone(),
+                rate_limiter: rate_limiter.clone(),
+                background_tasks: app_background_tasks.clone(),
+                supervisor: supervisor.clone(),
+                publication_cache: publication_cache.clone(),
+                view_deduplication: view_deduplication.clone(),
+                upload_intents: upload_intents.clone(),
             }))
-            .wrap(middleware::Logger::default())
+            .wrap(api::audit::AuditLog)
+            .wrap(api::request_id::AssignRequestId)
+            .wrap(middleware::Logger::new(
+                r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{X-Request-Id}o"#,
+            ))
             .wrap(middleware::NormalizePath::trim())
-            .wrap(crate::auth::Privy)
             .wrap(
                 Cors::default()
                     .allowed_origin(&CONFIG.client_origin)
@@ -113,12 +270,62 @@ async fn main() -> std::io::Result<()> {
                         header::CONTENT_TYPE,
                         header::AUTHORIZATION,
                         header::ACCEPT,
+                        api::request_id::REQUEST_ID_HEADER,
                     ])
+                    .expose_headers(vec![api::request_id::REQUEST_ID_HEADER])
                     .supports_credentials(),
             )
+            .configure(|cfg| api::body_limits::config(cfg, &CONFIG.body_limits))
             .configure(api::config)
-    })
-    .bind("0.0.0.0:8080")?
-    .run()
-    .await
+            .configure(|cfg| {
+                if CONFIG.api_docs {
+                    api::docs::config(cfg);
+                }
+            })
+            .default_service(web::to(api::error::default_service))
+    });
+    if let Some(workers) = CONFIG.server_workers {
+        server = server.workers(workers);
+    }
+    for listener in listeners {
+        tracing::info!("starting HTTP server at http://{}", listener.local_addr()?);
+        server = server.listen(listener)?;
+    }
+
+    let grace = Duration::from_secs(CONFIG.shutdown_grace_secs);
+    let server = server
+        .disable_signals()
+        .shutdown_timeout(CONFIG.shutdown_grace_secs)
+        .run();
+
+    // Stop accepting connections on SIGTERM or SIGINT, and let in-flight requests finish
+    let server_handle = server.handle();
+    let (stopping_tx, mut stopping_rx) = tokio::sync::oneshot::channel();
+    actix_web::rt::spawn(async move {
+        let signal = shutdown::shutdown_signal().await;
+        tracing::info!("Received {}, shutting down", signal);
+        let _ = stopping_tx.send(Instant::now());
+        server_handle.stop(true).await;
+    });
+
+    server.await?;
+
+    // Background tasks get what is left of the grace period once requests are drained, or all of
+    // it if the server stopped on its own
+    let remaining = match stopping_rx.try_recv() {
+        Ok(stopping_at) => grace.saturating_sub(stopping_at.elapsed()),
+        Err(_) => grace,
+    };
+    let report = background_tasks.shutdown(remaining).await;
+    tracing::info!(
+        "Stopped, {} background tasks finished, {} cancelled and {} aborted",
+        report.finished,
+        report.cancelled.len(),
+        report.aborted.len()
+    );
+    if !report.aborted.is_empty() {
+        tracing::warn!("Aborted background tasks: {}", report.aborted.join(", "));
+    }
+
+    Ok(())
 }
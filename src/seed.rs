@@ -0,0 +1,229 @@
+//! Deterministic dataset for local development, written through the [SqlClient] operations so that
+//! seeding also exercises the write paths.
+
+use uuid::Uuid;
+
+use crate::{
+    common::pagination::PageQuery,
+    db::sql::{
+        AuthorOperations, CitationOperations, PublicationAuthorOperations, PublicationOperations,
+        SqlClient, UserOperations,
+        models::{NewAuthor, NewCitation, NewPublication, NewUser},
+    },
+};
+
+/// Number of publications seeded when `--count` is not given.
+pub const DEFAULT_SEED_COUNT: usize = 25;
+
+/// Publications owned by each seeded user.
+const PUBLICATIONS_PER_USER: usize = 3;
+
+const NAMES: &[&str] = &[
+    "Ada Okafor",
+    "Bruno Lindqvist",
+    "Chen Wei",
+    "Dalia Haddad",
+    "Emeka Mensah",
+    "Freya Novak",
+    "Gabriel Costa",
+    "Hana Sato",
+];
+
+const AFFILIATIONS: &[&str] = &[
+    "University of Lisbon",
+    "ETH Zurich",
+    "University of Nairobi",
+    "Kyoto University",
+];
+
+const TOPICS: &[(&str, &[&str])] = &[
+    (
+        "Consensus under partial synchrony",
+        &["distributed-systems", "consensus"],
+    ),
+    (
+        "Zero-knowledge proofs of citation",
+        &["cryptography", "zero-knowledge"],
+    ),
+    (
+        "Incentives for open peer review",
+        &["economics", "peer-review"],
+    ),
+    (
+        "Reproducible machine learning pipelines",
+        &["machine-learning", "reproducibility"],
+    ),
+    (
+        "Storage proofs for scientific datasets",
+        &["storage", "cryptography"],
+    ),
+];
+
+/// Rows created by a [seed] run, all zero once the dataset exists.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SeedReport {
+    pub users: usize,
+    pub authors: usize,
+    pub publications: usize,
+    pub citations: usize,
+}
+
+/// Returns the Privy id of the `index`th seeded user.
+pub fn seed_user_id(index: usize) -> String {
+    format!("did:privy:seed-user-{:02}", index + 1)
+}
+
+/// Seeds `count` publications, owned by users with author profiles, co-authored by the previous
+/// user (the second one for the first user) and citing the two publications seeded before them.
+/// The first user is an admin.
+///
+/// Rows are looked up by their Privy id, title or publications first, so that running it again
+/// only creates what is missing.
+pub async fn seed(sql_client: &SqlClient, count: usize) -> Result<SeedReport, sqlx::Error> {
+    let mut report = SeedReport::default();
+    let user_count = count.div_ceil(PUBLICATIONS_PER_USER).max(1);
+
+    for index in 0..user_count {
+        let privy_id = seed_user_id(index);
+        if find(sql_client.get_user(privy_id.clone()).await)?.is_none() {
+            sql_client
+                .create_user(&NewUser {
+                    privy_id: privy_id.clone(),
+                })
+                .await?;
+            report.users += 1;
+        }
+
+        if find(sql_client.get_author(&privy_id).await)?.is_none() {
+            sql_client
+                .create_author(&NewAuthor {
+                    privy_id: privy_id.clone(),
+                    name: NAMES[index % NAMES.len()].to_string(),
+                    email: Some(format!("seed-user-{:02}@example.com", index + 1)),
+                    affiliation: Some(AFFILIATIONS[index % AFFILIATIONS.len()].to_string()),
+                })
+                .await?;
+            report.authors += 1;
+        }
+    }
+    sql_client.set_user_admin(&seed_user_id(0), true).await?;
+
+    let mut publication_ids: Vec<Uuid> = Vec::with_capacity(count);
+    for index in 0..count {
+        let owner = index / PUBLICATIONS_PER_USER;
+        let owner_id = seed_user_id(owner);
+        let (topic, tags) = TOPICS[index % TOPICS.len()];
+        let title = format!("{topic} ({})", index + 1);
+
+        let existing = sql_client
+            .list_publications_by_user(&owner_id, PageQuery::default())
+            .await?
+            .0
+            .into_iter()
+            .find(|publication| publication.title == title);
+        let publication_id = match existing {
+            Some(publication) => publication.id,
+            None => {
+                report.publications += 1;
+                sql_client
+                    .create_publication(&NewPublication {
+                        user_id: owner_id.clone(),
+                        title,
+                        about: Some(format!("Seeded publication on {}.", topic.to_lowercase())),
+                        tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+                        s3key: None,
+                        paper_hash: None,
+                        file_sha256: None,
+                        file_size: None,
+                    })
+                    .await?
+                    .id
+            }
+        };
+
+        let mut authors = vec![owner_id];
+        // The co-author does not depend on `count`, so that seeding more keeps earlier authors
+        match owner {
+            0 if user_count > 1 => authors.push(seed_user_id(1)),
+            0 => {}
+            _ => authors.push(seed_user_id(owner - 1)),
+        }
+        sql_client
+            .set_publication_authors(publication_id, &authors)
+            .await?;
+
+        for &cited_id in publication_ids.iter().rev().take(2) {
+            if sql_client
+                .get_citation_by_publications(publication_id, cited_id)
+                .await?
+                .is_none()
+            {
+                sql_client
+                    .create_citation(&NewCitation {
+                        citing_publication_id: publication_id,
+                        cited_publication_id: cited_id,
+                    })
+                    .await?;
+                report.citations += 1;
+            }
+        }
+        publication_ids.push(publication_id);
+    }
+
+    Ok(report)
+}
+
+/// Turns a missing row into `None`.
+fn find<T>(result: Result<T, sqlx::Error>) -> Result<Option<T>, sqlx::Error> {
+    match result {
+        Ok(row) => Ok(Some(row)),
+        Err(sqlx::Error::RowNotFound) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_seed_is_idempotent(pool: PgPool) -> sqlx::Result<()> {
+        let sql_client = SqlClient::new(pool).await;
+
+        let report = seed(&sql_client, 7).await?;
+        assert_eq!(
+            report,
+            SeedReport {
+                users: 3,
+                authors: 3,
+                publications: 7,
+                // Every publication cites the two before it
+                citations: 11,
+            }
+        );
+
+        let report = seed(&sql_client, 7).await?;
+        assert_eq!(report, SeedReport::default());
+        assert_eq!(sql_client.count_users().await?, 3);
+        assert_eq!(sql_client.count_authors().await?, 3);
+        assert_eq!(sql_client.count_publications().await?, 7);
+        assert_eq!(sql_client.count_citations().await?, 11);
+
+        assert!(sql_client.get_user(seed_user_id(0)).await?.is_admin);
+        let last = sql_client
+            .list_publications_by_user(&seed_user_id(2), PageQuery::default())
+            .await?
+            .0
+            .into_iter()
+            .find(|publication| publication.title.ends_with("(7)"))
+            .unwrap();
+        let authors =
+            PublicationAuthorOperations::get_publication_authors(&sql_client, last.id).await?;
+        assert_eq!(authors.len(), 2);
+        assert_eq!(authors[0].author_id, seed_user_id(2));
+        assert_eq!(authors[1].author_id, seed_user_id(1));
+        Ok(())
+    }
+}
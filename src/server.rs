@@ -0,0 +1,70 @@
+use std::{io, net::TcpListener};
+
+/// Binds a listener on `port` for each of `addresses`, which may be IP addresses or host names.
+///
+/// Failures name the address that could not be bound, and explain that the port is taken when
+/// another process already listens on it.
+pub fn bind_listeners(addresses: &[String], port: u16) -> io::Result<Vec<TcpListener>> {
+    addresses
+        .iter()
+        .map(|address| {
+            TcpListener::bind((address.as_str(), port)).map_err(|err| {
+                let reason = match err.kind() {
+                    io::ErrorKind::AddrInUse => {
+                        "the port is already in use, is another server running?".to_string()
+                    }
+                    _ => err.to_string(),
+                };
+                io::Error::new(
+                    err.kind(),
+                    format!("Failed to bind {address}:{port}: {reason}"),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, HttpServer};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_serves_on_configured_port() {
+        let listeners = bind_listeners(&["127.0.0.1".to_string()], 0).unwrap();
+        let address = listeners[0].local_addr().unwrap();
+
+        let mut server = HttpServer::new(|| App::new().configure(crate::api::config)).workers(1);
+        for listener in listeners {
+            server = server.listen(listener).unwrap();
+        }
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let resp = reqwest::get(format!("http://{address}/healthz"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["status"], "ok");
+
+        handle.stop(true).await;
+    }
+
+    #[test]
+    fn test_bind_occupied_port() {
+        let occupied = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = occupied.local_addr().unwrap().port();
+
+        let err = bind_listeners(&["127.0.0.1".to_string()], port).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Failed to bind 127.0.0.1:{port}: the port is already in use, is another server running?"
+            )
+        );
+    }
+}